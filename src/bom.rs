@@ -0,0 +1,146 @@
+use std::fmt;
+
+use crate::object_3d::{Object3D, Shape3D};
+
+//{{{ pub struct BomLine
+
+#[derive(Debug, Clone)]
+pub struct BomLine
+{
+	pub part_number : String, // The name for parts without a number
+	pub description : String,
+	pub purchased   : bool,
+	pub quantity    : usize,
+}
+//}}}
+
+//{{{ pub struct BillOfMaterials
+
+// All tagged parts of an assembly, counted by part number. Parts nested inside other parts are not listed separately.
+#[derive(Debug, Clone, Default)]
+pub struct BillOfMaterials
+{
+	pub lines : Vec<BomLine>,
+}
+
+//{{{
+impl BillOfMaterials
+{
+	//{{{
+	pub fn from_assembly(assembly: &Object3D) -> Self
+	{
+		let mut bom = Self::default();
+		bom.add(assembly);
+		bom
+	}
+	//}}}
+	//{{{
+	pub fn add(&mut self, object: &Object3D)
+	{
+		if let Some(part) = &object.part
+		{
+			let part_number = part.number.clone().unwrap_or_else(|| object.name.clone());
+			match self.lines.iter_mut().find(|line| line.part_number == part_number)
+			{
+				Some(line) => line.quantity += 1,
+				None       => self.lines.push(BomLine
+				{
+					part_number,
+					description : part.description.clone().unwrap_or_default(),
+					purchased   : part.purchased,
+					quantity    : 1,
+				}),
+			}
+			return;
+		}
+
+		if let Shape3D::Composite{children, ..} = &object.shape
+		{
+			for child in children
+			{
+				self.add(child);
+			}
+		}
+	}
+	//}}}
+}
+//}}}
+
+//{{{
+// One tab separated line per part: quantity, part number, description
+impl fmt::Display for BillOfMaterials
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+	{
+		writeln!(f, "Qty\tPart\tDescription")?;
+		for line in &self.lines
+		{
+			writeln!(f, "{}\t{}\t{}", line.quantity, line.part_number, line.description)?;
+		}
+		Ok(())
+	}
+}
+//}}}
+//}}}
+
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::hardware::{Hardware, ScrewHead};
+	use crate::object_3d::{cube, union};
+
+	//{{{
+	#[test]
+	fn counting()
+	{
+		let screw = Hardware::metric_screw(4.0, 20.0, ScrewHead::SocketCap).unwrap();
+
+		let mut side = cube("Side", 18.0, 300.0, 600.0);
+		side.set_part_number("CAB-01");
+		side.set_part_description("Side panel");
+		let mut shelf = cube("Shelf", 500.0, 300.0, 18.0);
+		shelf.tag_as_part();
+
+		// A sub-assembly with a part number is one part, whatever it is made of
+		let mut drawer = union("Drawer", [cube("Front", 500.0, 18.0, 150.0), screw.object("Handle screw")]);
+		drawer.set_part_number("DR-01");
+
+		let fixings = union("Fixings", [screw.object("Screw 1"), screw.object("Screw 2"), Hardware::nut(4.0).unwrap().object("Nut")]);
+		let cabinet = union("Cabinet", [side.clone(), side, shelf, drawer.clone(), drawer, fixings, screw.object("Screw 3")]);
+
+		let bom = BillOfMaterials::from_assembly(&cabinet);
+		let lines : Vec<(&str, usize, bool)> = bom.lines.iter().map(|line| (line.part_number.as_str(), line.quantity, line.purchased)).collect();
+		assert_eq!(lines, [
+			("CAB-01",          2, false),
+			("Shelf",           1, false),
+			("DR-01",           2, false),
+			("ISO 4762 M4x20",  3, true),
+			("ISO 4032 M4",     1, true),
+		]);
+
+		assert_eq!(bom.to_string(), "Qty\tPart\tDescription\n\
+			2\tCAB-01\tSide panel\n\
+			1\tShelf\t\n\
+			2\tDR-01\t\n\
+			3\tISO 4762 M4x20\tSocket head cap screw\n\
+			1\tISO 4032 M4\tHex nut\n");
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn merging()
+	{
+		// Adding further assemblies counts their parts into the existing lines
+		let screw = Hardware::wood_screw(4.0, 30.0);
+		let mut bom = BillOfMaterials::from_assembly(&union("Left", [screw.object("Screw 1"), screw.object("Screw 2")]));
+		bom.add(&screw.object("Screw 3"));
+		bom.add(&cube("Helper", 1.0, 1.0, 1.0));
+		assert_eq!(bom.lines.len(), 1);
+		assert_eq!((bom.lines[0].part_number.as_str(), bom.lines[0].quantity), ("Wood screw 4x30", 3));
+	}
+	//}}}
+}
+//}}}
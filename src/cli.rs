@@ -0,0 +1,579 @@
+// Command line driver for design binaries. A design registers its top level objects as named parts and hands the
+// arguments over:
+//
+//     fn main()
+//     {
+//         let mut cli = Cli::new("bett");
+//         cli.part("Sideboard_L", || sideboard("Sideboard_L"));
+//         cli.run();
+//     }
+//
+// The parts are only built when a command needs them. Designs with parameters apply those options first, hand the
+// parameters over, so that the OpenSCad code declares the variables the expressions refer to, and pass the remaining
+// arguments on:
+//
+//     let args = parameters.apply_command_line(); // --parameters FILE, --set NAME=VALUE
+//     cli.set_parameters(&parameters).run_with(args);
+
+use std::fmt;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command as Process;
+
+use crate::math::Matrix3D;
+use crate::export;
+use crate::mesh::{self, Mesh, TessellationError};
+use crate::object_3d::Object3D;
+use crate::scad_writer::{ScadWriter, scad_string};
+use crate::nesting::{self, NestingOptions, NestOutline};
+use crate::project::Project;
+use crate::units::LengthUnit;
+use crate::resolution::{Resolution, ResolutionProfile};
+use crate::parameters::Parameters;
+use crate::BillOfMaterials;
+
+const USAGE : &str = "\
+Commands:
+  list              List the parts
+  scad              OpenSCad code (the default)
+  stl               Binary STL, tessellated natively
+  obj               Wavefront OBJ with the colours in an MTL file next to it (default: <part>.obj)
+  gltf              glTF 2.0 with colours, in metres
+  3mf               3MF with one object per part, its colour and part number
+  svg               Outline: 2D parts as they are, 3D parts as seen from below
+  bom               Bill of materials
+  render            Render with OpenSCad (PNG, or any format OpenSCad knows by the --out extension)
+  project           One OpenSCad file per part and assembly.scad, into the --out directory (default: out)
+  nest              Nest the parts onto sheets for cutting: sheet-N.scad and sheet-N.svg into the --out directory
+                    (default: out) and the utilisation summary
+
+Options:
+  --part NAME       Only this part (repeatable, or comma separated). Default: all parts
+  --out PATH        Output file, or a directory (existing, or ending in /) for one file per part. Default: stdout
+  --compact         scad: one line per part, without indentation
+  --no-comments     scad: without the object names as comments
+  --unit UNIT       scad, stl, obj, 3mf, project: output unit, mm, cm, m or in (default: mm)
+  --resolution RES  $fn/$fa/$fs for all outputs: draft, preview or final (default: as set by the design)
+  --flat            project: lay the parts flat on the XY-plane for printing
+  --sheet WxH       nest: sheet size (default: 2500x1250)
+  --kerf WIDTH      nest: gap between the parts (default: 4)
+  --no-rotation     nest: don't turn parts by 90°
+  --bounding-box    nest: cut the bounding rectangles instead of the outlines
+  --help            This text";
+
+//{{{ pub enum CliError
+
+#[derive(Debug)]
+pub enum CliError
+{
+	Usage(String),
+	UnknownPart(String),
+	Io{ path: String, error: io::Error },
+	Tessellation(TessellationError),
+	OpenScad(String),
+	NameClash{ first: String, second: String, name: String },
+}
+
+//{{{
+impl fmt::Display for CliError
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+	{
+		match self
+		{
+			Self::Usage(message)                 => write!(f, "{}", message),
+			Self::UnknownPart(name)              => write!(f, "there is no part {}", name),
+			Self::Io{path, error}                => write!(f, "{}: {}", path, error),
+			Self::Tessellation(error)            => write!(f, "{}", error),
+			Self::OpenScad(message)              => write!(f, "openscad: {}", message),
+			Self::NameClash{first, second, name} => write!(f, "parts \"{}\" and \"{}\" would both be written as {}", first, second, name),
+		}
+	}
+}
+//}}}
+
+impl std::error::Error for CliError {}
+
+//{{{
+impl From<TessellationError> for CliError
+{
+	fn from(error: TessellationError) -> Self
+	{
+		Self::Tessellation(error)
+	}
+}
+//}}}
+//}}}
+
+//{{{ enum Command
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Command
+{
+	List,
+	Scad,
+	Stl,
+	Obj,
+	Gltf,
+	ThreeMf,
+	Svg,
+	Bom,
+	Render,
+	Project,
+	Nest,
+	Help,
+}
+
+//{{{
+impl Command
+{
+	//{{{
+	fn parse(word: &str) -> Option<Command>
+	{
+		match word
+		{
+			"list"    => Some(Self::List),
+			"scad"    => Some(Self::Scad),
+			"stl"     => Some(Self::Stl),
+			"obj"     => Some(Self::Obj),
+			"gltf"    => Some(Self::Gltf),
+			"3mf"     => Some(Self::ThreeMf),
+			"svg"     => Some(Self::Svg),
+			"bom"     => Some(Self::Bom),
+			"render"  => Some(Self::Render),
+			"project" => Some(Self::Project),
+			"nest"    => Some(Self::Nest),
+			"help"    => Some(Self::Help),
+			_         => None,
+		}
+	}
+	//}}}
+	//{{{
+	fn extension(&self) -> &'static str
+	{
+		match self
+		{
+			Self::Stl     => "stl",
+			Self::Obj     => "obj",
+			Self::Gltf    => "gltf",
+			Self::ThreeMf => "3mf",
+			Self::Svg     => "svg",
+			Self::Bom     => "txt",
+			Self::Render  => "png",
+			_             => "scad",
+		}
+	}
+	//}}}
+}
+//}}}
+//}}}
+
+//{{{ pub struct Cli
+
+pub struct Cli
+{
+	name       : String,
+	parts      : Vec<(String, Box<dyn Fn() -> Object3D>)>,
+	resolution : Resolution,
+	parameters : Parameters,
+}
+
+//{{{
+impl Cli
+{
+	//{{{
+	pub fn new(name: &str) -> Self
+	{
+		Self { name: String::from(name), parts: vec![], resolution: Resolution::default(), parameters: Parameters::new() }
+	}
+	//}}}
+	//{{{
+	// Registers a top level object, built on demand
+	pub fn part(&mut self, name: &str, build: impl Fn() -> Object3D + 'static) -> &mut Self
+	{
+		self.parts.push((String::from(name), Box::new(build)));
+		self
+	}
+	//}}}
+	//{{{
+	// The resolution of the design, unless --resolution chooses another one
+	pub fn set_resolution(&mut self, resolution: impl Into<Resolution>) -> &mut Self
+	{
+		self.resolution = resolution.into();
+		self
+	}
+	//}}}
+	//{{{
+	// The parameters the parts' expressions refer to, with their values already applied (see Parameters::apply_args()).
+	// The OpenSCad outputs declare them for the Customizer in front of the geometry.
+	pub fn set_parameters(&mut self, parameters: &Parameters) -> &mut Self
+	{
+		self.parameters = parameters.clone();
+		self
+	}
+	//}}}
+	//{{{
+	pub fn part_names(&self) -> Vec<&str>
+	{
+		self.parts.iter().map(|(name, _)| name.as_str()).collect()
+	}
+	//}}}
+
+	//{{{
+	// Runs the command given on the command line. On errors, it prints the message and exits.
+	pub fn run(&self)
+	{
+		self.run_with(std::env::args().skip(1).collect());
+	}
+	//}}}
+	//{{{
+	pub fn run_with(&self, args: Vec<String>)
+	{
+		if let Err(error) = self.run_args(args)
+		{
+			eprintln!("error: {}", error);
+			std::process::exit(match error { CliError::Usage(_) | CliError::UnknownPart(_) => 2, _ => 1 });
+		}
+	}
+	//}}}
+	//{{{
+	pub fn run_args(&self, args: Vec<String>) -> Result<(), CliError>
+	{
+		//{{{ Parse the arguments
+
+		let mut command    = None;
+		let mut names      = vec![];
+		let mut out        = None;
+		let mut flat       = false;
+		let mut compact    = false;
+		let mut comments   = true;
+		let mut unit       = LengthUnit::Millimetre;
+		let mut resolution = self.resolution;
+		let mut nesting    = NestingOptions::default();
+
+		let mut args = args.into_iter();
+		while let Some(arg) = args.next()
+		{
+			let (option, value) = match arg.split_once('=')
+			{
+				Some((option, value)) if option.starts_with("--") => (String::from(option), Some(String::from(value))),
+				_                                                 => (arg.clone(), None),
+			};
+			match option.as_str()
+			{
+				"--part" | "--out" | "--unit" | "--resolution" | "--sheet" | "--kerf" =>
+				{
+					let value = value.or_else(|| args.next()).ok_or_else(|| CliError::Usage(format!("{} needs a value", option)))?;
+					let invalid = || CliError::Usage(format!("invalid value for {}: {}", option, value));
+					match option.as_str()
+					{
+						"--part"       => names.extend(value.split(',').map(|name| String::from(name.trim()))),
+						"--out"        => out = Some(value.clone()),
+						"--unit"       => unit = value.parse().map_err(CliError::Usage)?,
+						"--resolution" => resolution = value.parse::<ResolutionProfile>().map_err(CliError::Usage)?.resolution(),
+						"--sheet"      =>
+						{
+							let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+							nesting.sheet_width  = width.trim().parse().map_err(|_| invalid())?;
+							nesting.sheet_height = height.trim().parse().map_err(|_| invalid())?;
+						}
+						_              => nesting.kerf = value.parse().map_err(|_| invalid())?,
+					}
+				}
+				"--flat"         => flat = true,
+				"--compact"      => compact = true,
+				"--no-comments"  => comments = false,
+				"--no-rotation"  => nesting.rotation = false,
+				"--bounding-box" => nesting.outline = NestOutline::BoundingBox,
+				"--help" | "-h"  => command = Some(Command::Help),
+				_ if arg.starts_with('-') => return Err(CliError::Usage(format!("unknown option {}\n\n{}", arg, self.usage()))),
+				_ =>
+				{
+					if command.is_some() { return Err(CliError::Usage(format!("unexpected argument {}", arg))); }
+					command = Some(Command::parse(&arg).ok_or_else(|| CliError::Usage(format!("unknown command {}\n\n{}", arg, self.usage())))?);
+				}
+			}
+		}
+		let command = command.unwrap_or(Command::Scad);
+		if command == Command::Gltf && unit != LengthUnit::Millimetre
+		{
+			return Err(CliError::Usage(String::from("--unit can not be used with gltf, glTF is always in metres")));
+		}
+		//}}}
+
+		if command == Command::Help
+		{
+			println!("{}", self.usage());
+			return Ok(());
+		}
+
+		let selected = self.select(&names)?;
+		if command == Command::List
+		{
+			let list : String = selected.iter().map(|&i| format!("{}\n", self.parts[i].0)).collect();
+			return write_output(out.as_deref().map(Path::new), list.as_bytes());
+		}
+		if command == Command::Project
+		{
+			let mut project = Project::new(&self.name);
+			project.set_lay_flat(flat).set_output_unit(unit).set_resolution(resolution).set_parameters(&self.parameters);
+			for &i in &selected { project.add_named(&self.parts[i].0, (self.parts[i].1)()); }
+			return project.write(Path::new(out.as_deref().unwrap_or("out"))).map(|_| ());
+		}
+		if command == Command::Nest
+		{
+			let objects : Vec<Object3D> = selected.iter().map(|&i| (self.parts[i].1)()).collect();
+			nesting.resolution = resolution;
+			let layout    = nesting::nest(&objects, &nesting)?;
+			let directory = Path::new(out.as_deref().unwrap_or("out"));
+			for (i, sheet) in layout.sheets.iter().enumerate()
+			{
+				let name = format!("sheet-{}", i+1);
+				write_output(Some(&directory.join(format!("{}.scad", name))), sheet.scad(&name).as_bytes())?;
+				write_output(Some(&directory.join(format!("{}.svg", name))), sheet.svg().as_bytes())?;
+			}
+			return write_output(None, layout.to_string().as_bytes());
+		}
+
+		for (parts, path) in self.targets(&selected, out.as_deref(), command)
+		{
+			let objects : Vec<Object3D> = parts.iter().map(|&i| (self.parts[i].1)()).collect();
+			let name = if parts.len() == 1 { self.parts[parts[0]].0.clone() } else { self.name.clone() };
+			match command
+			{
+				Command::Scad    => write_scad(path.as_deref(), &objects, compact, comments, unit, &resolution, &self.parameters)?,
+				Command::Stl     =>
+				{
+					let mut mesh = Mesh::default();
+					for object in &objects { mesh.append(&Mesh::from_object_with(object, &resolution)?); }
+					if unit != LengthUnit::Millimetre { mesh.transform(&unit.convert(&Matrix3D::identity())); }
+					let mut bytes = vec![];
+					mesh.write_stl(&name, &mut bytes).map_err(|error| CliError::Io{ path: String::from("<stl>"), error })?;
+					write_output(path.as_deref(), &bytes)?;
+				}
+				Command::Obj     =>
+				{
+					let mut meshes = export::coloured_meshes(&objects, &resolution)?;
+					if unit != LengthUnit::Millimetre
+					{
+						for coloured in &mut meshes { coloured.mesh.transform(&unit.convert(&Matrix3D::identity())); }
+					}
+					let obj_path = path.unwrap_or_else(|| PathBuf::from(format!("{}.obj", file_name(&name))));
+					let mtl_path = obj_path.with_extension("mtl");
+					let mtl_name = mtl_path.file_name().map_or(String::new(), |name| name.to_string_lossy().into_owned());
+					let (mut obj, mut mtl) = (vec![], vec![]);
+					export::write_obj(&meshes, &mtl_name, &mut obj, &mut mtl).map_err(|error| CliError::Io{ path: String::from("<obj>"), error })?;
+					write_output(Some(&obj_path), &obj)?;
+					write_output(Some(&mtl_path), &mtl)?;
+				}
+				Command::Gltf    =>
+				{
+					let meshes = export::coloured_meshes(&objects, &resolution)?;
+					let mut bytes = vec![];
+					export::write_gltf(&meshes, &mut bytes).map_err(|error| CliError::Io{ path: String::from("<gltf>"), error })?;
+					write_output(path.as_deref(), &bytes)?;
+				}
+				Command::ThreeMf =>
+				{
+					let items = export::build_items(&objects, &resolution)?;
+					let mut bytes = vec![];
+					export::write_3mf(&name, &items, unit, &mut bytes).map_err(|error| CliError::Io{ path: String::from("<3mf>"), error })?;
+					write_output(path.as_deref(), &bytes)?;
+				}
+				Command::Svg     =>
+				{
+					let mut loops = vec![];
+					for object in &objects { loops.extend(mesh::outline_with(object, &resolution)?); }
+					write_output(path.as_deref(), mesh::svg(&loops).as_bytes())?;
+				}
+				Command::Bom     =>
+				{
+					let mut bom = BillOfMaterials::default();
+					for object in &objects { bom.add(object); }
+					write_output(path.as_deref(), bom.to_string().as_bytes())?;
+				}
+				Command::Render  =>
+				{
+					let image = path.unwrap_or_else(|| PathBuf::from(format!("{}.png", file_name(&name))));
+					render(&name, &scad(&objects, &resolution, &self.parameters), &image)?;
+				}
+				Command::List | Command::Project | Command::Nest | Command::Help => unreachable!(),
+			}
+		}
+		Ok(())
+	}
+	//}}}
+
+	//{{{
+	fn usage(&self) -> String
+	{
+		format!("Usage: {} [COMMAND] [--part NAME]... [--out PATH] [OPTION]...\n\n{}", self.name, USAGE)
+	}
+	//}}}
+	//{{{
+	// Indices of the selected parts, in the order they were registered
+	fn select(&self, names: &[String]) -> Result<Vec<usize>, CliError>
+	{
+		if let Some(unknown) = names.iter().find(|name| !self.parts.iter().any(|(part, _)| part == *name))
+		{
+			return Err(CliError::UnknownPart(unknown.clone()));
+		}
+		Ok((0..self.parts.len()).filter(|&i| names.is_empty() || names.contains(&self.parts[i].0)).collect())
+	}
+	//}}}
+	//{{{
+	// The outputs: one per part for a directory, otherwise all selected parts together in one
+	fn targets(&self, selected: &[usize], out: Option<&str>, command: Command) -> Vec<(Vec<usize>, Option<PathBuf>)>
+	{
+		match out
+		{
+			Some(out) if out.ends_with('/') || Path::new(out).is_dir() =>
+			{
+				selected.iter().map(|&i| (vec![i], Some(Path::new(out).join(format!("{}.{}", file_name(&self.parts[i].0), command.extension()))))).collect()
+			}
+			Some("-") | None => vec![(selected.to_vec(), None)],
+			Some(out)        => vec![(selected.to_vec(), Some(PathBuf::from(out)))],
+		}
+	}
+	//}}}
+}
+//}}}
+//}}}
+
+//{{{ Helpers
+
+//{{{
+fn scad(objects: &[Object3D], resolution: &Resolution, parameters: &Parameters) -> String
+{
+	scad_string(|writer| writer.set_modules(true).set_resolution(*resolution).set_parameters(parameters).write_objects(objects))
+}
+//}}}
+//{{{
+// Streams the OpenSCad code of the objects to the output, with the repeated objects as modules
+fn write_scad(path: Option<&Path>, objects: &[Object3D], compact: bool, comments: bool, unit: LengthUnit, resolution: &Resolution, parameters: &Parameters) -> Result<(), CliError>
+{
+	let name       = path.map_or(String::from("<stdout>"), |path| path.display().to_string());
+	let mut writer = ScadWriter::new(open_output(path)?);
+	writer.set_modules(true).set_compact(compact).set_comments(comments).set_output_unit(unit).set_resolution(*resolution).set_parameters(parameters);
+	writer.write_objects(objects).map_err(|error| CliError::Io{ path: name, error })
+}
+//}}}
+//{{{
+// Part names as file names: anything but letters, digits, '-' and '_' becomes '_'
+pub(crate) fn file_name(name: &str) -> String
+{
+	name.chars().map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect()
+}
+//}}}
+//{{{
+pub(crate) fn write_output(path: Option<&Path>, bytes: &[u8]) -> Result<(), CliError>
+{
+	let mut out = open_output(path)?;
+	out.write_all(bytes).and_then(|_| out.flush()).map_err(io_error(path.unwrap_or(Path::new("<stdout>"))))
+}
+//}}}
+//{{{
+// The file (its directory is created if needed), or stdout for None
+fn open_output(path: Option<&Path>) -> Result<Box<dyn Write>, CliError>
+{
+	match path
+	{
+		None       => Ok(Box::new(io::BufWriter::new(io::stdout().lock()))),
+		Some(path) =>
+		{
+			if let Some(directory) = path.parent().filter(|directory| !directory.as_os_str().is_empty())
+			{
+				std::fs::create_dir_all(directory).map_err(io_error(directory))?;
+			}
+			let file = std::fs::File::create(path).map_err(io_error(path))?;
+			Ok(Box::new(io::BufWriter::new(file)))
+		}
+	}
+}
+//}}}
+//{{{
+fn io_error(path: &Path) -> impl FnOnce(io::Error) -> CliError
+{
+	let path = path.display().to_string();
+	move |error| CliError::Io{ path, error }
+}
+//}}}
+//{{{
+// Writes the code to a temporary file and lets OpenSCad render it to 'image'
+fn render(name: &str, code: &str, image: &Path) -> Result<(), CliError>
+{
+	let input = std::env::temp_dir().join(format!("{}-{}.scad", file_name(name), std::process::id()));
+	write_output(Some(&input), code.as_bytes())?;
+
+	let result = Process::new("openscad").arg("-o").arg(image).arg(&input).output();
+	let _ = std::fs::remove_file(&input);
+
+	match result
+	{
+		Err(error) if error.kind() == io::ErrorKind::NotFound => Err(CliError::OpenScad(String::from("not found, please install OpenSCad or add it to the PATH"))),
+		Err(error)                                            => Err(CliError::OpenScad(error.to_string())),
+		Ok(output) if !output.status.success()                => Err(CliError::OpenScad(String::from_utf8_lossy(&output.stderr).trim().to_string())),
+		Ok(_)                                                 => Ok(()),
+	}
+}
+//}}}
+//}}}
+
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::object_3d::cube;
+	use crate::parameters::Parameter;
+
+	//{{{
+	// Runs the command line with --out into a temporary file and returns what was written
+	fn output(cli: &Cli, args: &[&str], extension: &str) -> String
+	{
+		let path = std::env::temp_dir().join(format!("rusty_scad-cli-{}-{}.{}", std::process::id(), args.join("_").replace(['/', '-', ' '], ""), extension));
+		let mut args : Vec<String> = args.iter().map(|&arg| String::from(arg)).collect();
+		args.extend([String::from("--out"), path.display().to_string()]);
+		cli.run_args(args).expect("the command runs");
+		let text = std::fs::read_to_string(&path).expect("the output was written");
+		let _ = std::fs::remove_file(&path);
+		text
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn parametrised_cube()
+	{
+		let mut parameters = Parameters::new();
+		parameters.add(Parameter::number("width", 20.0).range(10.0, 1.0, 50.0).unit("mm").description("Panel width")).unwrap();
+		parameters.set_assignment("width=30").expect("width is a parameter");
+
+		let width    = parameters.expr("width");
+		let mut cli  = Cli::new("panel");
+		cli.part("panel", move || cube("panel", width.clone(), 2.0*width.clone(), 3.0));
+		cli.set_parameters(&parameters);
+
+		let code = output(&cli, &["scad"], "scad");
+		assert!(code.starts_with("/* [Parameters] */\n// Panel width [mm]\nwidth = 30; // [10:1:50]\n\n/* [Hidden] */\n\n"));
+		assert!(code.contains("cube([width, (2 * width), 3]"));
+
+		// The declarations come before the geometry in compact code too, and the geometry still has the applied value
+		let compact = output(&cli, &["scad", "--compact"], "scad");
+		assert!(compact.find("width = 30;").expect("declared") < compact.find("cube(").expect("a cube"));
+		let mesh = Mesh::from_object(&(cli.parts[0].1)()).expect("a cube tessellates");
+		assert!((mesh.volume() - 30.0*60.0*3.0).abs() < 1e-6);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn gltf_unit()
+	{
+		let mut cli = Cli::new("panel");
+		cli.part("panel", || cube("panel", 10.0, 10.0, 1.0));
+		let args = ["gltf", "--unit", "cm"].iter().map(|&arg| String::from(arg)).collect();
+		assert!(matches!(cli.run_args(args), Err(CliError::Usage(message)) if message.contains("glTF is always in metres")));
+	}
+	//}}}
+}
+//}}}
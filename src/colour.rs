@@ -0,0 +1,417 @@
+// Colours of objects, as OpenSCad's color(): a name from the CSS list OpenSCad knows, RGBA components from 0 to 1, or
+// only the transparency of the inherited colour.
+//
+//     board.set_colour(colour_hex("#ff8800"));
+//     glass.set_colour(colour_hsv(200.0, 0.3, 1.0).with_alpha(0.4));
+//
+// Parts can get their colour from their material instead, see Palette. The exporters (crate::export) write the colours
+// as RGBA, with the names resolved by the same table.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::object_3d::{Object3D, Shape3D};
+use crate::scad_writer::number;
+use crate::units::Angle;
+
+// Numeric representation of a colour
+type ColourVec = [f64; 4];
+
+//{{{ pub enum Colour
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Colour
+{
+	Unset,
+	Numeric(ColourVec),
+	Named(String),
+	Alpha(f64),         // Only the transparency, the colour is inherited
+}
+//}}}
+
+//{{{ Colour constructors
+
+//{{{
+pub fn colour_none() -> Colour
+{
+	Colour::Unset
+}
+//}}}
+//{{{
+// A CSS colour name, in any case as in OpenSCad. Unknown names are kept, OpenSCad ignores them; see try_colour_named().
+pub fn colour_named(name: &str) -> Colour
+{
+	Colour::Named(name.to_string())
+}
+//}}}
+//{{{
+// As colour_named(), but unknown names are an error
+pub fn try_colour_named(name: &str) -> Result<Colour, String>
+{
+	if is_colour_name(name) { Ok(colour_named(name)) } else { Err(format!("unknown colour name {}", name)) }
+}
+//}}}
+//{{{
+pub fn colour_rgba(r : f64, g : f64, b : f64, a : f64) -> Colour
+{
+	Colour::Numeric(ColourVec::from([r,g,b,a]))
+}
+//}}}
+//{{{
+pub fn colour_rgb(r : f64, g : f64, b : f64) -> Colour
+{
+	colour_rgba(r,g,b,1.0)
+}
+//}}}
+//{{{
+// "#rgb", "#rgba", "#rrggbb" or "#rrggbbaa". Malformed ones leave the colour unset; see try_colour_hex().
+pub fn colour_hex(hex: &str) -> Colour
+{
+	try_colour_hex(hex).unwrap_or(Colour::Unset)
+}
+//}}}
+//{{{
+// As colour_hex(), but malformed ones are an error
+pub fn try_colour_hex(hex: &str) -> Result<Colour, String>
+{
+	parse_hex(hex).map(Colour::Numeric).ok_or_else(|| format!("invalid hex colour {}", hex))
+}
+//}}}
+//{{{
+// Hue as an angle (0° red, 120° green, 240° blue), saturation and value from 0 to 1
+pub fn colour_hsv(h: impl Into<Angle>, s: f64, v: f64) -> Colour
+{
+	colour_hsva(h, s, v, 1.0)
+}
+//}}}
+//{{{
+pub fn colour_hsva(h: impl Into<Angle>, s: f64, v: f64, a: f64) -> Colour
+{
+	let sector = h.into().to_deg().rem_euclid(360.0)/60.0;
+	let c      = v*s;
+	let x      = c*(1.0 - (sector%2.0 - 1.0).abs());
+	let (r, g, b) = match sector as u32
+	{
+		0 => (  c,   x, 0.0),
+		1 => (  x,   c, 0.0),
+		2 => (0.0,   c,   x),
+		3 => (0.0,   x,   c),
+		4 => (  x, 0.0,   c),
+		_ => (  c, 0.0,   x),
+	};
+	let m = v - c;
+	colour_rgba(r+m, g+m, b+m, a)
+}
+//}}}
+//{{{
+// Makes the inherited colour transparent, color(alpha=a)
+pub fn colour_alpha(a: f64) -> Colour
+{
+	Colour::Alpha(a)
+}
+//}}}
+//{{{
+pub fn is_colour_name(name: &str) -> bool
+{
+	named_rgba(name).is_some()
+}
+//}}}
+//}}}
+
+//{{{
+impl Colour
+{
+	//{{{
+	pub fn is_set(&self) -> bool
+	{
+		*self != Self::Unset
+	}
+	//}}}
+	//{{{
+	// The same colour with transparency 'a' (1 is opaque). Names become numeric, unknown ones are dropped like OpenSCad
+	// does.
+	pub fn with_alpha(&self, a: f64) -> Colour
+	{
+		match self.rgba()
+		{
+			Some([r, g, b, _]) => colour_rgba(r, g, b, a),
+			None               => colour_alpha(a),
+		}
+	}
+	//}}}
+	//{{{
+	// RGBA components from 0 to 1, None where the colour is inherited
+	pub fn rgba(&self) -> Option<[f64; 4]>
+	{
+		match self
+		{
+			Self::Unset | Self::Alpha(_) => None,
+			Self::Numeric(vec)           => Some(*vec),
+			Self::Named(name)            => named_rgba(name),
+		}
+	}
+	//}}}
+	//{{{
+	// The color() call, None if unset
+	pub(crate) fn scad_call(&self) -> Option<String>
+	{
+		match &self
+		{
+			Self::Unset                 => None,
+			Self::Numeric([r, g, b, a]) =>
+			{
+				let alpha = if *a == 1.0 { String::new() } else { format!(", {}", number(*a)) };
+				Some(format!("color([{}, {}, {}{}])", number(*r), number(*g), number(*b), alpha))
+			}
+			Self::Named(name)           => Some(format!("color(\"{}\")", name)),
+			Self::Alpha(a)              => Some(format!("color(alpha={})", number(*a))),
+		}
+	}
+	//}}}
+}
+//}}}
+//{{{
+// Names and hex colours, as colour_named() and colour_hex() take them
+impl FromStr for Colour
+{
+	type Err = String;
+
+	fn from_str(text: &str) -> Result<Self, String>
+	{
+		if text.starts_with('#') { try_colour_hex(text) } else { try_colour_named(text) }
+	}
+}
+//}}}
+//{{{
+// Names as they are, numeric colours in hex
+impl fmt::Display for Colour
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+	{
+		match &self
+		{
+			Self::Unset        => write!(f, "Unset"),
+			Self::Numeric(vec) =>
+			{
+				let byte = |x: f64| (x.clamp(0.0, 1.0)*255.0).round() as u8;
+				write!(f, "#{:02x}{:02x}{:02x}", byte(vec[0]), byte(vec[1]), byte(vec[2]))?;
+				if vec[3] != 1.0 { write!(f, "{:02x}", byte(vec[3]))?; }
+				Ok(())
+			}
+			Self::Named(name)  => write!(f, "{}", name),
+			Self::Alpha(a)     => write!(f, "alpha {}", a),
+		}
+	}
+}
+//}}}
+
+//{{{ pub struct Palette
+
+// Colours by material, e.g. all birch plywood parts in "burlywood". Materials are set on parts, see
+// Object3D::set_material().
+#[derive(Debug, Clone, Default)]
+pub struct Palette
+{
+	colours : HashMap<String, Colour>,
+}
+
+//{{{
+impl Palette
+{
+	//{{{
+	pub fn new() -> Self
+	{
+		Self::default()
+	}
+	//}}}
+	//{{{
+	pub fn set(&mut self, material: &str, colour: Colour) -> &mut Self
+	{
+		self.colours.insert(String::from(material), colour);
+		self
+	}
+	//}}}
+	//{{{
+	pub fn colour(&self, material: &str) -> Option<&Colour>
+	{
+		self.colours.get(material)
+	}
+	//}}}
+	//{{{
+	// Colours the parts in the tree by their material. Parts with a colour of their own keep it.
+	pub fn apply(&self, object: &mut Object3D)
+	{
+		let material = object.part.as_ref().and_then(|part| part.material.as_deref());
+		if let Some(colour) = material.and_then(|material| self.colour(material))
+		{
+			if !object.colour.is_set() { object.set_colour(colour.clone()); }
+		}
+
+		match &mut object.shape
+		{
+			Shape3D::Composite{children, ..} => for child in children { self.apply(child); },
+			Shape3D::Lextrude{child, ..}     => self.apply(child),
+			_                                => (),
+		}
+	}
+	//}}}
+}
+//}}}
+//}}}
+
+//{{{ Helpers
+
+//{{{
+fn parse_hex(hex: &str) -> Option<ColourVec>
+{
+	let digits = hex.strip_prefix('#')?;
+	if !digits.chars().all(|c| c.is_ascii_hexdigit()) { return None; }
+	let values : Vec<f64> = match digits.len()
+	{
+		3 | 4 => digits.chars().map(|c| (c.to_digit(16).unwrap_or(0)*17) as f64/255.0).collect(),
+		6 | 8 => (0..digits.len()).step_by(2).map(|i| u8::from_str_radix(&digits[i..i+2], 16).unwrap_or(0) as f64/255.0).collect(),
+		_     => return None,
+	};
+	Some([values[0], values[1], values[2], values.get(3).copied().unwrap_or(1.0)])
+}
+//}}}
+//{{{
+fn named_rgba(name: &str) -> Option<ColourVec>
+{
+	let name = name.to_lowercase();
+	if name == "transparent" { return Some([0.0, 0.0, 0.0, 0.0]); }
+	NAMED_COLOURS.iter().find(|(known, _)| *known == name).map(|(_, rgb)|
+	{
+		[((rgb >> 16) & 0xff) as f64/255.0, ((rgb >> 8) & 0xff) as f64/255.0, (rgb & 0xff) as f64/255.0, 1.0]
+	})
+}
+//}}}
+
+// The CSS colour names, which OpenSCad's color() knows as well
+const NAMED_COLOURS : &[(&str, u32)] =
+&[
+	("aliceblue",            0xf0f8ff), ("antiquewhite",         0xfaebd7), ("aqua",                 0x00ffff),
+	("aquamarine",           0x7fffd4), ("azure",                0xf0ffff), ("beige",                0xf5f5dc),
+	("bisque",               0xffe4c4), ("black",                0x000000), ("blanchedalmond",       0xffebcd),
+	("blue",                 0x0000ff), ("blueviolet",           0x8a2be2), ("brown",                0xa52a2a),
+	("burlywood",            0xdeb887), ("cadetblue",            0x5f9ea0), ("chartreuse",           0x7fff00),
+	("chocolate",            0xd2691e), ("coral",                0xff7f50), ("cornflowerblue",       0x6495ed),
+	("cornsilk",             0xfff8dc), ("crimson",              0xdc143c), ("cyan",                 0x00ffff),
+	("darkblue",             0x00008b), ("darkcyan",             0x008b8b), ("darkgoldenrod",        0xb8860b),
+	("darkgray",             0xa9a9a9), ("darkgreen",            0x006400), ("darkgrey",             0xa9a9a9),
+	("darkkhaki",            0xbdb76b), ("darkmagenta",          0x8b008b), ("darkolivegreen",       0x556b2f),
+	("darkorange",           0xff8c00), ("darkorchid",           0x9932cc), ("darkred",              0x8b0000),
+	("darksalmon",           0xe9967a), ("darkseagreen",         0x8fbc8f), ("darkslateblue",        0x483d8b),
+	("darkslategray",        0x2f4f4f), ("darkslategrey",        0x2f4f4f), ("darkturquoise",        0x00ced1),
+	("darkviolet",           0x9400d3), ("deeppink",             0xff1493), ("deepskyblue",          0x00bfff),
+	("dimgray",              0x696969), ("dimgrey",              0x696969), ("dodgerblue",           0x1e90ff),
+	("firebrick",            0xb22222), ("floralwhite",          0xfffaf0), ("forestgreen",          0x228b22),
+	("fuchsia",              0xff00ff), ("gainsboro",            0xdcdcdc), ("ghostwhite",           0xf8f8ff),
+	("gold",                 0xffd700), ("goldenrod",            0xdaa520), ("gray",                 0x808080),
+	("green",                0x008000), ("greenyellow",          0xadff2f), ("grey",                 0x808080),
+	("honeydew",             0xf0fff0), ("hotpink",              0xff69b4), ("indianred",            0xcd5c5c),
+	("indigo",               0x4b0082), ("ivory",                0xfffff0), ("khaki",                0xf0e68c),
+	("lavender",             0xe6e6fa), ("lavenderblush",        0xfff0f5), ("lawngreen",            0x7cfc00),
+	("lemonchiffon",         0xfffacd), ("lightblue",            0xadd8e6), ("lightcoral",           0xf08080),
+	("lightcyan",            0xe0ffff), ("lightgoldenrodyellow", 0xfafad2), ("lightgray",            0xd3d3d3),
+	("lightgreen",           0x90ee90), ("lightgrey",            0xd3d3d3), ("lightpink",            0xffb6c1),
+	("lightsalmon",          0xffa07a), ("lightseagreen",        0x20b2aa), ("lightskyblue",         0x87cefa),
+	("lightslategray",       0x778899), ("lightslategrey",       0x778899), ("lightsteelblue",       0xb0c4de),
+	("lightyellow",          0xffffe0), ("lime",                 0x00ff00), ("limegreen",            0x32cd32),
+	("linen",                0xfaf0e6), ("magenta",              0xff00ff), ("maroon",               0x800000),
+	("mediumaquamarine",     0x66cdaa), ("mediumblue",           0x0000cd), ("mediumorchid",         0xba55d3),
+	("mediumpurple",         0x9370db), ("mediumseagreen",       0x3cb371), ("mediumslateblue",      0x7b68ee),
+	("mediumspringgreen",    0x00fa9a), ("mediumturquoise",      0x48d1cc), ("mediumvioletred",      0xc71585),
+	("midnightblue",         0x191970), ("mintcream",            0xf5fffa), ("mistyrose",            0xffe4e1),
+	("moccasin",             0xffe4b5), ("navajowhite",          0xffdead), ("navy",                 0x000080),
+	("oldlace",              0xfdf5e6), ("olive",                0x808000), ("olivedrab",            0x6b8e23),
+	("orange",               0xffa500), ("orangered",            0xff4500), ("orchid",               0xda70d6),
+	("palegoldenrod",        0xeee8aa), ("palegreen",            0x98fb98), ("paleturquoise",        0xafeeee),
+	("palevioletred",        0xdb7093), ("papayawhip",           0xffefd5), ("peachpuff",            0xffdab9),
+	("peru",                 0xcd853f), ("pink",                 0xffc0cb), ("plum",                 0xdda0dd),
+	("powderblue",           0xb0e0e6), ("purple",               0x800080), ("rebeccapurple",        0x663399),
+	("red",                  0xff0000), ("rosybrown",            0xbc8f8f), ("royalblue",            0x4169e1),
+	("saddlebrown",          0x8b4513), ("salmon",               0xfa8072), ("sandybrown",           0xf4a460),
+	("seagreen",             0x2e8b57), ("seashell",             0xfff5ee), ("sienna",               0xa0522d),
+	("silver",               0xc0c0c0), ("skyblue",              0x87ceeb), ("slateblue",            0x6a5acd),
+	("slategray",            0x708090), ("slategrey",            0x708090), ("snow",                 0xfffafa),
+	("springgreen",          0x00ff7f), ("steelblue",            0x4682b4), ("tan",                  0xd2b48c),
+	("teal",                 0x008080), ("thistle",              0xd8bfd8), ("tomato",               0xff6347),
+	("turquoise",            0x40e0d0), ("violet",               0xee82ee), ("wheat",                0xf5deb3),
+	("white",                0xffffff), ("whitesmoke",           0xf5f5f5), ("yellow",               0xffff00),
+	("yellowgreen",          0x9acd32),
+];
+//}}}
+
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::object_3d::{cube, union};
+
+	//{{{
+	#[test]
+	fn constructors()
+	{
+		assert_eq!(colour_hex("#ff8800"), colour_rgb(1.0, 136.0/255.0, 0.0));
+		assert_eq!(colour_hex("#f80"), colour_hex("#ff8800"));
+		assert_eq!(colour_hex("#ff880080").rgba().map(|c| (c[3]*255.0).round()), Some(128.0));
+		assert_eq!(colour_hex("ff8800"), Colour::Unset);
+		assert_eq!(colour_hex("#ff88"), colour_hex("#ffff8888"));
+		assert_eq!(colour_hsv(0.0, 1.0, 1.0), colour_rgb(1.0, 0.0, 0.0));
+		assert_eq!(colour_hsv(240.0, 1.0, 0.5), colour_rgb(0.0, 0.0, 0.5));
+		assert_eq!(colour_hsv(-60.0, 1.0, 1.0), colour_rgb(1.0, 0.0, 1.0));
+		assert_eq!(colour_named("Red").with_alpha(0.5), colour_rgba(1.0, 0.0, 0.0, 0.5));
+		assert_eq!(colour_none().with_alpha(0.5), colour_alpha(0.5));
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn names_and_text()
+	{
+		assert!(is_colour_name("rebeccapurple") && is_colour_name("DarkSlateGrey") && is_colour_name("transparent"));
+		assert!(!is_colour_name("birch"));
+		assert_eq!("tomato".parse(), Ok(colour_named("tomato")));
+		assert_eq!("#123456".parse::<Colour>().map(|colour| colour.to_string()), Ok(String::from("#123456")));
+		assert!("birch".parse::<Colour>().is_err());
+		assert_eq!(try_colour_named("Tomato"), Ok(colour_named("Tomato")));
+		assert_eq!(try_colour_named("birch"), Err(String::from("unknown colour name birch")));
+		assert_eq!(colour_named("birch").rgba(), None);
+		assert_eq!(try_colour_hex("#f80"), Ok(colour_hex("#ff8800")));
+		assert_eq!(try_colour_hex("#ff8"), Ok(colour_rgb(1.0, 1.0, 136.0/255.0)));
+		assert_eq!(try_colour_hex("ff8800"), Err(String::from("invalid hex colour ff8800")));
+		assert_eq!(colour_rgba(1.0, 0.5, 0.0, 0.2).scad_call().as_deref(), Some("color([1, 0.5, 0, 0.2])"));
+		assert_eq!(colour_rgb(0.1, 0.2, 0.3).scad_call().as_deref(), Some("color([0.1, 0.2, 0.3])"));
+		assert_eq!(colour_alpha(0.25).scad_call().as_deref(), Some("color(alpha=0.25)"));
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn palette_and_modifiers()
+	{
+		let mut board = cube("board", 10.0, 10.0, 1.0);
+		board.set_material("birch");
+		let mut bolt = cube("bolt", 1.0, 1.0, 5.0);
+		bolt.set_material("steel");
+		bolt.set_colour(colour_named("gold"));
+		let mut assembly = union("assembly", [board, bolt]);
+
+		let mut palette = Palette::new();
+		palette.set("birch", colour_named("burlywood")).set("steel", colour_named("silver"));
+		palette.apply(&mut assembly);
+		let colours : Vec<String> = match &assembly.shape
+		{
+			Shape3D::Composite{children, ..} => children.iter().map(|child| child.colour.to_string()).collect(),
+			_                                => vec![],
+		};
+		assert_eq!(colours, ["burlywood", "gold"]);
+
+		assembly.set_background();
+		assert!(!assembly.to_string().contains("color("));
+	}
+	//}}}
+}
+//}}}
@@ -0,0 +1,495 @@
+// Boolean operations on polygon soups with BSP trees (after Evan Wallace's csg.js) and convex hulls.
+//
+// Polygons are convex, planar and counter-clockwise when seen from outside. The trees are built and walked with explicit
+// stacks instead of recursion, since the trees of finely tessellated parts (threads, spheres) are thousands of levels deep.
+
+use crate::math::{Point3D, point3D, vector3D, Vector3D};
+
+const EPSILON : f64 = 1e-5;
+
+//{{{ Vector helpers
+
+//{{{
+pub(crate) fn sub(a: &Point3D, b: &Point3D) -> Vector3D
+{
+	vector3D(a[0]-b[0], a[1]-b[1], a[2]-b[2])
+}
+//}}}
+//{{{
+pub(crate) fn dot(a: &Vector3D, b: &Vector3D) -> f64
+{
+	a[0]*b[0] + a[1]*b[1] + a[2]*b[2]
+}
+//}}}
+//{{{
+fn lerp(a: &Point3D, b: &Point3D, t: f64) -> Point3D
+{
+	point3D(a[0] + (b[0]-a[0])*t, a[1] + (b[1]-a[1])*t, a[2] + (b[2]-a[2])*t)
+}
+//}}}
+//}}}
+
+//{{{ pub(crate) struct Plane
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Plane
+{
+	pub normal : Vector3D,
+	pub w      : f64,
+}
+
+//{{{
+impl Plane
+{
+	//{{{
+	// Plane through the points, using Newell's method so that it also works for polygons with collinear first points.
+	// None for degenerate polygons.
+	pub fn from_points(points: &[Point3D]) -> Option<Plane>
+	{
+		let mut n = [0.0; 3];
+		for i in 0..points.len()
+		{
+			let (a, b) = (points[i], points[(i+1)%points.len()]);
+			n[0] += (a[1]-b[1])*(a[2]+b[2]);
+			n[1] += (a[2]-b[2])*(a[0]+b[0]);
+			n[2] += (a[0]-b[0])*(a[1]+b[1]);
+		}
+		let length = (n[0]*n[0] + n[1]*n[1] + n[2]*n[2]).sqrt();
+		if length < 1e-12 { return None; }
+
+		let normal = vector3D(n[0]/length, n[1]/length, n[2]/length);
+		let w      = points.iter().map(|p| dot(&normal, &vector3D(p[0], p[1], p[2]))).sum::<f64>()/(points.len() as f64);
+		Some(Plane{ normal, w })
+	}
+	//}}}
+	//{{{
+	pub fn distance(&self, point: &Point3D) -> f64
+	{
+		dot(&self.normal, &vector3D(point[0], point[1], point[2])) - self.w
+	}
+	//}}}
+	//{{{
+	fn flip(&mut self)
+	{
+		self.normal = vector3D(-self.normal[0], -self.normal[1], -self.normal[2]);
+		self.w      = -self.w;
+	}
+	//}}}
+	//{{{
+	// Sorts the polygon into the lists, splitting it if it spans the plane
+	fn split(&self, polygon: Polygon, coplanar_front: &mut Vec<Polygon>, coplanar_back: &mut Vec<Polygon>, front: &mut Vec<Polygon>, back: &mut Vec<Polygon>)
+	{
+		const COPLANAR : u8 = 0;
+		const FRONT    : u8 = 1;
+		const BACK     : u8 = 2;
+		const SPANNING : u8 = 3;
+
+		let types : Vec<u8> = polygon.vertices.iter().map(|v|
+		{
+			let t = self.distance(v);
+			if t < -EPSILON { BACK } else if t > EPSILON { FRONT } else { COPLANAR }
+		}).collect();
+		let polygon_type = types.iter().fold(COPLANAR, |a, b| a | b);
+
+		match polygon_type
+		{
+			COPLANAR => if dot(&self.normal, &polygon.plane.normal) > 0.0 { coplanar_front.push(polygon) } else { coplanar_back.push(polygon) },
+			FRONT    => front.push(polygon),
+			BACK     => back.push(polygon),
+			_        =>
+			{
+				let (mut f, mut b) = (vec![], vec![]);
+				let n = polygon.vertices.len();
+				for i in 0..n
+				{
+					let j        = (i+1)%n;
+					let (ti, tj) = (types[i], types[j]);
+					let (vi, vj) = (polygon.vertices[i], polygon.vertices[j]);
+					if ti != BACK  { f.push(vi); }
+					if ti != FRONT { b.push(vi); }
+					if (ti | tj) == SPANNING
+					{
+						let t = (self.w - dot(&self.normal, &vector3D(vi[0], vi[1], vi[2])))/dot(&self.normal, &sub(&vj, &vi));
+						let v = lerp(&vi, &vj, t);
+						f.push(v);
+						b.push(v);
+					}
+				}
+				if f.len() >= 3 { front.push(Polygon{ vertices: f, plane: polygon.plane }); }
+				if b.len() >= 3 { back.push(Polygon{ vertices: b, plane: polygon.plane }); }
+			}
+		}
+	}
+	//}}}
+}
+//}}}
+//}}}
+
+//{{{ pub(crate) struct Polygon
+
+#[derive(Debug, Clone)]
+pub(crate) struct Polygon
+{
+	pub vertices : Vec<Point3D>,
+	pub plane    : Plane,
+}
+
+//{{{
+impl Polygon
+{
+	//{{{
+	// None for degenerate polygons, which are simply dropped
+	pub fn new(vertices: Vec<Point3D>) -> Option<Polygon>
+	{
+		if vertices.len() < 3 { return None; }
+		Plane::from_points(&vertices).map(|plane| Polygon{ vertices, plane })
+	}
+	//}}}
+	//{{{
+	fn flip(&mut self)
+	{
+		self.vertices.reverse();
+		self.plane.flip();
+	}
+	//}}}
+}
+//}}}
+//}}}
+
+//{{{ struct Node
+
+#[derive(Default)]
+struct Node
+{
+	plane    : Option<Plane>,
+	front    : Option<Box<Node>>,
+	back     : Option<Box<Node>>,
+	polygons : Vec<Polygon>,
+}
+
+//{{{
+impl Node
+{
+	//{{{
+	fn new(polygons: Vec<Polygon>) -> Node
+	{
+		let mut node = Node::default();
+		node.build(polygons);
+		node
+	}
+	//}}}
+	//{{{
+	fn build(&mut self, polygons: Vec<Polygon>)
+	{
+		let mut work : Vec<(&mut Node, Vec<Polygon>)> = vec![(self, polygons)];
+		while let Some((node, polygons)) = work.pop()
+		{
+			if polygons.is_empty() { continue; }
+			let Node{ plane, front, back, polygons: own } = node;
+			let plane = *plane.get_or_insert(polygons[0].plane);
+
+			let (mut front_polygons, mut back_polygons) = (vec![], vec![]);
+			let mut coplanar_back = vec![];
+			for polygon in polygons
+			{
+				plane.split(polygon, own, &mut coplanar_back, &mut front_polygons, &mut back_polygons);
+			}
+			own.append(&mut coplanar_back);
+
+			if !front_polygons.is_empty() { work.push((front.get_or_insert_with(Box::default), front_polygons)); }
+			if !back_polygons.is_empty()  { work.push((back.get_or_insert_with(Box::default),  back_polygons)); }
+		}
+	}
+	//}}}
+	//{{{
+	// Calls f on every node of the tree, before walking into its children
+	fn visit_mut(&mut self, mut f: impl FnMut(&mut Node))
+	{
+		let mut stack = vec![self];
+		while let Some(node) = stack.pop()
+		{
+			f(&mut *node);
+			let Node{ front, back, .. } = node;
+			if let Some(front) = front { stack.push(front); }
+			if let Some(back)  = back  { stack.push(back); }
+		}
+	}
+	//}}}
+	//{{{
+	// Turns solid into empty space and vice versa
+	fn invert(&mut self)
+	{
+		self.visit_mut(|node|
+		{
+			for polygon in &mut node.polygons { polygon.flip(); }
+			if let Some(plane) = &mut node.plane { plane.flip(); }
+			std::mem::swap(&mut node.front, &mut node.back);
+		});
+	}
+	//}}}
+	//{{{
+	// Removes the parts of the polygons inside this tree
+	fn clip_polygons(&self, polygons: Vec<Polygon>) -> Vec<Polygon>
+	{
+		let mut result = vec![];
+		let mut work   = vec![(self, polygons)];
+		while let Some((node, polygons)) = work.pop()
+		{
+			let plane = match node.plane
+			{
+				Some(plane) => plane,
+				None        => { result.extend(polygons); continue; }
+			};
+
+			let (mut front, mut back) = (vec![], vec![]);
+			let mut coplanar_front = vec![];
+			let mut coplanar_back  = vec![];
+			for polygon in polygons
+			{
+				plane.split(polygon, &mut coplanar_front, &mut coplanar_back, &mut front, &mut back);
+			}
+			front.append(&mut coplanar_front);
+			back.append(&mut coplanar_back);
+
+			match &node.front
+			{
+				Some(child) => work.push((child, front)),
+				None        => result.extend(front),
+			}
+			if let Some(child) = &node.back { work.push((child, back)); }
+		}
+		result
+	}
+	//}}}
+	//{{{
+	// Removes the parts of this tree's polygons inside the other tree
+	fn clip_to(&mut self, other: &Node)
+	{
+		self.visit_mut(|node|
+		{
+			let polygons  = std::mem::take(&mut node.polygons);
+			node.polygons = other.clip_polygons(polygons);
+		});
+	}
+	//}}}
+	//{{{
+	fn all_polygons(&self) -> Vec<Polygon>
+	{
+		let mut result = vec![];
+		let mut stack  = vec![self];
+		while let Some(node) = stack.pop()
+		{
+			result.extend(node.polygons.iter().cloned());
+			if let Some(front) = &node.front { stack.push(front); }
+			if let Some(back)  = &node.back  { stack.push(back); }
+		}
+		result
+	}
+	//}}}
+}
+//}}}
+//{{{
+// Dropping a deep tree recursively would overflow the stack
+impl Drop for Node
+{
+	fn drop(&mut self)
+	{
+		let mut stack : Vec<Box<Node>> = vec![];
+		stack.extend(self.front.take());
+		stack.extend(self.back.take());
+		while let Some(mut node) = stack.pop()
+		{
+			stack.extend(node.front.take());
+			stack.extend(node.back.take());
+		}
+	}
+}
+//}}}
+//}}}
+
+//{{{ Boolean operations
+
+//{{{
+pub(crate) fn union(a: Vec<Polygon>, b: Vec<Polygon>) -> Vec<Polygon>
+{
+	if a.is_empty() { return b; }
+	if b.is_empty() { return a; }
+	let (mut a, mut b) = (Node::new(a), Node::new(b));
+	a.clip_to(&b);
+	b.clip_to(&a);
+	b.invert();
+	b.clip_to(&a);
+	b.invert();
+	a.build(b.all_polygons());
+	a.all_polygons()
+}
+//}}}
+//{{{
+pub(crate) fn difference(a: Vec<Polygon>, b: Vec<Polygon>) -> Vec<Polygon>
+{
+	if a.is_empty() || b.is_empty() { return a; }
+	let (mut a, mut b) = (Node::new(a), Node::new(b));
+	a.invert();
+	a.clip_to(&b);
+	b.clip_to(&a);
+	b.invert();
+	b.clip_to(&a);
+	b.invert();
+	a.build(b.all_polygons());
+	a.invert();
+	a.all_polygons()
+}
+//}}}
+//{{{
+pub(crate) fn intersection(a: Vec<Polygon>, b: Vec<Polygon>) -> Vec<Polygon>
+{
+	if a.is_empty() || b.is_empty() { return vec![]; }
+	let (mut a, mut b) = (Node::new(a), Node::new(b));
+	a.invert();
+	b.clip_to(&a);
+	b.invert();
+	a.clip_to(&b);
+	b.clip_to(&a);
+	a.build(b.all_polygons());
+	a.invert();
+	a.all_polygons()
+}
+//}}}
+//}}}
+
+//{{{
+// Convex hull of the points as triangles, incrementally. Empty if the points are (nearly) coplanar.
+pub(crate) fn hull(points: &[Point3D]) -> Vec<Polygon>
+{
+	let n = points.len();
+	if n < 4 { return vec![]; }
+
+	//{{{ Initial tetrahedron
+
+	let extent = points.iter().fold(0.0_f64, |m, p| m.max(p[0].abs()).max(p[1].abs()).max(p[2].abs()));
+	let eps    = 1e-9*extent.max(1.0);
+
+	let i0 = 0;
+	let i1 = match (1..n).max_by(|&a, &b| sub(&points[a], &points[i0]).l2_norm().total_cmp(&sub(&points[b], &points[i0]).l2_norm())) { Some(i) => i, None => return vec![] };
+	let line = sub(&points[i1], &points[i0]);
+	let i2 = match (0..n).max_by(|&a, &b| line.cross(&sub(&points[a], &points[i0])).l2_norm().total_cmp(&line.cross(&sub(&points[b], &points[i0])).l2_norm())) { Some(i) => i, None => return vec![] };
+	let normal = line.cross(&sub(&points[i2], &points[i0]));
+	if normal.l2_norm() < eps { return vec![]; }
+	let i3 = match (0..n).max_by(|&a, &b| dot(&normal, &sub(&points[a], &points[i0])).abs().total_cmp(&dot(&normal, &sub(&points[b], &points[i0])).abs())) { Some(i) => i, None => return vec![] };
+	if dot(&normal, &sub(&points[i3], &points[i0])).abs() < eps*normal.l2_norm() { return vec![]; }
+	//}}}
+
+	// Faces as vertex indices, counter-clockwise from outside
+	let outside = |face: &[usize; 3], p: &Point3D| -> f64
+	{
+		let (a, b, c) = (points[face[0]], points[face[1]], points[face[2]]);
+		let normal = sub(&b, &a).cross(&sub(&c, &a));
+		dot(&normal, &sub(p, &a))/normal.l2_norm().max(1e-300)
+	};
+
+	let mut faces = vec![[i0, i1, i2], [i0, i2, i3], [i0, i3, i1], [i1, i3, i2]];
+	if outside(&faces[0], &points[i3]) > 0.0
+	{
+		for face in &mut faces { face.swap(1, 2); }
+	}
+
+	for (i, p) in points.iter().enumerate()
+	{
+		if i == i0 || i == i1 || i == i2 || i == i3 { continue; }
+		let visible : Vec<bool> = faces.iter().map(|face| outside(face, p) > eps).collect();
+		if !visible.contains(&true) { continue; }
+
+		// Horizon: edges of visible faces whose neighbour is not visible
+		let visible_edges : std::collections::HashSet<(usize, usize)> = faces.iter().zip(&visible).filter(|(_, &v)| v)
+			.flat_map(|(f, _)| vec![(f[0], f[1]), (f[1], f[2]), (f[2], f[0])]).collect();
+		let horizon : Vec<(usize, usize)> = visible_edges.iter().filter(|(a, b)| !visible_edges.contains(&(*b, *a))).cloned().collect();
+
+		faces = faces.into_iter().zip(visible).filter(|(_, v)| !v).map(|(f, _)| f).collect();
+		faces.extend(horizon.into_iter().map(|(a, b)| [a, b, i]));
+	}
+
+	faces.iter().filter_map(|f| Polygon::new(vec![points[f[0]], points[f[1]], points[f[2]]])).collect()
+}
+//}}}
+
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	//{{{
+	fn square(z: f64) -> Polygon
+	{
+		Polygon::new(vec![point3D(0.0, 0.0, z), point3D(2.0, 0.0, z), point3D(2.0, 2.0, z), point3D(0.0, 2.0, z)]).expect("square")
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn planes()
+	{
+		let plane = square(3.0).plane;
+		assert!((plane.normal[2] - 1.0).abs() < 1e-12 && (plane.w - 3.0).abs() < 1e-12);
+		assert!((plane.distance(&point3D(5.0, -7.0, 1.0)) + 2.0).abs() < 1e-12);
+
+		// Newell's method does not depend on the first three points
+		let plane = Plane::from_points(&[point3D(0.0, 0.0, 0.0), point3D(1.0, 0.0, 0.0), point3D(2.0, 0.0, 0.0), point3D(2.0, 0.0, 1.0)]).expect("not degenerate");
+		assert!((plane.normal[1] + 1.0).abs() < 1e-12);
+
+		assert!(Polygon::new(vec![point3D(0.0, 0.0, 0.0), point3D(1.0, 1.0, 1.0), point3D(2.0, 2.0, 2.0)]).is_none());
+		assert!(Polygon::new(vec![point3D(0.0, 0.0, 0.0), point3D(1.0, 1.0, 1.0)]).is_none());
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn splitting()
+	{
+		let plane = Plane{ normal: vector3D(1.0, 0.0, 0.0), w: 0.5 };
+		let split = |polygon: Polygon|
+		{
+			let (mut coplanar_front, mut coplanar_back, mut front, mut back) = (vec![], vec![], vec![], vec![]);
+			plane.split(polygon, &mut coplanar_front, &mut coplanar_back, &mut front, &mut back);
+			(coplanar_front, coplanar_back, front, back)
+		};
+
+		// Spanning: both halves keep the polygon's plane and meet at x = 0.5
+		let (coplanar_front, coplanar_back, front, back) = split(square(0.0));
+		assert!(coplanar_front.is_empty() && coplanar_back.is_empty());
+		assert_eq!((front.len(), back.len()), (1, 1));
+		assert!(front[0].vertices.iter().all(|v| v[0] >= 0.5-EPSILON) && back[0].vertices.iter().all(|v| v[0] <= 0.5+EPSILON));
+		assert_eq!((front[0].vertices.len(), back[0].vertices.len()), (4, 4));
+		assert!((front[0].plane.normal[2] - 1.0).abs() < 1e-12);
+
+		// Touching the plane with an edge only is not spanning
+		let touching = Polygon::new(vec![point3D(0.5, 0.0, 0.0), point3D(2.0, 0.0, 0.0), point3D(2.0, 2.0, 0.0), point3D(0.5, 2.0, 0.0)]).expect("square");
+		let (_, _, front, back) = split(touching);
+		assert_eq!((front.len(), back.len()), (1, 0));
+
+		// Coplanar polygons are sorted by their orientation
+		let mut facing = Polygon::new(vec![point3D(0.5, 0.0, 0.0), point3D(0.5, 1.0, 0.0), point3D(0.5, 1.0, 1.0)]).expect("triangle");
+		let (coplanar_front, coplanar_back, ..) = split(facing.clone());
+		assert_eq!((coplanar_front.len(), coplanar_back.len()), (1, 0));
+		facing.flip();
+		let (coplanar_front, coplanar_back, ..) = split(facing);
+		assert_eq!((coplanar_front.len(), coplanar_back.len()), (0, 1));
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn hulls()
+	{
+		let mut points = vec![];
+		for i in 0..8 { points.push(point3D((i & 1) as f64, ((i >> 1) & 1) as f64, (i >> 2) as f64)); }
+		points.push(point3D(0.5, 0.5, 0.5));  // Inside
+		let faces = hull(&points);
+		assert_eq!(faces.len(), 12);
+		assert!(faces.iter().all(|face| face.vertices.iter().all(|v| v[0] != 0.5)));
+		// Facing outwards
+		assert!(faces.iter().all(|face| face.plane.distance(&point3D(0.5, 0.5, 0.5)) < 0.0));
+
+		assert!(hull(&points[..4]).is_empty());  // Coplanar
+	}
+	//}}}
+}
+//}}}
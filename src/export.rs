@@ -0,0 +1,604 @@
+// Exports with colours, on top of the native tessellation (see mesh.rs): Wavefront OBJ with an MTL material library,
+// glTF 2.0 as a single .gltf file with the buffer embedded, and 3MF with one build item per part.
+//
+//     let meshes = export::coloured_meshes(&objects, &Resolution::default())?;
+//     export::write_obj(&meshes, "bed.mtl", &mut obj, &mut mtl)?;
+//     export::write_gltf(&meshes, &mut gltf)?;
+//     export::write_3mf("bed", &export::build_items(&objects, &Resolution::default())?, LengthUnit::Millimetre, &mut file)?;
+//
+// For OBJ and glTF every top level object becomes one mesh. Unions of differently coloured objects are split into their children, so
+// that multi-material parts keep their colours. Objects without a colour get the default material of the format.
+
+use std::io::{self, Write};
+
+use crate::colour::Colour;
+use crate::math::{HasRefSys3D, Matrix3D};
+use crate::mesh::{self, Mesh, TessellationError};
+use crate::object_3d::{BooleanOp, Object3D, Shape3D};
+use crate::resolution::Resolution;
+use crate::units::LengthUnit;
+use crate::zip::ZipArchive;
+
+//{{{ pub struct ColouredMesh
+
+#[derive(Debug, Clone)]
+pub struct ColouredMesh
+{
+	pub name   : String,
+	pub mesh   : Mesh,               // In world coordinates
+	pub colour : Option<[f64; 4]>,   // RGBA from 0 to 1, None for the default colour
+}
+//}}}
+
+//{{{ Tessellation
+
+//{{{
+// The meshes of the objects with their colours. 'resolution' is the one around the objects, as for Mesh::from_object_with().
+pub fn coloured_meshes(objects: &[Object3D], resolution: &Resolution) -> Result<Vec<ColouredMesh>, TessellationError>
+{
+	let mut meshes = vec![];
+	for object in objects
+	{
+		collect(object, &Matrix3D::identity(), None, resolution, &mut meshes)?;
+	}
+	Ok(meshes)
+}
+//}}}
+//{{{
+// 'parent' places the parent's coordinate system in the world, 'inherited' is the parent's colour
+fn collect(object: &Object3D, parent: &Matrix3D, inherited: Option<[f64; 4]>, resolution: &Resolution, meshes: &mut Vec<ColouredMesh>) -> Result<(), TessellationError>
+{
+	if mesh::skipped(object) { return Ok(()); }
+	let placement = object.ref_sys * parent;
+	let colour    = effective_colour(&object.colour, inherited);
+
+	if let Shape3D::Composite{op: BooleanOp::union, children} = &object.shape
+	{
+		let mut colours = vec![];
+		for child in children { leaf_colours(child, colour, &mut colours); }
+		if colours.len() > 1
+		{
+			let resolution = object.resolution.within(resolution);
+			for child in children { collect(child, &placement, colour, &resolution, meshes)?; }
+			return Ok(());
+		}
+	}
+
+	let mut placed = object.clone();
+	placed.set_ref_sys(placement);
+	let mesh = Mesh::from_object_with(&placed, resolution)?;
+	if !mesh.triangles.is_empty()
+	{
+		meshes.push(ColouredMesh { name: object.name.clone(), mesh, colour });
+	}
+	Ok(())
+}
+//}}}
+//{{{
+// The distinct colours the primitives of the tree end up with
+fn leaf_colours(object: &Object3D, inherited: Option<[f64; 4]>, colours: &mut Vec<Option<[f64; 4]>>)
+{
+	if mesh::skipped(object) { return; }
+	let colour = effective_colour(&object.colour, inherited);
+	match &object.shape
+	{
+		Shape3D::Composite{children, ..} => for child in children { leaf_colours(child, colour, colours); },
+		Shape3D::Lextrude{child, ..}     => leaf_colours(child, colour, colours),
+		_                                => if !colours.contains(&colour) { colours.push(colour); },
+	}
+}
+//}}}
+//{{{
+fn effective_colour(colour: &Colour, inherited: Option<[f64; 4]>) -> Option<[f64; 4]>
+{
+	match colour
+	{
+		Colour::Alpha(a) => inherited.map(|[r, g, b, _]| [r, g, b, *a]),
+		_                => colour.rgba().or(inherited),
+	}
+}
+//}}}
+//}}}
+
+//{{{ OBJ
+
+//{{{
+// Wavefront OBJ in the model's coordinates, with the materials in 'mtl' (referred to as 'mtl_name')
+pub fn write_obj(meshes: &[ColouredMesh], mtl_name: &str, obj: &mut dyn Write, mtl: &mut dyn Write) -> io::Result<()>
+{
+	writeln!(obj, "# rusty_scad")?;
+	writeln!(obj, "mtllib {}", mtl_name)?;
+
+	let mut materials : Vec<Option<[f64; 4]>> = vec![];
+	let mut offset = 1;
+	for coloured in meshes
+	{
+		writeln!(obj, "o {}", coloured.name.replace(char::is_whitespace, "_"))?;
+		writeln!(obj, "usemtl {}", material_name(coloured.colour))?;
+		if !materials.contains(&coloured.colour) { materials.push(coloured.colour); }
+
+		for v in &coloured.mesh.vertices
+		{
+			writeln!(obj, "v {} {} {}", v[0], v[1], v[2])?;
+		}
+		for t in &coloured.mesh.triangles
+		{
+			writeln!(obj, "f {} {} {}", t[0]+offset, t[1]+offset, t[2]+offset)?;
+		}
+		offset += coloured.mesh.vertices.len();
+	}
+
+	writeln!(mtl, "# rusty_scad")?;
+	for colour in materials
+	{
+		let [r, g, b, a] = colour.unwrap_or(DEFAULT_COLOUR);
+		writeln!(mtl, "newmtl {}", material_name(colour))?;
+		writeln!(mtl, "Kd {} {} {}", r, g, b)?;
+		writeln!(mtl, "d {}", a)?;
+	}
+	Ok(())
+}
+//}}}
+//}}}
+
+//{{{ glTF
+
+//{{{
+// glTF 2.0 with an embedded buffer. glTF is in metres with the y-axis up, so the model (millimetres, z up) is scaled and
+// turned accordingly.
+pub fn write_gltf(meshes: &[ColouredMesh], out: &mut dyn Write) -> io::Result<()>
+{
+	let mut buffer      : Vec<u8>       = vec![];
+	let mut views       : Vec<String>   = vec![];
+	let mut accessors   : Vec<String>   = vec![];
+	let mut gltf_meshes : Vec<String>   = vec![];
+	let mut nodes       : Vec<String>   = vec![];
+	let mut materials   : Vec<[f64; 4]> = vec![];
+
+	for coloured in meshes
+	{
+		let positions : Vec<[f32; 3]> = coloured.mesh.vertices.iter().map(|v| [(0.001*v[0]) as f32, (0.001*v[2]) as f32, (-0.001*v[1]) as f32]).collect();
+		let mut min = positions.first().copied().unwrap_or_default();
+		let mut max = min;
+		for p in &positions
+		{
+			for i in 0..3 { min[i] = min[i].min(p[i]); max[i] = max[i].max(p[i]); }
+		}
+
+		let offset = buffer.len();
+		for p in &positions { for x in p { buffer.extend_from_slice(&x.to_le_bytes()); } }
+		views.push(format!("{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{},\"target\":34962}}", offset, buffer.len()-offset));
+		accessors.push(format!("{{\"bufferView\":{},\"componentType\":5126,\"count\":{},\"type\":\"VEC3\",\"min\":[{},{},{}],\"max\":[{},{},{}]}}",
+			views.len()-1, positions.len(), min[0], min[1], min[2], max[0], max[1], max[2]));
+
+		let offset = buffer.len();
+		for t in &coloured.mesh.triangles { for &i in t { buffer.extend_from_slice(&(i as u32).to_le_bytes()); } }
+		views.push(format!("{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{},\"target\":34963}}", offset, buffer.len()-offset));
+		accessors.push(format!("{{\"bufferView\":{},\"componentType\":5125,\"count\":{},\"type\":\"SCALAR\"}}", views.len()-1, 3*coloured.mesh.triangles.len()));
+
+		let material = match coloured.colour
+		{
+			Some(colour) =>
+			{
+				let index = materials.iter().position(|known| *known == colour).unwrap_or_else(|| { materials.push(colour); materials.len()-1 });
+				format!(",\"material\":{}", index)
+			}
+			None         => String::new(),
+		};
+		gltf_meshes.push(format!("{{\"name\":{},\"primitives\":[{{\"attributes\":{{\"POSITION\":{}}},\"indices\":{}{}}}]}}",
+			json_string(&coloured.name), accessors.len()-2, accessors.len()-1, material));
+		nodes.push(format!("{{\"name\":{},\"mesh\":{}}}", json_string(&coloured.name), gltf_meshes.len()-1));
+	}
+
+	let materials : Vec<String> = materials.iter().map(|&colour|
+	{
+		let [r, g, b, a] = colour;
+		let blend = if a < 1.0 { ",\"alphaMode\":\"BLEND\"" } else { "" };
+		format!("{{\"name\":\"{}\",\"pbrMetallicRoughness\":{{\"baseColorFactor\":[{},{},{},{}],\"metallicFactor\":0,\"roughnessFactor\":1}}{}}}",
+			material_name(Some(colour)), r, g, b, a, blend)
+	}).collect();
+
+	let mut entries = vec!
+	[
+		String::from("\"asset\":{\"version\":\"2.0\",\"generator\":\"rusty_scad\"}"),
+		String::from("\"scene\":0"),
+		format!("\"scenes\":[{{\"nodes\":[{}]}}]", (0..nodes.len()).map(|i| i.to_string()).collect::<Vec<_>>().join(",")),
+	];
+	if !nodes.is_empty()
+	{
+		entries.push(format!("\"nodes\":[{}]", nodes.join(",\n")));
+		entries.push(format!("\"meshes\":[{}]", gltf_meshes.join(",\n")));
+		entries.push(format!("\"accessors\":[{}]", accessors.join(",\n")));
+		entries.push(format!("\"bufferViews\":[{}]", views.join(",\n")));
+		entries.push(format!("\"buffers\":[{{\"byteLength\":{},\"uri\":\"data:application/octet-stream;base64,{}\"}}]", buffer.len(), base64(&buffer)));
+	}
+	if !materials.is_empty() { entries.push(format!("\"materials\":[{}]", materials.join(",\n"))); }
+	writeln!(out, "{{\n{}\n}}", entries.join(",\n"))
+}
+//}}}
+//}}}
+
+//{{{ 3MF
+
+//{{{ pub struct BuildItem
+
+// A part of a 3MF model: the mesh in the part's own coordinates and where the build places it
+#[derive(Debug, Clone)]
+pub struct BuildItem
+{
+	pub name        : String,
+	pub number      : Option<String>,
+	pub description : Option<String>,
+	pub material    : Option<String>,
+	pub mesh        : Mesh,
+	pub placement   : Matrix3D,           // Part coordinates to world coordinates
+	pub colour      : Option<[f64; 4]>,
+}
+//}}}
+
+//{{{
+// One build item per part (see Object3D::tag_as_part()); a top level object without parts is a single item. Parts inside
+// parts belong to the outer one, as in the bill of materials.
+pub fn build_items(objects: &[Object3D], resolution: &Resolution) -> Result<Vec<BuildItem>, TessellationError>
+{
+	let mut items = vec![];
+	for object in objects
+	{
+		let count = items.len();
+		collect_parts(object, &Matrix3D::identity(), None, resolution, &mut items)?;
+		if items.len() == count && !mesh::skipped(object)
+		{
+			items.push(build_item(object, object.ref_sys, effective_colour(&object.colour, None), resolution)?);
+		}
+	}
+	items.retain(|item| !item.mesh.triangles.is_empty());
+	Ok(items)
+}
+//}}}
+//{{{
+fn collect_parts(object: &Object3D, parent: &Matrix3D, inherited: Option<[f64; 4]>, resolution: &Resolution, items: &mut Vec<BuildItem>) -> Result<(), TessellationError>
+{
+	if mesh::skipped(object) { return Ok(()); }
+	let placement = object.ref_sys * parent;
+	let colour    = effective_colour(&object.colour, inherited);
+
+	if object.is_part()
+	{
+		items.push(build_item(object, placement, colour, resolution)?);
+	}
+	else if let Shape3D::Composite{children, ..} = &object.shape
+	{
+		let resolution = object.resolution.within(resolution);
+		for child in children { collect_parts(child, &placement, colour, &resolution, items)?; }
+	}
+	Ok(())
+}
+//}}}
+//{{{
+fn build_item(object: &Object3D, placement: Matrix3D, colour: Option<[f64; 4]>, resolution: &Resolution) -> Result<BuildItem, TessellationError>
+{
+	let mut local = object.clone();
+	local.set_ref_sys(Matrix3D::identity());
+	let mut mesh = Mesh::from_object_with(&local, resolution)?;
+
+	// 3MF doesn't allow mirroring build transforms, so mirrored parts get their placement baked into the mesh
+	let mut placement = placement;
+	if mesh::determinant(&placement) < 0.0
+	{
+		mesh.transform(&placement);
+		placement = Matrix3D::identity();
+	}
+
+	let part = object.part.clone().unwrap_or_default();
+	Ok(BuildItem
+	{
+		name        : object.name.clone(),
+		number      : part.number,
+		description : part.description,
+		material    : part.material,
+		mesh,
+		placement,
+		colour,
+	})
+}
+//}}}
+//{{{
+// A 3MF package (a zip archive) with one object and build item per item, in 'unit'. The colours become base materials,
+// named after the part's material where it has one.
+pub fn write_3mf(title: &str, items: &[BuildItem], unit: LengthUnit, out: &mut dyn Write) -> io::Result<()>
+{
+	let mut archive = ZipArchive::new();
+	archive.add("[Content_Types].xml", CONTENT_TYPES.as_bytes())?;
+	archive.add("_rels/.rels", RELATIONSHIPS.as_bytes())?;
+	archive.add("3D/3dmodel.model", model_3mf(title, items, unit).as_bytes())?;
+	archive.write(out)
+}
+//}}}
+//{{{
+fn model_3mf(title: &str, items: &[BuildItem], unit: LengthUnit) -> String
+{
+	let unit_name = match unit
+	{
+		LengthUnit::Millimetre => "millimeter",
+		LengthUnit::Centimetre => "centimeter",
+		LengthUnit::Metre      => "meter",
+		LengthUnit::Inch       => "inch",
+	};
+	let factor = unit.millimetres();
+
+	let mut materials : Vec<(String, [f64; 4])> = vec![];
+	let mut indices   : Vec<Option<usize>>      = vec![];
+	for item in items
+	{
+		indices.push(item.colour.map(|colour|
+		{
+			let name = item.material.clone().unwrap_or_else(|| material_name(Some(colour)));
+			materials.iter().position(|known| *known == (name.clone(), colour)).unwrap_or_else(|| { materials.push((name, colour)); materials.len()-1 })
+		}));
+	}
+
+	let mut model = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+	model.push_str(&format!("<model unit=\"{}\" xml:lang=\"en-US\" xmlns=\"http://schemas.microsoft.com/3dmanufacturing/core/2015/02\">\n", unit_name));
+	model.push_str(&format!(" <metadata name=\"Title\">{}</metadata>\n", xml_text(title)));
+	model.push_str(" <metadata name=\"Application\">rusty_scad</metadata>\n");
+	model.push_str(" <resources>\n");
+
+	// Object ids start at 2, 1 is the base materials
+	if !materials.is_empty()
+	{
+		model.push_str("  <basematerials id=\"1\">\n");
+		for (name, colour) in &materials
+		{
+			let [r, g, b, a] = colour.map(|x| (x.clamp(0.0, 1.0)*255.0).round() as u8);
+			model.push_str(&format!("   <base name=\"{}\" displaycolor=\"#{:02X}{:02X}{:02X}{:02X}\"/>\n", xml_text(name), r, g, b, a));
+		}
+		model.push_str("  </basematerials>\n");
+	}
+	for (i, item) in items.iter().enumerate()
+	{
+		let mut attributes = format!("id=\"{}\" type=\"model\" name=\"{}\"", i+2, xml_text(&item.name));
+		if let Some(number) = &item.number { attributes.push_str(&format!(" partnumber=\"{}\"", xml_text(number))); }
+		if let Some(index)  = indices[i]   { attributes.push_str(&format!(" pid=\"1\" pindex=\"{}\"", index)); }
+		model.push_str(&format!("  <object {}>\n", attributes));
+
+		model.push_str("   <metadatagroup>\n");
+		model.push_str(&format!("    <metadata name=\"Title\">{}</metadata>\n", xml_text(&item.name)));
+		if let Some(description) = &item.description
+		{
+			model.push_str(&format!("    <metadata name=\"Description\">{}</metadata>\n", xml_text(description)));
+		}
+		model.push_str("   </metadatagroup>\n");
+
+		model.push_str("   <mesh>\n    <vertices>\n");
+		for v in &item.mesh.vertices
+		{
+			model.push_str(&format!("     <vertex x=\"{}\" y=\"{}\" z=\"{}\"/>\n", v[0]/factor, v[1]/factor, v[2]/factor));
+		}
+		model.push_str("    </vertices>\n    <triangles>\n");
+		for t in &item.mesh.triangles
+		{
+			model.push_str(&format!("     <triangle v1=\"{}\" v2=\"{}\" v3=\"{}\"/>\n", t[0], t[1], t[2]));
+		}
+		model.push_str("    </triangles>\n   </mesh>\n  </object>\n");
+	}
+	model.push_str(" </resources>\n <build>\n");
+	for (i, item) in items.iter().enumerate()
+	{
+		// 3MF multiplies row vectors from the left, so the transform is the transposed rotation followed by the translation
+		let m = &item.placement;
+		let transform = [m[0][0], m[1][0], m[2][0], m[0][1], m[1][1], m[2][1], m[0][2], m[1][2], m[2][2], m[0][3]/factor, m[1][3]/factor, m[2][3]/factor];
+		let transform : Vec<String> = transform.iter().map(|x| x.to_string()).collect();
+		let number = item.number.as_ref().map_or(String::new(), |number| format!(" partnumber=\"{}\"", xml_text(number)));
+		model.push_str(&format!("  <item objectid=\"{}\" transform=\"{}\"{}/>\n", i+2, transform.join(" "), number));
+	}
+	model.push_str(" </build>\n</model>\n");
+	model
+}
+//}}}
+
+const CONTENT_TYPES : &str = "\
+<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">
+ <Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>
+ <Default Extension=\"model\" ContentType=\"application/vnd.ms-package.3dmanufacturing-3dmodel+xml\"/>
+</Types>
+";
+
+const RELATIONSHIPS : &str = "\
+<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">
+ <Relationship Target=\"/3D/3dmodel.model\" Id=\"rel0\" Type=\"http://schemas.microsoft.com/3dmanufacturing/2013/01/3dmodel\"/>
+</Relationships>
+";
+//}}}
+
+//{{{ Helpers
+
+// Light grey for the objects without a colour, where the format needs one
+const DEFAULT_COLOUR : [f64; 4] = [0.8, 0.8, 0.8, 1.0];
+
+//{{{
+// Materials are named after their colour, e.g. "colour_ff8800"
+fn material_name(colour: Option<[f64; 4]>) -> String
+{
+	match colour
+	{
+		Some(rgba) => format!("colour_{}", Colour::Numeric(rgba).to_string().trim_start_matches('#')),
+		None       => String::from("default"),
+	}
+}
+//}}}
+//{{{
+fn json_string(text: &str) -> String
+{
+	let mut json = String::from("\"");
+	for c in text.chars()
+	{
+		match c
+		{
+			'"'                    => json.push_str("\\\""),
+			'\\'                   => json.push_str("\\\\"),
+			c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+			c                      => json.push(c),
+		}
+	}
+	json.push('"');
+	json
+}
+//}}}
+//{{{
+fn xml_text(text: &str) -> String
+{
+	text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+//}}}
+//{{{
+fn base64(bytes: &[u8]) -> String
+{
+	const ALPHABET : &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+	let mut text = String::with_capacity(bytes.len().div_ceil(3)*4);
+	for chunk in bytes.chunks(3)
+	{
+		let n = (chunk[0] as u32) << 16 | (*chunk.get(1).unwrap_or(&0) as u32) << 8 | *chunk.get(2).unwrap_or(&0) as u32;
+		for i in 0..4
+		{
+			text.push(if i <= chunk.len() { ALPHABET[(n >> (18 - 6*i) & 63) as usize] as char } else { '=' });
+		}
+	}
+	text
+}
+//}}}
+//}}}
+
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::colour::{colour_named, colour_rgba};
+	use crate::math::Is3DObject;
+	use crate::object_3d::{cube, difference, union};
+
+	//{{{
+	#[test]
+	fn unions_split_by_colour()
+	{
+		let mut red  = cube("red", 1.0, 1.0, 1.0);
+		red.set_colour(colour_named("red"));
+		let mut blue = cube("blue", 1.0, 1.0, 1.0);
+		blue.set_colour(colour_rgba(0.0, 0.0, 1.0, 0.5));
+		blue.translate_x(5.0);
+		let mut both = union("both", [red.clone(), blue]);
+		both.translate_z(10.0);
+
+		let meshes = coloured_meshes(&[both], &Resolution::default()).expect("cubes tessellate");
+		let names : Vec<&str> = meshes.iter().map(|m| m.name.as_str()).collect();
+		assert_eq!(names, ["red", "blue"]);
+		assert_eq!(meshes[0].colour, Some([1.0, 0.0, 0.0, 1.0]));
+		let (min, _) = meshes[1].mesh.bounding_box().expect("not empty");
+		assert!((min[0] - 4.5).abs() < 1e-9 && (min[2] - 9.5).abs() < 1e-9);
+
+		// Differences and uniformly coloured unions stay whole
+		let cut = difference("cut", [red.clone(), cube("hole", 0.5, 0.5, 2.0)]);
+		let one = union("one", [red.clone(), red]);
+		let meshes = coloured_meshes(&[cut, one], &Resolution::default()).expect("cubes tessellate");
+		assert_eq!(meshes.len(), 2);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn obj_and_gltf()
+	{
+		let mut red = cube("red", 1.0, 1.0, 1.0);
+		red.set_colour(colour_named("red"));
+		let meshes = coloured_meshes(&[red, cube("plain", 1.0, 1.0, 1.0)], &Resolution::default()).expect("cubes tessellate");
+
+		let (mut obj, mut mtl) = (vec![], vec![]);
+		write_obj(&meshes, "test.mtl", &mut obj, &mut mtl).expect("writing to memory");
+		let (obj, mtl) = (String::from_utf8(obj).expect("text"), String::from_utf8(mtl).expect("text"));
+		assert!(obj.contains("mtllib test.mtl\no red\nusemtl colour_ff0000\n"));
+		assert!(obj.contains("usemtl default\n"));
+		assert!(mtl.contains("newmtl colour_ff0000\nKd 1 0 0\nd 1\n"));
+
+		let mut gltf = vec![];
+		write_gltf(&meshes, &mut gltf).expect("writing to memory");
+		let gltf = String::from_utf8(gltf).expect("text");
+		assert!(gltf.contains("\"baseColorFactor\":[1,0,0,1]"));
+		assert_eq!(gltf.matches("\"mesh\":").count(), 2);
+		assert_eq!(gltf.matches("\"material\":").count(), 1);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn three_mf()
+	{
+		let mut board = cube("board", 10.0, 10.0, 1.0);
+		board.tag_as_part();
+		board.set_part_number("B-1");
+		board.set_colour(colour_named("red"));
+		board.translate_x(20.0);
+		let mut left = board.clone();
+		left.name = String::from("left <a>");
+		left.mirror(crate::vector3D(1.0, 0.0, 0.0));
+		let helper = cube("helper", 1.0, 1.0, 1.0);
+		let mut frame = union("frame", [board, left, helper]);
+		frame.translate_z(5.0);
+
+		let items = build_items(&[frame, cube("loose", 1.0, 1.0, 1.0)], &Resolution::default()).expect("cubes tessellate");
+		let names : Vec<&str> = items.iter().map(|item| item.name.as_str()).collect();
+		assert_eq!(names, ["board", "left <a>", "loose"]);
+		assert_eq!(items[0].placement[0][3], 20.0);
+		assert_eq!(items[0].placement[2][3], 5.0);
+		let (min, _) = items[1].mesh.bounding_box().expect("not empty");  // Mirrored, so the placement is in the mesh
+		assert!((min[0] + 25.0).abs() < 1e-9 && (min[2] - 4.5).abs() < 1e-9);
+		assert_eq!(items[1].placement[0][0], 1.0);
+		assert_eq!(items[1].placement[2][3], 0.0);
+
+		let mut bytes = vec![];
+		write_3mf("frame", &items, LengthUnit::Millimetre, &mut bytes).expect("writing to memory");
+		assert_eq!(&bytes[0..4], b"PK\x03\x04");
+		assert_eq!(&bytes[bytes.len()-22..bytes.len()-18], b"PK\x05\x06");
+		assert_eq!(bytes[bytes.len()-12], 3);                    // Entries
+
+		// The entries are stored, so the model can be read straight out of the archive
+		let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i+1]]) as usize;
+		let u32_at = |i: usize| u32::from_le_bytes([bytes[i], bytes[i+1], bytes[i+2], bytes[i+3]]);
+		let mut header = 0;
+		while &bytes[header+30..header+30+u16_at(header+26)] != b"3D/3dmodel.model"
+		{
+			header += 30 + u16_at(header+26) + u32_at(header+18) as usize;
+		}
+		let start = header + 30 + u16_at(header+26);
+		let model = &bytes[start..start + u32_at(header+18) as usize];
+		let crc   = u32_at(header+14);
+		assert_eq!(crate::zip::crc32(model), crc);
+
+		let model = std::str::from_utf8(model).expect("text");
+		assert!(model.contains("<base name=\"colour_ff0000\" displaycolor=\"#FF0000FF\"/>"));
+		assert_eq!(model.matches("<base ").count(), 1);
+		assert!(model.contains("<object id=\"2\" type=\"model\" name=\"board\" partnumber=\"B-1\" pid=\"1\" pindex=\"0\">"));
+		assert!(model.contains("<metadata name=\"Title\">left &lt;a&gt;</metadata>"));
+		assert!(model.contains("<item objectid=\"2\" transform=\"1 0 0 0 1 0 0 0 1 20 0 5\" partnumber=\"B-1\"/>"));
+		assert!(model.contains("<object id=\"4\" type=\"model\" name=\"loose\">"));
+		assert_eq!(model.matches("<triangle ").count(), 3*12);
+
+		// In centimetres, the vertices and the translations are converted and the model says so
+		let model = model_3mf("frame", &items, LengthUnit::Centimetre);
+		assert!(model.contains("<model unit=\"centimeter\""));
+		assert!(model.contains("<item objectid=\"2\" transform=\"1 0 0 0 1 0 0 0 1 2 0 0.5\" partnumber=\"B-1\"/>"));
+		assert!(model.contains("<vertex x=\"-0.5\" y=\"-0.5\" z=\"-0.05\"/>"));
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn base64_padding()
+	{
+		assert_eq!(base64(b""), "");
+		assert_eq!(base64(b"f"), "Zg==");
+		assert_eq!(base64(b"fo"), "Zm8=");
+		assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+	}
+	//}}}
+}
+//}}}
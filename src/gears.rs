@@ -0,0 +1,420 @@
+// Spur gears, racks and pulleys. All dimensions are in mm, angles in degrees.
+//
+// Gears and pulleys turn around the z-axis and are centred on the xy-plane. Each has these anchors:
+// - "axis":  On the axis, its z-axis along the axis. Snap it onto a shaft.
+// - "pitch": On the pitch circle, in the middle of a tooth (or belt/rope), its x-axis pointing radially outwards.
+// - "mesh":  On the pitch circle, in the middle of a tooth gap, its x-axis pointing radially inwards.
+// Two gears mesh with gear.anchor("mesh").snap_to(&mut other_gear.anchor("pitch")), so a tooth of one sits in a gap of the other.
+// Racks run along x with their teeth towards +y and have the same anchors on their pitch line.
+
+use std::f64::consts::PI;
+
+use crate::math::{Is3DObject, Point2D, point2D};
+use crate::object_3d::{Object3D, circle, cylinder, difference, polygon_paths, union};
+
+const FN : i32 = 64;
+
+//{{{ Helpers
+
+//{{{
+fn polar(r: f64, phi: f64) -> Point2D
+{
+	point2D(r*phi.cos(), r*phi.sin())
+}
+//}}}
+//{{{
+fn involute(alpha: f64) -> f64
+{
+	alpha.tan() - alpha
+}
+//}}}
+//{{{
+// Points of a circle, clockwise so that OpenSCad treats it as a hole
+fn bore_path(r: f64, start: usize) -> (Vec<Point2D>, Vec<i32>)
+{
+	let n = FN as usize;
+	let points = (0..n).rev().map(|i| polar(r, 2.0*PI*(i as f64)/(n as f64))).collect();
+	(points, (start..start+n).map(|i| i as i32).collect())
+}
+//}}}
+//{{{
+// Extrudes a 2D outline to 'thickness', centred on the xy-plane. The result is wrapped into a union, so that its own
+// ref_sys stays free for placing it (and snapping it).
+fn extrude_centred(name: &str, mut outline: Object3D, thickness: f64) -> Object3D
+{
+	outline.linear_extrude(thickness);
+	outline.translate_z(-0.5*thickness);
+	union(name, vec![outline])
+}
+//}}}
+//{{{
+// The "pitch" and "mesh" anchors around the z-axis. The pitch anchor sits at angle 'tooth', the mesh anchor half a pitch further.
+fn add_wheel_anchors(object: &mut Object3D, pitch_radius: f64, tooth: f64, gap: f64)
+{
+	object.create_anchor("axis");
+	{
+		let a = object.create_anchor("pitch");
+		a.translate_x(pitch_radius);
+		a.rotate_z(tooth.to_degrees());
+	}
+	{
+		let a = object.create_anchor("mesh");
+		a.rotate_z(180.0);
+		a.translate_x(pitch_radius);
+		a.rotate_z(gap.to_degrees());
+	}
+}
+//}}}
+//}}}
+
+//{{{
+// Involute spur gear of the given module (pitch diameter / teeth), with a tooth centred on the +x axis.
+// Undercut is not modelled, so keep to about 17 teeth or more at 20°.
+pub fn spur_gear(name: &str, module: f64, teeth: i32, thickness: f64, pressure_angle: f64, bore: f64) -> Object3D
+{
+	let z      = teeth.max(3);
+	let alpha  = pressure_angle.to_radians();
+	let rp     = 0.5*module*(z as f64);
+	let rb     = rp*alpha.cos();
+	let ra     = rp + module;
+	let rf     = (rp - 1.25*module).max(0.1*rp);
+	let step   = 2.0*PI/(z as f64);
+
+	// Half the angular width of the tooth at radius r
+	let half_tooth = |r: f64| { let r = r.max(rb); step/4.0 + involute(alpha) - involute((rb/r).min(1.0).acos()) };
+
+	let flank_steps = 8;
+	let flank_start = rf.max(rb);
+	let flank_radius = |i: usize| flank_start + (ra-flank_start)*(i as f64)/(flank_steps as f64);
+
+	let mut points = vec![];
+	for tooth in 0..z
+	{
+		let centre = (tooth as f64)*step;
+
+		// Rising flank, tip, falling flank
+		if rf < rb { points.push(polar(rf, centre - half_tooth(rb))); }
+		for i in 0..=flank_steps         { let r = flank_radius(i); points.push(polar(r, centre - half_tooth(r))); }
+		for i in (0..=flank_steps).rev() { let r = flank_radius(i); points.push(polar(r, centre + half_tooth(r))); }
+		if rf < rb { points.push(polar(rf, centre + half_tooth(rb))); }
+
+		// Root, up to the next tooth
+		let from = centre + half_tooth(rb);
+		let to   = centre + step - half_tooth(rb);
+		for i in 1..4 { points.push(polar(rf, from + (to-from)*(i as f64)/4.0)); }
+	}
+
+	let mut paths = vec![(0..points.len() as i32).collect::<Vec<i32>>()];
+	if bore > 0.0
+	{
+		let (mut bore_points, bore_indices) = bore_path(0.5*bore, points.len());
+		points.append(&mut bore_points);
+		paths.push(bore_indices);
+	}
+
+	let mut gear = extrude_centred(name, polygon_paths(&(String::from("outline of ")+name), points, paths), thickness);
+	add_wheel_anchors(&mut gear, rp, 0.0, 0.5*step);
+	gear
+}
+//}}}
+//{{{
+// Straight rack with 'teeth' teeth, the first one centred on x = 0. Its body reaches 'base' below the tooth roots.
+pub fn rack(name: &str, module: f64, teeth: i32, thickness: f64, base: f64, pressure_angle: f64) -> Object3D
+{
+	let pitch  = PI*module;
+	let tan    = pressure_angle.to_radians().tan();
+	let top    = module;
+	let bottom = -1.25*module;
+
+	let tip_half  = 0.25*pitch - top*tan;
+	let root_half = 0.25*pitch - bottom*tan;
+
+	let first = -0.5*pitch;
+	let last  = ((teeth.max(1)-1) as f64)*pitch + 0.5*pitch;
+
+	// Counter-clockwise: along the bottom, then back over the teeth
+	let mut points = vec![point2D(first, bottom-base), point2D(last, bottom-base), point2D(last, bottom)];
+	for tooth in (0..teeth.max(1)).rev()
+	{
+		let x = (tooth as f64)*pitch;
+		points.push(point2D(x+root_half, bottom));
+		points.push(point2D(x+tip_half,  top));
+		points.push(point2D(x-tip_half,  top));
+		points.push(point2D(x-root_half, bottom));
+	}
+	points.push(point2D(first, bottom));
+
+	let mut rack = extrude_centred(name, polygon_paths(&(String::from("outline of ")+name), points, vec![]), thickness);
+	{
+		let a = rack.create_anchor("pitch");
+		a.rotate_z(90.0);
+	}
+	{
+		let a = rack.create_anchor("mesh");
+		a.rotate_z(-90.0);
+		a.translate_x(0.5*pitch);
+	}
+	rack
+}
+//}}}
+
+//{{{ pub enum BeltProfile
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BeltProfile
+{
+	GT2,
+	HTD3M,
+	HTD5M,
+}
+
+//{{{
+impl BeltProfile
+{
+	// Pitch, groove depth, groove radius and pitch line offset (between belt pitch line and pulley outside)
+	fn dimensions(&self) -> (f64, f64, f64, f64)
+	{
+		match self
+		{
+			Self::GT2   => (2.0, 0.75, 0.555, 0.254),
+			Self::HTD3M => (3.0, 1.14, 0.86,  0.381),
+			Self::HTD5M => (5.0, 2.06, 1.49,  0.5715),
+		}
+	}
+}
+//}}}
+//}}}
+
+//{{{
+// Timing pulley for a belt of the given 'width', with flanges on both sides. The grooves are approximated by circles.
+pub fn timing_pulley(name: &str, profile: BeltProfile, teeth: i32, width: f64, bore: f64) -> Object3D
+{
+	let (pitch, depth, groove, offset) = profile.dimensions();
+	let z       = teeth.max(3);
+	let rp      = (z as f64)*pitch/(2.0*PI);
+	let outside = rp - offset;
+	let step    = 2.0*PI/(z as f64);
+
+	let mut disc = vec![{ let mut c = circle(&(String::from("disc of ")+name), outside); c.set_fn(4*z); c }];
+	for tooth in 0..z
+	{
+		let mut g = circle(&(String::from("groove of ")+name), groove);
+		g.set_fn(16);
+		g.translate_x(outside - depth + groove);
+		g.rotate_z(((tooth as f64 + 0.5)*step).to_degrees());
+		disc.push(g);
+	}
+	let teeth_section = extrude_centred(&(String::from("teeth of ")+name), difference(&(String::from("outline of ")+name), disc), width);
+
+	let flange = 1.0;
+	let mut parts = vec![teeth_section];
+	for side in [-1.0, 1.0]
+	{
+		let mut f = cylinder(&(String::from("flange of ")+name), flange, outside + depth, outside + depth);
+		f.set_fn(FN);
+		f.translate_z(if side < 0.0 { -0.5*width - flange } else { 0.5*width });
+		parts.push(f);
+	}
+
+	let mut pulley = bored(name, union(&(String::from("body of ")+name), parts), bore, width + 2.0*flange);
+	add_wheel_anchors(&mut pulley, rp, 0.0, 0.5*step);
+	pulley
+}
+//}}}
+//{{{
+// Pulley with a 90° V-groove for a rope of 'rope_diameter', whose centre runs on 'pitch_diameter'
+pub fn v_groove_pulley(name: &str, rope_diameter: f64, pitch_diameter: f64, bore: f64) -> Object3D
+{
+	let rope    = 0.5*rope_diameter;
+	let rp      = 0.5*pitch_diameter;
+	let bottom  = rp - rope*2.0_f64.sqrt();
+	let outside = rp + rope;
+	let flank   = outside - bottom;
+
+	let mut parts = vec![];
+	for side in [-1.0, 1.0]
+	{
+		let mut cone = cylinder(&(String::from("flank of ")+name), flank, bottom, outside);
+		cone.set_fn(FN);
+		if side < 0.0 { cone.rotate_x(180.0); }
+		parts.push(cone);
+	}
+
+	let mut pulley = bored(name, union(&(String::from("body of ")+name), parts), bore, 2.0*flank);
+	add_wheel_anchors(&mut pulley, rp, 0.0, 0.0);
+	pulley
+}
+//}}}
+//{{{
+fn bored(name: &str, body: Object3D, bore: f64, length: f64) -> Object3D
+{
+	if bore <= 0.0 { return union(name, vec![body]); }
+
+	let mut hole = cylinder(&(String::from("bore of ")+name), length + 0.02, 0.5*bore, 0.5*bore);
+	hole.set_fn(FN);
+	hole.translate_z(-0.5*length - 0.01);
+	difference(name, vec![body, hole])
+}
+//}}}
+
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::math::Matrix3D;
+	use crate::mesh::Mesh;
+	use crate::object_3d::{Shape3D, intersection};
+
+	//{{{
+	fn assert_close(actual: f64, expected: f64, tolerance: f64)
+	{
+		assert!((actual - expected).abs() < tolerance, "{} instead of {}", actual, expected);
+	}
+	//}}}
+	//{{{
+	// Position and direction of the x-axis of an anchor on a placed object
+	fn anchor_frame(object: &Object3D, anchor: &str) -> ((f64, f64, f64), (f64, f64, f64))
+	{
+		let m : Matrix3D = object.anchors[anchor].ref_sys * object.ref_sys;
+		(m.get_translate(), (m[0][0], m[1][0], m[2][0]))
+	}
+	//}}}
+	//{{{
+	fn overlap(a: &Object3D, b: &Object3D) -> f64
+	{
+		Mesh::from_object(&intersection("overlap", vec![a.clone(), b.clone()])).expect("solid").volume()
+	}
+	//}}}
+	//{{{
+	// The points of the gear's outline
+	fn outline(gear: &Object3D) -> Vec<Point2D>
+	{
+		let mut object = gear;
+		loop
+		{
+			match &object.shape
+			{
+				Shape3D::Composite{children, ..} => object = &children[0],
+				Shape3D::Lextrude{child, ..}     => object = child,
+				Shape3D::Polygon{points, ..}     => return points.clone(),
+				_                                => panic!("{} is not an extruded polygon", object.name),
+			}
+		}
+	}
+	//}}}
+
+	//{{{
+	#[test]
+	fn meshing()
+	{
+		let mut pinion = spur_gear("pinion", 2.0, 18, 5.0, 20.0, 0.0);
+		let mut wheel  = spur_gear("wheel",  2.0, 30, 5.0, 20.0, 0.0);
+		wheel.anchor("mesh").snap_to(&mut pinion.anchor("pitch"));
+
+		// The axes are the sum of the pitch radii apart
+		let (x, y, z) = wheel.ref_sys.get_translate();
+		assert_close(x, 18.0 + 30.0, 1e-9);
+		assert_close(y, 0.0, 1e-9);
+		assert_close(z, 0.0, 1e-9);
+
+		// A tooth of the pinion sits in a gap of the wheel. Turned by half a tooth, the teeth run into each other.
+		assert!(overlap(&pinion, &wheel) < 1e-3);
+		pinion.rotate_z(360.0/36.0);
+		assert!(overlap(&pinion, &wheel) > 10.0);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn tooth_thickness()
+	{
+		// Where the flanks of the tooth on the +x axis cross the pitch circle
+		let (module, teeth) = (3.0, 24);
+		let rp = 0.5*module*(teeth as f64);
+		let points = outline(&spur_gear("gear", module, teeth, 5.0, 20.0, 0.0));
+		let crossings : Vec<f64> = points.iter().zip(points.iter().cycle().skip(1)).filter_map(|(p, q)|
+		{
+			let (rp_p, rp_q) = (p.l2_norm(), q.l2_norm());
+			if (rp_p - rp)*(rp_q - rp) >= 0.0 || p[0] < 0.0 { return None; }
+			let t = (rp - rp_p)/(rp_q - rp_p);
+			let (x, y) = (p[0] + (q[0] - p[0])*t, p[1] + (q[1] - p[1])*t);
+			Some(y.atan2(x))
+		}).filter(|phi| phi.abs() < PI/(teeth as f64)).collect();
+		assert_eq!(crossings.len(), 2);
+
+		// Half the circular pitch, on the arc. The flanks are chords of the involute, so only nearly.
+		assert_close(rp*(crossings[1] - crossings[0]).abs(), 0.5*PI*module, 5e-3);
+		assert_close(crossings[0] + crossings[1], 0.0, 1e-9);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn rack_anchors()
+	{
+		// Pitch anchor on the pitch line in the middle of the first tooth, facing away from the rack.
+		// Mesh anchor half a pitch further, in the gap, facing into the rack.
+		let module = 2.0;
+		let mut rack = rack("rack", module, 10, 5.0, 4.0, 20.0);
+		let ((x, y, _), (dx, dy, _)) = anchor_frame(&rack, "pitch");
+		assert_close(x, 0.0, 1e-9);
+		assert_close(y, 0.0, 1e-9);
+		assert_close(dx, 0.0, 1e-9);
+		assert_close(dy, 1.0, 1e-9);
+		let ((x, y, _), (dx, dy, _)) = anchor_frame(&rack, "mesh");
+		assert_close(x, 0.5*PI*module, 1e-9);
+		assert_close(y, 0.0, 1e-9);
+		assert_close(dx, 0.0, 1e-9);
+		assert_close(dy, -1.0, 1e-9);
+
+		// A gear put onto the rack stands its pitch radius above the pitch line, with a tooth in the gap
+		let mut gear = spur_gear("gear", module, 20, 5.0, 20.0, 0.0);
+		gear.anchor("mesh").snap_to(&mut rack.anchor("pitch"));
+		let (x, y, _) = gear.ref_sys.get_translate();
+		assert_close(x, 0.0, 1e-9);
+		assert_close(y, 20.0, 1e-9);
+		assert!(overlap(&gear, &rack) < 1e-3);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn pulley_anchors()
+	{
+		// The pitch circle of a timing pulley is as long as its teeth are pitched
+		let pulley = timing_pulley("pulley", BeltProfile::GT2, 20, 6.0, 5.0);
+		let rp = 20.0*2.0/(2.0*PI);
+		let ((x, y, _), (dx, dy, _)) = anchor_frame(&pulley, "pitch");
+		assert_close(x, rp, 1e-9);
+		assert_close(y, 0.0, 1e-9);
+		assert_close(dx, 1.0, 1e-9);
+		assert_close(dy, 0.0, 1e-9);
+
+		// The mesh anchor sits over a groove, half a tooth further round, and faces the axis
+		let phi = PI/20.0;
+		let ((x, y, _), (dx, dy, _)) = anchor_frame(&pulley, "mesh");
+		assert_close(x, rp*phi.cos(), 1e-9);
+		assert_close(y, rp*phi.sin(), 1e-9);
+		assert_close(dx, -phi.cos(), 1e-9);
+		assert_close(dy, -phi.sin(), 1e-9);
+		let ((x, y, z), _) = anchor_frame(&pulley, "axis");
+		assert_eq!((x, y, z), (0.0, 0.0, 0.0));
+
+		// The rope of a V-groove pulley runs around the pitch circle, touching both flanks of the groove
+		let (rope, pitch_diameter) = (6.0, 60.0);
+		let pulley = v_groove_pulley("sheave", rope, pitch_diameter, 8.0);
+		let ((x, y, _), _) = anchor_frame(&pulley, "pitch");
+		assert_close(x, 0.5*pitch_diameter, 1e-9);
+		assert_close(y, 0.0, 1e-9);
+		let ((x, _, _), (dx, _, _)) = anchor_frame(&pulley, "mesh");
+		assert_close(x, 0.5*pitch_diameter, 1e-9);
+		assert_close(dx, -1.0, 1e-9);
+
+		// The 45° flanks reach down to where a rope of that diameter, centred on the pitch circle, touches both of them
+		let bottom = 0.5*pitch_diameter - 0.5*rope*2.0_f64.sqrt();
+		let (low, high) = Mesh::from_object(&pulley).expect("solid").bounding_box().expect("not empty");
+		assert_close(high[0], 0.5*pitch_diameter + 0.5*rope, 1e-9);
+		assert_close(high[2] - low[2], 2.0*(high[0] - bottom), 1e-9);
+	}
+	//}}}
+}
+//}}}
@@ -0,0 +1,491 @@
+// Fasteners and other bought in hardware. All dimensions are in mm.
+//
+// Every item stands on the z-axis. Its "head" anchor sits where the head meets the surface of the part (the bearing face,
+// or the top of a countersunk head) with the z-axis pointing away from the part, its "tip" anchor at the end of the shank
+// pointing the other way. The part's surface is at z = 0 and the material below it.
+//
+// The cutout generators use the same frame. Their "head" anchor is the seat, where the item's "head" anchor has to go,
+// so e.g. screw.anchor("head").snap_to(&mut hole.anchor("head")) puts a screw into a counterbore.
+//
+// Items created with object() are tagged as purchased parts, so they end up in the bill of materials. The metric items
+// are None for sizes without standard dimensions (see metric_size()).
+
+use crate::math::Is3DObject;
+use crate::object_3d::{Object3D, cylinder, union, difference};
+
+// Cutouts reach this far past the surfaces they open up, so OpenSCad doesn't leave zero thickness skins
+const OVERSHOOT : f64 = 0.01;
+const FN        : i32 = 32;
+
+//{{{ pub struct MetricSize
+
+// Standard dimensions of a metric (ISO) thread size
+#[derive(Debug, Clone, Copy)]
+pub struct MetricSize
+{
+	pub d                  : f64,
+	pub pitch              : f64, // Coarse thread
+	pub socket_cap_head    : (f64, f64), // ISO 4762: Head diameter and height
+	pub hex_head           : (f64, f64), // ISO 4017: Width across flats and height
+	pub countersunk_head   : Option<(f64, f64)>, // ISO 10642: Head diameter and height, the standard starts at M3
+	pub nut                : (f64, f64), // ISO 4032: Width across flats and height
+	pub washer             : (f64, f64, f64), // ISO 7089: Inner diameter, outer diameter and thickness
+	pub clearance          : (f64, f64, f64), // ISO 273: Fine, medium and coarse clearance holes
+	pub insert             : (f64, f64, f64), // Heat set inserts: Outer diameter, length and hole diameter
+}
+
+const METRIC_SIZES : [MetricSize; 9] =
+[
+	MetricSize{ d:  2.0, pitch: 0.40, socket_cap_head: ( 3.8,  2.0), hex_head: ( 4.0, 1.4), countersunk_head: None,               nut: ( 4.0,  1.6), washer: ( 2.2,  5.0, 0.3), clearance: ( 2.2,  2.4,  2.6), insert: ( 3.6,  4.0,  3.2) },
+	MetricSize{ d:  2.5, pitch: 0.45, socket_cap_head: ( 4.5,  2.5), hex_head: ( 5.0, 1.7), countersunk_head: None,               nut: ( 5.0,  2.0), washer: ( 2.7,  6.0, 0.5), clearance: ( 2.7,  2.9,  3.1), insert: ( 4.6,  5.7,  4.0) },
+	MetricSize{ d:  3.0, pitch: 0.50, socket_cap_head: ( 5.5,  3.0), hex_head: ( 5.5, 2.0), countersunk_head: Some(( 6.72, 1.86)), nut: ( 5.5,  2.4), washer: ( 3.2,  7.0, 0.5), clearance: ( 3.2,  3.4,  3.6), insert: ( 5.0,  5.7,  4.0) },
+	MetricSize{ d:  4.0, pitch: 0.70, socket_cap_head: ( 7.0,  4.0), hex_head: ( 7.0, 2.8), countersunk_head: Some(( 8.96, 2.48)), nut: ( 7.0,  3.2), washer: ( 4.3,  9.0, 0.8), clearance: ( 4.3,  4.5,  4.8), insert: ( 6.3,  8.1,  5.6) },
+	MetricSize{ d:  5.0, pitch: 0.80, socket_cap_head: ( 8.5,  5.0), hex_head: ( 8.0, 3.5), countersunk_head: Some((11.20, 3.10)), nut: ( 8.0,  4.7), washer: ( 5.3, 10.0, 1.0), clearance: ( 5.3,  5.5,  5.8), insert: ( 7.0,  9.5,  6.4) },
+	MetricSize{ d:  6.0, pitch: 1.00, socket_cap_head: (10.0,  6.0), hex_head: (10.0, 4.0), countersunk_head: Some((13.44, 3.72)), nut: (10.0,  5.2), washer: ( 6.4, 12.0, 1.6), clearance: ( 6.4,  6.6,  7.0), insert: ( 8.0, 12.7,  7.2) },
+	MetricSize{ d:  8.0, pitch: 1.25, socket_cap_head: (13.0,  8.0), hex_head: (13.0, 5.3), countersunk_head: Some((17.92, 4.96)), nut: (13.0,  6.8), washer: ( 8.4, 16.0, 1.6), clearance: ( 8.4,  9.0, 10.0), insert: (10.0, 12.7,  9.6) },
+	MetricSize{ d: 10.0, pitch: 1.50, socket_cap_head: (16.0, 10.0), hex_head: (16.0, 6.4), countersunk_head: Some((22.40, 6.20)), nut: (16.0,  8.4), washer: (10.5, 20.0, 2.0), clearance: (10.5, 11.0, 12.0), insert: (12.0, 14.0, 11.2) },
+	MetricSize{ d: 12.0, pitch: 1.75, socket_cap_head: (18.0, 12.0), hex_head: (18.0, 7.5), countersunk_head: Some((26.88, 7.44)), nut: (18.0, 10.8), washer: (13.0, 24.0, 2.5), clearance: (13.0, 13.5, 14.5), insert: (16.0, 20.0, 15.0) },
+];
+
+//{{{
+// Looks up a standard size by its nominal diameter, e.g. 3.0 for M3. None for sizes not in the table (M2 to M12).
+pub fn metric_size(d: f64) -> Option<&'static MetricSize>
+{
+	METRIC_SIZES.iter().find(|size| (size.d-d).abs() < 1e-6)
+}
+//}}}
+//}}}
+
+//{{{ pub enum ScrewHead, HardwareKind
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScrewHead
+{
+	SocketCap,
+	Hex,
+	Countersunk,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HardwareKind
+{
+	MetricScrew(ScrewHead),
+	Nut,
+	Washer,
+	WoodScrew,
+	ThreadedInsert,
+}
+//}}}
+
+//{{{ pub struct Hardware
+
+#[derive(Debug, Clone)]
+pub struct Hardware
+{
+	pub kind          : HardwareKind,
+	pub d             : f64, // Nominal diameter of the thread (or of the insert's outside)
+	pub length        : f64, // Of the shank, below the head anchor
+	pub head_diameter : f64, // Width across flats for hex heads and nuts
+	pub head_height   : f64,
+	pub clearance     : f64, // Diameter of the clearance hole
+	pub pilot         : f64, // Diameter of the hole the item is screwed or pressed into
+}
+
+//{{{
+impl Hardware
+{
+	//{{{ Constructors
+
+	//{{{
+	// ISO 4762, ISO 4017 or ISO 10642 screw. The length is measured like the standards do: below the head, and over the
+	// whole screw for countersunk heads. ISO 10642 has no sizes below M3.
+	pub fn metric_screw(d: f64, length: f64, head: ScrewHead) -> Option<Self>
+	{
+		let size = metric_size(d)?;
+		let (head_diameter, head_height) = match head
+		{
+			ScrewHead::SocketCap   => size.socket_cap_head,
+			ScrewHead::Hex         => size.hex_head,
+			ScrewHead::Countersunk => size.countersunk_head?,
+		};
+
+		Some(Self
+		{
+			kind          : HardwareKind::MetricScrew(head),
+			d,
+			length,
+			head_diameter,
+			head_height,
+			clearance     : size.clearance.1,
+			pilot         : d - size.pitch,
+		})
+	}
+	//}}}
+	//{{{
+	// ISO 4032 hex nut
+	pub fn nut(d: f64) -> Option<Self>
+	{
+		let size = metric_size(d)?;
+		Some(Self
+		{
+			kind          : HardwareKind::Nut,
+			d,
+			length        : 0.0,
+			head_diameter : size.nut.0,
+			head_height   : size.nut.1,
+			clearance     : size.clearance.1,
+			pilot         : d - size.pitch,
+		})
+	}
+	//}}}
+	//{{{
+	// ISO 7089 flat washer
+	pub fn washer(d: f64) -> Option<Self>
+	{
+		let size = metric_size(d)?;
+		Some(Self
+		{
+			kind          : HardwareKind::Washer,
+			d,
+			length        : 0.0,
+			head_diameter : size.washer.1,
+			head_height   : size.washer.2,
+			clearance     : size.clearance.1,
+			pilot         : size.washer.0,
+		})
+	}
+	//}}}
+	//{{{
+	// Countersunk wood screw (90° head of twice the diameter), e.g. wood_screw(4.0, 30.0) for a 4x30 screw
+	pub fn wood_screw(d: f64, length: f64) -> Self
+	{
+		Self
+		{
+			kind          : HardwareKind::WoodScrew,
+			d,
+			length,
+			head_diameter : 2.0*d,
+			head_height   : 0.5*d,
+			clearance     : 1.1*d,
+			pilot         : 0.6*d,
+		}
+	}
+	//}}}
+	//{{{
+	// Heat set threaded insert for plastic parts, flush with the surface
+	pub fn threaded_insert(d: f64) -> Option<Self>
+	{
+		let size = metric_size(d)?;
+		Some(Self
+		{
+			kind          : HardwareKind::ThreadedInsert,
+			d,
+			length        : size.insert.1,
+			head_diameter : size.insert.0,
+			head_height   : 0.0,
+			clearance     : size.insert.2,
+			pilot         : size.insert.2,
+		})
+	}
+	//}}}
+	//}}}
+
+	//{{{
+	// Standard designation, used as part number in the bill of materials
+	pub fn designation(&self) -> String
+	{
+		match self.kind
+		{
+			HardwareKind::MetricScrew(ScrewHead::SocketCap)   => format!("ISO 4762 M{}x{}",  self.d, self.length),
+			HardwareKind::MetricScrew(ScrewHead::Hex)         => format!("ISO 4017 M{}x{}",  self.d, self.length),
+			HardwareKind::MetricScrew(ScrewHead::Countersunk) => format!("ISO 10642 M{}x{}", self.d, self.length),
+			HardwareKind::Nut                                 => format!("ISO 4032 M{}",     self.d),
+			HardwareKind::Washer                              => format!("ISO 7089 {}",      self.d),
+			HardwareKind::WoodScrew                           => format!("Wood screw {}x{}", self.d, self.length),
+			HardwareKind::ThreadedInsert                      => format!("Threaded insert M{}x{}", self.d, self.length),
+		}
+	}
+	//}}}
+	//{{{
+	pub fn description(&self) -> String
+	{
+		String::from(match self.kind
+		{
+			HardwareKind::MetricScrew(ScrewHead::SocketCap)   => "Socket head cap screw",
+			HardwareKind::MetricScrew(ScrewHead::Hex)         => "Hex head screw",
+			HardwareKind::MetricScrew(ScrewHead::Countersunk) => "Countersunk socket screw",
+			HardwareKind::Nut                                 => "Hex nut",
+			HardwareKind::Washer                              => "Flat washer",
+			HardwareKind::WoodScrew                           => "Countersunk wood screw",
+			HardwareKind::ThreadedInsert                      => "Heat set threaded insert",
+		})
+	}
+	//}}}
+
+	//{{{ Helpers
+
+	//{{{
+	fn is_countersunk(&self) -> bool
+	{
+		matches!(self.kind, HardwareKind::MetricScrew(ScrewHead::Countersunk) | HardwareKind::WoodScrew)
+	}
+	//}}}
+	//{{{
+	fn is_hex(&self) -> bool
+	{
+		matches!(self.kind, HardwareKind::MetricScrew(ScrewHead::Hex) | HardwareKind::Nut)
+	}
+	//}}}
+	//{{{
+	// Cylinder from z1 to z2, hexagonal ones are given by their width across flats
+	fn prism(name: &str, diameter: f64, z1: f64, z2: f64, hex: bool) -> Object3D
+	{
+		let r = if hex { diameter/3.0_f64.sqrt() } else { 0.5*diameter };
+		let mut prism = cylinder(name, z2-z1, r, r);
+		prism.set_fn(if hex { 6 } else { FN });
+		prism.translate_z(z1);
+		prism
+	}
+	//}}}
+	//{{{
+	// 90° cone, 'diameter' wide at z = top, narrowing towards -z down to 'bottom_diameter'
+	fn cone(name: &str, diameter: f64, bottom_diameter: f64, top: f64) -> Object3D
+	{
+		let h = 0.5*(diameter-bottom_diameter).max(0.0);
+		let mut cone = cylinder(name, h, 0.5*bottom_diameter, 0.5*diameter);
+		cone.set_fn(FN);
+		cone.translate_z(top-h);
+		cone
+	}
+	//}}}
+	//{{{
+	fn add_anchors(object: &mut Object3D, seat: f64, tip: f64)
+	{
+		object.create_anchor("head").translate_z(seat);
+
+		let anchor = object.create_anchor("tip");
+		anchor.rotate_x(180.0);
+		anchor.translate_z(tip);
+	}
+	//}}}
+	//}}}
+
+	//{{{
+	// The item itself, tagged for the bill of materials. Threads are not modelled.
+	pub fn object(&self, name: &str) -> Object3D
+	{
+		let mut parts = vec![];
+		match self.kind
+		{
+			HardwareKind::MetricScrew(_) | HardwareKind::WoodScrew =>
+			{
+				if self.is_countersunk()
+				{
+					parts.push(Self::cone(&(String::from("head of ")+name), self.head_diameter, self.d, 0.0));
+				} else {
+					parts.push(Self::prism(&(String::from("head of ")+name), self.head_diameter, 0.0, self.head_height, self.is_hex()));
+				}
+				parts.push(Self::prism(&(String::from("shank of ")+name), self.d, -self.length, 0.0, false));
+			}
+			HardwareKind::Nut | HardwareKind::Washer =>
+			{
+				let body = Self::prism(&(String::from("body of ")+name), self.head_diameter, 0.0, self.head_height, self.is_hex());
+				let bore = Self::prism(&(String::from("bore of ")+name), self.pilot, -OVERSHOOT, self.head_height+OVERSHOOT, false);
+				parts.push(difference(&(String::from("ring of ")+name), vec![body, bore]));
+			}
+			HardwareKind::ThreadedInsert =>
+			{
+				let body = Self::prism(&(String::from("body of ")+name), self.head_diameter, -self.length, 0.0, false);
+				let bore = Self::prism(&(String::from("bore of ")+name), metric_size(self.d).map_or(self.d, |size| self.d - size.pitch), -self.length-OVERSHOOT, OVERSHOOT, false);
+				parts.push(difference(&(String::from("sleeve of ")+name), vec![body, bore]));
+			}
+		}
+
+		let mut object = union(name, parts);
+		Self::add_anchors(&mut object, 0.0, -self.length);
+		object.set_part_number(&self.designation());
+		object.set_part_description(&self.description());
+		if let Some(part) = &mut object.part { part.purchased = true; }
+		object
+	}
+	//}}}
+
+	//{{{ Cutouts, 'depth' deep below the surface
+
+	//{{{
+	// Hole the shank passes through freely. For threaded inserts, the hole they are pressed into.
+	pub fn clearance_hole(&self, name: &str, depth: f64) -> Object3D
+	{
+		let mut hole = union(name, vec![Self::prism(&(String::from("hole of ")+name), self.clearance, -depth, OVERSHOOT, false)]);
+		Self::add_anchors(&mut hole, 0.0, -depth);
+		hole
+	}
+	//}}}
+	//{{{
+	// Hole the screw cuts its thread into (tap drill, pilot hole in wood)
+	pub fn pilot_hole(&self, name: &str, depth: f64) -> Object3D
+	{
+		let mut hole = union(name, vec![Self::prism(&(String::from("hole of ")+name), self.pilot, -depth, OVERSHOOT, false)]);
+		Self::add_anchors(&mut hole, 0.0, -depth);
+		hole
+	}
+	//}}}
+	//{{{
+	// Clearance hole with a pocket that sinks the head (or nut, washer) below the surface. Hex pockets form a nut trap.
+	pub fn counterbore(&self, name: &str, depth: f64) -> Object3D
+	{
+		let gap        = self.clearance - self.d;
+		let bore_depth = self.head_height;
+
+		let bore = Self::prism(&(String::from("bore of ")+name), self.head_diameter + 2.0*gap.max(0.1), -bore_depth, OVERSHOOT, self.is_hex());
+		let hole = Self::prism(&(String::from("hole of ")+name), self.clearance, -depth, 0.0, false);
+
+		let mut cutout = union(name, vec![bore, hole]);
+		Self::add_anchors(&mut cutout, -bore_depth, -depth);
+		cutout
+	}
+	//}}}
+	//{{{
+	// Clearance hole with a 90° countersink for the head. Other heads get a chamfer of their diameter.
+	pub fn countersink(&self, name: &str, depth: f64) -> Object3D
+	{
+		let gap  = self.clearance - self.d;
+		let sink = Self::cone(&(String::from("countersink of ")+name), self.head_diameter + gap.max(0.0) + 2.0*OVERSHOOT, self.clearance, OVERSHOOT);
+		let hole = Self::prism(&(String::from("hole of ")+name), self.clearance, -depth, 0.0, false);
+
+		let mut cutout = union(name, vec![sink, hole]);
+		Self::add_anchors(&mut cutout, 0.0, -depth);
+		cutout
+	}
+	//}}}
+	//}}}
+}
+//}}}
+//}}}
+
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::mesh::Mesh;
+
+	//{{{
+	fn bounding_box(object: &Object3D) -> ([f64; 3], [f64; 3])
+	{
+		let (low, high) = Mesh::from_object(object).expect("solid").bounding_box().expect("not empty");
+		([low[0], low[1], low[2]], [high[0], high[1], high[2]])
+	}
+	//}}}
+	//{{{
+	fn assert_close(actual: f64, expected: f64)
+	{
+		assert!((actual - expected).abs() < 1e-9, "{} instead of {}", actual, expected);
+	}
+	//}}}
+	//{{{
+	// Puts the item into the cutout by its head anchor and returns the volume of it that sticks out of the cutout
+	fn protrusion(item: &Hardware, cutout: &mut Object3D) -> f64
+	{
+		let mut object = item.object("item");
+		object.anchor("head").snap_to(&mut cutout.anchor("head"));
+		Mesh::from_object(&difference("protrusion", vec![object, cutout.clone()])).expect("solid").volume()
+	}
+	//}}}
+
+	//{{{
+	#[test]
+	fn sizes()
+	{
+		assert_eq!(metric_size(5.0).map(|size| size.pitch), Some(0.8));
+		assert!(metric_size(7.0).is_none());
+
+		assert!(Hardware::metric_screw(2.0, 10.0, ScrewHead::SocketCap).is_some());
+		assert!(Hardware::metric_screw(2.5, 10.0, ScrewHead::Countersunk).is_none());
+		let screw = Hardware::metric_screw(3.0, 10.0, ScrewHead::Countersunk).unwrap();
+		assert_eq!(screw.designation(), "ISO 10642 M3x10");
+		assert_eq!((screw.head_diameter, screw.head_height, screw.clearance), (6.72, 1.86, 3.4));
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn holes()
+	{
+		// Medium clearance, through the surface by the overshoot
+		let screw = Hardware::metric_screw(4.0, 20.0, ScrewHead::SocketCap).unwrap();
+		let (low, high) = bounding_box(&screw.clearance_hole("hole", 15.0));
+		assert_close(high[0], 2.25);
+		assert_close(low[2], -15.0);
+		assert_close(high[2], OVERSHOOT);
+		assert_close(bounding_box(&screw.pilot_hole("tap", 15.0)).1[0], 1.65);
+
+		let mut hole = screw.clearance_hole("hole", 15.0);
+		assert_eq!(hole.anchors["head"].ref_sys.get_translate(), (0.0, 0.0, 0.0));
+		assert_eq!(hole.anchors["tip"].ref_sys.get_translate(), (0.0, 0.0, -15.0));
+
+		// The head sits on the surface, the shank goes through the hole
+		let mut screw_object = screw.object("screw");
+		screw_object.anchor("head").snap_to(&mut hole.anchor("head"));
+		let (low, high) = bounding_box(&screw_object);
+		assert_close(low[2], -20.0);
+		assert_close(high[2], 4.0);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn counterbores()
+	{
+		// The head of a socket cap screw sinks in flush with the surface
+		let screw = Hardware::metric_screw(4.0, 20.0, ScrewHead::SocketCap).unwrap();
+		let mut cutout = screw.counterbore("counterbore", 25.0);
+		assert_eq!(cutout.anchors["head"].ref_sys.get_translate(), (0.0, 0.0, -4.0));
+		assert_eq!(cutout.anchors["tip"].ref_sys.get_translate(), (0.0, 0.0, -25.0));
+		let (low, high) = bounding_box(&cutout);
+		assert_close(low[2], -25.0);
+		assert_close(high[0], 0.5*(7.0 + 2.0*0.5));
+		assert!(protrusion(&screw, &mut cutout).abs() < 1e-6);
+
+		let mut screw_object = screw.object("screw");
+		screw_object.anchor("head").snap_to(&mut cutout.anchor("head"));
+		assert_close(bounding_box(&screw_object).1[2], 0.0);
+
+		// Nut traps are hexagonal and as deep as the nut is high
+		let nut = Hardware::nut(4.0).unwrap();
+		let mut trap = nut.counterbore("nut trap", 10.0);
+		assert_eq!(trap.anchors["head"].ref_sys.get_translate(), (0.0, 0.0, -3.2));
+		assert!(protrusion(&nut, &mut trap).abs() < 1e-6);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn countersinks()
+	{
+		// The top of the head is flush with the surface and the cone of the head stays inside the countersink
+		let screw = Hardware::metric_screw(4.0, 20.0, ScrewHead::Countersunk).unwrap();
+		let mut cutout = screw.countersink("countersink", 25.0);
+		assert_eq!(cutout.anchors["head"].ref_sys.get_translate(), (0.0, 0.0, 0.0));
+		assert!(protrusion(&screw, &mut cutout).abs() < 1e-6);
+
+		// The 90° countersink is as wide as the head plus the clearance gap at the surface, so the head could sink in
+		// by half the gap, but no further
+		let (low, high) = bounding_box(&cutout);
+		assert_close(high[0], 0.5*(8.96 + 0.5) + OVERSHOOT);
+		assert_close(high[2], OVERSHOOT);
+		assert_close(low[2], -25.0);
+		let sunk = |depth: f64|
+		{
+			let mut screw_object = screw.object("screw");
+			screw_object.translate_z(-depth);
+			Mesh::from_object(&difference("protrusion", vec![screw_object, cutout.clone()])).expect("solid").volume()
+		};
+		assert!(sunk(0.25).abs() < 1e-6);
+		assert!(sunk(0.35) > 1e-3);
+
+		let wood_screw = Hardware::wood_screw(4.0, 30.0);
+		let mut cutout = wood_screw.countersink("countersink", 35.0);
+		assert!(protrusion(&wood_screw, &mut cutout).abs() < 1e-6);
+	}
+	//}}}
+}
+//}}}
@@ -0,0 +1,383 @@
+// Woodworking joints as matched male/female pairs.
+//
+// All joints share one frame, in which both parts are generated in their assembled position:
+// - The joint face is the xz-plane at y = 0. The male board ends there and its part of the joint sticks out towards +y.
+// - The female board lies on the +y side. Its part of the joint is a cutout, to be subtracted from it with difference().
+// - The male part is added to its board with union().
+// Both parts get an anchor "joint" at the origin of this frame. As union() and difference() collect the anchors of their
+// children, the boards end up with "<part name>::joint" anchors, and snapping one onto the other puts the boards together.
+
+use std::fmt;
+
+use crate::math::{Is3DObject, point2D};
+use crate::object_3d::{Object3D, cube_coords, cylinder, polygon, union};
+
+// Cutouts reach this far past the surfaces they open up, so OpenSCad doesn't leave zero thickness skins
+const OVERSHOOT : f64 = 0.01;
+
+//{{{ pub enum JointError
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JointError
+{
+	// The flanks of the tails would meet before the tails end, at this angle and tail ratio
+	TailsTooLong{ joint: String, length: f64, max_length: f64 },
+}
+
+//{{{
+impl fmt::Display for JointError
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+	{
+		match self
+		{
+			Self::TailsTooLong{joint, length, max_length} => write!(f, "the tails of {} are {} long, at this angle and tail ratio they must be shorter than {}",
+				joint, length, max_length),
+		}
+	}
+}
+//}}}
+
+impl std::error::Error for JointError {}
+//}}}
+
+//{{{ pub struct JointFit
+
+#[derive(Debug, Clone, Copy)]
+pub struct JointFit
+{
+	// Radius of the cutter. Inside corners of pockets get round reliefs ("dog bones") of this radius, so that square parts
+	// still fit. 0.0 disables them.
+	pub tool_clearance : f64,
+	// Total gap between the mating faces. Cutouts grow by half of it on each side, the male parts keep their nominal size.
+	pub tolerance      : f64,
+}
+
+//{{{
+impl Default for JointFit
+{
+	fn default() -> Self
+	{
+		Self
+		{
+			tool_clearance : 0.0,
+			tolerance      : 0.0,
+		}
+	}
+}
+//}}}
+
+//{{{
+impl JointFit
+{
+	pub fn new(tool_clearance: f64, tolerance: f64) -> Self
+	{
+		Self{ tool_clearance, tolerance }
+	}
+}
+//}}}
+//}}}
+
+//{{{ pub struct JointPair
+
+#[derive(Debug, Clone)]
+pub struct JointPair
+{
+	pub male   : Object3D,
+	pub female : Object3D,
+}
+
+//{{{
+impl JointPair
+{
+	fn new(male: Object3D, female: Object3D) -> Self
+	{
+		let mut pair = Self{ male, female };
+		pair.male.create_anchor("joint");
+		pair.female.create_anchor("joint");
+		pair
+	}
+}
+//}}}
+//}}}
+
+//{{{ pub struct DovetailLayout
+
+#[derive(Debug, Clone, Copy)]
+pub struct DovetailLayout
+{
+	pub tails      : i32,
+	pub angle      : f64, // Flank angle in degrees, 7.1 (1:8) for hardwood, 9.5 (1:6) for softwood
+	pub tail_ratio : f64, // Share of each tail's pitch taken up by the wide end of the tail
+}
+
+//{{{
+impl Default for DovetailLayout
+{
+	fn default() -> Self
+	{
+		Self
+		{
+			tails      : 3,
+			angle      : 7.1,
+			tail_ratio : 0.66,
+		}
+	}
+}
+//}}}
+//}}}
+
+//{{{ Helpers
+
+//{{{
+// Round reliefs along the y-axis, centred on the given inside corners (x, z)
+fn dog_bones(name: &str, corners: &[(f64, f64)], y1: f64, y2: f64, fit: &JointFit) -> Vec<Object3D>
+{
+	if fit.tool_clearance <= 0.0 { return vec![]; }
+
+	corners.iter().map(|&(x, z)|
+	{
+		let mut bone = cylinder(&(String::from("dog bone for ")+name), y2-y1, fit.tool_clearance, fit.tool_clearance);
+		bone.rotate_x(-90.0);
+		bone.translate(x, y1, z);
+		bone
+	}).collect()
+}
+//}}}
+//{{{
+// Prism along the z-axis over a trapezoid in the xy-plane, 'narrow' wide at y1 and 'wide' wide at y2
+fn trapezoid_prism(name: &str, x: f64, narrow: f64, wide: f64, (y1, y2): (f64, f64), (z1, z2): (f64, f64)) -> Object3D
+{
+	let mut prism = polygon(name, vec![
+		point2D(x-0.5*narrow, y1),
+		point2D(x+0.5*narrow, y1),
+		point2D(x+0.5*wide,   y2),
+		point2D(x-0.5*wide,   y2),
+	]);
+	prism.linear_extrude(z2-z1);
+	prism.translate_z(z1);
+	prism
+}
+//}}}
+//{{{
+fn dovetail(name: &str, width: f64, thickness: f64, length: f64, socket_depth: f64, layout: &DovetailLayout, fit: &JointFit) -> Result<JointPair, JointError>
+{
+	let tails  = layout.tails.max(1);
+	let pitch  = width/(tails as f64);
+	let wide   = layout.tail_ratio*pitch;
+	let slope  = layout.angle.to_radians().tan();
+	let narrow = wide - 2.0*length*slope;
+	if narrow <= 0.0
+	{
+		return Err(JointError::TailsTooLong{ joint: String::from(name), length, max_length: 0.5*wide/slope });
+	}
+
+	let mut tail_parts   = vec![];
+	let mut socket_parts = vec![];
+	for i in 0..tails
+	{
+		let x = -0.5*width + (i as f64 + 0.5)*pitch;
+		tail_parts.push(trapezoid_prism(&(String::from("tail for ")+name), x, narrow, wide, (0.0, length), (-0.5*thickness, 0.5*thickness)));
+
+		// The socket continues the flanks of the tail down to y = -OVERSHOOT, widened by the tolerance
+		let socket_narrow = narrow + fit.tolerance - 2.0*OVERSHOOT*slope;
+		let socket_wide   = narrow + fit.tolerance + 2.0*socket_depth*slope;
+		socket_parts.push(trapezoid_prism(&(String::from("socket for ")+name), x, socket_narrow, socket_wide, (-OVERSHOOT, socket_depth),
+			(-0.5*thickness-OVERSHOOT, 0.5*(thickness+fit.tolerance))));
+	}
+
+	Ok(JointPair::new(
+		union(&(String::from("tails for ")+name),   tail_parts),
+		union(&(String::from("sockets for ")+name), socket_parts)))
+}
+//}}}
+//}}}
+
+//{{{
+// Box joint between two boards meeting at a corner.
+// The joint edge runs along x over 'width', the male board is 'thickness' thick (along z), the female board 'depth' (along y).
+// The edge is divided into 'slots' equal slots. The male board gets fingers on the even ones, the female board keeps the odd ones.
+pub fn finger_joint(name: &str, width: f64, thickness: f64, depth: f64, slots: i32, fit: &JointFit) -> JointPair
+{
+	let slots      = slots.max(1);
+	let slot_width = width/(slots as f64);
+
+	let mut fingers = vec![];
+	let mut cutouts = vec![];
+	for i in (0..slots).step_by(2)
+	{
+		let x1 = -0.5*width + (i as f64)*slot_width;
+		let x2 = x1 + slot_width;
+		fingers.push(cube_coords(&(String::from("finger for ")+name), x1, 0.0, -0.5*thickness, x2, depth, 0.5*thickness));
+
+		// Slots at the ends of the edge are open to the outside
+		let cx1 = if i == 0       { x1 - OVERSHOOT } else { x1 - 0.5*fit.tolerance };
+		let cx2 = if i == slots-1 { x2 + OVERSHOOT } else { x2 + 0.5*fit.tolerance };
+		let cz = 0.5*(thickness + fit.tolerance);
+		cutouts.push(cube_coords(&(String::from("finger slot for ")+name), cx1, -OVERSHOOT, -0.5*thickness-OVERSHOOT, cx2, depth+OVERSHOOT, cz));
+
+		let mut corners = vec![];
+		if i != 0       { corners.push((cx1, cz)); }
+		if i != slots-1 { corners.push((cx2, cz)); }
+		cutouts.append(&mut dog_bones(name, &corners, -OVERSHOOT, depth+OVERSHOOT, fit));
+	}
+
+	JointPair::new(
+		union(&(String::from("fingers for ")+name),      fingers),
+		union(&(String::from("finger slots for ")+name), cutouts))
+}
+//}}}
+//{{{
+// Through dovetails. The tails belong to the male board ('thickness' thick along z) and run through the whole female board
+// ('depth' thick along y). The sockets in the female board leave the pins standing.
+// Fails if the tails are too long for their flanks at the layout's angle, which would make them come to a point.
+pub fn dovetail_through(name: &str, width: f64, thickness: f64, depth: f64, layout: &DovetailLayout, fit: &JointFit) -> Result<JointPair, JointError>
+{
+	dovetail(name, width, thickness, depth, depth+OVERSHOOT, layout, fit)
+}
+//}}}
+//{{{
+// Half-blind dovetails, e.g. for drawer fronts. The tails stop 'lap' short of the far face of the female board, which stays intact.
+pub fn dovetail_half_blind(name: &str, width: f64, thickness: f64, depth: f64, lap: f64, layout: &DovetailLayout, fit: &JointFit) -> Result<JointPair, JointError>
+{
+	let length = (depth-lap).max(0.0);
+	dovetail(name, width, thickness, length, length+0.5*fit.tolerance, layout, fit)
+}
+//}}}
+//{{{
+// Tenon of 'width' (along x) by 'thickness' (along z), 'length' long, and the matching blind mortise.
+// The mortise is a little deeper than the tenon is long (by half the tolerance), to leave room for glue.
+pub fn mortise_and_tenon(name: &str, width: f64, thickness: f64, length: f64, fit: &JointFit) -> JointPair
+{
+	let tenon = cube_coords(&(String::from("tenon for ")+name), -0.5*width, 0.0, -0.5*thickness, 0.5*width, length, 0.5*thickness);
+
+	let x = 0.5*(width     + fit.tolerance);
+	let z = 0.5*(thickness + fit.tolerance);
+	let y = length + 0.5*fit.tolerance;
+	let mut parts = vec![cube_coords(&(String::from("mortise for ")+name), -x, -OVERSHOOT, -z, x, y, z)];
+	parts.append(&mut dog_bones(name, &[(-x, -z), (x, -z), (x, z), (-x, z)], -OVERSHOOT, y, fit));
+
+	JointPair::new(tenon, union(&(String::from("mortise for ")+name), parts))
+}
+//}}}
+//{{{
+// Groove of 'length' (along x) across the female board, 'depth' deep, housing the end of a board 'width' thick (along z).
+// The male part is the end of the housed board inside the groove, for boards modelled to stop at the surface.
+// The groove runs out at both ends, so the cutter leaves no corners that need relief.
+pub fn dado(name: &str, width: f64, depth: f64, length: f64, fit: &JointFit) -> JointPair
+{
+	let end = cube_coords(&(String::from("dado tongue for ")+name), -0.5*length, 0.0, -0.5*width, 0.5*length, depth, 0.5*width);
+
+	let z = 0.5*(width + fit.tolerance);
+	let groove = cube_coords(&(String::from("dado for ")+name), -0.5*length-OVERSHOOT, -OVERSHOOT, -z, 0.5*length+OVERSHOOT, depth, z);
+
+	JointPair::new(end, groove)
+}
+//}}}
+//{{{
+// Step along the edge of the female board, which ends at z = 0 (the board lies at z < 0). The step is 'depth' deep (along y)
+// and 'width' wide (along z), the end of the male board (which is 'width' thick) sits in it.
+pub fn rabbet(name: &str, width: f64, depth: f64, length: f64, fit: &JointFit) -> JointPair
+{
+	let end = cube_coords(&(String::from("rabbet tongue for ")+name), -0.5*length, 0.0, -width, 0.5*length, depth, 0.0);
+
+	let step = cube_coords(&(String::from("rabbet for ")+name),
+		-0.5*length-OVERSHOOT, -OVERSHOOT, -width-fit.tolerance,
+		 0.5*length+OVERSHOOT,      depth, OVERSHOOT);
+
+	JointPair::new(end, step)
+}
+//}}}
+
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::mesh::Mesh;
+	use crate::object_3d::{difference, intersection};
+
+	//{{{
+	// Volume of the male part, moved by the given offsets, that overlaps what is left of the female board after the cutout
+	fn overlaps(pair: &JointPair, board: &Object3D, offsets: &[(f64, f64, f64)]) -> Vec<f64>
+	{
+		let rest = difference("rest of the board", [board.clone(), pair.female.clone()]);
+		offsets.iter().map(|&(x, y, z)|
+		{
+			let mut male = pair.male.clone();
+			male.translate(x, y, z);
+			Mesh::from_object(&intersection("overlap", [male, rest.clone()])).expect("solid joint").volume()
+		}).collect()
+	}
+	//}}}
+	//{{{
+	// Moves the male part by half the tolerance towards every face of the board it mates with, which together grows it by
+	// the tolerance. It must not run into the board, but has to when it moves a little further.
+	fn assert_fits(pair: &JointPair, board: &Object3D, tolerance: f64, directions: &[(f64, f64, f64)])
+	{
+		let offsets = |distance: f64| -> Vec<(f64, f64, f64)>
+		{
+			std::iter::once((0.0, 0.0, 0.0)).chain(directions.iter().map(|&(x, y, z)| (distance*x, distance*y, distance*z))).collect()
+		};
+		let inside = offsets(0.5*tolerance);
+		for (offset, volume) in inside.iter().zip(overlaps(pair, board, &inside))
+		{
+			assert!(volume.abs() < 1e-6, "{} runs into the board by {} when moved by {:?}", pair.male.name, volume, offset);
+		}
+		let outside = offsets(0.5*tolerance + 0.1);
+		for (offset, volume) in outside.iter().zip(overlaps(pair, board, &outside)).skip(1)
+		{
+			assert!(volume > 1e-3, "{} has more room than the tolerance when moved by {:?}", pair.male.name, offset);
+		}
+	}
+	//}}}
+
+	//{{{
+	#[test]
+	fn fits()
+	{
+		let fit = JointFit::new(0.0, 0.3);
+		let sides = [(-1.0, 0.0, 0.0), (1.0, 0.0, 0.0)];
+
+		// The female boards of corner joints lie above the male board, the male parts mate with their sides and top
+		let board = cube_coords("side", -50.0, 0.0, -9.0, 50.0, 12.0, 100.0);
+		let pair  = finger_joint("corner", 100.0, 18.0, 12.0, 5, &fit);
+		assert_fits(&pair, &board, fit.tolerance, &[sides[0], sides[1], (0.0, 0.0, 1.0)]);
+
+		let board = cube_coords("drawer side", -60.0, 0.0, -7.5, 60.0, 12.0, 100.0);
+		let pair  = dovetail_through("drawer back", 120.0, 15.0, 12.0, &DovetailLayout::default(), &fit).unwrap();
+		assert_fits(&pair, &board, fit.tolerance, &[sides[0], sides[1], (0.0, 0.0, 1.0)]);
+
+		// Half-blind tails also mate with the bottom of their sockets
+		let board = cube_coords("drawer front", -60.0, 0.0, -7.5, 60.0, 20.0, 100.0);
+		let pair  = dovetail_half_blind("drawer front", 120.0, 15.0, 20.0, 5.0, &DovetailLayout::default(), &fit).unwrap();
+		assert_fits(&pair, &board, fit.tolerance, &[sides[0], sides[1], (0.0, 1.0, 0.0), (0.0, 0.0, 1.0)]);
+
+		// The tenon sits inside the mortise on all sides
+		let board = cube_coords("leg", -50.0, 0.0, -30.0, 50.0, 50.0, 30.0);
+		let pair  = mortise_and_tenon("rail", 60.0, 20.0, 30.0, &fit);
+		assert_fits(&pair, &board, fit.tolerance, &[sides[0], sides[1], (0.0, 1.0, 0.0), (0.0, 0.0, -1.0), (0.0, 0.0, 1.0)]);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn long_tails()
+	{
+		// 3 tails over 30 are 6.6 wide, at 1:8 their flanks meet after 26.4
+		let layout = DovetailLayout::default();
+		assert!(dovetail_through("box", 30.0, 10.0, 26.0, &layout, &JointFit::default()).is_ok());
+		match dovetail_through("box", 30.0, 10.0, 27.0, &layout, &JointFit::default())
+		{
+			Err(JointError::TailsTooLong{joint, length, max_length}) =>
+			{
+				assert_eq!((joint.as_str(), length), ("box", 27.0));
+				assert!((max_length - 0.5*6.6/7.1f64.to_radians().tan()).abs() < 1e-9);
+			},
+			other => panic!("{:?} instead of TailsTooLong", other.map(|pair| pair.male.name)),
+		}
+		assert!(dovetail_half_blind("box", 30.0, 10.0, 40.0, 10.0, &layout, &JointFit::default()).is_err());
+	}
+	//}}}
+}
+//}}}
@@ -0,0 +1,202 @@
+use std::fmt;
+
+use crate::object_3d::{Object3D, Shape3D};
+
+//{{{ pub enum LabelError
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LabelError
+{
+	MissingAnchor{ object: String, anchor: String },
+}
+
+//{{{
+impl fmt::Display for LabelError
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+	{
+		match self
+		{
+			Self::MissingAnchor{object, anchor} => write!(f, "{} has no anchor \"{}\" to engrave a label on", object, anchor),
+		}
+	}
+}
+//}}}
+
+impl std::error::Error for LabelError {}
+//}}}
+
+//{{{ pub enum LabelText
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LabelText
+{
+	Name,
+	PartNumber, // Falls back to the name for parts without a number
+}
+//}}}
+
+//{{{ pub struct LabelOptions
+
+#[derive(Debug, Clone)]
+pub struct LabelOptions
+{
+	pub text  : LabelText,
+	pub font  : String,
+	pub size  : f64,
+	pub depth : f64,
+}
+
+//{{{
+impl Default for LabelOptions
+{
+	fn default() -> Self
+	{
+		Self
+		{
+			text  : LabelText::Name,
+			font  : String::from("Liberation Sans:style=Bold"),
+			size  : 6.0,
+			depth : 0.6,
+		}
+	}
+}
+//}}}
+//}}}
+
+//{{{
+// Engraves every tagged part in the assembly with its name or part number, on the part's label anchor.
+// Returns the names of the parts that were skipped, because they don't have that anchor.
+pub fn engrave_part_labels(assembly: &mut Object3D, options: &LabelOptions) -> Vec<String>
+{
+	let mut skipped = vec![];
+
+	if let Some(part) = assembly.part.clone()
+	{
+		// Bought in parts don't get labels
+		if part.purchased { return skipped; }
+
+		let text = match (options.text, &part.number)
+		{
+			(LabelText::PartNumber, Some(number)) => number.clone(),
+			_                                     => assembly.name.clone(),
+		};
+
+		if assembly.engrave_label_with(&part.label_anchor, &text, options).is_err()
+		{
+			skipped.push(assembly.name.clone());
+		}
+		return skipped;
+	}
+
+	if let Shape3D::Composite{ref mut children, ..} = assembly.shape
+	{
+		for child in children
+		{
+			skipped.append(&mut engrave_part_labels(child, options));
+		}
+	}
+	skipped
+}
+//}}}
+
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::{cube, union, BooleanOp, Is3DObject};
+
+	//{{{
+	// A board with its label anchor on the top face
+	fn board(name: &str) -> Object3D
+	{
+		let mut board = cube(name, 100.0, 50.0, 18.0);
+		board.tag_as_part();
+		board.create_anchor("label").translate(50.0, 25.0, 18.0);
+		board
+	}
+	//}}}
+	//{{{
+	// The text and the extrusion height of an engraved label, and the label's placement
+	fn engraving(object: &Object3D) -> Option<(String, f64, (f64, f64, f64))>
+	{
+		match &object.shape
+		{
+			Shape3D::Composite{op: BooleanOp::difference, children} =>
+			{
+				let label = &children[1];
+				match &label.shape
+				{
+					Shape3D::Lextrude{height, child, ..} => match &child.shape
+					{
+						Shape3D::Text{text, ..} => Some((text.clone(), height.value(), label.ref_sys.get_translate())),
+						_                       => None,
+					},
+					_ => None,
+				}
+			},
+			_ => None,
+		}
+	}
+	//}}}
+
+	//{{{
+	#[test]
+	fn part_labels()
+	{
+		let mut numbered = board("Side");
+		numbered.set_part_number("B-01");
+		let unnumbered = board("Shelf");
+		let mut no_anchor = cube("Back", 100.0, 6.0, 300.0);
+		no_anchor.tag_as_part();
+		let mut bought = board("Hinge");
+		if let Some(part) = &mut bought.part { part.purchased = true; }
+		let helper = cube("Drill guide", 10.0, 10.0, 10.0);
+
+		let mut assembly = union("Cabinet", [numbered, unnumbered, no_anchor, bought, helper]);
+		let options = LabelOptions{ text: LabelText::PartNumber, depth: 1.5, ..Default::default() };
+		assert_eq!(engrave_part_labels(&mut assembly, &options), ["Back"]);
+
+		let parts = match &assembly.shape
+		{
+			Shape3D::Composite{children, ..} => children.clone(),
+			_                                => panic!("the assembly is no longer a union"),
+		};
+
+		// Part numbers are used where there are some, names otherwise.
+		// The label is sunk into the face by its depth and overshoots it a little.
+		let (text, height, (x, y, z)) = engraving(&parts[0]).unwrap();
+		assert_eq!(text, "B-01");
+		assert!((height - 1.51).abs() < 1e-9);
+		assert!((x - 50.0).abs() < 1e-9 && (y - 25.0).abs() < 1e-9 && (z - 16.5).abs() < 1e-9);
+		assert_eq!(engraving(&parts[1]).map(|(text, ..)| text), Some(String::from("Shelf")));
+
+		// Skipped, bought in and untagged objects are left alone
+		assert!(engraving(&parts[2]).is_none());
+		assert!(engraving(&parts[3]).is_none());
+		assert!(engraving(&parts[4]).is_none());
+
+		// The engraved part keeps its part info, its base doesn't
+		assert!(parts[0].is_part());
+		if let Shape3D::Composite{children, ..} = &parts[0].shape { assert!(!children[0].is_part()); }
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn missing_anchor()
+	{
+		let mut back = cube("Back", 100.0, 6.0, 300.0);
+		assert_eq!(back.engrave_label("front", "Back", 0.6), Err(LabelError::MissingAnchor{ object: String::from("Back"), anchor: String::from("front") }));
+		assert!(matches!(back.shape, Shape3D::Cube{..}));
+
+		let options = LabelOptions::default();
+		let mut labelled = board("Side");
+		labelled.set_label_anchor("front");
+		assert_eq!(engrave_part_labels(&mut labelled, &options), ["Side"]);
+		assert!(engraving(&labelled).is_none());
+	}
+	//}}}
+}
+//}}}
@@ -2,59 +2,96 @@
 #![allow(unused_variables)]
 #![allow(unused_mut)]
 #![allow(unused_imports)]
+#![allow(clippy::let_and_return)]
+#![allow(clippy::vec_init_then_push)]
 
 //extern crate rusty_scad;
 use rusty_scad::*;
-use std::f64::consts::PI;
+use std::sync::OnceLock;
 
-//{{{ Design Constants
+//{{{ Design Parameters
+
+// The variants of the bed. They can be changed with --parameters FILE (TOML or JSON) and --set NAME=VALUE,
+// e.g. --set MATTRESS_WIDTH=160
+static PARAMETERS : OnceLock<Parameters> = OnceLock::new();
+
+//{{{
+fn bed_parameters() -> Parameters
+{
+	let mut parameters = Parameters::new();
+	for parameter in
+	[
+		Parameter::number("MATTRESS_LENGTH", 200.0).range(180.0, 10.0, 220.0).unit("cm").group("Mattress"),
+		Parameter::number("MATTRESS_WIDTH",  140.0).range( 80.0, 10.0, 200.0).unit("cm").group("Mattress"),
+		Parameter::number("STORAGE_LENGTH",   41.0).range(  0.0,  1.0,  80.0).unit("cm").group("Mattress").description("Storage space at the head end"),
+
+		//                                         Rounded              400kg: 41.8 x 14.7 x 18.8, 500kg: 48.2 x 18.0 x 27.0
+		Parameter::number("MOTOR_WIDTH",  50.0).range(30.0, 0.1, 70.0).unit("cm").group("Motor"),
+		Parameter::number("MOTOR_HEIGHT", 16.0).range(10.0, 0.1, 25.0).unit("cm").group("Motor"),
+		Parameter::number("MOTOR_DEPTH",  25.0).range(15.0, 0.1, 35.0).unit("cm").group("Motor"),
+	]
+	{
+		parameters.add(parameter).expect("the names are valid OpenSCad variables");
+	}
+	parameters
+}
+//}}}
+//{{{
+fn parameter(name: &str) -> f64
+{
+	PARAMETERS.get_or_init(bed_parameters).value(name)
+}
+//}}}
+
+fn mattress_length()    -> Length { Length::cm(parameter("MATTRESS_LENGTH")) }
+fn storage_length()     -> Length { Length::cm(parameter("STORAGE_LENGTH")) }
+fn mattress_width()     -> Length { Length::cm(parameter("MATTRESS_WIDTH")) }
+fn motor_width()        -> Length { Length::cm(parameter("MOTOR_WIDTH")) }
+fn motor_height()       -> Length { Length::cm(parameter("MOTOR_HEIGHT")) }
+fn motor_depth()        -> Length { Length::cm(parameter("MOTOR_DEPTH")) }
 
-const MATTRESS_LENGTH         : f64 = 200.0;
-const STORAGE_LENGTH          : f64 =  41.0;
-const MATTRESS_WIDTH          : f64 = 140.0;
+fn frame_slat_spacing() -> Length { (mattress_length()-(FRAME_SLAT_COUNT as f64)*FRAME_SLAT_WIDTH)/((FRAME_SLAT_COUNT as f64)-0.5) }
+fn frame_slat_start()   -> Length { 0.5*(mattress_length()-FRAME_SLAT_WIDTH) }
+
+fn bed_length()         -> Length { mattress_length()+storage_length()+3.0*FRAME_THICKNESS }
+fn bed_width()          -> Length { mattress_width()+2.0*FRAME_THICKNESS }
+
+// The mattress will be centered
+fn foot_end()           -> Length { -mattress_length()/2.0 }
+fn head_end()           -> Length {  mattress_length()/2.0+(bed_length()-mattress_length())-2.0*FRAME_THICKNESS }
+//}}}
+
+//{{{ Design Constants
 
 const NOTCHES                 : i32 =     6;
 
-//                                    Rounded   400kg   500kg
-const MOTOR_WIDTH             : f64 =  50.0; // 41.8; // 48.2
-const MOTOR_HEIGHT            : f64 =  16.0; // 14.7; // 18.0
-const MOTOR_DEPTH             : f64 =  25.0; // 18.8; // 27.0
-const FRAME_HEIGHT            : f64 =  20.0;
-const FRAME_THICKNESS         : f64 =   3.0;
+const FRAME_HEIGHT            : Length = Length::cm(  20.0);
+const FRAME_THICKNESS         : Length = Length::cm(   3.0);
 
-const COVER_THICKNESS         : f64 =   0.5;
-const COVER_GROOVE_DEPTH      : f64 =   0.5;
-const CABLE_HOUSING_HEIGHT    : f64 =   3.0;
+const COVER_THICKNESS         : Length = Length::cm(   0.5);
+const COVER_GROOVE_DEPTH      : Length = Length::cm(   0.5);
+const CABLE_HOUSING_HEIGHT    : Length = Length::cm(   3.0);
 
 const FRAME_SLAT_COUNT        : i32 =    20;
-const FRAME_SLAT_WIDTH        : f64 =   5.0;
-const FRAME_SLAT_THICKNESS    : f64 =   3.0;
-const FRAME_SLAT_SPACING      : f64 = (MATTRESS_LENGTH-(FRAME_SLAT_COUNT as f64)*FRAME_SLAT_WIDTH)/((FRAME_SLAT_COUNT as f64)-0.5);
-const FRAME_SLAT_START        : f64 = 0.5*(MATTRESS_LENGTH-FRAME_SLAT_WIDTH);
+const FRAME_SLAT_WIDTH        : Length = Length::cm(   5.0);
+const FRAME_SLAT_THICKNESS    : Length = Length::cm(   3.0);
 
-const FOOT_CABLE_HEIGHT       : f64 = -0.5*FRAME_HEIGHT + 0.5*FRAME_SLAT_THICKNESS;
-const HEAD_CABLE_HEIGHT       : f64 = FOOT_CABLE_HEIGHT + 1.0;
+const FOOT_CABLE_HEIGHT       : Length = Length::mm(-0.5*FRAME_HEIGHT.to_mm() + 0.5*FRAME_SLAT_THICKNESS.to_mm());
+const HEAD_CABLE_HEIGHT       : Length = Length::mm(FOOT_CABLE_HEIGHT.to_mm() + Length::cm(1.0).to_mm());
 
-const BOTTOM_COVER_BOTTOM     : f64 = -0.5*FRAME_HEIGHT;
-const MIDDLE_COVER_BOTTOM     : f64 = BOTTOM_COVER_BOTTOM+COVER_THICKNESS+CABLE_HOUSING_HEIGHT;
+const BOTTOM_COVER_BOTTOM     : Length = Length::mm(-0.5*FRAME_HEIGHT.to_mm());
+const MIDDLE_COVER_BOTTOM     : Length = Length::mm(BOTTOM_COVER_BOTTOM.to_mm()+COVER_THICKNESS.to_mm()+CABLE_HOUSING_HEIGHT.to_mm());
 
-const BED_LENGTH              : f64 = MATTRESS_LENGTH+STORAGE_LENGTH+3.0*FRAME_THICKNESS;
-const BED_WIDTH               : f64 = MATTRESS_WIDTH+2.0*FRAME_THICKNESS;
+const ROLL_WIDTH              : Length = Length::cm(   0.7);
+const ROLL_DIAMETER           : Length = Length::cm(   2.5);
 
-// The mattress will be centered
-const FOOT_END                : f64 = -MATTRESS_LENGTH/2.0;
-const HEAD_END                : f64 =  MATTRESS_LENGTH/2.0+(BED_LENGTH-MATTRESS_LENGTH)-2.0*FRAME_THICKNESS;
-
-const ROLL_WIDTH              : f64 =  0.7;
-const ROLL_DIAMETER           : f64 =  2.5;
-
-const DRILL_INSET             : f64 =  1.0;
-const DRILL_DEPTH             : f64 = 18.0;
-const DRILL_BORE_MINOR        : f64 =  1.0;
-const DRILL_BORE_MAJOR        : f64 =  1.0;
-const DRILL_MID_MINOR         : f64 =   DRILL_INSET + 0.5*DRILL_BORE_MINOR;
-const DRILL_MID_MAJOR         : f64 =   DRILL_INSET + 0.5*DRILL_BORE_MAJOR;
-const DRILL_BOTTOM            : f64 = -(DRILL_DEPTH - 0.5*FRAME_HEIGHT);
+const DRILL_INSET             : Length = Length::cm(   1.0);
+const DRILL_DEPTH             : Length = Length::cm(  18.0);
+const DRILL_BORE_MINOR        : Length = Length::cm(   1.0);
+const DRILL_BORE_MAJOR        : Length = Length::cm(   1.0);
+const DRILL_MID_MINOR         : Length = Length::mm(DRILL_INSET.to_mm() + 0.5*DRILL_BORE_MINOR.to_mm());
+const DRILL_MID_MAJOR         : Length = Length::mm(DRILL_INSET.to_mm() + 0.5*DRILL_BORE_MAJOR.to_mm());
+const DRILL_BOTTOM            : Length = Length::mm(-(DRILL_DEPTH.to_mm() - 0.5*FRAME_HEIGHT.to_mm()));
 const FN                      : i32 = 20;
 //}}}
 
@@ -93,50 +130,50 @@ pub fn dovetails(name: &str, bottom_stage: f64) -> Vec<Object3D>
 // TODO: Maybe remove these.
 // Rationale: It should be easy to remove the covers. So they should simply be screwed in place
 //{{{
-pub fn cover_cutouts_front(name: &str, y: f64) -> Vec<Object3D>
+pub fn cover_cutouts_front(name: &str, y: Length) -> Vec<Object3D>
 {
 	let mut parts = vec![];
 
 	//{{{
 	parts.push(cube_coords(&(String::from("bottom cover groove for ")+name),
-		-MATTRESS_WIDTH/2.0-0.5,
+		-mattress_width()/2.0-Length::cm(0.5),
 		y-COVER_GROOVE_DEPTH,
 		BOTTOM_COVER_BOTTOM,
 
-		 MATTRESS_WIDTH/2.0+0.5,
+		 mattress_width()/2.0+Length::cm(0.5),
 		y+COVER_GROOVE_DEPTH,
 		BOTTOM_COVER_BOTTOM+COVER_THICKNESS,
 	));
 	//}}}
 	//{{{
 	parts.push(cube_coords(&(String::from("left side middle cover groove for ")+name),
-		-MATTRESS_WIDTH/2.0-0.5,
+		-mattress_width()/2.0-Length::cm(0.5),
 		y-COVER_GROOVE_DEPTH,
 		MIDDLE_COVER_BOTTOM,
 
-		-MOTOR_WIDTH/2.0-FRAME_THICKNESS+COVER_GROOVE_DEPTH,
+		-motor_width()/2.0-FRAME_THICKNESS+COVER_GROOVE_DEPTH,
 		y+COVER_GROOVE_DEPTH,
 		MIDDLE_COVER_BOTTOM+COVER_THICKNESS,
 	));
 	//}}}
 	//{{{
 	parts.push(cube_coords(&(String::from("right side middle cover groove for ")+name),
-		 MATTRESS_WIDTH/2.0+0.5,
+		 mattress_width()/2.0+Length::cm(0.5),
 		y-COVER_GROOVE_DEPTH,
 		MIDDLE_COVER_BOTTOM,
 
-		 MOTOR_WIDTH/2.0+FRAME_THICKNESS-COVER_GROOVE_DEPTH,
+		 motor_width()/2.0+FRAME_THICKNESS-COVER_GROOVE_DEPTH,
 		y+COVER_GROOVE_DEPTH,
 		MIDDLE_COVER_BOTTOM+COVER_THICKNESS,
 	));
 	//}}}
 	//{{{
 	parts.push(cube_coords(&(String::from("middle side middle cover groove for ")+name),
-		-MOTOR_WIDTH/2.0-COVER_GROOVE_DEPTH,
+		-motor_width()/2.0-COVER_GROOVE_DEPTH,
 		y-COVER_GROOVE_DEPTH,
 		MIDDLE_COVER_BOTTOM,
 
-		 MOTOR_WIDTH/2.0+COVER_GROOVE_DEPTH,
+		 motor_width()/2.0+COVER_GROOVE_DEPTH,
 		y+COVER_GROOVE_DEPTH,
 		MIDDLE_COVER_BOTTOM+COVER_THICKNESS,
 	));
@@ -147,29 +184,29 @@ pub fn cover_cutouts_front(name: &str, y: f64) -> Vec<Object3D>
 //}}}
 
 //{{{
-pub fn cover_cutouts_side(name: &str, x: f64) -> Vec<Object3D>
+pub fn cover_cutouts_side(name: &str, x: Length) -> Vec<Object3D>
 {
 	let mut parts = vec![];
 
 	//{{{
 	parts.push(cube_coords(&(String::from("middle cover groove for ")+name),
 		x-COVER_GROOVE_DEPTH,
-		HEAD_END+COVER_GROOVE_DEPTH,
+		head_end()+COVER_GROOVE_DEPTH,
 		MIDDLE_COVER_BOTTOM,
 
 		x+COVER_GROOVE_DEPTH,
-		0.5*MATTRESS_LENGTH+FRAME_THICKNESS-COVER_GROOVE_DEPTH,
+		0.5*mattress_length()+FRAME_THICKNESS-COVER_GROOVE_DEPTH,
 		MIDDLE_COVER_BOTTOM+COVER_THICKNESS,
 	));
 	//}}}
 	//{{{
 	parts.push(cube_coords(&(String::from("bottom cover groove for ")+name),
 		x-COVER_GROOVE_DEPTH,
-		HEAD_END+COVER_GROOVE_DEPTH,
+		head_end()+COVER_GROOVE_DEPTH,
 		BOTTOM_COVER_BOTTOM,
 
 		x+COVER_GROOVE_DEPTH,
-		0.5*MATTRESS_LENGTH+FRAME_THICKNESS-COVER_GROOVE_DEPTH,
+		0.5*mattress_length()+FRAME_THICKNESS-COVER_GROOVE_DEPTH,
 		BOTTOM_COVER_BOTTOM+COVER_THICKNESS,
 	));
 	//}}}
@@ -184,7 +221,7 @@ pub fn drill_minor(name: &str) -> Vec<Object3D>
 {
 	let mut parts = vec![];
 
-	let mut drill = cylinder(&(String::from("minor vertical drill for ")+name), DRILL_DEPTH+1.0, 0.5*DRILL_BORE_MINOR, 0.5*DRILL_BORE_MINOR);
+	let mut drill = cylinder(&(String::from("minor vertical drill for ")+name), DRILL_DEPTH+Length::cm(1.0), 0.5*DRILL_BORE_MINOR, 0.5*DRILL_BORE_MINOR);
 	drill.translate_z(DRILL_BOTTOM);
 	drill.set_fn(20);
 	//drill.set_debug();
@@ -201,10 +238,10 @@ pub fn sideboard(name: &str) -> Object3D
 	//{{{
 	let mut board = cube_coords(&(String::from("base board for ")+name),
 		-FRAME_THICKNESS/2.0,
-		FOOT_END,
+		foot_end(),
 		-FRAME_HEIGHT/2.0,
 		FRAME_THICKNESS/2.0,
-		HEAD_END,
+		head_end(),
 		FRAME_HEIGHT/2.0);
 	//}}}
 
@@ -215,11 +252,11 @@ pub fn sideboard(name: &str) -> Object3D
 	let mut parts = vec![board];
 	{
 		let mut dovetails_head = dovetails(name, 0.0);
-		for dovetail in &mut dovetails_head { dovetail.translate_y(HEAD_END+0.5*FRAME_THICKNESS); }
+		for dovetail in &mut dovetails_head { dovetail.translate_y(head_end()+0.5*FRAME_THICKNESS); }
 		parts.append(&mut dovetails_head);
 
 		let mut dovetails_foot = dovetails(name, 0.0);
-		for dovetail in &mut dovetails_foot { dovetail.translate_y(FOOT_END-0.5*FRAME_THICKNESS); }
+		for dovetail in &mut dovetails_foot { dovetail.translate_y(foot_end()-0.5*FRAME_THICKNESS); }
 		parts.append(&mut dovetails_foot);
 	}
 	//let mut board = union(name, parts);
@@ -230,20 +267,20 @@ pub fn sideboard(name: &str) -> Object3D
 
 	{
 		let mut a = board.create_anchor("Foot pulley vertical");
-		a.translate(0.0, FOOT_END-0.5*FRAME_THICKNESS, FOOT_CABLE_HEIGHT); // TODO
-		//a.rel_rotate_z(135.0);
-		a.rel_rotate(90.0, 315.0, 0.0);
+		a.translate(0.0, foot_end()-0.5*FRAME_THICKNESS, FOOT_CABLE_HEIGHT); // TODO
+		// The pulley axis at 45° between -x and -y, with the anchor's y-axis up
+		a.look_at(point3D(-10.0, (foot_end()-0.5*FRAME_THICKNESS).to_mm()-10.0, FOOT_CABLE_HEIGHT.to_mm()), vector3D(0.0, 0.0, 1.0));
 	}
 	//{
 	//	let mut a = board.create_anchor("Head pulley vertical");
-	//	a.translate(0.0, HEAD_END+0.5*FRAME_THICKNESS, HEAD_CABLE_HEIGHT); // TODO
+	//	a.translate(0.0, head_end()+0.5*FRAME_THICKNESS, HEAD_CABLE_HEIGHT); // TODO
 	//	a.rel_rotate(90.0, 225.0, 0.0);
 	//}
 	{
 		let mut a = board.create_anchor("Foot pulley horizontal");
-		a.translate(0.0, FOOT_END-0.5*FRAME_THICKNESS, FOOT_CABLE_HEIGHT); // TODO
+		a.translate(0.0, foot_end()-0.5*FRAME_THICKNESS, FOOT_CABLE_HEIGHT); // TODO
 		a.rel_rotate(90.0, 135.0, 90.0);
-		a.rel_translate_z( 5.6);
+		a.rel_translate_z(Length::cm(5.6));
 	}
 	//}}}
 
@@ -251,7 +288,7 @@ pub fn sideboard(name: &str) -> Object3D
 	//{{{ Cut out the notches for the bulkhead
 	{
 		let mut bulkhead_cutouts = dovetails(name, 0.5);
-		for dovetail in &mut bulkhead_cutouts { dovetail.translate(2.0, MATTRESS_LENGTH/2.0+0.5*FRAME_THICKNESS, 0.0); }
+		for dovetail in &mut bulkhead_cutouts { dovetail.translate(Length::cm(2.0), mattress_length()/2.0+0.5*FRAME_THICKNESS, 0.0); }
 		parts.append(&mut bulkhead_cutouts);
 	}
 	//}}}
@@ -263,14 +300,14 @@ pub fn sideboard(name: &str) -> Object3D
 	//{{{ Add the drills
 	{
 		let mut drill = drill_minor(name);
-		drill[0].translate(-0.5*FRAME_THICKNESS+DRILL_MID_MINOR, HEAD_END+FRAME_THICKNESS-DRILL_MID_MINOR, 0.0);
+		drill[0].translate(-0.5*FRAME_THICKNESS+DRILL_MID_MINOR, head_end()+FRAME_THICKNESS-DRILL_MID_MINOR, 0.0);
 		parts.append(&mut drill);
 	}
 	//}}}
 	//{{{ Add the drills
 	{
 		let mut drill = drill_minor(name);
-		drill[0].translate(-0.5*FRAME_THICKNESS+DRILL_MID_MINOR, FOOT_END-FRAME_THICKNESS+DRILL_MID_MINOR, 0.0);
+		drill[0].translate(-0.5*FRAME_THICKNESS+DRILL_MID_MINOR, foot_end()-FRAME_THICKNESS+DRILL_MID_MINOR, 0.0);
 		parts.append(&mut drill);
 	}
 	//}}}
@@ -316,18 +353,18 @@ pub fn sideboard(name: &str) -> Object3D
 //{{{
 pub fn frontboard(name: &str) -> Object3D
 {
-	let board = cube(&(String::from("base board for ")+name), BED_WIDTH-2.0*FRAME_THICKNESS, FRAME_THICKNESS, FRAME_HEIGHT);
+	let board = cube(&(String::from("base board for ")+name), bed_width()-2.0*FRAME_THICKNESS, FRAME_THICKNESS, FRAME_HEIGHT);
 
 	//{{{ Add the end dovetails
 
 	let mut parts = vec![board];
 	{
 		let mut dovetails_left = dovetails(name, 1.0);
-		for dovetail in &mut dovetails_left { dovetail.translate_x(-0.5*(MATTRESS_WIDTH+FRAME_THICKNESS)); }
+		for dovetail in &mut dovetails_left { dovetail.translate_x(-0.5*(mattress_width()+FRAME_THICKNESS)); }
 		parts.append(&mut dovetails_left);
 
 		let mut dovetails_right = dovetails(name, 1.0);
-		for dovetail in &mut dovetails_right { dovetail.translate_x(0.5*(MATTRESS_WIDTH+FRAME_THICKNESS)); }
+		for dovetail in &mut dovetails_right { dovetail.translate_x(0.5*(mattress_width()+FRAME_THICKNESS)); }
 		parts.append(&mut dovetails_right);
 	}
 	let mut board = union(name, parts);
@@ -346,12 +383,12 @@ pub fn headboard(name: &str) -> Object3D
 	//{{{ Cut out the notches for the bulkhead spacer
 	{
 		let mut bulkhead_cutouts = dovetails(name, 0.5);
-		for dovetail in &mut bulkhead_cutouts { dovetail.translate(-0.5*(MOTOR_WIDTH+FRAME_THICKNESS), -2.0, 0.0); }
+		for dovetail in &mut bulkhead_cutouts { dovetail.translate(-0.5*(motor_width()+FRAME_THICKNESS), Length::cm(-2.0), 0.0); }
 		parts.append(&mut bulkhead_cutouts);
 	}
 	{
 		let mut bulkhead_cutouts = dovetails(name, 0.5);
-		for dovetail in &mut bulkhead_cutouts { dovetail.translate( 0.5*(MOTOR_WIDTH+FRAME_THICKNESS), -2.0, 0.0); }
+		for dovetail in &mut bulkhead_cutouts { dovetail.translate( 0.5*(motor_width()+FRAME_THICKNESS), Length::cm(-2.0), 0.0); }
 		parts.append(&mut bulkhead_cutouts);
 	}
 	//}}}
@@ -362,14 +399,14 @@ pub fn headboard(name: &str) -> Object3D
 	//{{{ Add the drills
 	{
 		let mut drill = drill_minor(name);
-		drill[0].translate(-0.5*BED_WIDTH+DRILL_MID_MINOR, 0.5*FRAME_THICKNESS-DRILL_MID_MINOR, 0.0);
+		drill[0].translate(-0.5*bed_width()+DRILL_MID_MINOR, 0.5*FRAME_THICKNESS-DRILL_MID_MINOR, 0.0);
 		parts.append(&mut drill);
 	}
 	//}}}
 	//{{{ Add the drills
 	{
 		let mut drill = drill_minor(name);
-		drill[0].translate( 0.5*BED_WIDTH-DRILL_MID_MINOR, 0.5*FRAME_THICKNESS-DRILL_MID_MINOR, 0.0);
+		drill[0].translate( 0.5*bed_width()-DRILL_MID_MINOR, 0.5*FRAME_THICKNESS-DRILL_MID_MINOR, 0.0);
 		parts.append(&mut drill);
 	}
 	//}}}
@@ -382,18 +419,18 @@ pub fn headboard(name: &str) -> Object3D
 //{{{
 pub fn bulkhead(name: &str) -> Object3D
 {
-	let board = cube(&(String::from("base board for ")+name), MATTRESS_WIDTH, FRAME_THICKNESS, FRAME_HEIGHT);
+	let board = cube(&(String::from("base board for ")+name), mattress_width(), FRAME_THICKNESS, FRAME_HEIGHT);
 
 	//{{{ Add the end dovetails
 
 	let mut parts = vec![board];
 	{
 		let mut dovetails_left = dovetails(name, 0.5);
-		for dovetail in &mut dovetails_left { dovetail.translate_x(-(0.5*(MATTRESS_WIDTH+FRAME_THICKNESS)-2.0)); }
+		for dovetail in &mut dovetails_left { dovetail.translate_x(-(0.5*(mattress_width()+FRAME_THICKNESS)-Length::cm(2.0))); }
 		parts.append(&mut dovetails_left);
 
 		let mut dovetails_right = dovetails(name, 0.5);
-		for dovetail in &mut dovetails_right { dovetail.translate_x(0.5*(MATTRESS_WIDTH+FRAME_THICKNESS)-2.0); }
+		for dovetail in &mut dovetails_right { dovetail.translate_x(0.5*(mattress_width()+FRAME_THICKNESS)-Length::cm(2.0)); }
 		parts.append(&mut dovetails_right);
 	}
 	let mut board = union(name, parts);
@@ -403,12 +440,12 @@ pub fn bulkhead(name: &str) -> Object3D
 	//{{{ Cut out the notches for the bulkhead spacer
 	{
 		let mut bulkhead_cutouts = dovetails(name, 0.5);
-		for dovetail in &mut bulkhead_cutouts { dovetail.translate(-0.5*(MOTOR_WIDTH+FRAME_THICKNESS), 2.0, 0.0); }
+		for dovetail in &mut bulkhead_cutouts { dovetail.translate(-0.5*(motor_width()+FRAME_THICKNESS), Length::cm(2.0), 0.0); }
 		parts.append(&mut bulkhead_cutouts);
 	}
 	{
 		let mut bulkhead_cutouts = dovetails(name, 0.5);
-		for dovetail in &mut bulkhead_cutouts { dovetail.translate( 0.5*(MOTOR_WIDTH+FRAME_THICKNESS), 2.0, 0.0); }
+		for dovetail in &mut bulkhead_cutouts { dovetail.translate( 0.5*(motor_width()+FRAME_THICKNESS), Length::cm(2.0), 0.0); }
 		parts.append(&mut bulkhead_cutouts);
 	}
 	//}}}
@@ -431,11 +468,11 @@ pub fn bulkhead_spacer(name: &str) -> Object3D
 	//{{{
 	let board = cube_coords(&(String::from("base board for ")+name),
 	-0.5*FRAME_THICKNESS,
-	HEAD_END,
+	head_end(),
 	BOTTOM_COVER_BOTTOM+COVER_THICKNESS,
 
 	0.5*FRAME_THICKNESS,
-	0.5*MATTRESS_LENGTH+FRAME_THICKNESS,
+	0.5*mattress_length()+FRAME_THICKNESS,
 	0.5*FRAME_HEIGHT);
 	//}}}
 
@@ -445,11 +482,11 @@ pub fn bulkhead_spacer(name: &str) -> Object3D
 	let mut parts = vec![board];
 	{
 		let mut dovetails_head = dovetails(name, 0.5);
-		for dovetail in &mut dovetails_head { dovetail.translate_y(HEAD_END+0.5*FRAME_THICKNESS-2.0); }
+		for dovetail in &mut dovetails_head { dovetail.translate_y(head_end()+0.5*FRAME_THICKNESS-Length::cm(2.0)); }
 		parts.append(&mut dovetails_head);
 
 		let mut dovetails_foot = dovetails(name, 0.5);
-		for dovetail in &mut dovetails_foot { dovetail.translate_y(0.5*MATTRESS_LENGTH+0.5*FRAME_THICKNESS+2.0); }
+		for dovetail in &mut dovetails_foot { dovetail.translate_y(0.5*mattress_length()+0.5*FRAME_THICKNESS+Length::cm(2.0)); }
 		parts.append(&mut dovetails_foot);
 	}
 	let mut board = union(name, parts);
@@ -487,11 +524,11 @@ pub fn frame_slat(name: &str) -> Object3D
 {
 	//{{{
 	let board = cube_coords(&(String::from("base board for ")+name),
-		-0.5*MATTRESS_WIDTH,
+		-0.5*mattress_width(),
 		-0.5*FRAME_SLAT_WIDTH,
 		-0.5*FRAME_SLAT_THICKNESS,
 
-		 0.5*MATTRESS_WIDTH,
+		 0.5*mattress_width(),
 		 0.5*FRAME_SLAT_WIDTH,
 		 0.5*FRAME_SLAT_THICKNESS);
 	//}}}
@@ -500,120 +537,68 @@ pub fn frame_slat(name: &str) -> Object3D
 }
 //}}}
 
-//{{{
-pub fn arc(mut start_point: Point2D, mut end_point: Point2D, pivot: Point2D, steps: i32) -> Vec<Point2D>
-{
-	let start_vector       = pivot - &start_point;
-	let   end_vector       = pivot -   &end_point;
-
-	let start_radius : f64 = start_vector.l2_norm();
-	let   end_radius : f64 =   end_vector.l2_norm();
-	let  step_radius : f64 = (end_radius-start_radius)/(steps.abs() as f64);
 
-	let start_angle  : f64 = start_vector[0].atan2(start_vector[1]);
-	let end_angle    : f64 =   end_vector[0].atan2(  end_vector[1]);
-
-	let mut angle_range  : f64 = end_angle - start_angle;
-	if steps.signum() <= 0
-	{
-		eprintln!("arc: steps is negative");
-		if angle_range < 0.0
-		{
-			eprintln!("arc: angle too small");
-			angle_range += 2.0*PI;
-		};
-		if angle_range > PI
-		{
-			eprintln!("arc: angle too big");
-			angle_range -= 2.0*PI;
-		};
-	}
-	let step_angle   : f64 = angle_range/(steps.abs() as f64);
 
-
-	let steps = steps.abs();
-
-	eprintln!("arc:\n\tstart_vector = {}\n\tend_vector = {}\n\tstart_angle = {}\n\tend_angle = {}\n\tangle_range = {}\n\tstart_radius = {}\n\tend_radius = {}\n\tsteps = {}", start_vector, end_vector, start_angle.to_degrees(), end_angle.to_degrees(), angle_range.to_degrees(), start_radius, end_radius, steps);
-
-	let mut points = vec![start_point];
-
-	for i in 0..steps.abs()
-	{
-		let phi = start_angle  + (i as f64)*step_angle;
-		let r   = start_radius + (i as f64)*step_radius;
-		let v   = vector2D(phi.sin(), phi.cos());
-
-		eprintln!("		step = {}, phi = {}, r = {}, v = {}", i, phi.to_degrees(), r, v);
-
-
-		points.push(pivot + &(v*r));
-		//points.push(pivot + &(vector2D(phi.cos(), phi.sin())*r));
-	}
-
-	points
+//{{{
+// Path2D takes its points in mm
+fn point(x: impl Into<Length>, y: impl Into<Length>) -> Point2D
+{
+	point2D(x.into().to_mm(), y.into().to_mm())
 }
 //}}}
 
-
 //{{{
 pub fn sprenger_block_3511100355_cutout(name: &str) -> Object3D
 {
-	const ROLL_DIAMETER           : f64 =  2.5;
-	const ROLL_DIAMETER_INNER     : f64 =  1.875;
-	const SCREW_HEAD_DIAMETER     : f64 =  0.75;
-	const SCREW_HEAD_HEIGHT       : f64 =  0.125;
-	const WIRE_DIAMETER           : f64 =  0.3;
-	const ROLL_WIDTH              : f64 =  0.7;
-	const ROLL_HEIGHT             : f64 =  1.7;
-
-
-	const BLOCK_WIDTH             : f64 = 1.02;
-	const BLOCK_DIAMETER          : f64 =  2.5;
-	const BLOCK_HEIGHT            : f64 =  3.38;
-	const BASE_WIDTH              : f64 =  3.5;
-	const SHEET_THICKNESS         : f64 =  0.13;
-	const LOWER_BEND_RADIUS       : f64 =  0.17;
-
-	const BLOCK_HEIGHT_SLANT      : f64 =  2.2;
-	const SLANT_INSET             : f64 =  0.475;
-	const SLANT_HEIGHT            : f64 = BLOCK_HEIGHT-BLOCK_HEIGHT_SLANT;
+	const ROLL_DIAMETER           : Length = Length::cm(   2.5);
+	const ROLL_DIAMETER_INNER     : Length = Length::cm( 1.875);
+	const SCREW_HEAD_DIAMETER     : Length = Length::cm(  0.75);
+	const SCREW_HEAD_HEIGHT       : Length = Length::cm( 0.125);
+	const WIRE_DIAMETER           : Length = Length::cm(   0.3);
+	const ROLL_WIDTH              : Length = Length::cm(   0.7);
+	const ROLL_HEIGHT             : Length = Length::cm(   1.7);
+
+
+	const BLOCK_WIDTH             : Length = Length::cm(  1.02);
+	const BLOCK_DIAMETER          : Length = Length::cm(   2.5);
+	const BLOCK_HEIGHT            : Length = Length::cm(  3.38);
+	const BASE_WIDTH              : Length = Length::cm(   3.5);
+	const SHEET_THICKNESS         : Length = Length::cm(  0.13);
+	const LOWER_BEND_RADIUS       : Length = Length::cm(  0.17);
+
+	const BLOCK_HEIGHT_SLANT      : Length = Length::cm(   2.2);
+	const SLANT_INSET             : Length = Length::cm( 0.475);
+	const SLANT_HEIGHT            : Length = Length::mm(BLOCK_HEIGHT.to_mm()-BLOCK_HEIGHT_SLANT.to_mm());
 	#[allow(non_snake_case)]
 	let   SLANT_ANGLE             : f64 = (SLANT_INSET/SLANT_HEIGHT).atan().to_degrees();
 
 	//{{{
-	let mut points : Vec<Point2D> = vec![
-		point2D(-0.5*BASE_WIDTH, 0.0),
-		point2D(-0.5*BASE_WIDTH, SHEET_THICKNESS),
-	];
-
-
-	points.append(&mut arc(point2D(-0.5*BLOCK_WIDTH-LOWER_BEND_RADIUS, SHEET_THICKNESS),                    // Start
-				           point2D(-0.5*BLOCK_WIDTH                  , SHEET_THICKNESS+LOWER_BEND_RADIUS),  // End
-				           point2D(-0.5*BLOCK_WIDTH-LOWER_BEND_RADIUS, SHEET_THICKNESS+LOWER_BEND_RADIUS),  // Pivot
-				            FN/4));                                                                           // Steps
-
-
-	points.append(&mut arc(point2D(-0.5*BLOCK_WIDTH,                   BLOCK_HEIGHT-0.5*BLOCK_WIDTH),       // Start
-				           point2D( 0.5*BLOCK_WIDTH,                   BLOCK_HEIGHT-0.5*BLOCK_WIDTH),       // End
-				           point2D(0.0,                                BLOCK_HEIGHT-0.5*BLOCK_WIDTH),       // Pivot
-				            FN/2));                                                                           // Steps
-
-	points.append(&mut arc(point2D( 0.5*BLOCK_WIDTH,                   SHEET_THICKNESS+LOWER_BEND_RADIUS),  // Start
-				           point2D( 0.5*BLOCK_WIDTH+LOWER_BEND_RADIUS, SHEET_THICKNESS),                    // End
-				           point2D( 0.5*BLOCK_WIDTH+LOWER_BEND_RADIUS, SHEET_THICKNESS+LOWER_BEND_RADIUS),  // Pivot
-				           -FN/2));                                                                           // Steps
-
-	points.push(point2D( 0.5*BASE_WIDTH, SHEET_THICKNESS));
-	points.push(point2D( 0.5*BASE_WIDTH, 0.0));
+	let mut poly = Path2D::new()
+		.move_to(point(-0.5*BASE_WIDTH,                   0.0))
+		.line_to(point(-0.5*BASE_WIDTH,                   SHEET_THICKNESS))
+		.line_to(point(-0.5*BLOCK_WIDTH-LOWER_BEND_RADIUS, SHEET_THICKNESS))
+		.arc_to_centre(point(-0.5*BLOCK_WIDTH,                   SHEET_THICKNESS+LOWER_BEND_RADIUS),  // End
+		               point(-0.5*BLOCK_WIDTH-LOWER_BEND_RADIUS, SHEET_THICKNESS+LOWER_BEND_RADIUS),  // Centre
+		               ArcDirection::CounterClockwise)
+		.line_to(point(-0.5*BLOCK_WIDTH,                   BLOCK_HEIGHT-0.5*BLOCK_WIDTH))
+		.arc_to_centre(point( 0.5*BLOCK_WIDTH,                   BLOCK_HEIGHT-0.5*BLOCK_WIDTH),       // End
+		               point(0.0,                                BLOCK_HEIGHT-0.5*BLOCK_WIDTH),       // Centre
+		               ArcDirection::Clockwise)
+		.line_to(point( 0.5*BLOCK_WIDTH,                   SHEET_THICKNESS+LOWER_BEND_RADIUS))
+		.arc_to_centre(point( 0.5*BLOCK_WIDTH+LOWER_BEND_RADIUS, SHEET_THICKNESS),                    // End
+		               point( 0.5*BLOCK_WIDTH+LOWER_BEND_RADIUS, SHEET_THICKNESS+LOWER_BEND_RADIUS),  // Centre
+		               ArcDirection::CounterClockwise)
+		.line_to(point( 0.5*BASE_WIDTH,                   SHEET_THICKNESS))
+		.line_to(point( 0.5*BASE_WIDTH,                   0.0))
+		.close()
+		.polygon(name);
 	//}}}
 
-	let mut poly = polygon(name, points);
-
 	poly.linear_extrude(BLOCK_DIAMETER);
 	poly.rotate_x(90.0);
 	poly.translate_y(0.5*BLOCK_DIAMETER);
 
-	let mut slant1 = cube_coords("slant for sprenger_block_3511100355", -BASE_WIDTH, 0.0, -1.0, BASE_WIDTH, 1.0, 2.0);
+	let mut slant1 = cube_coords("slant for sprenger_block_3511100355", -BASE_WIDTH, 0.0, Length::cm(-1.0), BASE_WIDTH, Length::cm(1.0), Length::cm(2.0));
 	slant1.rotate_x(SLANT_ANGLE);
 	slant1.translate(0.0, 0.5*BLOCK_DIAMETER, BLOCK_HEIGHT_SLANT);
 
@@ -626,16 +611,15 @@ pub fn sprenger_block_3511100355_cutout(name: &str) -> Object3D
 	//{{{ Add the screw heads
 
 	let mut screw_head1 = cylinder(&(String::from("Lower half roll for ") + name), SCREW_HEAD_HEIGHT, 0.5*SCREW_HEAD_DIAMETER, 0.0);
-	screw_head1.set_fn(FN);
 	screw_head1.rotate_y(90.0);
 	screw_head1.translate(0.5*BLOCK_WIDTH, 0.0, ROLL_HEIGHT);
 	let mut screw_head2 = cylinder(&(String::from("Lower half roll for ") + name), SCREW_HEAD_HEIGHT, 0.5*SCREW_HEAD_DIAMETER, 0.0);
-	screw_head2.set_fn(FN);
 	screw_head2.rotate_y(-90.0);
 	screw_head2.translate(-0.5*BLOCK_WIDTH, 0.0, ROLL_HEIGHT);
 	//}}}
 
 	let mut block = union(name, [housing, screw_head1, screw_head2]);
+	block.set_resolution(Resolution::face_number(FN));
 
 	//{{{ Anchors::Contact: y and z align to the cable
 	{
@@ -760,80 +744,69 @@ pub fn sprenger_block_3511100355_cutout(name: &str) -> Object3D
 //{{{
 pub fn sprenger_block_3511100355(name: &str) -> Object3D
 {
-	const ROLL_DIAMETER           : f64 =  2.5;
-	const ROLL_DIAMETER_INNER     : f64 =  1.875;
-	const SCREW_HEAD_DIAMETER     : f64 =  0.75;
-	const SCREW_HEAD_HEIGHT       : f64 =  0.125;
-	const WIRE_DIAMETER           : f64 =  0.3;
-	const ROLL_WIDTH              : f64 =  0.7;
-	const ROLL_HEIGHT             : f64 =  1.7;
-
-
-	const BLOCK_WIDTH             : f64 = 1.02;
-	const BLOCK_DIAMETER          : f64 =  2.5;
-	const BLOCK_HEIGHT            : f64 =  3.38;
-	const BASE_WIDTH              : f64 =  3.5;
-	const SHEET_THICKNESS         : f64 =  0.13;
-	const LOWER_BEND_RADIUS       : f64 =  0.17;
-
-	const BLOCK_HEIGHT_SLANT      : f64 =  2.2;
-	const SLANT_INSET             : f64 =  0.475;
-	const SLANT_HEIGHT            : f64 = BLOCK_HEIGHT-BLOCK_HEIGHT_SLANT;
+	const ROLL_DIAMETER           : Length = Length::cm(   2.5);
+	const ROLL_DIAMETER_INNER     : Length = Length::cm( 1.875);
+	const SCREW_HEAD_DIAMETER     : Length = Length::cm(  0.75);
+	const SCREW_HEAD_HEIGHT       : Length = Length::cm( 0.125);
+	const WIRE_DIAMETER           : Length = Length::cm(   0.3);
+	const ROLL_WIDTH              : Length = Length::cm(   0.7);
+	const ROLL_HEIGHT             : Length = Length::cm(   1.7);
+
+
+	const BLOCK_WIDTH             : Length = Length::cm(  1.02);
+	const BLOCK_DIAMETER          : Length = Length::cm(   2.5);
+	const BLOCK_HEIGHT            : Length = Length::cm(  3.38);
+	const BASE_WIDTH              : Length = Length::cm(   3.5);
+	const SHEET_THICKNESS         : Length = Length::cm(  0.13);
+	const LOWER_BEND_RADIUS       : Length = Length::cm(  0.17);
+
+	const BLOCK_HEIGHT_SLANT      : Length = Length::cm(   2.2);
+	const SLANT_INSET             : Length = Length::cm( 0.475);
+	const SLANT_HEIGHT            : Length = Length::mm(BLOCK_HEIGHT.to_mm()-BLOCK_HEIGHT_SLANT.to_mm());
 	#[allow(non_snake_case)]
 	let   SLANT_ANGLE             : f64 = (SLANT_INSET/SLANT_HEIGHT).atan().to_degrees();
 
 	//{{{
-	let mut points : Vec<Point2D> = vec![
-		point2D(-0.5*BASE_WIDTH, 0.0),
-		point2D(-0.5*BASE_WIDTH, SHEET_THICKNESS),
-	];
-
-
-	points.append(&mut arc(point2D(-0.5*BLOCK_WIDTH-LOWER_BEND_RADIUS, SHEET_THICKNESS),                    // Start
-				           point2D(-0.5*BLOCK_WIDTH                  , SHEET_THICKNESS+LOWER_BEND_RADIUS),  // End
-				           point2D(-0.5*BLOCK_WIDTH-LOWER_BEND_RADIUS, SHEET_THICKNESS+LOWER_BEND_RADIUS),  // Pivot
-				            FN/4));                                                                           // Steps
-
-
-	points.append(&mut arc(point2D(-0.5*BLOCK_WIDTH,                   BLOCK_HEIGHT-0.5*BLOCK_WIDTH),       // Start
-				           point2D( 0.5*BLOCK_WIDTH,                   BLOCK_HEIGHT-0.5*BLOCK_WIDTH),       // End
-				           point2D(0.0,                                BLOCK_HEIGHT-0.5*BLOCK_WIDTH),       // Pivot
-				            FN/2));                                                                           // Steps
-
-	points.append(&mut arc(point2D( 0.5*BLOCK_WIDTH,                   SHEET_THICKNESS+LOWER_BEND_RADIUS),  // Start
-				           point2D( 0.5*BLOCK_WIDTH+LOWER_BEND_RADIUS, SHEET_THICKNESS),                    // End
-				           point2D( 0.5*BLOCK_WIDTH+LOWER_BEND_RADIUS, SHEET_THICKNESS+LOWER_BEND_RADIUS),  // Pivot
-				           -FN/4));                                                                           // Steps
-
-	points.push(point2D( 0.5*BASE_WIDTH, SHEET_THICKNESS));
-	points.push(point2D( 0.5*BASE_WIDTH, 0.0));
-
-	points.append(&mut arc(point2D( 0.5*BLOCK_WIDTH+LOWER_BEND_RADIUS, 0.0),                                // Start
-				           point2D( 0.5*BLOCK_WIDTH-SHEET_THICKNESS,   SHEET_THICKNESS+LOWER_BEND_RADIUS),  // End
-				           point2D( 0.5*BLOCK_WIDTH+LOWER_BEND_RADIUS, SHEET_THICKNESS+LOWER_BEND_RADIUS),  // Pivot
-				           -FN/4));                                                                           // Steps
-
-	points.append(&mut arc(point2D( 0.5*BLOCK_WIDTH-SHEET_THICKNESS,   BLOCK_HEIGHT-0.5*BLOCK_WIDTH),       // Start
-				           point2D(-0.5*BLOCK_WIDTH+SHEET_THICKNESS,   BLOCK_HEIGHT-0.5*BLOCK_WIDTH),       // End
-				           point2D(0.0,                                BLOCK_HEIGHT-0.5*BLOCK_WIDTH),       // Pivot
-				             FN/4));                                                                           // Steps
-
-	points.append(&mut arc(
-				           point2D(-0.5*BLOCK_WIDTH+SHEET_THICKNESS,   SHEET_THICKNESS+LOWER_BEND_RADIUS),  // Start
-	                       point2D(-0.5*BLOCK_WIDTH-LOWER_BEND_RADIUS, 0.0),                                // End
-				           point2D(-0.5*BLOCK_WIDTH-LOWER_BEND_RADIUS, SHEET_THICKNESS+LOWER_BEND_RADIUS),  // Pivot
-				           -FN/4));                                                                           // Steps
+	let mut poly = Path2D::new()
+		.move_to(point(-0.5*BASE_WIDTH,                   0.0))
+		.line_to(point(-0.5*BASE_WIDTH,                   SHEET_THICKNESS))
+		.line_to(point(-0.5*BLOCK_WIDTH-LOWER_BEND_RADIUS, SHEET_THICKNESS))
+		.arc_to_centre(point(-0.5*BLOCK_WIDTH,                   SHEET_THICKNESS+LOWER_BEND_RADIUS),  // End
+		               point(-0.5*BLOCK_WIDTH-LOWER_BEND_RADIUS, SHEET_THICKNESS+LOWER_BEND_RADIUS),  // Centre
+		               ArcDirection::CounterClockwise)
+		.line_to(point(-0.5*BLOCK_WIDTH,                   BLOCK_HEIGHT-0.5*BLOCK_WIDTH))
+		.arc_to_centre(point( 0.5*BLOCK_WIDTH,                   BLOCK_HEIGHT-0.5*BLOCK_WIDTH),       // End
+		               point(0.0,                                BLOCK_HEIGHT-0.5*BLOCK_WIDTH),       // Centre
+		               ArcDirection::Clockwise)
+		.line_to(point( 0.5*BLOCK_WIDTH,                   SHEET_THICKNESS+LOWER_BEND_RADIUS))
+		.arc_to_centre(point( 0.5*BLOCK_WIDTH+LOWER_BEND_RADIUS, SHEET_THICKNESS),                    // End
+		               point( 0.5*BLOCK_WIDTH+LOWER_BEND_RADIUS, SHEET_THICKNESS+LOWER_BEND_RADIUS),  // Centre
+		               ArcDirection::CounterClockwise)
+		.line_to(point( 0.5*BASE_WIDTH,                   SHEET_THICKNESS))
+		.line_to(point( 0.5*BASE_WIDTH,                   0.0))
+		.line_to(point( 0.5*BLOCK_WIDTH+LOWER_BEND_RADIUS, 0.0))
+		.arc_to_centre(point( 0.5*BLOCK_WIDTH-SHEET_THICKNESS,   SHEET_THICKNESS+LOWER_BEND_RADIUS),  // End
+		               point( 0.5*BLOCK_WIDTH+LOWER_BEND_RADIUS, SHEET_THICKNESS+LOWER_BEND_RADIUS),  // Centre
+		               ArcDirection::Clockwise)
+		.line_to(point( 0.5*BLOCK_WIDTH-SHEET_THICKNESS,   BLOCK_HEIGHT-0.5*BLOCK_WIDTH))
+		.arc_to_centre(point(-0.5*BLOCK_WIDTH+SHEET_THICKNESS,   BLOCK_HEIGHT-0.5*BLOCK_WIDTH),       // End
+		               point(0.0,                                BLOCK_HEIGHT-0.5*BLOCK_WIDTH),       // Centre
+		               ArcDirection::CounterClockwise)
+		.line_to(point(-0.5*BLOCK_WIDTH+SHEET_THICKNESS,   SHEET_THICKNESS+LOWER_BEND_RADIUS))
+		.arc_to_centre(point(-0.5*BLOCK_WIDTH-LOWER_BEND_RADIUS, 0.0),                                // End
+		               point(-0.5*BLOCK_WIDTH-LOWER_BEND_RADIUS, SHEET_THICKNESS+LOWER_BEND_RADIUS),  // Centre
+		               ArcDirection::Clockwise)
+		.close()
+		.polygon(name);
 	//}}}
 
-	let mut poly = polygon(name, points);
-
 	poly.linear_extrude(BLOCK_DIAMETER);
 	poly.rotate_x(90.0);
 	poly.translate_y(0.5*BLOCK_DIAMETER);
 
 	//{{{ Cut of the slanted sides
 
-	let mut slant1 = cube_coords("slant for sprenger_block_3511100355", -BASE_WIDTH, 0.0, -1.0, BASE_WIDTH, 1.0, 2.0);
+	let mut slant1 = cube_coords("slant for sprenger_block_3511100355", -BASE_WIDTH, 0.0, Length::cm(-1.0), BASE_WIDTH, Length::cm(1.0), Length::cm(2.0));
 	slant1.rotate_x(SLANT_ANGLE);
 	slant1.translate(0.0, 0.5*BLOCK_DIAMETER, BLOCK_HEIGHT_SLANT);
 
@@ -847,25 +820,22 @@ pub fn sprenger_block_3511100355(name: &str) -> Object3D
 	//{{{ Add the roll and screw heads
 
 	let mut roll1 = cylinder(&(String::from("Lower half roll for ") + name), 0.5*ROLL_WIDTH, 0.5*ROLL_DIAMETER_INNER, 0.5*ROLL_DIAMETER);
-	roll1.set_fn(FN);
 	roll1.rotate_y(90.0);
 	roll1.translate_z(ROLL_HEIGHT);
 	let mut roll2 = cylinder(&(String::from("Lower half roll for ") + name), 0.5*ROLL_WIDTH, 0.5*ROLL_DIAMETER_INNER, 0.5*ROLL_DIAMETER);
-	roll2.set_fn(FN);
 	roll2.rotate_y(-90.0);
 	roll2.translate_z(ROLL_HEIGHT);
 
 	let mut screw_head1 = cylinder(&(String::from("Lower half roll for ") + name), SCREW_HEAD_HEIGHT, 0.5*SCREW_HEAD_DIAMETER, 0.0);
-	screw_head1.set_fn(FN);
 	screw_head1.rotate_y(90.0);
 	screw_head1.translate(0.5*BLOCK_WIDTH, 0.0, ROLL_HEIGHT);
 	let mut screw_head2 = cylinder(&(String::from("Lower half roll for ") + name), SCREW_HEAD_HEIGHT, 0.5*SCREW_HEAD_DIAMETER, 0.0);
-	screw_head2.set_fn(FN);
 	screw_head2.rotate_y(-90.0);
 	screw_head2.translate(-0.5*BLOCK_WIDTH, 0.0, ROLL_HEIGHT);
 	//}}}
 
 	let mut block = union(name, [housing, roll1, roll2, screw_head1, screw_head2]);
+	block.set_resolution(Resolution::face_number(FN));
 
 	//{{{ Anchors::Contact: y and z align to the cable
 	{
@@ -991,14 +961,19 @@ pub fn sprenger_block_3511100355(name: &str) -> Object3D
 
 fn main()
 {
+	let mut parameters = bed_parameters();
+	let args = parameters.apply_command_line();
+	PARAMETERS.set(parameters).expect("the parameters are set before building the bed");
+
+	let mut cli = Cli::new("bett");
 
 //	////{{{ Print all the constants
 //
-//	//eprintln!("MATTRESS_LENGTH = {}", MATTRESS_LENGTH);
-//	//eprintln!("STORAGE_LENGTH  = {}", STORAGE_LENGTH );
-//	//eprintln!("MATTRESw_WIDTH  = {}", MATTRESS_WIDTH );
-//	//eprintln!("BED_LENGTH      = {}", BED_LENGTH     );
-//	//eprintln!("BED_WIDTH       = {}", BED_WIDTH      );
+//	//eprintln!("mattress_length() = {}", mattress_length());
+//	//eprintln!("storage_length()  = {}", storage_length() );
+//	//eprintln!("MATTRESw_WIDTH  = {}", mattress_width() );
+//	//eprintln!("bed_length()      = {}", bed_length()     );
+//	//eprintln!("bed_width()       = {}", bed_width()      );
 //	//eprintln!("FRAME_HEIGHT    = {}", FRAME_HEIGHT   );
 //	//eprintln!("FRAME_THICKNESS     = {}", FRAME_THICKNESS    );
 //	////}}}
@@ -1007,24 +982,25 @@ fn main()
 
 
 	//{{{
-	let mut sideboard_l = sideboard("Sideboard_L");
-	sideboard_l.translate_x(-(BED_WIDTH-FRAME_THICKNESS)/2.0);
-	//sideboard_l.set_colour(colour_named("red"));
-	sideboard_l.set_show_anchors();
-	println!("{}", sideboard_l);
+	cli.part("Sideboard_L", ||
+	{
+		let mut sideboard_l = sideboard("Sideboard_L");
+		sideboard_l.translate_x(-(bed_width()-FRAME_THICKNESS)/2.0);
+		//sideboard_l.set_colour(colour_named("red"));
+		sideboard_l.set_show_anchors();
+		sideboard_l
+	});
 	//}}}
 
 //
 //	//{{{
-//	let mut sideboard_r = sideboard_l.clone();
-//	sideboard_r.name = String::from("Sideboard_R");
-//	sideboard_r.scale_x(-1.0);
+//	let mut sideboard_r = sideboard_l.mirrored_copy("Sideboard_R");
 //	println!("{}", sideboard_r);
 //	//}}}
 //
 //	//{{{
 //	let mut headboard = headboard("Headboard");
-//	headboard.translate_y(BED_LENGTH-100.0-1.5*FRAME_THICKNESS);
+//	headboard.translate_y(bed_length()-100.0-1.5*FRAME_THICKNESS);
 //	headboard.set_colour(colour_named("green"));
 //	println!("{}", headboard);
 //	//}}}
@@ -1038,19 +1014,19 @@ fn main()
 //
 //	//{{{
 //	let mut bulkhead = bulkhead("Bulkhead");
-//	bulkhead.translate_y((MATTRESS_LENGTH+FRAME_THICKNESS)/2.0);
+//	bulkhead.translate_y((mattress_length()+FRAME_THICKNESS)/2.0);
 //	bulkhead.set_colour(colour_named("yellow"));
 //	println!("{}", bulkhead);
 //	//}}}
 //
 //	//{{{
 //	let mut bottom_cover = cube_coords("Bottom cover",
-//		-(0.5*MATTRESS_WIDTH+COVER_GROOVE_DEPTH),
-//		HEAD_END+COVER_GROOVE_DEPTH,
+//		-(0.5*mattress_width()+COVER_GROOVE_DEPTH),
+//		head_end()+COVER_GROOVE_DEPTH,
 //		BOTTOM_COVER_BOTTOM,
 //
-//		0.5*MATTRESS_WIDTH+COVER_GROOVE_DEPTH,
-//		0.5*MATTRESS_LENGTH+FRAME_THICKNESS-COVER_GROOVE_DEPTH,
+//		0.5*mattress_width()+COVER_GROOVE_DEPTH,
+//		0.5*mattress_length()+FRAME_THICKNESS-COVER_GROOVE_DEPTH,
 //		BOTTOM_COVER_BOTTOM+COVER_THICKNESS,
 //		);
 //	bottom_cover.set_colour(colour_named("blue"));
@@ -1059,12 +1035,12 @@ fn main()
 //
 //	//{{{
 //	let mut middle_cover_l = cube_coords("Bottom cover",
-//		-(0.5*MATTRESS_WIDTH+COVER_GROOVE_DEPTH),
-//		HEAD_END+COVER_GROOVE_DEPTH,
+//		-(0.5*mattress_width()+COVER_GROOVE_DEPTH),
+//		head_end()+COVER_GROOVE_DEPTH,
 //		MIDDLE_COVER_BOTTOM,
 //
-//		-0.5*MOTOR_WIDTH-FRAME_THICKNESS+COVER_GROOVE_DEPTH,
-//		0.5*MATTRESS_LENGTH+FRAME_THICKNESS-COVER_GROOVE_DEPTH,
+//		-0.5*motor_width()-FRAME_THICKNESS+COVER_GROOVE_DEPTH,
+//		0.5*mattress_length()+FRAME_THICKNESS-COVER_GROOVE_DEPTH,
 //		MIDDLE_COVER_BOTTOM+COVER_THICKNESS,
 //		);
 //	middle_cover_l.set_colour(colour_named("blue"));
@@ -1073,12 +1049,12 @@ fn main()
 //
 //	//{{{
 //	let mut middle_cover_r = cube_coords("Bottom cover",
-//		0.5*MATTRESS_WIDTH+COVER_GROOVE_DEPTH,
-//		HEAD_END+COVER_GROOVE_DEPTH,
+//		0.5*mattress_width()+COVER_GROOVE_DEPTH,
+//		head_end()+COVER_GROOVE_DEPTH,
 //		MIDDLE_COVER_BOTTOM,
 //
-//		0.5*MOTOR_WIDTH+FRAME_THICKNESS-COVER_GROOVE_DEPTH,
-//		0.5*MATTRESS_LENGTH+FRAME_THICKNESS-COVER_GROOVE_DEPTH,
+//		0.5*motor_width()+FRAME_THICKNESS-COVER_GROOVE_DEPTH,
+//		0.5*mattress_length()+FRAME_THICKNESS-COVER_GROOVE_DEPTH,
 //		MIDDLE_COVER_BOTTOM+COVER_THICKNESS,
 //		);
 //	middle_cover_r.set_colour(colour_named("blue"));
@@ -1087,14 +1063,12 @@ fn main()
 //
 //	//{{{
 //	let mut bulkhead_spacer_l = bulkhead_spacer("Bulkhead spacer L");
-//	bulkhead_spacer_l.translate_x(-0.5*(MOTOR_WIDTH+FRAME_THICKNESS));
+//	bulkhead_spacer_l.translate_x(-0.5*(motor_width()+FRAME_THICKNESS));
 //	println!("{}", bulkhead_spacer_l);
 //	//}}}
 //
 //	//{{{
-//	let mut bulkhead_spacer_r = bulkhead_spacer_l.clone();
-//	bulkhead_spacer_r.name = String::from("Bulkhead spacer R");
-//	bulkhead_spacer_r.scale_x(-1.0);
+//	let mut bulkhead_spacer_r = bulkhead_spacer_l.mirrored_copy("Bulkhead spacer R");
 //	println!("{}", bulkhead_spacer_r);
 //	//}}}
 //
@@ -1131,7 +1105,7 @@ fn main()
 //	{
 //		let i = i as f64;
 //		let mut slat = frame_slat(&format!("Frame slat {}", i));
-//		slat.translate(0.0, FRAME_SLAT_START - i*(FRAME_SLAT_SPACING+FRAME_SLAT_WIDTH), -0.5*(FRAME_HEIGHT-FRAME_SLAT_THICKNESS));
+//		slat.translate(0.0, frame_slat_start() - i*(frame_slat_spacing()+FRAME_SLAT_WIDTH), -0.5*(FRAME_HEIGHT-FRAME_SLAT_THICKNESS));
 //		slat.set_colour(colour_rgba(1.0, 1.0, 0.0, 0.2));
 //		println!("{}", slat);
 //	}
@@ -1148,11 +1122,14 @@ fn main()
 
 
 	//{{{
-	//let mut block = sprenger_block_3511100355_cutout("tester");
-	let mut block = sprenger_block_3511100355("tester");
-	block.set_debug();
-	block.set_show_anchors();
-	println!("{}", block);
+	cli.part("tester", ||
+	{
+		//let mut block = sprenger_block_3511100355_cutout("tester");
+		let mut block = sprenger_block_3511100355("tester");
+		block.set_debug();
+		block.set_show_anchors();
+		block
+	});
 	//}}}
 
 //
@@ -1176,5 +1153,5 @@ fn main()
 //	//}}}
 //
 
-
+	cli.run_with(args);
 }
@@ -1,8 +1,11 @@
 pub use core::borrow::{Borrow, BorrowMut};
 pub use std::convert::{AsRef, AsMut};
 pub use std::ops::{Deref, DerefMut, Not, Add, Sub, Mul, BitXor};
+use std::ops::{Neg, Div, AddAssign, SubAssign};
 use std::fmt;
 
+use crate::units::{Length, Angle};
+
 
 //{{{ 2D Stuff
 
@@ -10,7 +13,7 @@ use std::fmt;
 
 #[derive(Default, Debug,  Clone, Copy)]
 pub struct Matrix2D(M2D);
-pub type M2D = vecmath::Matrix3<f64>;
+pub type M2D = [[f64; 3]; 3];
 //{{{
 impl Deref for Matrix2D
 {
@@ -77,8 +80,8 @@ impl fmt::Display for Matrix2D
 {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
 	{
-		let indentation = if let Some(width) = f.width() { width } else { 0 as usize };
-		write!(f, "{0}[{1:16.10?},\n{0} {2:16.10?},\n{0} {3:16.10?}]", "\t".repeat(indentation as usize), self[0], self[1], self[2])
+		let indentation = f.width().unwrap_or_default();
+		write!(f, "{0}[{1:16.10?},\n{0} {2:16.10?},\n{0} {3:16.10?}]", "\t".repeat(indentation), self[0], self[1], self[2])
 	}
 }
 //}}}
@@ -90,18 +93,20 @@ impl Add<&Matrix2D> for Matrix2D
 
     fn add(self, other: &Matrix2D) -> Matrix2D
 	{
-		Self(vecmath::mat3_add(**other, *self))
+		Self(elementwise(&self, other, |a, b| a + b))
     }
 }
 //}}}
 //{{{
+// Legacy: other - self, the way round this has always worked. Kept so that existing code doesn't quietly change sign;
+// Point3 - Point3 and Vector3 - Vector3 subtract the usual way.
 impl Sub<&Matrix2D> for Matrix2D
 {
     type Output = Self;
 
     fn sub(self, other: &Matrix2D) -> Matrix2D
 	{
-		Self(vecmath::mat3_sub(**other, *self))
+		Self(elementwise(other, &self, |a, b| a - b))
     }
 }
 //}}}
@@ -126,7 +131,7 @@ impl Mul<&Matrix2D> for Matrix2D
 
     fn mul(self, other: &Matrix2D) -> Matrix2D
 	{
-		Self(vecmath::row_mat3_mul(**other, *self))
+		Self(matrix_product(other, &self))
     }
 }
 //}}}
@@ -137,7 +142,7 @@ impl Mul<Matrix2D> for Matrix2D
 
     fn mul(self, other: Matrix2D) -> Matrix2D
 	{
-		Self(vecmath::row_mat3_mul(*other, *self))
+		Self(matrix_product(&other, &self))
     }
 }
 //}}}
@@ -148,7 +153,7 @@ impl Not for Matrix2D
 
     fn not(self) -> Self
 	{
-		Self(vecmath::mat3_inv(*self))
+		Self(matrix_inverse(&self))
     }
 }
 //}}}
@@ -169,11 +174,10 @@ impl Matrix2D
 	//{{{
 	fn display(&self, indentation: usize) -> String
 	{
-		let indent   = "\t".repeat(indentation as usize);
-		( format!("{}[{:16.10?},\n", indent, self[0])
+		let indent   = "\t".repeat(indentation);
+		format!("{}[{:16.10?},\n", indent, self[0])
 		+&format!("{} {:16.10?},\n", indent, self[1])
-		+&format!("{} {:16.10?},\n", indent, self[2])
-		+&format!("{} {:16.10?}]",   indent, self[3]))
+		+&format!("{} {:16.10?}]",   indent, self[2])
 	}
 	//}}}
 
@@ -434,7 +438,7 @@ fn matrix2D<Matrix: AsRef<[Row]>, Row: AsRef<[f64]>>(matrix: Matrix) -> Matrix2D
 #[derive(Clone, Copy, Debug)]
 pub struct Vector2D(pub V2D);
 pub type Point2D=Vector2D; 
-pub type V2D = vecmath::Vector3<f64>;
+pub type V2D = [f64; 3];
 //{{{
 impl Deref for Vector2D
 {
@@ -491,12 +495,12 @@ impl fmt::Display for Vector2D
 {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
 	{
-		let indentation = if let Some(width) = f.width() { width } else { 0 as usize };
+		let indentation = f.width().unwrap_or_default();
 		if f.alternate()
 		{
-			write!(f, "{0}[{1:16.10?}, {2:16.10?}, {3:16.10?}]", "\t".repeat(indentation as usize), self[0], self[1], self[2])
+			write!(f, "{0}[{1:16.10?}, {2:16.10?}, {3:16.10?}]", "\t".repeat(indentation), self[0], self[1], self[2])
 		} else {
-			write!(f, "{0}[{1:16.10?}, {2:16.10?}]",             "\t".repeat(indentation as usize), self[0], self[1])
+			write!(f, "{0}[{1:16.10?}, {2:16.10?}]",             "\t".repeat(indentation), self[0], self[1])
 		}
 
 	}
@@ -510,18 +514,20 @@ impl Add<&Vector2D> for Vector2D
 
     fn add(self, other: &Self) -> Self
 	{
-		Self(vecmath::vec3_add(**other, *self))
+		Self([self[0] + other[0], self[1] + other[1], self[2] + other[2]])
     }
 }
 //}}}
 //{{{
+// Legacy: other - self, the way round this has always worked. Kept so that existing code doesn't quietly change sign;
+// Point3 - Point3 and Vector3 - Vector3 subtract the usual way.
 impl Sub<&Vector2D> for Vector2D
 {
     type Output = Self;
 
     fn sub(self, other: &Self) -> Self
 	{
-		Self(vecmath::vec3_sub(**other, *self))
+		Self([other[0] - self[0], other[1] - self[1], other[2] - self[2]])
     }
 }
 //}}}
@@ -554,7 +560,7 @@ impl Vector2D
 	//{{{
 	pub fn l2_norm(&self) -> f64
 	{
-		vecmath::vec3_dot(**self, **self).sqrt()
+		(*self * *self).sqrt()
 	}
 	//}}}
 }
@@ -575,14 +581,14 @@ pub fn vector2D(x: f64, y: f64) -> Point2D
 	Vector2D([x, y, 0.0])
 }
 
-////{{{
-////fn vector2D<Vector: AsRef<[f64]>>(vector: Vector) -> Vector2D
+//{{{
+//fn vector2D<Vector: AsRef<[f64]>>(vector: Vector) -> Vector2D
 //#[allow(non_snake_case)]
 //fn vector2D<Vector: AsRef<[f64]>>(vector: Vector) -> Vector2D
 //{
 //	Vector2D([ vector.as_ref()[0], vector.as_ref()[1], 0.0])
 //}
-////}}}
+//}}}
 //}}}
 //}}}
 
@@ -790,7 +796,7 @@ impl<T> Is2DObject for T where T: HasRefSys2D
 
 #[derive(Default, Debug, Clone, Copy)]
 pub struct Matrix3D(M3D);
-pub type M3D = vecmath::Matrix4<f64>;
+pub type M3D = [[f64; 4]; 4];
 //{{{
 impl Deref for Matrix3D
 {
@@ -856,8 +862,8 @@ impl fmt::Display for Matrix3D
 {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
 	{
-		let indentation = if let Some(width) = f.width() { width } else { 0 as usize };
-		write!(f, "{0}[{1:16.10?},\n{0} {2:16.10?},\n{0} {3:16.10?},\n{0} {4:16.10?}]", "\t".repeat(indentation as usize), self[0], self[1], self[2], self[3])
+		let indentation = f.width().unwrap_or_default();
+		write!(f, "{0}[{1:16.10?},\n{0} {2:16.10?},\n{0} {3:16.10?},\n{0} {4:16.10?}]", "\t".repeat(indentation), self[0], self[1], self[2], self[3])
 	}
 }
 //}}}
@@ -869,18 +875,20 @@ impl Add<&Matrix3D> for Matrix3D
 
     fn add(self, other: &Matrix3D) -> Matrix3D
 	{
-		Self(vecmath::mat4_add(**other, *self))
+		Self(elementwise(&self, other, |a, b| a + b))
     }
 }
 //}}}
 //{{{
+// Legacy: other - self, the way round this has always worked. Kept so that existing code doesn't quietly change sign;
+// Point3 - Point3 and Vector3 - Vector3 subtract the usual way.
 impl Sub<&Matrix3D> for Matrix3D
 {
     type Output = Self;
 
     fn sub(self, other: &Matrix3D) -> Matrix3D
 	{
-		Self(vecmath::mat4_sub(**other, *self))
+		Self(elementwise(other, &self, |a, b| a - b))
     }
 }
 //}}}
@@ -906,7 +914,7 @@ impl Mul<&Matrix3D> for Matrix3D
 
     fn mul(self, other: &Matrix3D) -> Matrix3D
 	{
-		Self(vecmath::row_mat4_mul(**other, *self))
+		Self(matrix_product(other, &self))
     }
 }
 //}}}
@@ -917,7 +925,18 @@ impl Mul<Matrix3D> for Matrix3D
 
     fn mul(self, other: Matrix3D) -> Matrix3D
 	{
-		Self(vecmath::row_mat4_mul(*other, *self))
+		Self(matrix_product(&other, &self))
+    }
+}
+//}}}
+//{{{
+impl Mul<Vector3D> for Matrix3D
+{
+    type Output = Vector3D;
+
+    fn mul(self, vector: Vector3D) -> Vector3D
+	{
+		Vector3D(matrix_vector_product(&self, &vector))
     }
 }
 //}}}
@@ -928,7 +947,7 @@ impl Not for Matrix3D
 
     fn not(self) -> Self
 	{
-		Self(vecmath::mat4_inv(*self))
+		Self(matrix_inverse(&self))
     }
 }
 //}}}
@@ -950,11 +969,11 @@ impl Matrix3D
 	//{{{
 	fn display(&self, indentation: usize) -> String
 	{
-		let indent   = "\t".repeat(indentation as usize);
-		( format!("{}[{:16.10?},\n", indent, self[0])
+		let indent   = "\t".repeat(indentation);
+		format!("{}[{:16.10?},\n", indent, self[0])
 		+&format!("{} {:16.10?},\n", indent, self[1])
 		+&format!("{} {:16.10?},\n", indent, self[2])
-		+&format!("{} {:16.10?}]",   indent, self[3]))
+		+&format!("{} {:16.10?}]",   indent, self[3])
 	}
 	//}}}
 
@@ -1080,6 +1099,54 @@ impl Matrix3D
 		self.rel_rotate_z(z);
 	}
 	//}}}
+
+	//{{{
+	// Turns 'angle' degrees around the line through 'pivot' along 'axis', right handed
+	pub fn rotate_about(&mut self, axis: &Vector3D, angle: f64, pivot: &Point3D)
+	{
+		self.translate(-pivot[0], -pivot[1], -pivot[2]);
+		*self = *self * Quaternion::from_axis_angle(axis, angle).matrix();
+		self.translate(pivot[0], pivot[1], pivot[2]);
+	}
+	//}}}
+	//{{{
+	// Turns the local z-axis towards 'target' and the local y-axis as close to 'up' as possible, around the local origin.
+	// Scale and mirror are kept (singular matrices lose their scale). Shear is dropped, since it would tilt the local
+	// z-axis away from the target again. Nothing happens if the target is the origin.
+	pub fn look_at(&mut self, target: &Point3D, up: &Vector3D)
+	{
+		let translation = (self[0][3], self[1][3], self[2][3]);
+		let forward     = difference_3d(target, &point3D(translation.0, translation.1, translation.2)).normalized();
+		if forward.l2_norm() < 1e-12 { return; }
+
+		let mut right = up.cross(&forward).normalized();
+		if right.l2_norm() < 1e-12
+		{
+			// 'up' is parallel to the direction, any perpendicular will do
+			let other = if forward[0].abs() < 0.9 { vector3D(1.0, 0.0, 0.0) } else { vector3D(0.0, 1.0, 0.0) };
+			right = other.cross(&forward).normalized();
+		}
+		let up = forward.cross(&right);
+
+		let mut rotation = Matrix3D::identity();
+		for (j, axis) in [right, up, forward].iter().enumerate()
+		{
+			for i in 0..3 { rotation[i][j] = axis[i]; }
+		}
+
+		let mut decomposition = self.decompose().unwrap_or(Decomposition
+		{
+			translation,
+			rotation    : Quaternion::identity(),
+			mirror      : false,
+			shear       : (0.0, 0.0, 0.0),
+			scale       : (1.0, 1.0, 1.0),
+		});
+		decomposition.rotation = Quaternion::from_rotation(&rotation);
+		decomposition.shear    = (0.0, 0.0, 0.0);
+		*self = decomposition.matrix();
+	}
+	//}}}
 	//}}}
 
 	//{{{Translations
@@ -1241,12 +1308,31 @@ impl Matrix3D
 	}
 	//}}}
 	//}}}
+
+	//{{{ Mirror
+	//{{{
+	// Reflects at the plane through the origin with the normal 'normal' (of any length). This turns right handed into
+	// left handed coordinates, see is_mirrored().
+	pub fn mirror(&mut self, normal: &Vector3D)
+	{
+		let normal         = normal.normalized();
+		let mut reflection = Self::identity();
+		for i in 0..3
+		{
+			for j in 0..3 { reflection[i][j] -= 2.0*normal[i]*normal[j]; }
+		}
+
+		*self = *self * reflection;
+	}
+	//}}}
+	//}}}
 	//}}}
 
 
 	//{{{ Get 3D Coordinates, Rotation, Scale, Shear
-	
-	// See: https://math.stackexchange.com/questions/237369/given-this-transformation-matrix-how-do-i-decompose-it-into-translation-rotati
+
+	// The getters read the decomposition of the matrix (see decompose()), so they agree with each other and with rotate(),
+	// which turns around x, then y, then z. Singular matrices have no rotation and no shear.
 	//{{{ Positions in a MultMatrix
 	//
 	// [ (0,0) (0,1) (0,2) (0,3) ]
@@ -1255,43 +1341,40 @@ impl Matrix3D
 	// [ (3,0) (3,1) (3,2) (3,3) ]
 	//}}}
 
-
-	//{{{ Positions in an Affine Matrix
-	//
-	// [    Sx+cox(y)+cos(z)             -sin(z)              sin(y)              deltaX ]
-	// [              sin(z)    Sy+cos(x)+cos(z)             -sin(x)              deltaX ]
-	// [             -sin(y)              sin(x)    Sz+cos(x)+cos(y)              deltaX ]
-	// [                   0                   0                   0                   1 ]
-	//}}}
-	//{{{ Positions in a Rotation Matrix
-	//
-	// [       cox(y)+cos(z)             -sin(z)              sin(y)               (0,3) ]
-	// [              sin(z)       cos(x)+cos(z)             -sin(x)               (1,3) ]
-	// [             -sin(y)              sin(x)       cos(x)+cos(y)               (2,3) ]
-	// [               (3,0)               (3,1)               (3,2)               (3,3) ]
-	//}}}
 	//{{{
 	pub fn get_rotate_x(&self) -> f64
 	{
-		(self[1][2]/self.get_scale_y()).asin().to_degrees()
+		self.get_rotate().0
 	}
 	//}}}
 	//{{{
 	pub fn get_rotate_y(&self) -> f64
 	{
-		(self[2][0]/self.get_scale_z()).asin().to_degrees()
+		self.get_rotate().1
 	}
 	//}}}
 	//{{{
 	pub fn get_rotate_z(&self) -> f64
 	{
-		(self[0][1]/self.get_scale_x()).asin().to_degrees()
+		self.get_rotate().2
 	}
 	//}}}
 	//{{{
 	pub fn get_rotate(&self) -> ( f64, f64, f64)
 	{
-		(self.get_rotate_x(), self.get_rotate_y(), self.get_rotate_z())
+		self.get_quaternion().euler_angles()
+	}
+	//}}}
+	//{{{
+	pub fn get_quaternion(&self) -> Quaternion
+	{
+		self.decompose().map_or(Quaternion::identity(), |decomposition| decomposition.rotation)
+	}
+	//}}}
+	//{{{
+	pub fn get_axis_angle(&self) -> AxisAngle
+	{
+		self.get_quaternion().axis_angle()
 	}
 	//}}}
 
@@ -1323,28 +1406,102 @@ impl Matrix3D
 	//{{{
 	pub fn get_scale_x(&self) -> f64
 	{
-		self.column(0).l2_norm()
+		self.get_scale().0
 	}
 	//}}}
 	//{{{
 	pub fn get_scale_y(&self) -> f64
 	{
-		self.column(1).l2_norm()
+		self.get_scale().1
 	}
 	//}}}
 	//{{{
 	pub fn get_scale_z(&self) -> f64
 	{
-		self.column(2).l2_norm()
+		self.get_scale().2
 	}
 	//}}}
 	//{{{
+	// Always positive, see is_mirrored(). Singular matrices give the lengths of the columns.
 	pub fn get_scale(&self) -> (f64, f64, f64)
 	{
-		(self.get_scale_x(), self.get_scale_y(), self.get_scale_z())
+		match self.decompose()
+		{
+			Some(decomposition) => decomposition.scale,
+			None                => (self.column(0).l2_norm(), self.column(1).l2_norm(), self.column(2).l2_norm()),
+		}
+	}
+	//}}}
+	//{{{
+	// (xy, xz, yz), see Decomposition
+	pub fn get_shear(&self) -> (f64, f64, f64)
+	{
+		self.decompose().map_or((0.0, 0.0, 0.0), |decomposition| decomposition.shear)
+	}
+	//}}}
+	//{{{
+	// The matrix turns right handed into left handed coordinates, like scale_x(-1.0)
+	pub fn is_mirrored(&self) -> bool
+	{
+		let columns = [self.column(0), self.column(1), self.column(2)];
+		columns[0].cross(&columns[1])*columns[2] < 0.0
 	}
 	//}}}
 	//}}}
+
+	//{{{
+	// Splits the matrix into translate · rotate · mirror([1, 0, 0]) · shear · scale, applied right to left (scale first),
+	// by Gram-Schmidt orthogonalisation of the columns (a QR decomposition). None for singular and projective matrices.
+	pub fn decompose(&self) -> Option<Decomposition>
+	{
+		let tolerance = DECOMPOSITION_TOLERANCE*(0..3).flat_map(|i| (0..4).map(move |j| (i, j))).map(|(i, j)| self[i][j].abs()).fold(1.0, f64::max);
+		if !tolerance.is_finite() { return None; }
+		if self[3][0].abs() > tolerance || self[3][1].abs() > tolerance || self[3][2].abs() > tolerance || (self[3][3]-1.0).abs() > tolerance { return None; }
+
+		//{{{ Gram-Schmidt: the columns are q*u with orthonormal q and upper triangular u
+
+		let mut q = [[0.0; 3]; 3]; // The columns of q
+		let mut u = [[0.0; 3]; 3];
+		for j in 0..3
+		{
+			let mut column = [self[0][j], self[1][j], self[2][j]];
+			for i in 0..j
+			{
+				u[i][j] = dot3(&q[i], &column);
+				for (c, q) in column.iter_mut().zip(q[i].iter()) { *c -= u[i][j]*q; }
+			}
+			u[j][j] = dot3(&column, &column).sqrt();
+			if u[j][j] < tolerance { return None; }
+			q[j] = [column[0]/u[j][j], column[1]/u[j][j], column[2]/u[j][j]];
+		}
+		//}}}
+
+		//{{{ Rotation, with a mirrored x-axis if q turns right into left handed
+
+		let mirror = dot3(&cross3(&q[0], &q[1]), &q[2]) < 0.0;
+		if mirror { q[0] = [-q[0][0], -q[0][1], -q[0][2]]; }
+		let mut rotation = Matrix3D::identity();
+		for (j, column) in q.iter().enumerate()
+		{
+			for (i, x) in column.iter().enumerate() { rotation[i][j] = *x; }
+		}
+		//}}}
+
+		let decomposition = Decomposition
+		{
+			translation : (self[0][3], self[1][3], self[2][3]),
+			rotation    : Quaternion::from_rotation(&rotation),
+			mirror,
+			shear       : (u[0][1]/u[1][1], u[0][2]/u[2][2], u[1][2]/u[2][2]),
+			scale       : (u[0][0], u[1][1], u[2][2]),
+		};
+
+		// Rounding errors
+		let matrix = decomposition.matrix();
+		if (0..4).any(|i| (0..4).any(|j| (matrix[i][j]-self[i][j]).abs() > tolerance)) { return None; }
+		Some(decomposition)
+	}
+	//}}}
 }
 //}}}
 
@@ -1363,12 +1520,334 @@ fn matrix3D<Matrix: AsRef<[Row]>, Row: AsRef<[f64]>>(matrix: Matrix) -> Matrix3D
 //}}}
 //}}}
 
+//{{{ pub struct Decomposition
+
+// Relative tolerance of Matrix3D::decompose()
+const DECOMPOSITION_TOLERANCE : f64 = 1e-9;
+
+// A matrix as translate(translation) rotate(rotation) mirror([1, 0, 0]) multmatrix(shear) scale(scale), see
+// Matrix3D::decompose(). The shear (xy, xz, yz) moves x by xy*y + xz*z and y by yz*z.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Decomposition
+{
+	pub translation : (f64, f64, f64),
+	pub rotation    : Quaternion,
+	pub mirror      : bool,             // The x-axis is mirrored (before the rotation)
+	pub shear       : (f64, f64, f64),
+	pub scale       : (f64, f64, f64),  // Always positive
+}
+
+//{{{
+impl Decomposition
+{
+	//{{{
+	pub fn matrix(&self) -> Matrix3D
+	{
+		let mut shear = Matrix3D::identity();
+		shear[0][1] = self.shear.0;
+		shear[0][2] = self.shear.1;
+		shear[1][2] = self.shear.2;
+
+		let mut matrix = Matrix3D::identity();
+		matrix.scale(self.scale.0, self.scale.1, self.scale.2);
+		matrix = matrix * shear;
+		if self.mirror { matrix.scale_x(-1.0); }
+		matrix = matrix * self.rotation.matrix();
+		matrix.translate(self.translation.0, self.translation.1, self.translation.2);
+		matrix
+	}
+	//}}}
+	//{{{
+	// Degrees around x, then y, then z, like OpenSCad's rotate()
+	pub fn euler_angles(&self) -> (f64, f64, f64)
+	{
+		self.rotation.euler_angles()
+	}
+	//}}}
+	//{{{
+	pub fn has_shear(&self) -> bool
+	{
+		self.shear.0.abs() > DECOMPOSITION_TOLERANCE || self.shear.1.abs() > DECOMPOSITION_TOLERANCE || self.shear.2.abs() > DECOMPOSITION_TOLERANCE
+	}
+	//}}}
+}
+//}}}
+//}}}
+
+//{{{ pub struct Quaternion
+
+// A rotation as unit quaternion w + xi + yj + zk, with w >= 0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quaternion
+{
+	pub w : f64,
+	pub x : f64,
+	pub y : f64,
+	pub z : f64,
+}
+
+//{{{
+impl Quaternion
+{
+	//{{{
+	pub fn identity() -> Self
+	{
+		Self { w: 1.0, x: 0.0, y: 0.0, z: 0.0 }
+	}
+	//}}}
+	//{{{
+	// Turns 'angle' degrees around 'axis' (of any length), right handed
+	pub fn from_axis_angle(axis: &Vector3D, angle: f64) -> Self
+	{
+		let axis = axis.normalized();
+		let half = angle.to_radians()/2.0;
+		Self::normalized(half.cos(), axis[0]*half.sin(), axis[1]*half.sin(), axis[2]*half.sin())
+	}
+	//}}}
+	//{{{
+	// The rotation of Matrix3D::rotate(x, y, z): around x, then y, then z
+	pub fn from_euler_angles(x: f64, y: f64, z: f64) -> Self
+	{
+		Self::from_axis_angle(&vector3D(1.0, 0.0, 0.0), x) * Self::from_axis_angle(&vector3D(0.0, 1.0, 0.0), y) * Self::from_axis_angle(&vector3D(0.0, 0.0, 1.0), z)
+	}
+	//}}}
+	//{{{
+	// Of the upper left 3x3 of a pure rotation matrix (Shepperd's method, which divides by the largest component)
+	fn from_rotation(r: &Matrix3D) -> Self
+	{
+		let trace = r[0][0] + r[1][1] + r[2][2];
+		let (w, x, y, z) = if trace > 0.0
+		{
+			let s = (trace + 1.0).sqrt()*2.0;
+			(s/4.0, (r[2][1] - r[1][2])/s, (r[0][2] - r[2][0])/s, (r[1][0] - r[0][1])/s)
+		}
+		else if r[0][0] > r[1][1] && r[0][0] > r[2][2]
+		{
+			let s = (1.0 + r[0][0] - r[1][1] - r[2][2]).sqrt()*2.0;
+			((r[2][1] - r[1][2])/s, s/4.0, (r[0][1] + r[1][0])/s, (r[0][2] + r[2][0])/s)
+		}
+		else if r[1][1] > r[2][2]
+		{
+			let s = (1.0 + r[1][1] - r[0][0] - r[2][2]).sqrt()*2.0;
+			((r[0][2] - r[2][0])/s, (r[0][1] + r[1][0])/s, s/4.0, (r[1][2] + r[2][1])/s)
+		}
+		else
+		{
+			let s = (1.0 + r[2][2] - r[0][0] - r[1][1]).sqrt()*2.0;
+			((r[1][0] - r[0][1])/s, (r[0][2] + r[2][0])/s, (r[1][2] + r[2][1])/s, s/4.0)
+		};
+
+		Self::normalized(w, x, y, z)
+	}
+	//}}}
+	//{{{
+	// Scaled to length 1 with w >= 0. The zero quaternion becomes the identity.
+	fn normalized(w: f64, x: f64, y: f64, z: f64) -> Self
+	{
+		let length = (w*w + x*x + y*y + z*z).sqrt();
+		if length < 1e-12 { return Self::identity(); }
+		let length = if w < 0.0 { -length } else { length };
+		Self { w: w/length, x: x/length, y: y/length, z: z/length }
+	}
+	//}}}
+	//{{{
+	pub fn inverse(&self) -> Self
+	{
+		Self::normalized(self.w, -self.x, -self.y, -self.z)
+	}
+	//}}}
+	//{{{
+	// Spherical linear interpolation along the shorter arc: self at t = 0, other at t = 1, constant angular speed between
+	pub fn slerp(&self, other: &Self, t: f64) -> Self
+	{
+		let mut cos = self.w*other.w + self.x*other.x + self.y*other.y + self.z*other.z;
+		let sign    = if cos < 0.0 { cos = -cos; -1.0 } else { 1.0 };
+
+		// Nearly the same rotation, the linear interpolation is as good and doesn't divide by sin(0)
+		let (a, b) = if cos > 1.0 - 1e-9
+		{
+			(1.0 - t, t)
+		}
+		else
+		{
+			let angle = cos.min(1.0).acos();
+			(((1.0 - t)*angle).sin()/angle.sin(), (t*angle).sin()/angle.sin())
+		};
+		let b = b*sign;
+		Self::normalized(a*self.w + b*other.w, a*self.x + b*other.x, a*self.y + b*other.y, a*self.z + b*other.z)
+	}
+	//}}}
+	//{{{
+	pub fn matrix(&self) -> Matrix3D
+	{
+		let Self { w, x, y, z } = *self;
+		matrix3D(
+		[[ 1.0 - 2.0*(y*y + z*z),       2.0*(x*y - w*z),       2.0*(x*z + w*y)],
+		 [       2.0*(x*y + w*z), 1.0 - 2.0*(x*x + z*z),       2.0*(y*z - w*x)],
+		 [       2.0*(x*z - w*y),       2.0*(y*z + w*x), 1.0 - 2.0*(x*x + y*y)]])
+	}
+	//}}}
+	//{{{
+	// Degrees around x, then y, then z, like OpenSCad's rotate(). At y = ±90° (gimbal lock) only x + z or x - z is
+	// defined, z is 0 then.
+	pub fn euler_angles(&self) -> (f64, f64, f64)
+	{
+		let r     = self.matrix();
+		let sin_y = (-r[2][0]).clamp(-1.0, 1.0);
+		if sin_y.abs() < 1.0 - 1e-12
+		{
+			let x = r[2][1].atan2(r[2][2]);
+			let y = sin_y.asin();
+			let z = r[1][0].atan2(r[0][0]);
+			(x.to_degrees(), y.to_degrees(), z.to_degrees())
+		}
+		else if sin_y > 0.0
+		{
+			(r[0][1].atan2(r[1][1]).to_degrees(), 90.0, 0.0)
+		}
+		else
+		{
+			((-r[0][1]).atan2(r[1][1]).to_degrees(), -90.0, 0.0)
+		}
+	}
+	//}}}
+	//{{{
+	// The identity turns 0° around z
+	pub fn axis_angle(&self) -> AxisAngle
+	{
+		let sin = (self.x*self.x + self.y*self.y + self.z*self.z).sqrt();
+		if sin < 1e-12 { return AxisAngle { axis: vector3D(0.0, 0.0, 1.0), angle: 0.0 }; }
+		AxisAngle
+		{
+			axis  : vector3D(self.x/sin, self.y/sin, self.z/sin),
+			angle : (2.0*sin.atan2(self.w)).to_degrees(),
+		}
+	}
+	//}}}
+}
+//}}}
+//}}}
+
+//{{{
+// Composition like Matrix3D: a*b turns by a, then by b
+impl Mul<Quaternion> for Quaternion
+{
+    type Output = Self;
+
+    fn mul(self, other: Quaternion) -> Quaternion
+	{
+		let (p, q) = (other, self);
+		Quaternion::normalized(p.w*q.w - p.x*q.x - p.y*q.y - p.z*q.z,
+		                       p.w*q.x + p.x*q.w + p.y*q.z - p.z*q.y,
+		                       p.w*q.y - p.x*q.z + p.y*q.w + p.z*q.x,
+		                       p.w*q.z + p.x*q.y - p.y*q.x + p.z*q.w)
+    }
+}
+//}}}
+//{{{
+impl Mul<Vector3D> for Quaternion
+{
+    type Output = Vector3D;
+
+    fn mul(self, vector: Vector3D) -> Vector3D
+	{
+		self.matrix()*vector
+    }
+}
+//}}}
+//{{{
+impl From<Quaternion> for Matrix3D
+{
+	fn from(quaternion: Quaternion) -> Self { quaternion.matrix() }
+}
+//}}}
+//{{{
+// The rotation part of the matrix, see Matrix3D::get_quaternion()
+impl From<&Matrix3D> for Quaternion
+{
+	fn from(matrix: &Matrix3D) -> Self { matrix.get_quaternion() }
+}
+//}}}
+//{{{
+impl From<AxisAngle> for Quaternion
+{
+	fn from(axis_angle: AxisAngle) -> Self { Quaternion::from_axis_angle(&axis_angle.axis, axis_angle.angle) }
+}
+//}}}
+//{{{
+impl From<Quaternion> for AxisAngle
+{
+	fn from(quaternion: Quaternion) -> Self { quaternion.axis_angle() }
+}
+//}}}
+
+//{{{ pub struct AxisAngle
+
+// A rotation by 'angle' degrees around the unit direction 'axis', right handed
+#[derive(Debug, Clone, Copy)]
+pub struct AxisAngle
+{
+	pub axis  : Vector3D,
+	pub angle : f64,
+}
+
+//{{{
+impl AxisAngle
+{
+	//{{{
+	// The axis may have any length
+	pub fn new(axis: &Vector3D, angle: f64) -> Self
+	{
+		Self { axis: axis.normalized(), angle }
+	}
+	//}}}
+	//{{{
+	pub fn quaternion(&self) -> Quaternion
+	{
+		Quaternion::from(*self)
+	}
+	//}}}
+	//{{{
+	pub fn matrix(&self) -> Matrix3D
+	{
+		self.quaternion().matrix()
+	}
+	//}}}
+}
+//}}}
+//{{{
+impl From<AxisAngle> for Matrix3D
+{
+	fn from(axis_angle: AxisAngle) -> Self { axis_angle.matrix() }
+}
+//}}}
+//{{{
+impl From<&Matrix3D> for AxisAngle
+{
+	fn from(matrix: &Matrix3D) -> Self { matrix.get_axis_angle() }
+}
+//}}}
+//}}}
+
+//{{{
+fn dot3(a: &[f64; 3], b: &[f64; 3]) -> f64
+{
+	a[0]*b[0] + a[1]*b[1] + a[2]*b[2]
+}
+//}}}
+//{{{
+fn cross3(a: &[f64; 3], b: &[f64; 3]) -> [f64; 3]
+{
+	[a[1]*b[2] - a[2]*b[1], a[2]*b[0] - a[0]*b[2], a[0]*b[1] - a[1]*b[0]]
+}
+//}}}
+
 //{{{ pub struct Vector3D
 
 #[derive(Clone, Copy, Debug)]
 pub struct Vector3D(V3D);
 pub type Point3D=Vector3D; 
-pub type V3D = vecmath::Vector4<f64>;
+pub type V3D = [f64; 4];
 //{{{
 impl Deref for Vector3D
 {
@@ -1424,12 +1903,12 @@ impl fmt::Display for Vector3D
 {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
 	{
-		let indentation = if let Some(width) = f.width() { width } else { 0 as usize };
+		let indentation = f.width().unwrap_or_default();
 		//write!(f, "{0}[{1:16.10?},\n{0} {2:16.10?},\n{0} {3:16.10?},\n{0} {4:16.10?}]", "\t".repeat(indentation as usize), self[0], self[1], self[2], self[3])
 		if !f.alternate() {
-			write!(f, "{0}[{1:16.10?}, {2:16.10?}, {3:16.10?},\n{0} {4:16.10?}]", "\t".repeat(indentation as usize), self[0], self[1], self[2], self[3])
+			write!(f, "{0}[{1:16.10?}, {2:16.10?}, {3:16.10?},\n{0} {4:16.10?}]", "\t".repeat(indentation), self[0], self[1], self[2], self[3])
 		} else {
-			write!(f, "{0}[{1:16.10?}, {2:16.10?}, {3:16.10?}]",                  "\t".repeat(indentation as usize), self[0], self[1], self[2])
+			write!(f, "{0}[{1:16.10?}, {2:16.10?}, {3:16.10?}]",                  "\t".repeat(indentation), self[0], self[1], self[2])
 		}
 	}
 }
@@ -1442,18 +1921,20 @@ impl Add<&Vector3D> for Vector3D
 
     fn add(self, other: &Self) -> Self
 	{
-		Self(vecmath::vec4_add(**other, *self))
+		Self([self[0] + other[0], self[1] + other[1], self[2] + other[2], self[3] + other[3]])
     }
 }
 //}}}
 //{{{
+// Legacy: other - self, the way round this has always worked. Kept so that existing code doesn't quietly change sign;
+// Point3 - Point3 and Vector3 - Vector3 subtract the usual way.
 impl Sub<&Vector3D> for Vector3D
 {
     type Output = Self;
 
     fn sub(self, other: &Self) -> Self
 	{
-		Self(vecmath::vec4_sub(**other, *self))
+		Self([other[0] - self[0], other[1] - self[1], other[2] - self[2], other[3] - self[3]])
     }
 }
 //}}}
@@ -1486,7 +1967,25 @@ impl Vector3D
 	//{{{
 	pub fn l2_norm(&self) -> f64
 	{
-		vecmath::vec4_dot(**self, **self).sqrt()
+		(*self * *self).sqrt()
+	}
+	//}}}
+	//{{{
+	// Cross product of the x, y and z components. The result is a direction vector.
+	pub fn cross(&self, other: &Vector3D) -> Vector3D
+	{
+		vector3D(self[1]*other[2] - self[2]*other[1],
+		         self[2]*other[0] - self[0]*other[2],
+		         self[0]*other[1] - self[1]*other[0])
+	}
+	//}}}
+	//{{{
+	// Scales the x, y and z components to length 1. Zero vectors are returned unchanged.
+	pub fn normalized(&self) -> Vector3D
+	{
+		let length = (self[0]*self[0] + self[1]*self[1] + self[2]*self[2]).sqrt();
+		if length < 1e-12 { return *self; }
+		Vector3D([self[0]/length, self[1]/length, self[2]/length, self[3]])
 	}
 	//}}}
 }
@@ -1521,22 +2020,25 @@ pub trait HasRefSys3D
 //{{{
 pub trait Is3DObject
 {
-	fn rotate_x(&mut self, x: f64);
-	fn rotate_y(&mut self, y: f64);
-	fn rotate_z(&mut self, z: f64);
-	fn rotate(&mut self, x: f64, y: f64, z: f64);
-	fn rel_rotate_x(&mut self, x: f64);
-	fn rel_rotate_y(&mut self, y: f64);
-	fn rel_rotate_z(&mut self, z: f64);
-	fn rel_rotate(&mut self, x: f64, y: f64, z: f64);
-	fn translate_x(&mut self, x: f64);
-	fn translate_y(&mut self, y: f64);
-	fn translate_z(&mut self, z: f64);
-	fn translate(&mut self, x: f64, y:f64, z: f64);
-	fn rel_translate_x(&mut self, x: f64);
-	fn rel_translate_y(&mut self, y: f64);
-	fn rel_translate_z(&mut self, z: f64);
-	fn rel_translate(&mut self, x: f64, y:f64, z: f64);
+	fn rotate_x(&mut self, x: impl Into<Angle>);
+	fn rotate_y(&mut self, y: impl Into<Angle>);
+	fn rotate_z(&mut self, z: impl Into<Angle>);
+	fn rotate(&mut self, x: impl Into<Angle>, y: impl Into<Angle>, z: impl Into<Angle>);
+	fn rel_rotate_x(&mut self, x: impl Into<Angle>);
+	fn rel_rotate_y(&mut self, y: impl Into<Angle>);
+	fn rel_rotate_z(&mut self, z: impl Into<Angle>);
+	fn rel_rotate(&mut self, x: impl Into<Angle>, y: impl Into<Angle>, z: impl Into<Angle>);
+	fn rotate_about(&mut self, axis: impl Into<Vector3D>, angle: impl Into<Angle>, pivot: impl Into<Point3D>);
+	fn look_at(&mut self, target: impl Into<Point3D>, up: impl Into<Vector3D>);
+	fn translate_x(&mut self, x: impl Into<Length>);
+	fn translate_y(&mut self, y: impl Into<Length>);
+	fn translate_z(&mut self, z: impl Into<Length>);
+	fn translate(&mut self, x: impl Into<Length>, y: impl Into<Length>, z: impl Into<Length>);
+	fn rel_translate_x(&mut self, x: impl Into<Length>);
+	fn rel_translate_y(&mut self, y: impl Into<Length>);
+	fn rel_translate_z(&mut self, z: impl Into<Length>);
+	fn rel_translate(&mut self, x: impl Into<Length>, y: impl Into<Length>, z: impl Into<Length>);
+	// Scale factors, not lengths, so they stay bare numbers and do not depend on the unit
 	fn scale_x(&mut self, x: f64);
 	fn scale_y(&mut self, y: f64);
 	fn scale_z(&mut self, z: f64);
@@ -1545,11 +2047,14 @@ pub trait Is3DObject
 	fn rel_scale_y(&mut self, y: f64);
 	fn rel_scale_z(&mut self, z: f64);
 	fn rel_scale(&mut self, x: f64, y:f64, z: f64);
+	fn mirror(&mut self, plane_normal: impl Into<Vector3D>);
 
 	fn get_rotate_x(&self) -> f64;
 	fn get_rotate_y(&self) -> f64;
 	fn get_rotate_z(&self) -> f64;
 	fn get_rotate(&self) -> ( f64, f64, f64);
+	fn get_quaternion(&self) -> Quaternion;
+	fn get_axis_angle(&self) -> AxisAngle;
 	fn get_translate_x(&self) -> f64;
 	fn get_translate_y(&self) -> f64;
 	fn get_translate_z(&self) -> f64;
@@ -1558,6 +2063,8 @@ pub trait Is3DObject
 	fn get_scale_y(&self) -> f64;
 	fn get_scale_z(&self) -> f64;
 	fn get_scale(&self) -> (f64, f64, f64);
+	fn get_shear(&self) -> (f64, f64, f64);
+	fn is_mirrored(&self) -> bool;
 }
 //}}}
 
@@ -1567,103 +2074,115 @@ impl<T> Is3DObject for T where T: HasRefSys3D
 	//{{{ 3D-Manipulation
 
 	//{{{
-	fn rotate_x(&mut self, x: f64)
+	fn rotate_x(&mut self, x: impl Into<Angle>)
 	{
-		self.ref_sys_mut().rotate_x(x);
+		self.ref_sys_mut().rotate_x(x.into().to_deg());
 	}
 	//}}}
 	//{{{
-	fn rotate_y(&mut self, y: f64)
+	fn rotate_y(&mut self, y: impl Into<Angle>)
 	{
-		self.ref_sys_mut().rotate_y(y);
+		self.ref_sys_mut().rotate_y(y.into().to_deg());
 	}
 	//}}}
 	//{{{
-	fn rotate_z(&mut self, z: f64)
+	fn rotate_z(&mut self, z: impl Into<Angle>)
 	{
-		self.ref_sys_mut().rotate_z(z);
+		self.ref_sys_mut().rotate_z(z.into().to_deg());
 	}
 	//}}}
 	//{{{
-	fn rotate(&mut self, x: f64, y: f64, z: f64)
+	fn rotate(&mut self, x: impl Into<Angle>, y: impl Into<Angle>, z: impl Into<Angle>)
 	{
-		self.ref_sys_mut().rotate(x, y, z);
+		self.ref_sys_mut().rotate(x.into().to_deg(), y.into().to_deg(), z.into().to_deg());
 	}
 	//}}}
 
 	//{{{
-	fn rel_rotate_x(&mut self, x: f64)
+	fn rel_rotate_x(&mut self, x: impl Into<Angle>)
+	{
+		self.ref_sys_mut().rel_rotate_x(x.into().to_deg());
+	}
+	//}}}
+	//{{{
+	fn rel_rotate_y(&mut self, y: impl Into<Angle>)
+	{
+		self.ref_sys_mut().rel_rotate_y(y.into().to_deg());
+	}
+	//}}}
+	//{{{
+	fn rel_rotate_z(&mut self, z: impl Into<Angle>)
 	{
-		self.ref_sys_mut().rel_rotate_x(x);
+		self.ref_sys_mut().rel_rotate_z(z.into().to_deg());
 	}
 	//}}}
 	//{{{
-	fn rel_rotate_y(&mut self, y: f64)
+	fn rel_rotate(&mut self, x: impl Into<Angle>, y: impl Into<Angle>, z: impl Into<Angle>)
 	{
-		self.ref_sys_mut().rel_rotate_y(y);
+		self.ref_sys_mut().rel_rotate(x.into().to_deg(), y.into().to_deg(), z.into().to_deg());
 	}
 	//}}}
 	//{{{
-	fn rel_rotate_z(&mut self, z: f64)
+	fn rotate_about(&mut self, axis: impl Into<Vector3D>, angle: impl Into<Angle>, pivot: impl Into<Point3D>)
 	{
-		self.ref_sys_mut().rel_rotate_z(z);
+		self.ref_sys_mut().rotate_about(&axis.into(), angle.into().to_deg(), &pivot.into());
 	}
 	//}}}
 	//{{{
-	fn rel_rotate(&mut self, x: f64, y: f64, z: f64)
+	fn look_at(&mut self, target: impl Into<Point3D>, up: impl Into<Vector3D>)
 	{
-		self.ref_sys_mut().rel_rotate(x, y, z);
+		self.ref_sys_mut().look_at(&target.into(), &up.into());
 	}
 	//}}}
 
 
 	//{{{
-	fn translate_x(&mut self, x: f64)
+	fn translate_x(&mut self, x: impl Into<Length>)
 	{
-		self.ref_sys_mut().translate_x(x);
+		self.ref_sys_mut().translate_x(x.into().to_mm());
 	}
 	//}}}
 	//{{{
-	fn translate_y(&mut self, y: f64)
+	fn translate_y(&mut self, y: impl Into<Length>)
 	{
-		self.ref_sys_mut().translate_y(y);
+		self.ref_sys_mut().translate_y(y.into().to_mm());
 	}
 	//}}}
 	//{{{
-	fn translate_z(&mut self, z: f64)
+	fn translate_z(&mut self, z: impl Into<Length>)
 	{
-		self.ref_sys_mut().translate_z(z);
+		self.ref_sys_mut().translate_z(z.into().to_mm());
 	}
 	//}}}
 	//{{{
-	fn translate(&mut self, x: f64, y:f64, z: f64)
+	fn translate(&mut self, x: impl Into<Length>, y: impl Into<Length>, z: impl Into<Length>)
 	{
-		self.ref_sys_mut().translate(x, y, z);
+		self.ref_sys_mut().translate(x.into().to_mm(), y.into().to_mm(), z.into().to_mm());
 	}
 	//}}}
 
 	//{{{
-	fn rel_translate_x(&mut self, x: f64)
+	fn rel_translate_x(&mut self, x: impl Into<Length>)
 	{
-		self.ref_sys_mut().rel_translate_x(x);
+		self.ref_sys_mut().rel_translate_x(x.into().to_mm());
 	}
 	//}}}
 	//{{{
-	fn rel_translate_y(&mut self, y: f64)
+	fn rel_translate_y(&mut self, y: impl Into<Length>)
 	{
-		self.ref_sys_mut().rel_translate_y(y);
+		self.ref_sys_mut().rel_translate_y(y.into().to_mm());
 	}
 	//}}}
 	//{{{
-	fn rel_translate_z(&mut self, z: f64)
+	fn rel_translate_z(&mut self, z: impl Into<Length>)
 	{
-		self.ref_sys_mut().rel_translate_z(z);
+		self.ref_sys_mut().rel_translate_z(z.into().to_mm());
 	}
 	//}}}
 	//{{{
-	fn rel_translate(&mut self, x: f64, y:f64, z: f64)
+	fn rel_translate(&mut self, x: impl Into<Length>, y: impl Into<Length>, z: impl Into<Length>)
 	{
-		self.ref_sys_mut().rel_translate(x, y, z);
+		self.ref_sys_mut().rel_translate(x.into().to_mm(), y.into().to_mm(), z.into().to_mm());
 	}
 	//}}}
 
@@ -1716,6 +2235,13 @@ impl<T> Is3DObject for T where T: HasRefSys3D
 		self.ref_sys_mut().rel_scale(x, y, z);
 	}
 	//}}}
+
+	//{{{
+	fn mirror(&mut self, plane_normal: impl Into<Vector3D>)
+	{
+		self.ref_sys_mut().mirror(&plane_normal.into());
+	}
+	//}}}
 	//}}}
 
 	//{{{ Get 3D Coordinates, Rotation, Scale, Shear
@@ -1739,13 +2265,13 @@ impl<T> Is3DObject for T where T: HasRefSys3D
 	//{{{
 	fn get_rotate_y(&self) -> f64
 	{
-		self.ref_sys().get_rotate_x()
+		self.ref_sys().get_rotate_y()
 	}
 	//}}}
 	//{{{
 	fn get_rotate_z(&self) -> f64
 	{
-		self.ref_sys().get_rotate_x()
+		self.ref_sys().get_rotate_z()
 	}
 	//}}}
 	//{{{
@@ -1754,6 +2280,18 @@ impl<T> Is3DObject for T where T: HasRefSys3D
 		self.ref_sys().get_rotate()
 	}
 	//}}}
+	//{{{
+	fn get_quaternion(&self) -> Quaternion
+	{
+		self.ref_sys().get_quaternion()
+	}
+	//}}}
+	//{{{
+	fn get_axis_angle(&self) -> AxisAngle
+	{
+		self.ref_sys().get_axis_angle()
+	}
+	//}}}
 
 	//{{{
 	fn get_translate_x(&self) -> f64
@@ -1804,10 +2342,1330 @@ impl<T> Is3DObject for T where T: HasRefSys3D
 		self.ref_sys().get_scale()
 	}
 	//}}}
+	//{{{
+	fn get_shear(&self) -> (f64, f64, f64)
+	{
+		self.ref_sys().get_shear()
+	}
+	//}}}
+	//{{{
+	fn is_mirrored(&self) -> bool
+	{
+		self.ref_sys().is_mirrored()
+	}
+	//}}}
+	//}}}
+}
+//}}}
+
+//}}}
+
+
+//{{{ Point3, Vector3, Transform3
+//
+// Typed linear algebra for new code. Points are positions, vectors are directions and offsets, so that
+//
+//     point - point = vector,   point + vector = point,   vector + vector = vector
+//
+// and translations only move points. Transform3 is an affine map with the usual operator semantics: a*b applies b
+// first, then a (a.then(b) reads the other way round). Migrating from the array based types:
+//
+//     point3D(x, y, z)                      Point3::new(x, y, z)
+//     vector3D(x, y, z)                     Vector3::new(x, y, z)
+//     Matrix3D::identity(), m.rotate(...)   Transform3::rotation(...), Transform3::translation(...), ...
+//     a*b (a first, then b)                 a.then(&b), or b*a
+//     m*point3D(...), m*vector3D(...)       t.transform_point(&p), t.transform_vector(&v)
+//     !m                                    t.inverse()
+//
+// Both sides convert with From/Into, e.g. object.set_ref_sys(transform.into()) or Point3::from(vector_3d).
+// Is3DObject's rotate_about(), look_at() and mirror() and sweep() take either kind. Matrix3D and Vector3D stay the
+// storage of objects and anchors; their Sub keeps the old other - self order. There is no typed layer for 2D yet.
+
+//{{{ pub struct Vector3
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Vector3
+{
+	pub x : f64,
+	pub y : f64,
+	pub z : f64,
+}
+
+//{{{
+impl Vector3
+{
+	//{{{
+	pub fn new(x: f64, y: f64, z: f64) -> Self
+	{
+		Self { x, y, z }
+	}
+	//}}}
+	//{{{
+	pub fn zero() -> Self
+	{
+		Self::new(0.0, 0.0, 0.0)
+	}
+	//}}}
+	//{{{
+	pub fn unit_x() -> Self
+	{
+		Self::new(1.0, 0.0, 0.0)
+	}
+	//}}}
+	//{{{
+	pub fn unit_y() -> Self
+	{
+		Self::new(0.0, 1.0, 0.0)
+	}
+	//}}}
+	//{{{
+	pub fn unit_z() -> Self
+	{
+		Self::new(0.0, 0.0, 1.0)
+	}
+	//}}}
+
+	//{{{
+	pub fn dot(&self, other: &Self) -> f64
+	{
+		self.x*other.x + self.y*other.y + self.z*other.z
+	}
+	//}}}
+	//{{{
+	// Right handed: unit_x().cross(&unit_y()) is unit_z()
+	pub fn cross(&self, other: &Self) -> Self
+	{
+		Self::new(self.y*other.z - self.z*other.y, self.z*other.x - self.x*other.z, self.x*other.y - self.y*other.x)
+	}
+	//}}}
+	//{{{
+	pub fn length(&self) -> f64
+	{
+		self.dot(self).sqrt()
+	}
+	//}}}
+	//{{{
+	// The unit vector in the same direction. None for (nearly) zero vectors, which have no direction.
+	pub fn normalize(&self) -> Option<Self>
+	{
+		let length = self.length();
+		if length < 1e-12 || !length.is_finite() { None } else { Some(*self/length) }
+	}
+	//}}}
+	//{{{
+	// In degrees, from 0 to 180. Zero vectors are at 0° to everything.
+	pub fn angle_between(&self, other: &Self) -> f64
+	{
+		// atan2 is exact for small and nearly opposite angles, where acos of the dot product is not
+		self.cross(other).length().atan2(self.dot(other)).to_degrees()
+	}
+	//}}}
+}
+//}}}
+//{{{
+impl Add for Vector3
+{
+	type Output = Self;
+
+	fn add(self, other: Self) -> Self { Self::new(self.x + other.x, self.y + other.y, self.z + other.z) }
+}
+//}}}
+//{{{
+impl Sub for Vector3
+{
+	type Output = Self;
+
+	fn sub(self, other: Self) -> Self { Self::new(self.x - other.x, self.y - other.y, self.z - other.z) }
+}
+//}}}
+//{{{
+impl Neg for Vector3
+{
+	type Output = Self;
+
+	fn neg(self) -> Self { Self::new(-self.x, -self.y, -self.z) }
+}
+//}}}
+//{{{
+impl Mul<f64> for Vector3
+{
+	type Output = Self;
+
+	fn mul(self, a: f64) -> Self { Self::new(a*self.x, a*self.y, a*self.z) }
+}
+//}}}
+//{{{
+impl Mul<Vector3> for f64
+{
+	type Output = Vector3;
+
+	fn mul(self, vector: Vector3) -> Vector3 { vector*self }
+}
+//}}}
+//{{{
+impl Div<f64> for Vector3
+{
+	type Output = Self;
+
+	fn div(self, a: f64) -> Self { Self::new(self.x/a, self.y/a, self.z/a) }
+}
+//}}}
+//{{{
+impl AddAssign for Vector3
+{
+	fn add_assign(&mut self, other: Self) { *self = *self + other; }
+}
+//}}}
+//{{{
+impl SubAssign for Vector3
+{
+	fn sub_assign(&mut self, other: Self) { *self = *self - other; }
+}
+//}}}
+//}}}
+
+//{{{ pub struct Point3
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Point3
+{
+	pub x : f64,
+	pub y : f64,
+	pub z : f64,
+}
+
+//{{{
+impl Point3
+{
+	//{{{
+	pub fn new(x: f64, y: f64, z: f64) -> Self
+	{
+		Self { x, y, z }
+	}
+	//}}}
+	//{{{
+	pub fn origin() -> Self
+	{
+		Self::new(0.0, 0.0, 0.0)
+	}
+	//}}}
+	//{{{
+	pub fn distance(&self, other: &Self) -> f64
+	{
+		(*other - *self).length()
+	}
+	//}}}
+	//{{{
+	// self at t = 0, other at t = 1
+	pub fn lerp(&self, other: &Self, t: f64) -> Self
+	{
+		*self + (*other - *self)*t
+	}
+	//}}}
+	//{{{
+	// The offset from the origin
+	pub fn to_vector(&self) -> Vector3
+	{
+		Vector3::new(self.x, self.y, self.z)
+	}
 	//}}}
 }
 //}}}
+//{{{
+impl Sub for Point3
+{
+	type Output = Vector3;
+
+	fn sub(self, other: Self) -> Vector3 { Vector3::new(self.x - other.x, self.y - other.y, self.z - other.z) }
+}
+//}}}
+//{{{
+impl Add<Vector3> for Point3
+{
+	type Output = Self;
+
+	fn add(self, vector: Vector3) -> Self { Self::new(self.x + vector.x, self.y + vector.y, self.z + vector.z) }
+}
+//}}}
+//{{{
+impl Sub<Vector3> for Point3
+{
+	type Output = Self;
 
+	fn sub(self, vector: Vector3) -> Self { self + -vector }
+}
+//}}}
+//{{{
+impl AddAssign<Vector3> for Point3
+{
+	fn add_assign(&mut self, vector: Vector3) { *self = *self + vector; }
+}
+//}}}
+//{{{
+impl SubAssign<Vector3> for Point3
+{
+	fn sub_assign(&mut self, vector: Vector3) { *self = *self - vector; }
+}
+//}}}
 //}}}
 
+//{{{ pub struct Transform3
+
+// An affine map, as 4x4 matrix for column vectors (the same layout as Matrix3D)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform3([[f64; 4]; 4]);
+
+//{{{
+impl Transform3
+{
+	//{{{
+	pub fn identity() -> Self
+	{
+		Self(*Matrix3D::identity())
+	}
+	//}}}
+	//{{{
+	pub fn translation(offset: &Vector3) -> Self
+	{
+		let mut matrix = Matrix3D::identity();
+		matrix.translate(offset.x, offset.y, offset.z);
+		Self::from(matrix)
+	}
+	//}}}
+	//{{{
+	// Degrees around x, then y, then z, like OpenSCad's rotate()
+	pub fn rotation(x: f64, y: f64, z: f64) -> Self
+	{
+		let mut matrix = Matrix3D::identity();
+		matrix.rotate(x, y, z);
+		Self::from(matrix)
+	}
+	//}}}
+	//{{{
+	// 'angle' degrees around 'axis' (of any length) through the origin, right handed
+	pub fn rotation_about(axis: &Vector3, angle: f64) -> Self
+	{
+		Self::from(Quaternion::from_axis_angle(&(*axis).into(), angle).matrix())
+	}
+	//}}}
+	//{{{
+	pub fn scaling(x: f64, y: f64, z: f64) -> Self
+	{
+		let mut matrix = Matrix3D::identity();
+		matrix.scale(x, y, z);
+		Self::from(matrix)
+	}
+	//}}}
+	//{{{
+	// At the plane through the origin with the normal 'normal'
+	pub fn mirror(normal: &Vector3) -> Self
+	{
+		let mut matrix = Matrix3D::identity();
+		matrix.mirror(&(*normal).into());
+		Self::from(matrix)
+	}
+	//}}}
+
+	//{{{
+	// self first, then other
+	pub fn then(&self, other: &Self) -> Self
+	{
+		*other**self
+	}
+	//}}}
+	//{{{
+	// None for singular transforms
+	pub fn inverse(&self) -> Option<Self>
+	{
+		let inverse = matrix_inverse(&self.0);
+		if inverse.iter().flatten().all(|x| x.is_finite()) { Some(Self(inverse)) } else { None }
+	}
+	//}}}
+
+	//{{{
+	// Points are translated
+	pub fn transform_point(&self, point: &Point3) -> Point3
+	{
+		let [x, y, z, _] = matrix_vector_product(&self.0, &[point.x, point.y, point.z, 1.0]);
+		Point3::new(x, y, z)
+	}
+	//}}}
+	//{{{
+	// Directions and offsets are not translated
+	pub fn transform_vector(&self, vector: &Vector3) -> Vector3
+	{
+		let [x, y, z, _] = matrix_vector_product(&self.0, &[vector.x, vector.y, vector.z, 0.0]);
+		Vector3::new(x, y, z)
+	}
+	//}}}
+	//{{{
+	// Surface normals stay perpendicular to the surface under non-uniform scaling (the inverse transpose is applied).
+	// The result is normalised. None for singular transforms.
+	pub fn transform_normal(&self, normal: &Vector3) -> Option<Vector3>
+	{
+		let inverse = self.inverse()?.0;
+		let [x, y, z] = [0, 1, 2].map(|j| inverse[0][j]*normal.x + inverse[1][j]*normal.y + inverse[2][j]*normal.z);
+		Vector3::new(x, y, z).normalize()
+	}
+	//}}}
 
+	//{{{
+	// Where the origin ends up
+	pub fn origin(&self) -> Point3
+	{
+		self.transform_point(&Point3::origin())
+	}
+	//}}}
+	//{{{
+	pub fn is_mirrored(&self) -> bool
+	{
+		Matrix3D::from(*self).is_mirrored()
+	}
+	//}}}
+	//{{{
+	pub fn decompose(&self) -> Option<Decomposition>
+	{
+		Matrix3D::from(*self).decompose()
+	}
+	//}}}
+}
+//}}}
+//{{{
+impl Default for Transform3
+{
+	fn default() -> Self { Self::identity() }
+}
+//}}}
+//{{{
+// a*b applies b first, then a
+impl Mul for Transform3
+{
+	type Output = Self;
+
+	fn mul(self, other: Self) -> Self { Self(matrix_product(&self.0, &other.0)) }
+}
+//}}}
+//{{{
+impl Mul<Point3> for Transform3
+{
+	type Output = Point3;
+
+	fn mul(self, point: Point3) -> Point3 { self.transform_point(&point) }
+}
+//}}}
+//{{{
+impl Mul<Vector3> for Transform3
+{
+	type Output = Vector3;
+
+	fn mul(self, vector: Vector3) -> Vector3 { self.transform_vector(&vector) }
+}
+//}}}
+//}}}
+
+//{{{ Conversions from and to the array based types
+
+//{{{
+impl From<Matrix3D> for Transform3
+{
+	fn from(matrix: Matrix3D) -> Self { Self(*matrix) }
+}
+//}}}
+//{{{
+impl From<Transform3> for Matrix3D
+{
+	fn from(transform: Transform3) -> Self { Matrix3D(transform.0) }
+}
+//}}}
+//{{{
+// Whether the Vector3D is a point or a direction, its x, y and z become the coordinates
+impl From<Vector3D> for Point3
+{
+	fn from(point: Vector3D) -> Self { Self::new(point[0], point[1], point[2]) }
+}
+//}}}
+//{{{
+impl From<Vector3D> for Vector3
+{
+	fn from(vector: Vector3D) -> Self { Self::new(vector[0], vector[1], vector[2]) }
+}
+//}}}
+//{{{
+impl From<Point3> for Vector3D
+{
+	fn from(point: Point3) -> Self { point3D(point.x, point.y, point.z) }
+}
+//}}}
+//{{{
+impl From<Vector3> for Vector3D
+{
+	fn from(vector: Vector3) -> Self { vector3D(vector.x, vector.y, vector.z) }
+}
+//}}}
+//{{{
+// The object API takes impl Into<Vector3D>, so that &point3D(...) keeps working next to the typed points and vectors
+impl From<&Vector3D> for Vector3D
+{
+	fn from(vector: &Vector3D) -> Self { *vector }
+}
+//}}}
+//{{{
+impl From<&Point3> for Vector3D
+{
+	fn from(point: &Point3) -> Self { Self::from(*point) }
+}
+//}}}
+//{{{
+impl From<&Vector3> for Vector3D
+{
+	fn from(vector: &Vector3) -> Self { Self::from(*vector) }
+}
+//}}}
+//}}}
+//}}}
+
+
+//{{{ Curves
+
+//{{{
+// Points that curves can be built from. Works on Point2D and Point3D, the homogeneous coordinate is interpolated as well.
+pub trait IsCurvePoint: Copy
+{
+	fn lerp(&self, other: &Self, t: f64) -> Self;
+	fn distance(&self, other: &Self) -> f64;
+	fn distance_to_segment(&self, start: &Self, end: &Self) -> f64;
+}
+//}}}
+
+//{{{
+impl IsCurvePoint for Vector2D
+{
+	fn lerp(&self, other: &Self, t: f64) -> Self
+	{
+		Vector2D([self[0] + t*(other[0]-self[0]), self[1] + t*(other[1]-self[1]), self[2] + t*(other[2]-self[2])])
+	}
+	fn distance(&self, other: &Self) -> f64
+	{
+		(other[0]-self[0]).hypot(other[1]-self[1])
+	}
+	fn distance_to_segment(&self, start: &Self, end: &Self) -> f64
+	{
+		let (dx, dy)   = (end[0]-start[0], end[1]-start[1]);
+		let length2    = dx*dx + dy*dy;
+		if length2 < 1e-24 { return self.distance(start); }
+		let t = (((self[0]-start[0])*dx + (self[1]-start[1])*dy)/length2).clamp(0.0, 1.0);
+		self.distance(&start.lerp(end, t))
+	}
+}
+//}}}
+//{{{
+impl IsCurvePoint for Vector3D
+{
+	fn lerp(&self, other: &Self, t: f64) -> Self
+	{
+		Vector3D([self[0] + t*(other[0]-self[0]), self[1] + t*(other[1]-self[1]), self[2] + t*(other[2]-self[2]), self[3] + t*(other[3]-self[3])])
+	}
+	fn distance(&self, other: &Self) -> f64
+	{
+		let (dx, dy, dz) = (other[0]-self[0], other[1]-self[1], other[2]-self[2]);
+		(dx*dx + dy*dy + dz*dz).sqrt()
+	}
+	fn distance_to_segment(&self, start: &Self, end: &Self) -> f64
+	{
+		let (dx, dy, dz) = (end[0]-start[0], end[1]-start[1], end[2]-start[2]);
+		let length2      = dx*dx + dy*dy + dz*dz;
+		if length2 < 1e-24 { return self.distance(start); }
+		let t = (((self[0]-start[0])*dx + (self[1]-start[1])*dy + (self[2]-start[2])*dz)/length2).clamp(0.0, 1.0);
+		self.distance(&start.lerp(end, t))
+	}
+}
+//}}}
+
+//{{{
+// A parametric curve, evaluated for t in [0, 1]
+pub trait IsCurve<P: IsCurvePoint>
+{
+	fn eval(&self, t: f64) -> P;
+
+	// Number of pieces the curve consists of (e.g. the knot spans of a spline). Each piece is subdivided separately.
+	fn spans(&self) -> usize
+	{
+		1
+	}
+
+	// Approximate the curve by line segments, none of which deviates more than 'tolerance' from the curve.
+	// Includes both the start and the end point.
+	fn flatten(&self, tolerance: f64) -> Vec<P>
+	{
+		let tolerance = tolerance.abs().max(1e-9);
+		let spans     = self.spans().max(1);
+		let mut points = vec![self.eval(0.0)];
+		for span in 0..spans
+		{
+			let t0 = (span as f64)/(spans as f64);
+			let t1 = ((span+1) as f64)/(spans as f64);
+			flatten_adaptive(self, (t0, self.eval(t0)), (t1, self.eval(t1)), tolerance, 0, &mut points);
+		}
+		points
+	}
+}
+//}}}
+//{{{
+// Splits [t0, t1] until the curve stays within 'tolerance' of the chord. Pushes every point after the start.
+// Always subdivides twice, so that S-shaped pieces whose midpoint happens to lie on the chord are not missed.
+fn flatten_adaptive<P: IsCurvePoint, C: IsCurve<P> + ?Sized>(curve: &C, (t0, p0): (f64, P), (t1, p1): (f64, P), tolerance: f64, depth: u32, points: &mut Vec<P>)
+{
+	let tm = 0.5*(t0+t1);
+	let pm = curve.eval(tm);
+
+	let flat = depth >= 2
+		&& pm.distance_to_segment(&p0, &p1)                        <= tolerance
+		&& curve.eval(0.5*(t0+tm)).distance_to_segment(&p0, &p1)   <= tolerance
+		&& curve.eval(0.5*(tm+t1)).distance_to_segment(&p0, &p1)   <= tolerance;
+
+	if flat || depth >= 20
+	{
+		points.push(p1);
+		return;
+	}
+
+	flatten_adaptive(curve, (t0, p0), (tm, pm), tolerance, depth+1, points);
+	flatten_adaptive(curve, (tm, pm), (t1, p1), tolerance, depth+1, points);
+}
+//}}}
+
+//{{{ pub struct QuadraticBezier
+
+#[derive(Debug, Clone, Copy)]
+pub struct QuadraticBezier<P: IsCurvePoint>
+{
+	pub start   : P,
+	pub control : P,
+	pub end     : P,
+}
+
+//{{{
+impl<P: IsCurvePoint> QuadraticBezier<P>
+{
+	pub fn new(start: P, control: P, end: P) -> Self
+	{
+		Self{ start, control, end }
+	}
+
+	// The same curve as a cubic bezier
+	pub fn to_cubic(&self) -> CubicBezier<P>
+	{
+		CubicBezier::new(self.start, self.start.lerp(&self.control, 2.0/3.0), self.end.lerp(&self.control, 2.0/3.0), self.end)
+	}
+}
+//}}}
+//{{{
+impl<P: IsCurvePoint> IsCurve<P> for QuadraticBezier<P>
+{
+	fn eval(&self, t: f64) -> P
+	{
+		let a = self.start.lerp(&self.control, t);
+		let b = self.control.lerp(&self.end, t);
+		a.lerp(&b, t)
+	}
+	fn flatten(&self, tolerance: f64) -> Vec<P>
+	{
+		self.to_cubic().flatten(tolerance)
+	}
+}
+//}}}
+//}}}
+
+//{{{ pub struct CubicBezier
+
+#[derive(Debug, Clone, Copy)]
+pub struct CubicBezier<P: IsCurvePoint>
+{
+	pub start    : P,
+	pub control1 : P,
+	pub control2 : P,
+	pub end      : P,
+}
+
+//{{{
+impl<P: IsCurvePoint> CubicBezier<P>
+{
+	pub fn new(start: P, control1: P, control2: P, end: P) -> Self
+	{
+		Self{ start, control1, control2, end }
+	}
+
+	// de Casteljau split at t
+	pub fn split(&self, t: f64) -> (Self, Self)
+	{
+		let p01   = self.start.lerp(&self.control1, t);
+		let p12   = self.control1.lerp(&self.control2, t);
+		let p23   = self.control2.lerp(&self.end, t);
+		let p012  = p01.lerp(&p12, t);
+		let p123  = p12.lerp(&p23, t);
+		let p0123 = p012.lerp(&p123, t);
+		(Self::new(self.start, p01, p012, p0123), Self::new(p0123, p123, p23, self.end))
+	}
+
+	//{{{
+	fn flatten_into(&self, tolerance: f64, depth: u32, points: &mut Vec<P>)
+	{
+		// The curve lies within the convex hull of its control points
+		let flat = self.control1.distance_to_segment(&self.start, &self.end) <= tolerance
+		        && self.control2.distance_to_segment(&self.start, &self.end) <= tolerance;
+
+		if flat || depth >= 20
+		{
+			points.push(self.end);
+			return;
+		}
+
+		let (first, second) = self.split(0.5);
+		first.flatten_into(tolerance, depth+1, points);
+		second.flatten_into(tolerance, depth+1, points);
+	}
+	//}}}
+}
+//}}}
+//{{{
+impl<P: IsCurvePoint> IsCurve<P> for CubicBezier<P>
+{
+	fn eval(&self, t: f64) -> P
+	{
+		let a = self.start.lerp(&self.control1, t);
+		let b = self.control1.lerp(&self.control2, t);
+		let c = self.control2.lerp(&self.end, t);
+		a.lerp(&b, t).lerp(&b.lerp(&c, t), t)
+	}
+	fn flatten(&self, tolerance: f64) -> Vec<P>
+	{
+		let mut points = vec![self.start];
+		self.flatten_into(tolerance.abs().max(1e-9), 0, &mut points);
+		points
+	}
+}
+//}}}
+//}}}
+
+//{{{ pub struct UniformBSpline
+
+// B-spline with uniform knots. Like all uniform B-splines, it does not pass through its first and last control point,
+// unless they are repeated 'degree' times. A closed spline wraps around and joins smoothly with its own start.
+// There is always at least one control point.
+#[derive(Debug, Clone)]
+pub struct UniformBSpline<P: IsCurvePoint>
+{
+	control_points : Vec<P>,
+	degree         : usize,
+	closed         : bool,
+}
+
+//{{{
+impl<P: IsCurvePoint> UniformBSpline<P>
+{
+	// None without control points
+	pub fn new(control_points: Vec<P>, degree: usize) -> Option<Self>
+	{
+		if control_points.is_empty() { return None; }
+		Some(Self{ control_points, degree: degree.max(1), closed: false })
+	}
+	pub fn new_closed(control_points: Vec<P>, degree: usize) -> Option<Self>
+	{
+		if control_points.is_empty() { return None; }
+		Some(Self{ control_points, degree: degree.max(1), closed: true })
+	}
+
+	pub fn control_points(&self) -> &[P] { &self.control_points }
+	pub fn degree(&self) -> usize { self.degree }
+	pub fn is_closed(&self) -> bool { self.closed }
+
+	//{{{
+	fn control_point(&self, i: usize) -> P
+	{
+		let n = self.control_points.len();
+		if self.closed { self.control_points[i % n] } else { self.control_points[i.min(n-1)] }
+	}
+	//}}}
+}
+//}}}
+//{{{
+impl<P: IsCurvePoint> IsCurve<P> for UniformBSpline<P>
+{
+	fn spans(&self) -> usize
+	{
+		let n = self.control_points.len();
+		if self.closed { n } else { n.saturating_sub(self.degree).max(1) }
+	}
+
+	// de Boor's algorithm on the knot span containing t
+	fn eval(&self, t: f64) -> P
+	{
+		let degree = self.degree;
+		if self.control_points.len() <= degree && !self.closed
+		{
+			// Not enough points for a spline, fall back to a polyline
+			let n = self.control_points.len();
+			if n == 1 { return self.control_points[0]; }
+			let s = t.clamp(0.0, 1.0)*((n-1) as f64);
+			let i = (s.floor() as usize).min(n-2);
+			return self.control_points[i].lerp(&self.control_points[i+1], s - i as f64);
+		}
+
+		let spans = self.spans();
+		let s     = t.clamp(0.0, 1.0)*(spans as f64);
+		let span  = (s.floor() as usize).min(spans-1);
+		let u     = s - span as f64;
+
+		// Knots are the integers, the span covers [degree, degree+1] relative to the first control point
+		let mut d : Vec<P> = (0..=degree).map(|j| self.control_point(span+j)).collect();
+		let x = u + degree as f64;
+		for r in 1..=degree
+		{
+			for j in (r..=degree).rev()
+			{
+				let knot  = j as f64;
+				let alpha = (x - knot)/((degree+1-r) as f64);
+				d[j] = d[j-1].lerp(&d[j], alpha);
+			}
+		}
+		d[degree]
+	}
+}
+//}}}
+//}}}
+
+//{{{ pub struct BezierPatch
+
+// Bicubic bezier surface. control_points[i][j] is the control point in row i (along u) and column j (along v).
+#[derive(Debug, Clone, Copy)]
+pub struct BezierPatch
+{
+	pub control_points : [[Point3D; 4]; 4],
+}
+
+//{{{
+impl BezierPatch
+{
+	pub fn new(control_points: [[Point3D; 4]; 4]) -> Self
+	{
+		Self{ control_points }
+	}
+
+	//{{{
+	pub fn eval(&self, u: f64, v: f64) -> Point3D
+	{
+		let rows : Vec<Point3D> = self.control_points.iter()
+			.map(|row| CubicBezier::new(row[0], row[1], row[2], row[3]).eval(v))
+			.collect();
+		CubicBezier::new(rows[0], rows[1], rows[2], rows[3]).eval(u)
+	}
+	//}}}
+	//{{{
+	// Surface normal (d/du x d/dv), normalised
+	pub fn normal(&self, u: f64, v: f64) -> Vector3D
+	{
+		let h  = 1e-5;
+		let du = difference_3d(&self.eval((u+h).min(1.0), v), &self.eval((u-h).max(0.0), v));
+		let dv = difference_3d(&self.eval(u, (v+h).min(1.0)), &self.eval(u, (v-h).max(0.0)));
+		du.cross(&dv).normalized()
+	}
+	//}}}
+	//{{{
+	// Number of segments along u and v so that the control polygon rows and columns are flattened within 'tolerance'
+	pub fn resolution(&self, tolerance: f64) -> (usize, usize)
+	{
+		let c = &self.control_points;
+		let mut nu = 1;
+		let mut nv = 1;
+		for (k, row) in c.iter().enumerate()
+		{
+			nv = nv.max(CubicBezier::new(row[0],  row[1],  row[2],  row[3]).flatten(tolerance).len()-1);
+			nu = nu.max(CubicBezier::new(c[0][k], c[1][k], c[2][k], c[3][k]).flatten(tolerance).len()-1);
+		}
+		(nu, nv)
+	}
+	//}}}
+}
+//}}}
+//}}}
+
+//{{{
+fn difference_3d(a: &Point3D, b: &Point3D) -> Vector3D
+{
+	vector3D(a[0]-b[0], a[1]-b[1], a[2]-b[2])
+}
+//}}}
+
+//{{{ Matrix arithmetic on plain arrays (row major, for column vectors)
+
+//{{{
+fn elementwise<const N: usize>(a: &[[f64; N]; N], b: &[[f64; N]; N], f: impl Fn(f64, f64) -> f64) -> [[f64; N]; N]
+{
+	let mut result = [[0.0; N]; N];
+	for (i, row) in result.iter_mut().enumerate()
+	{
+		for (j, x) in row.iter_mut().enumerate() { *x = f(a[i][j], b[i][j]); }
+	}
+	result
+}
+//}}}
+//{{{
+// a·b: b is applied first
+fn matrix_product<const N: usize>(a: &[[f64; N]; N], b: &[[f64; N]; N]) -> [[f64; N]; N]
+{
+	let mut result = [[0.0; N]; N];
+	for (i, row) in result.iter_mut().enumerate()
+	{
+		for (j, x) in row.iter_mut().enumerate() { *x = (0..N).map(|k| a[i][k]*b[k][j]).sum(); }
+	}
+	result
+}
+//}}}
+//{{{
+fn matrix_vector_product<const N: usize>(a: &[[f64; N]; N], v: &[f64; N]) -> [f64; N]
+{
+	let mut result = [0.0; N];
+	for (i, x) in result.iter_mut().enumerate() { *x = (0..N).map(|k| a[i][k]*v[k]).sum(); }
+	result
+}
+//}}}
+//{{{
+// Gauss-Jordan elimination with partial pivoting. Singular matrices give NaN.
+fn matrix_inverse<const N: usize>(m: &[[f64; N]; N]) -> [[f64; N]; N]
+{
+	let mut a       = *m;
+	let mut inverse = [[0.0; N]; N];
+	for (i, row) in inverse.iter_mut().enumerate() { row[i] = 1.0; }
+
+	for column in 0..N
+	{
+		let pivot = (column..N).max_by(|&i, &j| a[i][column].abs().total_cmp(&a[j][column].abs())).unwrap_or(column);
+		if a[pivot][column] == 0.0 { return [[f64::NAN; N]; N]; }
+		a.swap(column, pivot);
+		inverse.swap(column, pivot);
+
+		let scale = 1.0/a[column][column];
+		for j in 0..N
+		{
+			a[column][j]       *= scale;
+			inverse[column][j] *= scale;
+		}
+		for i in (0..N).filter(|&i| i != column)
+		{
+			let factor = a[i][column];
+			if factor == 0.0 { continue; }
+			for j in 0..N
+			{
+				a[i][j]       -= factor*a[column][j];
+				inverse[i][j] -= factor*inverse[column][j];
+			}
+		}
+	}
+	inverse
+}
+//}}}
+//}}}
+
+//}}}
+
+//{{{
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use proptest::prelude::*;
+
+	//{{{
+	fn assert_close(a: &Matrix3D, b: &Matrix3D)
+	{
+		let size = (0..4).flat_map(|i| (0..4).map(move |j| (i, j))).map(|(i, j)| a[i][j].abs()).fold(1.0, f64::max);
+		for i in 0..4
+		{
+			for j in 0..4
+			{
+				assert!((a[i][j]-b[i][j]).abs() < 1e-9*size, "\n{}\n!=\n{}", a, b);
+			}
+		}
+	}
+	//}}}
+	//{{{
+	// translate · rotate · shear · scale, with negative scales for mirrors
+	fn transform(translation: (f64, f64, f64), rotation: (f64, f64, f64), shear: (f64, f64, f64), scale: (f64, f64, f64)) -> Matrix3D
+	{
+		let mut shear_matrix = Matrix3D::identity();
+		shear_matrix[0][1] = shear.0;
+		shear_matrix[0][2] = shear.1;
+		shear_matrix[1][2] = shear.2;
+
+		let mut matrix = Matrix3D::identity();
+		matrix.scale(scale.0, scale.1, scale.2);
+		matrix = matrix * shear_matrix;
+		matrix.rotate(rotation.0, rotation.1, rotation.2);
+		matrix.translate(translation.0, translation.1, translation.2);
+		matrix
+	}
+	//}}}
+	//{{{
+	fn scale_factor() -> impl Strategy<Value = f64>
+	{
+		(0.1..10.0, any::<bool>()).prop_map(|(scale, negative): (f64, bool)| if negative { -scale } else { scale })
+	}
+	//}}}
+	//{{{
+	fn vector(range: f64) -> impl Strategy<Value = (f64, f64, f64)>
+	{
+		(-range..range, -range..range, -range..range)
+	}
+	//}}}
+
+	proptest!
+	{
+		//{{{
+		#[test]
+		fn decomposition_round_trip(translation in vector(1000.0), rotation in vector(360.0), shear in vector(2.0), scale in (scale_factor(), scale_factor(), scale_factor()))
+		{
+			let matrix        = transform(translation, rotation, shear, scale);
+			let decomposition = matrix.decompose().expect("regular affine matrices decompose");
+
+			assert_close(&decomposition.matrix(), &matrix);
+			prop_assert_eq!(decomposition.translation, translation);
+			prop_assert_eq!(decomposition.mirror, scale.0*scale.1*scale.2 < 0.0);
+			prop_assert_eq!(decomposition.mirror, matrix.is_mirrored());
+			prop_assert!(decomposition.scale.0 > 0.0 && decomposition.scale.1 > 0.0 && decomposition.scale.2 > 0.0);
+		}
+		//}}}
+		//{{{
+		#[test]
+		fn scale_and_mirror_without_shear(rotation in vector(360.0), scale in (scale_factor(), scale_factor(), scale_factor()))
+		{
+			let decomposition = transform((0.0, 0.0, 0.0), rotation, (0.0, 0.0, 0.0), scale).decompose().unwrap();
+
+			prop_assert!(!decomposition.has_shear());
+			prop_assert!((decomposition.scale.0 - scale.0.abs()).abs() < 1e-9);
+			prop_assert!((decomposition.scale.1 - scale.1.abs()).abs() < 1e-9);
+			prop_assert!((decomposition.scale.2 - scale.2.abs()).abs() < 1e-9);
+		}
+		//}}}
+		//{{{
+		#[test]
+		fn euler_angles_round_trip(x in -179.0..179.0, y in -89.0..89.0, z in -179.0..179.0)
+		{
+			let mut matrix = Matrix3D::identity();
+			matrix.rotate(x, y, z);
+			let (rx, ry, rz) = matrix.get_rotate();
+
+			prop_assert!((rx - x).abs() < 1e-6 && (ry - y).abs() < 1e-6 && (rz - z).abs() < 1e-6, "{:?} != {:?}", (rx, ry, rz), (x, y, z));
+		}
+		//}}}
+		//{{{
+		#[test]
+		fn euler_angles_in_gimbal_lock(x in -180.0..180.0, up in any::<bool>(), z in -180.0..180.0)
+		{
+			let y = if up { 90.0 } else { -90.0 };
+			let mut matrix = Matrix3D::identity();
+			matrix.rotate(x, y, z);
+			let (rx, ry, rz) = matrix.get_rotate();
+
+			prop_assert_eq!((ry, rz), (y, 0.0));
+			let mut rotation = Matrix3D::identity();
+			rotation.rotate(rx, ry, rz);
+			assert_close(&rotation, &matrix);
+		}
+		//}}}
+		//{{{
+		#[test]
+		fn quaternion_and_axis_angle(rotation in vector(360.0))
+		{
+			let mut matrix = Matrix3D::identity();
+			matrix.rotate(rotation.0, rotation.1, rotation.2);
+			let quaternion = matrix.get_quaternion();
+			assert_close(&quaternion.matrix(), &matrix);
+			prop_assert!(quaternion.w >= 0.0);
+
+			// The axis is fixed by the rotation, and the trace is 1 + 2cos(angle)
+			let axis_angle = matrix.get_axis_angle();
+			let axis       = axis_angle.axis;
+			prop_assert!((axis.l2_norm() - 1.0).abs() < 1e-9);
+			prop_assert!((0.0..=180.0).contains(&axis_angle.angle));
+			let turned = matrix*axis;
+			prop_assert!((turned[0]-axis[0]).abs() < 1e-9 && (turned[1]-axis[1]).abs() < 1e-9 && (turned[2]-axis[2]).abs() < 1e-9);
+			let trace = matrix[0][0] + matrix[1][1] + matrix[2][2];
+			prop_assert!((trace - (1.0 + 2.0*axis_angle.angle.to_radians().cos())).abs() < 1e-9);
+		}
+		//}}}
+		//{{{
+		#[test]
+		fn quaternion_composition(a in vector(360.0), b in vector(360.0))
+		{
+			let (qa, qb) = (Quaternion::from_euler_angles(a.0, a.1, a.2), Quaternion::from_euler_angles(b.0, b.1, b.2));
+			let mut matrix = Matrix3D::identity();
+			matrix.rotate(a.0, a.1, a.2);
+			assert_close(&qa.matrix(), &matrix);
+			matrix.rotate(b.0, b.1, b.2);
+			assert_close(&(qa*qb).matrix(), &matrix);
+			assert_close(&(qa*qa.inverse()).matrix(), &Matrix3D::identity());
+		}
+		//}}}
+		//{{{
+		#[test]
+		fn slerp_turns_at_constant_speed(axis in vector(1.0), angle in 1.0..179.0, t in 0.0..1.0)
+		{
+			prop_assume!(axis.0.abs() + axis.1.abs() + axis.2.abs() > 1e-3);
+			let axis  = vector3D(axis.0, axis.1, axis.2);
+			let start = Quaternion::from_euler_angles(10.0, 20.0, 30.0);
+			let end   = start*Quaternion::from_axis_angle(&axis, angle);
+
+			assert_close(&start.slerp(&end, 0.0).matrix(), &start.matrix());
+			assert_close(&start.slerp(&end, 1.0).matrix(), &end.matrix());
+			let step = (start.inverse()*start.slerp(&end, t)).axis_angle();
+			prop_assert!((step.angle - t*angle).abs() < 1e-6, "{} != {}", step.angle, t*angle);
+		}
+		//}}}
+		//{{{
+		#[test]
+		fn axis_angle_round_trip(axis in vector(1.0), angle in 0.01..179.99)
+		{
+			prop_assume!(axis.0.abs() + axis.1.abs() + axis.2.abs() > 1e-3);
+			let axis_angle = AxisAngle::new(&vector3D(axis.0, axis.1, axis.2), angle);
+			let back       = AxisAngle::from(&axis_angle.matrix());
+
+			prop_assert!((back.angle - angle).abs() < 1e-9);
+			prop_assert!((back.axis*axis_angle.axis - 1.0).abs() < 1e-9);
+		}
+		//}}}
+		//{{{
+		#[test]
+		fn rotate_about_keeps_the_pivot(axis in vector(1.0), angle in vector(360.0), pivot in vector(100.0))
+		{
+			prop_assume!(axis.0.abs() + axis.1.abs() + axis.2.abs() > 1e-3);
+			let pivot      = point3D(pivot.0, pivot.1, pivot.2);
+			let mut matrix = Matrix3D::identity();
+			matrix.rotate_about(&vector3D(axis.0, axis.1, axis.2), angle.0, &pivot);
+
+			let moved = matrix*pivot;
+			prop_assert!((0..3).all(|i| (moved[i]-pivot[i]).abs() < 1e-9));
+		}
+		//}}}
+		//{{{
+		#[test]
+		fn look_at_turns_z_to_the_target(position in vector(100.0), target in vector(100.0), scale in (scale_factor(), scale_factor(), scale_factor()))
+		{
+			let mut matrix = Matrix3D::identity();
+			matrix.scale(scale.0, scale.1, scale.2);
+			matrix.translate(position.0, position.1, position.2);
+			matrix.look_at(&point3D(target.0, target.1, target.2), &vector3D(0.0, 0.0, 1.0));
+
+			let direction = vector3D(target.0-position.0, target.1-position.1, target.2-position.2).normalized();
+			let z         = (matrix*vector3D(0.0, 0.0, 1.0)).normalized();
+			let y         = (matrix*vector3D(0.0, 1.0, 0.0)).normalized();
+			prop_assert!(z*direction > 1.0 - 1e-9);
+			prop_assert!(y[2] >= -1e-9, "the y-axis points down");
+			prop_assert_eq!(matrix.get_translate(), position);
+			prop_assert_eq!(matrix.is_mirrored(), scale.0*scale.1*scale.2 < 0.0);
+		}
+		//}}}
+	}
+
+	//{{{
+	#[test]
+	fn look_at_straight_up()
+	{
+		let mut matrix = Matrix3D::identity();
+		matrix.translate(1.0, 2.0, 3.0);
+		// 'up' is parallel to the direction, any y-axis is fine
+		matrix.look_at(&point3D(1.0, 2.0, 10.0), &vector3D(0.0, 0.0, 1.0));
+		let z = matrix*vector3D(0.0, 0.0, 1.0);
+		assert!((z[2] - 1.0).abs() < 1e-12);
+		assert!(!matrix.is_mirrored());
+
+		matrix.look_at(&point3D(1.0, 2.0, 3.0), &vector3D(0.0, 0.0, 1.0));
+		assert_eq!(matrix.get_translate(), (1.0, 2.0, 3.0));
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn point_and_vector_operators()
+	{
+		let (a, b) = (Point3::new(1.0, 2.0, 3.0), Point3::new(4.0, 6.0, 3.0));
+		assert_eq!(b - a, Vector3::new(3.0, 4.0, 0.0));
+		assert_eq!(a + (b - a), b);
+		assert_eq!(b - (b - a), a);
+		assert_eq!(a.distance(&b), 5.0);
+		assert_eq!(a.lerp(&b, 0.5), Point3::new(2.5, 4.0, 3.0));
+
+		let v = Vector3::new(3.0, 4.0, 0.0);
+		assert_eq!(v - Vector3::unit_x(), Vector3::new(2.0, 4.0, 0.0));
+		assert_eq!(2.0*v, v*2.0);
+		assert_eq!(v.normalize(), Some(Vector3::new(0.6, 0.8, 0.0)));
+		assert_eq!(Vector3::zero().normalize(), None);
+		assert_eq!(Vector3::unit_x().cross(&Vector3::unit_y()), Vector3::unit_z());
+		assert!((Vector3::unit_x().angle_between(&Vector3::new(1.0, 1.0, 0.0)) - 45.0).abs() < 1e-12);
+		assert!((Vector3::unit_x().angle_between(&-Vector3::unit_x()) - 180.0).abs() < 1e-12);
+
+		// The array based vectors keep their old other - self subtraction
+		let difference = point3D(4.0, 6.0, 3.0) - &point3D(1.0, 2.0, 3.0);
+		assert_eq!(Vector3::from(difference), Vector3::new(-3.0, -4.0, 0.0));
+		assert_eq!(difference[3], 0.0);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn transform_points_and_vectors()
+	{
+		let transform = Transform3::translation(&Vector3::new(10.0, 0.0, 0.0))*Transform3::rotation(0.0, 0.0, 90.0);
+		let point     = transform.transform_point(&Point3::new(1.0, 0.0, 0.0));
+		let vector    = transform.transform_vector(&Vector3::new(1.0, 0.0, 0.0));
+		assert!(point.distance(&Point3::new(10.0, 1.0, 0.0)) < 1e-12);
+		assert!((vector - Vector3::unit_y()).length() < 1e-12);
+
+		// a*b is b first, then a; then() the other way round; Matrix3D's a*b is a first
+		let rotation = Transform3::rotation(0.0, 0.0, 90.0);
+		let moved    = Transform3::translation(&Vector3::new(10.0, 0.0, 0.0));
+		assert_eq!(rotation.then(&moved), moved*rotation);
+		assert_eq!(Transform3::from(Matrix3D::from(rotation)*Matrix3D::from(moved)), moved*rotation);
+
+		let inverse = transform.inverse().unwrap();
+		assert!((inverse*transform*Point3::new(1.0, 2.0, 3.0)).distance(&Point3::new(1.0, 2.0, 3.0)) < 1e-12);
+		assert_eq!(Transform3::scaling(1.0, 0.0, 1.0).inverse(), None);
+
+		// Normals stay perpendicular to surfaces when scaled non-uniformly
+		let squash  = Transform3::scaling(1.0, 4.0, 1.0);
+		let surface = squash.transform_vector(&Vector3::new(1.0, -1.0, 0.0));
+		let normal  = squash.transform_normal(&Vector3::new(1.0, 1.0, 0.0)).unwrap();
+		assert!(surface.dot(&normal).abs() < 1e-12);
+
+		assert!(Transform3::mirror(&Vector3::unit_x()).is_mirrored());
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn singular_and_projective_matrices()
+	{
+		let mut flat = Matrix3D::identity();
+		flat.scale(1.0, 1.0, 0.0);
+		assert!(flat.decompose().is_none());
+		assert_eq!(flat.get_scale(), (1.0, 1.0, 0.0));
+		assert_eq!(flat.get_rotate(), (0.0, 0.0, 0.0));
+
+		let mut projective = Matrix3D::identity();
+		projective[3][2] = 0.5;
+		assert!(projective.decompose().is_none());
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn object_getters()
+	{
+		struct Object(Matrix3D);
+		impl HasRefSys3D for Object
+		{
+			fn ref_sys_mut(&mut self) -> &mut Matrix3D { &mut self.0 }
+			fn ref_sys(&self) -> &Matrix3D { &self.0 }
+			fn set_ref_sys(&mut self, ref_sys: Matrix3D) { self.0 = ref_sys; }
+		}
+
+		let mut object = Object(Matrix3D::identity());
+		object.rotate(10.0, 20.0, 30.0);
+		object.scale(2.0, 2.0, 2.0);
+		assert!((object.get_rotate_x() - 10.0).abs() < 1e-9);
+		assert!((object.get_rotate_y() - 20.0).abs() < 1e-9);
+		assert!((object.get_rotate_z() - 30.0).abs() < 1e-9);
+		assert!((object.get_scale_y() - 2.0).abs() < 1e-9);
+		assert!(!object.is_mirrored());
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn b_splines()
+	{
+		let close = |a: &Point2D, b: &Point2D| a.distance(b) < 1e-9;
+		let points = vec![point2D(0.0, 0.0), point2D(10.0, 0.0), point2D(10.0, 10.0), point2D(0.0, 10.0), point2D(-5.0, 5.0)];
+
+		// Cubic: starts at the average (P0 + 4 P1 + P2)/6 rather than at P0
+		let open = UniformBSpline::new(points.clone(), 3).expect("control points");
+		assert_eq!(open.spans(), 2);
+		assert!(close(&open.eval(0.0), &point2D(50.0/6.0, 10.0/6.0)));
+
+		// Repeating the end points 'degree' times makes the curve interpolate them
+		let mut clamped = vec![points[0], points[0]];
+		clamped.extend(points.iter().copied());
+		clamped.extend([points[4], points[4]]);
+		let clamped = UniformBSpline::new(clamped, 3).expect("control points");
+		assert!(close(&clamped.eval(0.0), &points[0]));
+		assert!(close(&clamped.eval(1.0), &points[4]));
+
+		// Degree 1 is the polyline through the points
+		let linear = UniformBSpline::new(points.clone(), 1).expect("control points");
+		assert!(close(&linear.eval(0.0), &points[0]) && close(&linear.eval(0.5), &points[2]) && close(&linear.eval(1.0), &points[4]));
+
+		// Closed: periodic, one span per control point, and smooth across the seam
+		let closed = UniformBSpline::new_closed(points.clone(), 3).expect("control points");
+		assert_eq!(closed.spans(), 5);
+		assert!(close(&closed.eval(0.0), &closed.eval(1.0)));
+		let flat = closed.flatten(0.01);
+		assert!(close(&flat[0], &flat[flat.len()-1]));
+		let h = 1e-6;
+		let (before, after) = (closed.eval(1.0-h), closed.eval(h));
+		let seam = closed.eval(0.0);
+		assert!(((seam[0]-before[0]) - (after[0]-seam[0])).abs() < 1e-9 && ((seam[1]-before[1]) - (after[1]-seam[1])).abs() < 1e-9);
+
+		// Too few points for the degree, a single point and none at all
+		let short = UniformBSpline::new(points[..2].to_vec(), 3).expect("control points");
+		assert!(close(&short.eval(0.5), &point2D(5.0, 0.0)));
+		assert!(close(&UniformBSpline::new(vec![points[2]], 3).expect("a control point").eval(0.7), &points[2]));
+		assert!(close(&UniformBSpline::new_closed(vec![points[2]], 3).expect("a control point").eval(0.7), &points[2]));
+		assert!(UniformBSpline::<Point2D>::new(vec![], 3).is_none());
+		assert!(UniformBSpline::<Point3D>::new_closed(vec![], 2).is_none());
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn look_at_direction()
+	{
+		let target = point3D(10.0, -4.0, 7.0);
+		let cases  =
+		[
+			((0.0, 0.0, 0.0),    (0.0, 0.0, 0.0),    (1.0, 1.0, 1.0)),
+			((30.0, -60.0, 10.0), (0.0, 0.0, 0.0),    (2.0, 3.0, 4.0)),
+			((30.0, -60.0, 10.0), (0.5, 0.3, 0.2),    (2.0, 3.0, 4.0)),
+			((0.0, 90.0, 0.0),   (0.0, 0.0, 0.0),    (-1.0, 2.0, 1.0)),
+		];
+		for (rotation, shear, scale) in cases
+		{
+			let mut matrix = transform((1.0, 2.0, 3.0), rotation, shear, scale);
+			matrix.look_at(&target, &vector3D(0.0, 0.0, 1.0));
+
+			let column  = |j: usize| vector3D(matrix[0][j], matrix[1][j], matrix[2][j]).normalized();
+			let along   = |v: Vector3D| { let forward = vector3D(9.0, -6.0, 4.0).normalized(); v[0]*forward[0] + v[1]*forward[1] + v[2]*forward[2] };
+			assert!((along(column(2)) - 1.0).abs() < 1e-9, "z points at the target: {:?}", (rotation, shear, scale));
+			assert!(along(column(1)).abs() < 1e-9 && column(1)[2] > 0.0);
+			assert!(along(column(0)).abs() < 1e-9);
+
+			assert_eq!(matrix.get_translate(), (1.0, 2.0, 3.0));
+			let kept = matrix.get_scale();
+			assert!((kept.0 - scale.0.abs()).abs() < 1e-9 && (kept.1 - scale.1.abs()).abs() < 1e-9 && (kept.2 - scale.2.abs()).abs() < 1e-9);
+			assert_eq!(matrix.is_mirrored(), scale.0 < 0.0);
+		}
+
+		// 'up' along the direction, and the target on the origin
+		let mut matrix = Matrix3D::identity();
+		matrix.look_at(&point3D(0.0, 0.0, -5.0), &vector3D(0.0, 0.0, 1.0));
+		assert!((matrix[2][2] + 1.0).abs() < 1e-9);
+		let before = matrix;
+		matrix.look_at(&point3D(0.0, 0.0, 0.0), &vector3D(0.0, 0.0, 1.0));
+		assert_close(&matrix, &before);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn matrix_2d_display()
+	{
+		// Matrix2D has three rows; display() used to print a fourth one and panicked
+		let text = Matrix2D::identity().display(1);
+		assert_eq!(text.lines().count(), 3);
+		assert!(text.starts_with("\t["));
+		assert!(text.ends_with("]"));
+	}
+	//}}}
+}
+//}}}
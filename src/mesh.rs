@@ -0,0 +1,861 @@
+// Native tessellation of Object3D trees into triangle meshes, for exporting without OpenSCad (STL, SVG outlines).
+//
+// The primitives are tessellated like OpenSCad does ($fn/$fa/$fs, the same vertex positions) and the CSG operations are
+// done with BSP trees. 2D shapes are handled as prisms of height 1, so that 2D booleans use the same code; the bottom faces
+// of the result are the 2D region. Text and minkowski() are not supported.
+//
+// Objects with the disable (*) or background (%) modifier are left out, as in an OpenSCad render.
+
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::csg::{self, Polygon, sub};
+use crate::math::{Matrix3D, Point2D, Point3D, point2D, point3D};
+use crate::object_3d::{BooleanOp, Object3D, Shape3D};
+use crate::resolution::Resolution;
+use crate::ScadModifier;
+
+// OpenSCad's defaults for $fa and $fs
+const DEFAULT_FA : f64 = 12.0;
+const DEFAULT_FS : f64 = 2.0;
+
+// Vertices closer than this are merged
+const WELD : f64 = 1e-6;
+
+//{{{ pub enum TessellationError
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TessellationError
+{
+	Unsupported{ object: String, feature: String },
+	Flat(String),  // A 2D object where a solid is needed
+	Solid(String), // A 3D object inside a 2D one
+}
+
+//{{{
+impl fmt::Display for TessellationError
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+	{
+		match self
+		{
+			Self::Unsupported{object, feature} => write!(f, "{}: {} can not be tessellated natively, render it with OpenSCad instead", object, feature),
+			Self::Flat(object)                 => write!(f, "{} is a 2D object, extrude it first", object),
+			Self::Solid(object)                => write!(f, "{} is a 3D object inside a 2D one", object),
+		}
+	}
+}
+//}}}
+
+impl std::error::Error for TessellationError {}
+//}}}
+
+//{{{ pub struct Mesh
+
+// Triangles are counter-clockwise when seen from outside (as in STL, the opposite of OpenSCad's polyhedron faces)
+#[derive(Debug, Clone, Default)]
+pub struct Mesh
+{
+	pub vertices  : Vec<Point3D>,
+	pub triangles : Vec<[usize; 3]>,
+}
+
+//{{{
+impl Mesh
+{
+	//{{{
+	pub fn from_object(object: &Object3D) -> Result<Mesh, TessellationError>
+	{
+		Self::from_object_with(object, &Resolution::default())
+	}
+	//}}}
+	//{{{
+	// With 'resolution' around the object, as set for the whole file
+	pub fn from_object_with(object: &Object3D, resolution: &Resolution) -> Result<Mesh, TessellationError>
+	{
+		if is_flat(object) { return Err(TessellationError::Flat(object.name.clone())); }
+		Ok(Self::from_polygons(&solid(object, resolution)?))
+	}
+	//}}}
+	//{{{
+	fn from_polygons(polygons: &[Polygon]) -> Mesh
+	{
+		let mut mesh  = Mesh::default();
+		let mut index = HashMap::new();
+		let mut weld  = |mesh: &mut Mesh, p: &Point3D| -> usize
+		{
+			*index.entry(key(p)).or_insert_with(|| { mesh.vertices.push(*p); mesh.vertices.len()-1 })
+		};
+
+		for polygon in polygons
+		{
+			let indices : Vec<usize> = polygon.vertices.iter().map(|p| weld(&mut mesh, p)).collect();
+			for i in 1..indices.len().saturating_sub(1)
+			{
+				let triangle = [indices[0], indices[i], indices[i+1]];
+				if triangle[0] != triangle[1] && triangle[1] != triangle[2] && triangle[2] != triangle[0]
+				{
+					mesh.triangles.push(triangle);
+				}
+			}
+		}
+		mesh
+	}
+	//}}}
+
+	//{{{
+	pub fn append(&mut self, other: &Mesh)
+	{
+		let offset = self.vertices.len();
+		self.vertices.extend(other.vertices.iter().cloned());
+		self.triangles.extend(other.triangles.iter().map(|t| [t[0]+offset, t[1]+offset, t[2]+offset]));
+	}
+	//}}}
+	//{{{
+	// Moves the vertices. Mirroring matrices flip the triangles, so that they still face outwards.
+	pub fn transform(&mut self, matrix: &Matrix3D)
+	{
+		for vertex in &mut self.vertices { *vertex = *matrix * *vertex; }
+		if determinant(matrix) < 0.0
+		{
+			for triangle in &mut self.triangles { triangle.swap(1, 2); }
+		}
+	}
+	//}}}
+	//{{{
+	// Minimum and maximum corner, None for empty meshes
+	pub fn bounding_box(&self) -> Option<(Point3D, Point3D)>
+	{
+		let first = self.vertices.first()?;
+		let mut min = [first[0], first[1], first[2]];
+		let mut max = min;
+		for v in &self.vertices
+		{
+			for i in 0..3
+			{
+				min[i] = min[i].min(v[i]);
+				max[i] = max[i].max(v[i]);
+			}
+		}
+		Some((point3D(min[0], min[1], min[2]), point3D(max[0], max[1], max[2])))
+	}
+	//}}}
+	//{{{
+	pub fn volume(&self) -> f64
+	{
+		self.triangles.iter().map(|t|
+		{
+			let (a, b, c) = (self.vertices[t[0]], self.vertices[t[1]], self.vertices[t[2]]);
+			a[0]*(b[1]*c[2] - b[2]*c[1]) - a[1]*(b[0]*c[2] - b[2]*c[0]) + a[2]*(b[0]*c[1] - b[1]*c[0])
+		}).sum::<f64>()/6.0
+	}
+	//}}}
+	//{{{
+	// Outline of the faces pointing down at the lowest level, e.g. of a board lying flat on the XY-plane.
+	// Outer loops are counter-clockwise, holes clockwise.
+	pub fn footprint(&self) -> Vec<Vec<Point2D>>
+	{
+		loops(&self.bottom_faces())
+	}
+	//}}}
+	//{{{
+	// Area of the faces pointing down at the lowest level, i.e. the contact area with the print bed
+	pub fn footprint_area(&self) -> f64
+	{
+		self.bottom_faces().iter().map(|face| signed_area(face)).sum()
+	}
+	//}}}
+	//{{{
+	// The faces pointing down at the lowest level, counter-clockwise when seen from above
+	fn bottom_faces(&self) -> Vec<Vec<Point2D>>
+	{
+		let min_z = match self.bounding_box() { Some((min, _)) => min[2], None => return vec![] };
+		self.triangles.iter().filter_map(|t|
+		{
+			let (a, b, c) = (self.vertices[t[0]], self.vertices[t[1]], self.vertices[t[2]]);
+			let flat_bottom = [a, b, c].iter().all(|p| (p[2]-min_z).abs() < 1e-6);
+			let normal_z    = (b[0]-a[0])*(c[1]-a[1]) - (b[1]-a[1])*(c[0]-a[0]);
+			// Seen from above, the bottom faces are clockwise
+			if flat_bottom && normal_z < 0.0 { Some(vec![point2D(a[0], a[1]), point2D(c[0], c[1]), point2D(b[0], b[1])]) } else { None }
+		}).collect()
+	}
+	//}}}
+
+	//{{{
+	// Binary STL
+	pub fn write_stl(&self, name: &str, out: &mut dyn Write) -> io::Result<()>
+	{
+		let mut header = [b' '; 80];
+		for (i, byte) in format!("rusty_scad {}", name).bytes().take(80).enumerate() { header[i] = byte; }
+		out.write_all(&header)?;
+		out.write_all(&(self.triangles.len() as u32).to_le_bytes())?;
+
+		for t in &self.triangles
+		{
+			let (a, b, c) = (self.vertices[t[0]], self.vertices[t[1]], self.vertices[t[2]]);
+			let normal    = sub(&b, &a).cross(&sub(&c, &a)).normalized();
+			for value in [normal[0], normal[1], normal[2], a[0], a[1], a[2], b[0], b[1], b[2], c[0], c[1], c[2]]
+			{
+				out.write_all(&(value as f32).to_le_bytes())?;
+			}
+			out.write_all(&[0, 0])?;
+		}
+		Ok(())
+	}
+	//}}}
+}
+//}}}
+//}}}
+
+//{{{ Outlines and SVG
+
+//{{{
+// The 2D outline of an object: the region of 2D objects, the footprint (see Mesh::footprint) of 3D ones
+pub fn outline(object: &Object3D) -> Result<Vec<Vec<Point2D>>, TessellationError>
+{
+	outline_with(object, &Resolution::default())
+}
+//}}}
+//{{{
+pub fn outline_with(object: &Object3D, resolution: &Resolution) -> Result<Vec<Vec<Point2D>>, TessellationError>
+{
+	if is_flat(object)
+	{
+		return Ok(Mesh::from_polygons(&flat(object, resolution)?).footprint());
+	}
+	Ok(Mesh::from_object_with(object, resolution)?.footprint())
+}
+//}}}
+//{{{
+// SVG document with the loops as one path (even-odd filled, so holes stay open). SVG's y-axis points down, so y is flipped.
+pub fn svg(loops: &[Vec<Point2D>]) -> String
+{
+	let points = loops.iter().flatten();
+	let (mut min_x, mut min_y, mut max_x, mut max_y) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+	for p in points
+	{
+		min_x = min_x.min(p[0]); max_x = max_x.max(p[0]);
+		min_y = min_y.min(p[1]); max_y = max_y.max(p[1]);
+	}
+	if min_x > max_x { min_x = 0.0; min_y = 0.0; max_x = 0.0; max_y = 0.0; }
+	let (width, height) = (max_x-min_x, max_y-min_y);
+
+	let mut path = String::from("");
+	for outline in loops
+	{
+		for (i, p) in outline.iter().enumerate()
+		{
+			path += &format!("{}{} {} ", if i == 0 { "M" } else { "L" }, p[0], -p[1]);
+		}
+		path += "Z ";
+	}
+
+	format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+		<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}mm\" height=\"{1}mm\" viewBox=\"{2} {3} {0} {1}\">\n\
+		<path d=\"{4}\" fill=\"lightgray\" fill-rule=\"evenodd\" stroke=\"black\" stroke-width=\"0.1\"/>\n\
+		</svg>\n", width, height, min_x, -max_y, path.trim_end())
+}
+//}}}
+//}}}
+
+//{{{ Helpers
+
+//{{{
+fn key(p: &Point3D) -> (i64, i64, i64)
+{
+	((p[0]/WELD).round() as i64, (p[1]/WELD).round() as i64, (p[2]/WELD).round() as i64)
+}
+//}}}
+//{{{
+pub(crate) fn determinant(m: &Matrix3D) -> f64
+{
+	m[0][0]*(m[1][1]*m[2][2] - m[1][2]*m[2][1]) - m[0][1]*(m[1][0]*m[2][2] - m[1][2]*m[2][0]) + m[0][2]*(m[1][0]*m[2][1] - m[1][1]*m[2][0])
+}
+//}}}
+//{{{
+fn transform(polygons: &mut Vec<Polygon>, matrix: &Matrix3D)
+{
+	let mirrored = determinant(matrix) < 0.0;
+	*polygons = polygons.drain(..).filter_map(|polygon|
+	{
+		let mut vertices : Vec<Point3D> = polygon.vertices.iter().map(|v| *matrix * *v).collect();
+		if mirrored { vertices.reverse(); }
+		Polygon::new(vertices)
+	}).collect();
+}
+//}}}
+//{{{
+// The part of a transformation that acts within the XY-plane, as OpenSCad ignores the rest for 2D objects
+fn flat_matrix(matrix: &Matrix3D) -> Matrix3D
+{
+	let mut m = *matrix;
+	m[0][2] = 0.0;
+	m[1][2] = 0.0;
+	m[2]    = [0.0, 0.0, 1.0, 0.0];
+	m
+}
+//}}}
+//{{{
+// Number of fragments of a circle, as OpenSCad's get_fragments_from_r()
+pub fn fragments(r: f64, resolution: &Resolution) -> usize
+{
+	if let Some(n) = resolution.face_number
+	{
+		if n > 0 { return n.max(3) as usize; }
+	}
+	if r < 1e-9 { return 3; }
+	let fa = resolution.face_angle.unwrap_or(DEFAULT_FA);
+	let fs = resolution.face_size.unwrap_or(DEFAULT_FS);
+	(360.0/fa).min(2.0*PI*r/fs).max(5.0).ceil() as usize
+}
+//}}}
+//{{{
+fn circle_points(r: f64, n: usize) -> Vec<Point2D>
+{
+	(0..n).map(|i| { let phi = 2.0*PI*(i as f64)/(n as f64); point2D(r*phi.cos(), r*phi.sin()) }).collect()
+}
+//}}}
+//{{{
+fn is_flat(object: &Object3D) -> bool
+{
+	match &object.shape
+	{
+		Shape3D::Square{..} | Shape3D::Circle{..} | Shape3D::Polygon{..} | Shape3D::Text{..} => true,
+		Shape3D::Composite{children, ..}                                                    => children.iter().any(is_flat),
+		_                                                                                   => false,
+	}
+}
+//}}}
+//{{{
+pub(crate) fn skipped(object: &Object3D) -> bool
+{
+	matches!(object.scad_modifier, ScadModifier::Disable | ScadModifier::Background)
+}
+//}}}
+//}}}
+
+//{{{ 2D helpers
+
+//{{{
+pub(crate) fn signed_area(points: &[Point2D]) -> f64
+{
+	let n = points.len();
+	(0..n).map(|i| { let (a, b) = (points[i], points[(i+1)%n]); a[0]*b[1] - b[0]*a[1] }).sum::<f64>()/2.0
+}
+//}}}
+//{{{
+// Ear clipping of a simple polygon, counter-clockwise
+fn triangulate(points: &[Point2D]) -> Vec<[usize; 3]>
+{
+	let cross = |o: Point2D, a: Point2D, b: Point2D| (a[0]-o[0])*(b[1]-o[1]) - (a[1]-o[1])*(b[0]-o[0]);
+	let mut remaining : Vec<usize> = (0..points.len()).collect();
+	let mut triangles = vec![];
+
+	while remaining.len() > 3
+	{
+		let n = remaining.len();
+		let ear = (0..n).find(|&i|
+		{
+			let (a, b, c) = (remaining[(i+n-1)%n], remaining[i], remaining[(i+1)%n]);
+			if cross(points[a], points[b], points[c]) <= 1e-12 { return false; }
+			remaining.iter().all(|&k|
+			{
+				k == a || k == b || k == c
+					|| cross(points[a], points[b], points[k]) < 0.0
+					|| cross(points[b], points[c], points[k]) < 0.0
+					|| cross(points[c], points[a], points[k]) < 0.0
+			})
+		});
+		// Degenerate (e.g. self intersecting) rest: cut off a vertex anyway
+		let i = ear.unwrap_or(0);
+		triangles.push([remaining[(i+n-1)%n], remaining[i], remaining[(i+1)%n]]);
+		remaining.remove(i);
+	}
+	if remaining.len() == 3 { triangles.push([remaining[0], remaining[1], remaining[2]]); }
+	triangles
+}
+//}}}
+//{{{
+// Boundary loops of a region given as counter-clockwise polygons. Edges shared by two polygons cancel, T-junctions
+// (a vertex of one polygon on the edge of another, as left by the BSP splits) are resolved first.
+pub(crate) fn loops(polygons: &[Vec<Point2D>]) -> Vec<Vec<Point2D>>
+{
+	type Key = (i64, i64);
+	let key2 = |p: &Point2D| -> Key { ((p[0]/WELD).round() as i64, (p[1]/WELD).round() as i64) };
+
+	let mut vertices : HashMap<Key, Point2D> = HashMap::new();
+	for p in polygons.iter().flatten() { vertices.entry(key2(p)).or_insert(*p); }
+	let vertices : Vec<Point2D> = vertices.into_values().collect();
+
+	//{{{ Directed edges, split at the vertices lying on them
+
+	let mut edges : HashMap<(Key, Key), i32> = HashMap::new();
+	let mut position = HashMap::new();
+	for polygon in polygons
+	{
+		let n = polygon.len();
+		for i in 0..n
+		{
+			let (a, b) = (polygon[i], polygon[(i+1)%n]);
+			let (dx, dy) = (b[0]-a[0], b[1]-a[1]);
+			let length2  = dx*dx + dy*dy;
+			if length2 < WELD*WELD { continue; }
+
+			let mut on_edge : Vec<(f64, Point2D)> = vertices.iter().filter_map(|v|
+			{
+				let t     = ((v[0]-a[0])*dx + (v[1]-a[1])*dy)/length2;
+				let cross = ((v[0]-a[0])*dy - (v[1]-a[1])*dx).abs()/length2.sqrt();
+				if t > 1e-9 && t < 1.0-1e-9 && cross < 10.0*WELD { Some((t, *v)) } else { None }
+			}).collect();
+			on_edge.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+			let mut chain = vec![a];
+			chain.extend(on_edge.into_iter().map(|(_, v)| v));
+			chain.push(b);
+			for pair in chain.windows(2)
+			{
+				let (ka, kb) = (key2(&pair[0]), key2(&pair[1]));
+				if ka == kb { continue; }
+				position.insert(ka, pair[0]);
+				position.insert(kb, pair[1]);
+				*edges.entry((ka, kb)).or_insert(0) += 1;
+			}
+		}
+	}
+	//}}}
+
+	//{{{ Chain the remaining edges into loops
+
+	let mut next : HashMap<Key, Vec<Key>> = HashMap::new();
+	let mut keys : Vec<_> = edges.keys().cloned().collect();
+	keys.sort();
+	for (a, b) in keys
+	{
+		let count = edges[&(a, b)] - edges.get(&(b, a)).cloned().unwrap_or(0);
+		for _ in 0..count.max(0) { next.entry(a).or_default().push(b); }
+	}
+
+	let mut result = vec![];
+	let mut starts : Vec<_> = next.keys().cloned().collect();
+	starts.sort();
+	for start in starts
+	{
+		while let Some(mut current) = next.get_mut(&start).and_then(|n| n.pop())
+		{
+			let mut outline = vec![position[&start]];
+			while current != start
+			{
+				outline.push(position[&current]);
+				current = match next.get_mut(&current).and_then(|n| n.pop()) { Some(k) => k, None => break };
+			}
+			// Drop the vertices left on straight edges by the splits
+			let n = outline.len();
+			let outline : Vec<Point2D> = (0..n).filter(|&i|
+			{
+				let (a, b, c) = (outline[(i+n-1)%n], outline[i], outline[(i+1)%n]);
+				let cross     = (b[0]-a[0])*(c[1]-b[1]) - (b[1]-a[1])*(c[0]-b[0]);
+				let along     = (b[0]-a[0])*(c[0]-b[0]) + (b[1]-a[1])*(c[1]-b[1]);
+				cross.abs() > WELD*((c[0]-a[0]).hypot(c[1]-a[1])) || along < 0.0
+			}).map(|i| outline[i]).collect();
+			if outline.len() >= 3 { result.push(outline); }
+		}
+	}
+	//}}}
+
+	result
+}
+//}}}
+//}}}
+
+//{{{ Tessellation
+
+//{{{
+// The solid of the object in its parent's coordinate system, 'resolution' is the one around it
+fn solid(object: &Object3D, resolution: &Resolution) -> Result<Vec<Polygon>, TessellationError>
+{
+	if skipped(object) { return Ok(vec![]); }
+	let mut polygons = shape(object, &object.resolution.within(resolution))?;
+	transform(&mut polygons, &object.ref_sys);
+	Ok(polygons)
+}
+//}}}
+//{{{
+fn shape(object: &Object3D, resolution: &Resolution) -> Result<Vec<Polygon>, TessellationError>
+{
+	let polygons = match &object.shape
+	{
+		Shape3D::Square{..} | Shape3D::Circle{..} | Shape3D::Polygon{..} | Shape3D::Text{..} => return Err(TessellationError::Flat(object.name.clone())),
+
+		//{{{
+		Shape3D::Cube{x, y, z} =>
+		{
+			let (x, y, z) = (0.5*x.value(), 0.5*y.value(), 0.5*z.value());
+			let p = |i: usize| point3D(if i & 1 == 0 { -x } else { x }, if i & 2 == 0 { -y } else { y }, if i & 4 == 0 { -z } else { z });
+			[[0, 2, 3, 1], [4, 5, 7, 6], [0, 1, 5, 4], [2, 6, 7, 3], [0, 4, 6, 2], [1, 3, 7, 5]].iter()
+				.filter_map(|face| Polygon::new(face.iter().map(|&i| p(i)).collect())).collect()
+		}
+		//}}}
+		//{{{
+		Shape3D::Sphere{r, face_number, face_angle, face_size} =>
+		{
+			let r     = r.value();
+			let n     = fragments(r, &Resolution::new(*face_number, *face_angle, *face_size).within(resolution));
+			let rings = n.div_ceil(2);
+			let ring  = |i: usize| -> Vec<Point3D>
+			{
+				let phi = PI*((i as f64) + 0.5)/(rings as f64);
+				circle_points(r*phi.sin(), n).iter().map(|p| point3D(p[0], p[1], r*phi.cos())).collect()
+			};
+
+			let mut polygons = vec![];
+			let top = ring(0);
+			polygons.extend(Polygon::new(top.clone()));
+			let mut previous = top;
+			for i in 1..rings
+			{
+				let current = ring(i);
+				for k in 0..n
+				{
+					let l = (k+1)%n;
+					polygons.extend(Polygon::new(vec![previous[k], current[k], current[l], previous[l]]));
+				}
+				previous = current;
+			}
+			previous.reverse();
+			polygons.extend(Polygon::new(previous));
+			polygons
+		}
+		//}}}
+		//{{{
+		Shape3D::Cylinder{h, r1, r2, face_number, face_angle, face_size} =>
+		{
+			let (h, r1, r2) = (h.value(), r1.value(), r2.value());
+			let n      = fragments(r1.max(r2), &Resolution::new(*face_number, *face_angle, *face_size).within(resolution));
+			let bottom : Vec<Point3D> = circle_points(r1, n).iter().map(|p| point3D(p[0], p[1], 0.0)).collect();
+			let top    : Vec<Point3D> = circle_points(r2, n).iter().map(|p| point3D(p[0], p[1], h)).collect();
+
+			let mut polygons = vec![];
+			for k in 0..n
+			{
+				let l = (k+1)%n;
+				let side = match (r1 > 0.0, r2 > 0.0)
+				{
+					(true,  true)  => vec![bottom[k], bottom[l], top[l], top[k]],
+					(true,  false) => vec![bottom[k], bottom[l], top[k]],
+					(false, true)  => vec![bottom[k], top[l], top[k]],
+					(false, false) => vec![],
+				};
+				polygons.extend(Polygon::new(side));
+			}
+			if r1 > 0.0 { polygons.extend(Polygon::new(bottom.into_iter().rev().collect())); }
+			if r2 > 0.0 { polygons.extend(Polygon::new(top)); }
+			polygons
+		}
+		//}}}
+		//{{{
+		Shape3D::Polyhedron{points, faces, ..} =>
+		{
+			let mut polygons = vec![];
+			for face in faces
+			{
+				// OpenSCad's faces are clockwise from outside
+				let vertices : Vec<Point3D> = face.iter().rev().map(|&i| points[i as usize]).collect();
+				polygons.extend(convex_pieces(vertices));
+			}
+			polygons
+		}
+		//}}}
+		//{{{
+		Shape3D::Lextrude{height, center, twist, slices, scale, face_number, face_angle, face_size, child, ..} =>
+		{
+			let region = Mesh::from_polygons(&flat(child, resolution)?).bottom_faces();
+
+			let (sx, sy) = match scale.len() { 0 => (1.0, 1.0), 1 => (scale[0], scale[0]), _ => (scale[0], scale[1]) };
+			let radius   = region.iter().flatten().map(|p| p[0].hypot(p[1])).fold(0.0, f64::max);
+			let slices   = if *slices > 0 { *slices as usize }
+			               else if *twist != 0.0 { ((twist.abs()/360.0*(fragments(radius, &Resolution::new(*face_number, *face_angle, *face_size).within(resolution)) as f64)).ceil() as usize).max(1) }
+			               else { 1 };
+
+			extrude(&region, height.value(), *center, *twist, slices, (sx, sy))
+		}
+		//}}}
+		//{{{
+		Shape3D::Composite{op, children} =>
+		{
+			let mut solids = vec![];
+			for child in children.iter().filter(|child| !skipped(child)) { solids.push(solid(child, resolution)?); }
+			combine(object, op, solids)?
+		}
+		//}}}
+	};
+	Ok(polygons)
+}
+//}}}
+//{{{
+fn combine(object: &Object3D, op: &BooleanOp, solids: Vec<Vec<Polygon>>) -> Result<Vec<Polygon>, TessellationError>
+{
+	let mut solids = solids.into_iter();
+	let first      = solids.next().unwrap_or_default();
+	Ok(match op
+	{
+		BooleanOp::union        => solids.fold(first, csg::union),
+		BooleanOp::difference   => solids.fold(first, csg::difference),
+		BooleanOp::intersection => solids.fold(first, csg::intersection),
+		BooleanOp::hull         =>
+		{
+			let points : Vec<Point3D> = std::iter::once(first).chain(solids).flatten().flat_map(|polygon| polygon.vertices).collect();
+			csg::hull(&points)
+		}
+		BooleanOp::minkowski    => return Err(TessellationError::Unsupported{ object: object.name.clone(), feature: String::from("minkowski()") }),
+	})
+}
+//}}}
+//{{{
+// Splits a planar polygon into triangles, unless it is convex already
+fn convex_pieces(vertices: Vec<Point3D>) -> Vec<Polygon>
+{
+	let polygon = match Polygon::new(vertices) { Some(polygon) => polygon, None => return vec![] };
+	if polygon.vertices.len() == 3 { return vec![polygon]; }
+
+	// Project along the largest component of the normal, keeping the orientation
+	let normal = polygon.plane.normal;
+	let axis   = (0..3).max_by(|&a, &b| normal[a].abs().total_cmp(&normal[b].abs())).unwrap_or(2);
+	let (u, v) = match axis { 0 => (1, 2), 1 => (2, 0), _ => (0, 1) };
+	let mut flat : Vec<Point2D> = polygon.vertices.iter().map(|p| point2D(p[u], p[v])).collect();
+	let flipped = normal[axis] < 0.0;
+	if flipped { flat.reverse(); }
+
+	let n      = flat.len();
+	let convex = (0..n).all(|i|
+	{
+		let (a, b, c) = (flat[i], flat[(i+1)%n], flat[(i+2)%n]);
+		(b[0]-a[0])*(c[1]-b[1]) - (b[1]-a[1])*(c[0]-b[0]) >= -1e-12
+	});
+	if convex { return vec![polygon]; }
+
+	let index = |i: usize| if flipped { n-1-i } else { i };
+	triangulate(&flat).iter()
+		.filter_map(|t| { let mut vertices = vec![polygon.vertices[index(t[0])], polygon.vertices[index(t[1])], polygon.vertices[index(t[2])]]; if flipped { vertices.reverse(); } Polygon::new(vertices) })
+		.collect()
+}
+//}}}
+//}}}
+
+//{{{ 2D objects as prisms
+
+//{{{
+// A 2D object as a prism from z = 0 to 1, in its parent's coordinate system
+fn flat(object: &Object3D, resolution: &Resolution) -> Result<Vec<Polygon>, TessellationError>
+{
+	if skipped(object) { return Ok(vec![]); }
+	let resolution   = object.resolution.within(resolution);
+	let mut polygons = match &object.shape
+	{
+		Shape3D::Square{x, y} =>
+		{
+			let (x, y) = (0.5*x.value(), 0.5*y.value());
+			prism(&[point2D(-x, -y), point2D(x, -y), point2D(x, y), point2D(-x, y)])
+		}
+		Shape3D::Circle{r, face_number, face_angle, face_size} =>
+		{
+			let r = r.value();
+			prism(&circle_points(r, fragments(r, &Resolution::new(*face_number, *face_angle, *face_size).within(&resolution))))
+		}
+		Shape3D::Polygon{points, paths, ..} =>
+		{
+			// The first path is the outline, the others are holes
+			let paths : Vec<Vec<Point2D>> = if paths.is_empty() { vec![points.clone()] }
+			                                else { paths.iter().map(|path| path.iter().map(|&i| points[i as usize]).collect()).collect() };
+			let mut paths = paths.iter();
+			let outline   = paths.next().map(|path| prism(path)).unwrap_or_default();
+			paths.map(|path| prism(path)).fold(outline, csg::difference)
+		}
+		Shape3D::Text{..} => return Err(TessellationError::Unsupported{ object: object.name.clone(), feature: String::from("text()") }),
+		Shape3D::Composite{op, children} =>
+		{
+			let mut prisms = vec![];
+			for child in children.iter().filter(|child| !skipped(child)) { prisms.push(flat(child, &resolution)?); }
+			combine(object, op, prisms)?
+		}
+		_ => return Err(TessellationError::Solid(object.name.clone())),
+	};
+	transform(&mut polygons, &flat_matrix(&object.ref_sys));
+	Ok(polygons)
+}
+//}}}
+//{{{
+fn prism(outline: &[Point2D]) -> Vec<Polygon>
+{
+	let mut outline = outline.to_vec();
+	if signed_area(&outline) < 0.0 { outline.reverse(); }
+	let region : Vec<Vec<Point2D>> = triangulate(&outline).iter().map(|t| vec![outline[t[0]], outline[t[1]], outline[t[2]]]).collect();
+	extrude(&region, 1.0, false, 0.0, 1, (1.0, 1.0))
+}
+//}}}
+//{{{
+// Extrudes a region given as counter-clockwise convex polygons
+fn extrude(region: &[Vec<Point2D>], height: f64, center: bool, twist: f64, slices: usize, scale: (f64, f64)) -> Vec<Polygon>
+{
+	let z0 = if center { -0.5*height } else { 0.0 };
+	let at = |p: &Point2D, k: usize| -> Point3D
+	{
+		let t   = (k as f64)/(slices as f64);
+		let phi = -(twist*t).to_radians(); // OpenSCad twists clockwise
+		let (x, y) = (p[0]*(1.0 + (scale.0-1.0)*t), p[1]*(1.0 + (scale.1-1.0)*t));
+		point3D(x*phi.cos() - y*phi.sin(), x*phi.sin() + y*phi.cos(), z0 + height*t)
+	};
+
+	let mut polygons = vec![];
+	for polygon in region
+	{
+		polygons.extend(Polygon::new(polygon.iter().rev().map(|p| at(p, 0)).collect()));
+		if scale.0 != 0.0 && scale.1 != 0.0 { polygons.extend(Polygon::new(polygon.iter().map(|p| at(p, slices)).collect())); }
+	}
+	for outline in loops(region)
+	{
+		let n = outline.len();
+		for i in 0..n
+		{
+			let (a, b) = (&outline[i], &outline[(i+1)%n]);
+			for k in 0..slices
+			{
+				if twist == 0.0
+				{
+					polygons.extend(Polygon::new(vec![at(a, k), at(b, k), at(b, k+1), at(a, k+1)]));
+				} else {
+					polygons.extend(Polygon::new(vec![at(a, k), at(b, k), at(b, k+1)]));
+					polygons.extend(Polygon::new(vec![at(a, k), at(b, k+1), at(a, k+1)]));
+				}
+			}
+		}
+	}
+	polygons
+}
+//}}}
+//}}}
+
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::math::Is3DObject;
+	use crate::object_3d::{cube, cylinder, difference, intersection, union};
+
+	//{{{
+	// Directed edges without a matching opposite edge. The edges are first split at the vertices lying on them, since
+	// the BSP operations leave T-junctions where a face is cut and its neighbour is not.
+	fn open_edges(mesh: &Mesh) -> usize
+	{
+		let mut edges = HashMap::new();
+		for t in &mesh.triangles
+		{
+			for (a, b) in [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])]
+			{
+				let (pa, pb) = (mesh.vertices[a], mesh.vertices[b]);
+				let edge     = sub(&pb, &pa);
+				let length   = edge.l2_norm();
+				let mut on_edge : Vec<(f64, usize)> = mesh.vertices.iter().enumerate().filter_map(|(i, p)|
+				{
+					let t = csg::dot(&sub(p, &pa), &edge)/(length*length);
+					let d = sub(p, &pa).cross(&edge).l2_norm()/length;
+					if t > 1e-9 && t < 1.0-1e-9 && d < 1e-6 { Some((t, i)) } else { None }
+				}).collect();
+				on_edge.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+				let chain : Vec<usize> = std::iter::once(a).chain(on_edge.into_iter().map(|(_, i)| i)).chain(std::iter::once(b)).collect();
+				for pair in chain.windows(2) { *edges.entry((pair[0], pair[1])).or_insert(0i32) += 1; }
+			}
+		}
+		edges.iter().filter(|((a, b), &count)| edges.get(&(*b, *a)).copied().unwrap_or(0) != count).count()
+	}
+	//}}}
+	//{{{
+	fn mesh(object: &Object3D) -> Mesh
+	{
+		Mesh::from_object_with(object, &Resolution::face_number(12)).expect("solid object")
+	}
+	//}}}
+	//{{{
+	fn assert_solid(mesh: &Mesh, volume: f64, min: [f64; 3], max: [f64; 3])
+	{
+		assert_eq!(open_edges(mesh), 0);
+		assert!((mesh.volume() - volume).abs() < 1e-9, "volume {} instead of {}", mesh.volume(), volume);
+		let (low, high) = mesh.bounding_box().expect("not empty");
+		for i in 0..3
+		{
+			assert!((low[i] - min[i]).abs() < 1e-9 && (high[i] - max[i]).abs() < 1e-9, "bounding box {:?} {:?}", low, high);
+		}
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn primitives()
+	{
+		let block = mesh(&cube("block", 1.0, 2.0, 3.0));
+		assert_eq!((block.vertices.len(), block.triangles.len()), (8, 12));
+		assert_solid(&block, 6.0, [-0.5, -1.0, -1.5], [0.5, 1.0, 1.5]);
+
+		// 12 side quads and two 12-gons split into fans
+		let rod = mesh(&cylinder("rod", 5.0, 2.0, 2.0));
+		assert_eq!((rod.vertices.len(), rod.triangles.len()), (24, 2*12 + 2*10));
+		assert_solid(&rod, 0.5*12.0*4.0*(2.0*PI/12.0).sin()*5.0, [-2.0, -2.0, 0.0], [2.0, 2.0, 5.0]);
+
+		let cone = mesh(&cylinder("cone", 3.0, 2.0, 0.0));
+		assert_eq!((cone.vertices.len(), cone.triangles.len()), (13, 12 + 10));
+		assert_solid(&cone, 0.5*12.0*4.0*(2.0*PI/12.0).sin()*3.0/3.0, [-2.0, -2.0, 0.0], [2.0, 2.0, 3.0]);
+
+		// Mirroring keeps the triangles facing outwards
+		let mut mirrored = cube("mirrored", 1.0, 2.0, 3.0);
+		mirrored.mirror(crate::math::vector3D(1.0, 0.0, 0.0));
+		assert_solid(&mesh(&mirrored), 6.0, [-0.5, -1.0, -1.5], [0.5, 1.0, 1.5]);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn overlapping_cubes()
+	{
+		let a     = cube("a", 2.0, 2.0, 2.0);
+		let mut b = cube("b", 2.0, 2.0, 2.0);
+		b.translate(1.0, 1.0, 1.0);
+
+		assert_solid(&mesh(&union("union", [a.clone(), b.clone()])), 8.0+8.0-1.0, [-1.0; 3], [2.0; 3]);
+		assert_solid(&mesh(&difference("difference", [a.clone(), b.clone()])), 8.0-1.0, [-1.0; 3], [1.0; 3]);
+		assert_solid(&mesh(&difference("difference", [b.clone(), a.clone()])), 8.0-1.0, [0.0; 3], [2.0; 3]);
+		assert_solid(&mesh(&intersection("intersection", [a, b])), 1.0, [0.0; 3], [1.0; 3]);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn coplanar_faces()
+	{
+		// Sharing the top, bottom, front and back planes
+		let a     = cube("a", 2.0, 2.0, 2.0);
+		let mut b = cube("b", 2.0, 2.0, 2.0);
+		b.translate_x(1.0);
+
+		assert_solid(&mesh(&union("union", [a.clone(), b.clone()])), 12.0, [-1.0, -1.0, -1.0], [2.0, 1.0, 1.0]);
+		assert_solid(&mesh(&difference("difference", [a.clone(), b.clone()])), 4.0, [-1.0; 3], [0.0, 1.0, 1.0]);
+		assert_solid(&mesh(&intersection("intersection", [a.clone(), b])), 8.0-4.0, [0.0, -1.0, -1.0], [1.0; 3]);
+
+		// Touching along a face
+		let mut c = cube("c", 2.0, 2.0, 2.0);
+		c.translate_x(2.0);
+		assert_solid(&mesh(&union("union", [a.clone(), c])), 16.0, [-1.0, -1.0, -1.0], [3.0, 1.0, 1.0]);
+
+		// Identical solids
+		assert_solid(&mesh(&union("union", [a.clone(), a.clone()])), 8.0, [-1.0; 3], [1.0; 3]);
+		assert_solid(&mesh(&intersection("intersection", [a.clone(), a.clone()])), 8.0, [-1.0; 3], [1.0; 3]);
+		assert!(mesh(&difference("difference", [a.clone(), a])).volume().abs() < 1e-9);
+
+		// A hole through the whole block, flush with the top and bottom
+		let block = cube("block", 4.0, 4.0, 2.0);
+		let hole  = cube("hole", 2.0, 2.0, 2.0);
+		assert_solid(&mesh(&difference("frame", [block, hole])), 32.0-8.0, [-2.0, -2.0, -1.0], [2.0, 2.0, 1.0]);
+	}
+	//}}}
+}
+//}}}
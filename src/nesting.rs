@@ -0,0 +1,526 @@
+// Nesting of flat parts onto sheets (plywood, MDF, ...) for cutting.
+//
+// Every tagged part (see Object3D::tag_as_part) of the given objects is laid flat like for printing (largest side down,
+// see Project) and its outline is taken: either the bounding rectangle, or the projection, i.e. the faces the part lies
+// on (pockets in the bottom side are not seen). Objects without tagged parts are taken as one part.
+//
+// The packer is a simple shelf packer: the parts, highest first, are put side by side onto shelves, a new shelf is
+// opened on top when a part doesn't fit any more, and a new sheet when the sheet is full. Parts are kept 'kerf' apart
+// and, if allowed, turned by 90° to fit. Placement is by bounding rectangles, also for projections.
+
+use std::fmt;
+
+use crate::math::{HasRefSys3D, Matrix3D, Point2D, point2D};
+use crate::mesh::{self, Mesh, TessellationError};
+use crate::object_3d::{Object3D, Shape3D, polygon_paths, square_coords};
+use crate::resolution::Resolution;
+
+// Tolerance for "fits"
+const EPSILON : f64 = 1e-9;
+
+//{{{ pub enum NestOutline
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NestOutline
+{
+	BoundingBox,
+	Projection,
+}
+//}}}
+
+//{{{ pub struct NestingOptions
+
+#[derive(Debug, Clone)]
+pub struct NestingOptions
+{
+	pub sheet_width  : f64,
+	pub sheet_height : f64,
+	pub kerf         : f64,  // Gap between the parts, the width of the saw cut
+	pub rotation     : bool, // Parts may be turned by 90°
+	pub outline      : NestOutline,
+	pub resolution   : Resolution, // Of the outlines of round parts
+}
+
+//{{{
+impl Default for NestingOptions
+{
+	fn default() -> Self
+	{
+		Self
+		{
+			sheet_width  : 2500.0,
+			sheet_height : 1250.0,
+			kerf         : 4.0,
+			rotation     : true,
+			outline      : NestOutline::Projection,
+			resolution   : Resolution::default(),
+		}
+	}
+}
+//}}}
+//}}}
+
+//{{{ pub struct NestedPart
+
+#[derive(Debug, Clone)]
+pub struct NestedPart
+{
+	pub name    : String,
+	pub x       : f64,                // Lower left corner of the bounding rectangle on the sheet
+	pub y       : f64,
+	pub width   : f64,                // Size on the sheet, i.e. after turning
+	pub height  : f64,
+	pub rotated : bool,
+	pub outline : Vec<Vec<Point2D>>,  // In sheet coordinates
+}
+
+//{{{
+impl NestedPart
+{
+	//{{{
+	pub fn area(&self) -> f64
+	{
+		self.outline.iter().map(|outline| mesh::signed_area(outline)).sum()
+	}
+	//}}}
+}
+//}}}
+//}}}
+
+//{{{ pub struct NestedSheet
+
+#[derive(Debug, Clone)]
+pub struct NestedSheet
+{
+	pub width  : f64,
+	pub height : f64,
+	pub parts  : Vec<NestedPart>,
+}
+
+//{{{
+impl NestedSheet
+{
+	//{{{
+	pub fn used_area(&self) -> f64
+	{
+		self.parts.iter().map(NestedPart::area).sum()
+	}
+	//}}}
+	//{{{
+	// Used part of the sheet, 0 to 1
+	pub fn utilisation(&self) -> f64
+	{
+		self.used_area()/(self.width*self.height)
+	}
+	//}}}
+	//{{{
+	// The sheet (as background) and the parts as 2D polygons
+	pub fn scad(&self, name: &str) -> String
+	{
+		let mut sheet = square_coords(name, 0.0, 0.0, self.width, self.height);
+		sheet.set_background();
+
+		let mut retval = format!("{}\n", sheet);
+		for part in &self.parts
+		{
+			let mut points = vec![];
+			let mut paths  = vec![];
+			for outline in &part.outline
+			{
+				paths.push((points.len()..points.len()+outline.len()).map(|i| i as i32).collect());
+				points.extend(outline.iter().cloned());
+			}
+			retval += &format!("{}\n", polygon_paths(&part.name, points, paths));
+		}
+		retval
+	}
+	//}}}
+	//{{{
+	// The sheet with the parts cut out
+	pub fn svg(&self) -> String
+	{
+		let mut loops = vec![rectangle(0.0, 0.0, self.width, self.height)];
+		for part in &self.parts
+		{
+			loops.extend(part.outline.iter().cloned());
+		}
+		mesh::svg(&loops)
+	}
+	//}}}
+}
+//}}}
+//}}}
+
+//{{{ pub struct Layout
+
+#[derive(Debug, Clone, Default)]
+pub struct Layout
+{
+	pub sheets   : Vec<NestedSheet>,
+	pub unplaced : Vec<String>,      // Parts larger than a sheet
+}
+
+//{{{
+// Utilisation summary: one line per sheet and the total
+impl fmt::Display for Layout
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+	{
+		let mut parts      = 0;
+		let mut used_area  = 0.0;
+		let mut sheet_area = 0.0;
+		for (i, sheet) in self.sheets.iter().enumerate()
+		{
+			writeln!(f, "Sheet {} ({} x {}): {} parts, {:.1}% used", i+1, sheet.width, sheet.height, sheet.parts.len(), 100.0*sheet.utilisation())?;
+			parts      += sheet.parts.len();
+			used_area  += sheet.used_area();
+			sheet_area += sheet.width*sheet.height;
+		}
+		let total = if sheet_area > 0.0 { used_area/sheet_area } else { 0.0 };
+		writeln!(f, "Total: {} sheets, {} parts, {:.1}% used", self.sheets.len(), parts, 100.0*total)?;
+		if !self.unplaced.is_empty()
+		{
+			writeln!(f, "Not placed (larger than a sheet): {}", self.unplaced.join(", "))?;
+		}
+		Ok(())
+	}
+}
+//}}}
+//}}}
+
+//{{{ Nesting
+
+//{{{
+// The tagged parts of an object tree, or the object itself if it has none
+pub fn flat_parts(object: &Object3D) -> Vec<Object3D>
+{
+	let mut parts = vec![];
+	collect_parts(object, &mut parts);
+	if parts.is_empty() { parts.push(object.clone()); }
+	parts
+}
+//}}}
+//{{{
+pub fn nest(objects: &[Object3D], options: &NestingOptions) -> Result<Layout, TessellationError>
+{
+	//{{{ Outlines of the parts, moved to the origin
+
+	let mut items = vec![];
+	for part in objects.iter().flat_map(flat_parts)
+	{
+		let name    = part.part.as_ref().and_then(|info| info.number.clone()).unwrap_or_else(|| part.name.clone());
+		let outline = flat_outline(&part, &options.resolution)?;
+		let (min, max) = bounds(&outline);
+		let outline : Vec<Vec<Point2D>> = match options.outline
+		{
+			NestOutline::BoundingBox => vec![rectangle(0.0, 0.0, max[0]-min[0], max[1]-min[1])],
+			NestOutline::Projection  => outline.iter().map(|l| l.iter().map(|p| point2D(p[0]-min[0], p[1]-min[1])).collect()).collect(),
+		};
+		items.push(Item { name, width: max[0]-min[0], height: max[1]-min[1], rotated: false, outline });
+	}
+	//}}}
+
+	let (sheet_width, sheet_height, kerf) = (options.sheet_width, options.sheet_height, options.kerf);
+	let fits = |width: f64, height: f64| width <= sheet_width+EPSILON && height <= sheet_height+EPSILON;
+
+	// Lying on the long side gives lower shelves
+	if options.rotation
+	{
+		for item in &mut items
+		{
+			if (item.height > item.width && fits(item.height, item.width)) || !fits(item.width, item.height) { item.rotate(); }
+		}
+	}
+	items.sort_by(|a, b| b.height.total_cmp(&a.height).then(b.width.total_cmp(&a.width)));
+
+	let mut layout  = Layout::default();
+	let mut shelves : Vec<Vec<Shelf>> = vec![]; // Per sheet
+	for mut item in items
+	{
+		if !fits(item.width, item.height)
+		{
+			layout.unplaced.push(item.name);
+			continue;
+		}
+
+		//{{{ First fit: an open shelf, a new shelf on a sheet, or a new sheet
+
+		let orientations = if options.rotation { vec![false, true] } else { vec![false] };
+		let size = |item: &Item, turned: bool| if turned { (item.height, item.width) } else { (item.width, item.height) };
+
+		let mut place = None;
+		'search: for (s, sheet) in shelves.iter().enumerate()
+		{
+			for (i, shelf) in sheet.iter().enumerate()
+			{
+				for &turned in &orientations
+				{
+					let (width, height) = size(&item, turned);
+					if shelf.used + width <= sheet_width+EPSILON && height <= shelf.height+EPSILON
+					{
+						place = Some((s, i, turned));
+						break 'search;
+					}
+				}
+			}
+		}
+		if place.is_none()
+		{
+			'open: for (s, sheet) in shelves.iter_mut().enumerate()
+			{
+				let y = sheet.last().map_or(0.0, |shelf| shelf.y + shelf.height + kerf);
+				for &turned in &orientations
+				{
+					let (width, height) = size(&item, turned);
+					if width <= sheet_width+EPSILON && y + height <= sheet_height+EPSILON
+					{
+						sheet.push(Shelf { y, height, used: 0.0 });
+						place = Some((s, sheet.len()-1, turned));
+						break 'open;
+					}
+				}
+			}
+		}
+		let (s, i, turned) = place.unwrap_or_else(||
+		{
+			shelves.push(vec![Shelf { y: 0.0, height: item.height, used: 0.0 }]);
+			layout.sheets.push(NestedSheet { width: sheet_width, height: sheet_height, parts: vec![] });
+			(shelves.len()-1, 0, false)
+		});
+		//}}}
+
+		if turned { item.rotate(); }
+		let shelf = &mut shelves[s][i];
+		let (x, y) = (shelf.used, shelf.y);
+		shelf.used += item.width + kerf;
+
+		layout.sheets[s].parts.push(NestedPart
+		{
+			outline : item.outline.iter().map(|l| l.iter().map(|p| point2D(p[0]+x, p[1]+y)).collect()).collect(),
+			name    : item.name,
+			x,
+			y,
+			width   : item.width,
+			height  : item.height,
+			rotated : item.rotated,
+		});
+	}
+	Ok(layout)
+}
+//}}}
+//}}}
+
+//{{{ Helpers
+
+//{{{ struct Item
+
+// A part to be placed, its outline with the lower left corner of the bounding rectangle at the origin
+struct Item
+{
+	name    : String,
+	width   : f64,
+	height  : f64,
+	rotated : bool,
+	outline : Vec<Vec<Point2D>>,
+}
+
+//{{{
+impl Item
+{
+	//{{{
+	// Turns by 90° counter-clockwise, staying at the origin
+	fn rotate(&mut self)
+	{
+		let height = self.height;
+		for outline in &mut self.outline
+		{
+			for p in outline.iter_mut() { *p = point2D(height-p[1], p[0]); }
+		}
+		std::mem::swap(&mut self.width, &mut self.height);
+		self.rotated = !self.rotated;
+	}
+	//}}}
+}
+//}}}
+//}}}
+
+//{{{
+struct Shelf
+{
+	y      : f64,
+	height : f64,
+	used   : f64, // Width taken, including the kerf after the last part
+}
+//}}}
+
+//{{{
+fn collect_parts(object: &Object3D, parts: &mut Vec<Object3D>)
+{
+	if object.is_part()
+	{
+		parts.push(object.clone());
+		return;
+	}
+	if let Shape3D::Composite{children, ..} = &object.shape
+	{
+		for child in children { collect_parts(child, parts); }
+	}
+}
+//}}}
+//{{{
+// Outline of the part in its own coordinate system, laid flat
+fn flat_outline(part: &Object3D, resolution: &Resolution) -> Result<Vec<Vec<Point2D>>, TessellationError>
+{
+	let mut local = part.clone();
+	local.set_ref_sys(Matrix3D::identity());
+
+	let mut mesh = match Mesh::from_object_with(&local, resolution)
+	{
+		Ok(mesh)                        => mesh,
+		Err(TessellationError::Flat(_)) => return mesh::outline_with(&local, resolution),
+		Err(error)                      => return Err(error),
+	};
+	mesh.transform(&crate::project::lay_flat(&mesh));
+	Ok(mesh.footprint())
+}
+//}}}
+//{{{
+fn bounds(loops: &[Vec<Point2D>]) -> (Point2D, Point2D)
+{
+	let (mut min_x, mut min_y, mut max_x, mut max_y) = (0.0f64, 0.0f64, 0.0f64, 0.0f64);
+	for (i, p) in loops.iter().flatten().enumerate()
+	{
+		if i == 0 { min_x = p[0]; max_x = p[0]; min_y = p[1]; max_y = p[1]; }
+		min_x = min_x.min(p[0]); max_x = max_x.max(p[0]);
+		min_y = min_y.min(p[1]); max_y = max_y.max(p[1]);
+	}
+	(point2D(min_x, min_y), point2D(max_x, max_y))
+}
+//}}}
+//{{{
+// Counter-clockwise
+fn rectangle(x1: f64, y1: f64, x2: f64, y2: f64) -> Vec<Point2D>
+{
+	vec![point2D(x1, y1), point2D(x2, y1), point2D(x2, y2), point2D(x1, y2)]
+}
+//}}}
+//}}}
+
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::object_3d::cube;
+
+	//{{{
+	fn boards(sizes: &[(f64, f64)]) -> Vec<Object3D>
+	{
+		sizes.iter().enumerate().map(|(i, &(x, y))| cube(&format!("board{}", i), x, y, 18.0)).collect()
+	}
+	//}}}
+	//{{{
+	fn options(sheet_width: f64, sheet_height: f64, rotation: bool) -> NestingOptions
+	{
+		NestingOptions { sheet_width, sheet_height, rotation, ..NestingOptions::default() }
+	}
+	//}}}
+	//{{{
+	// Inside the sheet, kept 'kerf' apart and the outline within the bounding rectangle
+	fn assert_valid(layout: &Layout, kerf: f64)
+	{
+		for sheet in &layout.sheets
+		{
+			for (i, a) in sheet.parts.iter().enumerate()
+			{
+				assert!(a.x >= -EPSILON && a.y >= -EPSILON && a.x + a.width <= sheet.width+EPSILON && a.y + a.height <= sheet.height+EPSILON, "{} is off the sheet", a.name);
+				assert!(a.outline.iter().flatten().all(|p| p[0] >= a.x-EPSILON && p[0] <= a.x+a.width+EPSILON && p[1] >= a.y-EPSILON && p[1] <= a.y+a.height+EPSILON));
+				for b in &sheet.parts[..i]
+				{
+					let apart = a.x >= b.x + b.width + kerf - EPSILON || b.x >= a.x + a.width + kerf - EPSILON
+					         || a.y >= b.y + b.height + kerf - EPSILON || b.y >= a.y + a.height + kerf - EPSILON;
+					assert!(apart, "{} and {} overlap", a.name, b.name);
+				}
+			}
+		}
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn parts_kept_apart()
+	{
+		let sizes  = [(300.0, 200.0), (120.0, 80.0), (450.0, 90.0), (200.0, 200.0), (60.0, 400.0), (90.0, 90.0), (310.0, 150.0), (75.0, 75.0)];
+		let layout = nest(&boards(&sizes), &options(1000.0, 500.0, true)).expect("boards tessellate");
+		assert_valid(&layout, 4.0);
+		assert_eq!(layout.sheets.len(), 1);
+		assert_eq!(layout.sheets[0].parts.len(), sizes.len());
+		assert!(layout.unplaced.is_empty());
+
+		let area : f64 = sizes.iter().map(|(x, y)| x*y).sum();
+		assert!((layout.sheets[0].used_area() - area).abs() < 1e-6);
+		assert!((layout.sheets[0].utilisation() - area/500_000.0).abs() < 1e-9);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn sheet_overflow()
+	{
+		// Two of these fit onto a sheet, side by side
+		let layout = nest(&boards(&[(450.0, 400.0); 5]), &options(1000.0, 500.0, true)).expect("boards tessellate");
+		assert_valid(&layout, 4.0);
+		let counts : Vec<usize> = layout.sheets.iter().map(|sheet| sheet.parts.len()).collect();
+		assert_eq!(counts, [2, 2, 1]);
+
+		// Without a gap, shelves fill the sheet exactly
+		let layout = nest(&boards(&[(500.0, 250.0); 5]), &NestingOptions { kerf: 0.0, ..options(1000.0, 500.0, true) }).expect("boards tessellate");
+		assert_valid(&layout, 0.0);
+		let counts : Vec<usize> = layout.sheets.iter().map(|sheet| sheet.parts.len()).collect();
+		assert_eq!(counts, [4, 1]);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn rotation()
+	{
+		// Standing parts are laid on their long side, for lower shelves
+		let layout = nest(&boards(&[(100.0, 300.0)]), &options(1000.0, 500.0, true)).expect("board tessellates");
+		let part   = &layout.sheets[0].parts[0];
+		assert!(part.rotated && part.width == 300.0 && part.height == 100.0);
+
+		// Turned to fit the sheet at all
+		let layout = nest(&boards(&[(400.0, 900.0)]), &options(1000.0, 500.0, true)).expect("board tessellates");
+		let part   = &layout.sheets[0].parts[0];
+		assert!(part.rotated && part.width == 900.0 && part.height == 400.0);
+		assert!((part.area() - 360_000.0).abs() < 1e-6);
+
+		// Turned to fit a shelf that is already open
+		let layout = nest(&boards(&[(600.0, 300.0), (300.0, 350.0)]), &options(1000.0, 700.0, true)).expect("boards tessellate");
+		assert_eq!(layout.sheets.len(), 1);
+		assert_valid(&layout, 4.0);
+
+		// Not allowed to turn
+		let layout = nest(&boards(&[(100.0, 300.0), (400.0, 900.0)]), &options(1000.0, 500.0, false)).expect("boards tessellate");
+		assert!(layout.sheets[0].parts.iter().all(|part| !part.rotated));
+		assert_eq!(layout.unplaced, ["board1"]);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn oversized_parts()
+	{
+		let layout = nest(&boards(&[(3000.0, 100.0), (200.0, 100.0), (2000.0, 2000.0)]), &options(1000.0, 500.0, true)).expect("boards tessellate");
+		assert_eq!(layout.unplaced, ["board0", "board2"]);
+		assert_eq!(layout.sheets.len(), 1);
+		assert_eq!(layout.sheets[0].parts[0].name, "board1");
+		assert!(layout.to_string().contains("Not placed (larger than a sheet): board0, board2\n"));
+
+		// Only parts too large
+		let layout = nest(&boards(&[(3000.0, 100.0)]), &options(1000.0, 500.0, true)).expect("board tessellates");
+		assert!(layout.sheets.is_empty());
+		assert_eq!(layout.unplaced, ["board0"]);
+		assert!(layout.to_string().starts_with("Total: 0 sheets, 0 parts, 0.0% used\n"));
+	}
+	//}}}
+}
+//}}}
@@ -1,33 +1,16 @@
 use std::fmt;
 use std::collections::HashMap;
 
-use crate::math::{Is3DObject, HasRefSys3D, Matrix3D, Point2D};
+use crate::math::{Is3DObject, HasRefSys3D, Matrix3D, Point2D, Point3D, IsCurve, BezierPatch};
 //use crate::{IsSerialisableScope, IsObject, Colour, BooleanOp};
-use crate::{IsSerialisableScope, IsObject, Colour};
+use crate::{IsObject, Colour, Expr};
+use crate::units::{Length, Angle};
+use crate::resolution::Resolution;
+use crate::scad_writer::{ScadWriter, FormatterWriter};
 
 use std::ops::{Index, IndexMut};
 //use crate::Colour;
 
-//{{{
-impl IsSerialisableScope for crate::math::Matrix3D
-{
-	fn serialise(&self, indentation: usize, child : &str) -> String
-	{
-		let indent   = "\t".repeat(indentation as usize);
-		//                              "multmatrix(m = [";
-		let shift_in = indent.clone() + "                ";
-
-		let mut retval = indent.clone();
-		retval += "multmatrix(m = \n";
-		retval += &format!("{:>indent$}\n{})\n", self, indent, indent=indentation+1);
-		retval += &format!("{}{{\n", indent);
-		retval += &child;
-		retval += &format!("{} }};\n", indent);
-		retval
-	}
-}
-//}}}
-
 //{{{
 pub mod anchors
 {
@@ -118,7 +101,7 @@ pub mod anchors
 		}
 		//}}}
 		//{{{
-		fn get_object_anchor(&self) -> crate::Object3D
+		pub(crate) fn get_object_anchor(&self) -> crate::Object3D
 		{
 			let mut object_anchor = crate::object_3d::object_anchor(&self.name);
 			object_anchor.set_ref_sys(self.ref_sys);
@@ -130,14 +113,12 @@ pub mod anchors
 	//}}}
 
 	//{{{
-	impl fmt::Display for Anchor // TODO
+	// The anchor gizmo
+	impl fmt::Display for Anchor
 	{
 		fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
 		{
-			use crate::IsSerialisableScope;
-			let indentation = if let Some(width) = f.width() { width } else { 0 as usize };
-
-			write!(f, "{:>indent$}", &self.get_object_anchor(), indent=indentation+1)
+			write!(f, "{}", &self.get_object_anchor())
 		}
 	}
 	//}}}
@@ -161,6 +142,82 @@ pub enum BooleanOp
 }
 //}}}
 
+//{{{ Text options
+
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+#[allow(non_camel_case_types)] // Like BooleanOp, the debug output is the OpenSCad value.
+pub enum TextHAlign
+{
+	left,
+	center,
+	right,
+}
+
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum TextVAlign
+{
+	top,
+	center,
+	baseline,
+	bottom,
+}
+
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq)]
+#[allow(non_camel_case_types)]
+pub enum TextDirection
+{
+	ltr,
+	rtl,
+	ttb,
+	btt,
+}
+
+//{{{
+// Names as OpenSCad identifiers: anything but letters, digits and '_' becomes '_', and they don't start with a digit
+pub(crate) fn scad_identifier(name: &str) -> String
+{
+	let name : String = name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '_' { c } else { '_' }).collect();
+	if name.starts_with(|c: char| c.is_ascii_digit()) || name.is_empty() { String::from("part_") + &name } else { name }
+}
+//}}}
+//{{{
+// The name of the other side: a trailing "_L" becomes "_R" and the other way round, likewise " L" and " R"
+fn swap_side(name: &str) -> String
+{
+	for (side, other) in [("_L", "_R"), ("_R", "_L"), (" L", " R"), (" R", " L")]
+	{
+		if let Some(stem) = name.strip_suffix(side) { return String::from(stem) + other; }
+	}
+	String::from(name)
+}
+//}}}
+//{{{
+// Escapes a string for use in an OpenSCad string literal
+pub fn escape_scad_string(string: &str) -> String
+{
+	let mut retval = String::with_capacity(string.len());
+	for c in string.chars()
+	{
+		match c
+		{
+			'"'  => retval += "\\\"",
+			'\\' => retval += "\\\\",
+			'\n' => retval += "\\n",
+			'\r' => retval += "\\r",
+			'\t' => retval += "\\t",
+			c if c.is_control() => retval += &format!("\\u{:04x}", c as u32),
+			c    => retval.push(c),
+		}
+	}
+	retval
+}
+//}}}
+//}}}
+
 //{{{ pub enum Shape3D
 
 #[derive(Debug)]
@@ -168,20 +225,20 @@ pub enum BooleanOp
 pub enum Shape3D
 {
 	// 2D
-	Square    { x: f64, y: f64},
-	Circle    { r: f64, face_number: Option<i32>, face_angle: Option<f64>, face_size: Option<f64> },
+	Square    { x: Expr, y: Expr},
+	Circle    { r: Expr, face_number: Option<i32>, face_angle: Option<f64>, face_size: Option<f64> },
 	Polygon   { points: Vec<Point2D>, paths: Vec<Vec<i32>>, convexity: i32, face_number: Option<i32>, face_angle: Option<f64>, face_size: Option<f64> },
-	Text      { text: String, font: String, size: i32, spacing: f64 },
+	Text      { text: String, font: String, size: f64, spacing: f64, halign: TextHAlign, valign: TextVAlign, direction: TextDirection, language: Option<String>, script: Option<String>, face_number: Option<i32>, face_angle: Option<f64>, face_size: Option<f64> },
 
 	// TODO: Make slices optional
-	Lextrude  { height: f64, center: bool, convexity: i32, twist: f64, slices: i32, scale: Vec<f64>, face_number: Option<i32>, face_angle: Option<f64>, face_size: Option<f64>, child: Box<Object3D> },
+	Lextrude  { height: Expr, center: bool, convexity: i32, twist: f64, slices: i32, scale: Vec<f64>, face_number: Option<i32>, face_angle: Option<f64>, face_size: Option<f64>, child: Box<Object3D> },
 	//Rextrude  { angle: f64, convexity: i32, face_number: Option<i32>, face_angle: Option<f64>, face_size: Option<f64>, child: Object3D },
 
 	// 3D
-	Cube      { x: f64, y: f64, z: f64},
-	Sphere    { r: f64, face_number: Option<i32>, face_angle: Option<f64>, face_size: Option<f64> },
-	Cylinder  { h: f64, r1 : f64, r2 : f64, face_angle  : Option<f64>, face_size   : Option<f64>, face_number : Option<i32> },
-	// TODO: Polyhedron
+	Cube      { x: Expr, y: Expr, z: Expr},
+	Sphere    { r: Expr, face_number: Option<i32>, face_angle: Option<f64>, face_size: Option<f64> },
+	Cylinder  { h: Expr, r1 : Expr, r2 : Expr, face_angle  : Option<f64>, face_size   : Option<f64>, face_number : Option<i32> },
+	Polyhedron{ points: Vec<Point3D>, faces: Vec<Vec<i32>>, convexity: i32 },
 
 	Composite { op: BooleanOp, children: Vec<Object3D> },
 }
@@ -190,16 +247,15 @@ pub enum Shape3D
 impl Shape3D
 {
 	//{{{
-	fn serialise(&self, indentation : usize) -> String
+	// The OpenSCad call, without the ';' and, for linear_extrude() and the booleans, without the children (see ScadWriter)
+	pub(crate) fn scad_call(&self) -> String
 	{
-		let tabs = "\t".repeat(indentation);
-		let indent = indentation + 1;
 		match &self
 		{
 			//{{{
 			Shape3D::Square{x,y}                                        =>
 			{
-				tabs + "square([" + &x.to_string() + ", " + &y.to_string() + "], center=true);"
+				String::from("square([") + &x.to_string() + ", " + &y.to_string() + "], center=true)"
 			}
 			//}}}
 			//{{{
@@ -209,13 +265,13 @@ impl Shape3D
 				let faa = if let Some(x) = face_angle  { String::from(", $fa=") + &x.to_string() } else { String::from("") };
 				let fas = if let Some(x) = face_size   { String::from(", $fs=") + &x.to_string() } else { String::from("") };
 
-				tabs + "circle( r=" + &r.to_string() + &fan + &faa + &fas + ");"
+				String::from("circle( r=") + &r.to_string() + &fan + &faa + &fas + ")"
 			}
 			//}}}
 			//{{{
 			Shape3D::Polygon{points, paths, convexity,face_number,face_angle,face_size} =>
 			{
-				let mut retval = tabs + "polygon( points=[";
+				let mut retval = String::from("polygon( points=[");
 				for point in points
 				{
 					retval += &point.to_string();
@@ -224,24 +280,30 @@ impl Shape3D
 				retval += "], paths=[";
 				for path in paths
 				{
-					//retval += path.to_string();
-					//retval += ", ";
-
-					retval+=&(String::from("[") + &path[0].to_string() + ", " + &path[1].to_string() + ", " + &path[2].to_string() + "],");
+					let indices : Vec<String> = path.iter().map(|index| index.to_string()).collect();
+					retval += &(String::from("[") + &indices.join(", ") + "],");
 				}
 				retval += "], convexity=";
 				retval += &convexity.to_string();
-				retval += ");\n";
+				retval += ")";
 
 				retval
 			}
 			//}}}
 			//{{{
-			Shape3D::Text{ref text, ref font, size, spacing} =>
+			Shape3D::Text{text, font, size, spacing, halign, valign, direction, language, script, face_number, face_angle, face_size} =>
 			{
-				format!("{0}text(\"{1}\", font=\"{2}\", spacing={3}, size={4});", tabs, &text, &font, spacing, size)
+				let lan = if let Some(x) = language    { format!(", language=\"{}\"", escape_scad_string(x)) } else { String::from("") };
+				let scr = if let Some(x) = script      { format!(", script=\"{}\"",   escape_scad_string(x)) } else { String::from("") };
+				let fan = if let Some(x) = face_number { String::from(", $fn=") + &x.to_string() } else { String::from("") };
+				let faa = if let Some(x) = face_angle  { String::from(", $fa=") + &x.to_string() } else { String::from("") };
+				let fas = if let Some(x) = face_size   { String::from(", $fs=") + &x.to_string() } else { String::from("") };
+
+				format!("text(\"{0}\", size={1}, font=\"{2}\", halign=\"{3:?}\", valign=\"{4:?}\", spacing={5}, direction=\"{6:?}\"{7}{8}{9}{10}{11})",
+					escape_scad_string(text), size, escape_scad_string(font), halign, valign, spacing, direction, lan, scr, fan, faa, fas)
 			}
 			//}}}
+			//{{{
 			Shape3D::Lextrude{ height, center, convexity, twist, slices, scale, face_number, face_angle, face_size, child } =>
 			{
 				let fan = if let Some(x) = face_number { String::from(", $fn=") + &x.to_string() } else { String::from("") };
@@ -253,17 +315,15 @@ impl Shape3D
 					_ => String::from("1")
 				};
 
-				format!("{0}linear_extrude(height = {1}, center = {2}, convexity = {3}, twist = {4}, slices = {5}, scale = {6} {7}) {8}",
-					tabs, height, center, convexity, twist, slices, scale_string, fan, child)
+				format!("linear_extrude(height = {0}, center = {1}, convexity = {2}, twist = {3}, slices = {4}, scale = {5} {6})",
+					height, center, convexity, twist, slices, scale_string, fan)
 			}
-			//{{{
-
 			//}}}
 
 			//{{{
 			Shape3D::Cube{x,y,z}                                        =>
 			{
-				tabs + "cube([" + &x.to_string() + ", " + &y.to_string() + ", " + &z.to_string() + "], center=true);"
+				String::from("cube([") + &x.to_string() + ", " + &y.to_string() + ", " + &z.to_string() + "], center=true)"
 			}
 			//}}}
 			//{{{
@@ -273,7 +333,7 @@ impl Shape3D
 				let faa = if let Some(x) = face_angle  { String::from(", $fa=") + &x.to_string() } else { String::from("") };
 				let fas = if let Some(x) = face_size   { String::from(", $fs=") + &x.to_string() } else { String::from("") };
 
-				tabs + "sphere( r=" + &r.to_string() + &fan + &faa + &fas + ");"
+				String::from("sphere( r=") + &r.to_string() + &fan + &faa + &fas + ")"
 			}
 			//}}}
 			//{{{
@@ -283,20 +343,21 @@ impl Shape3D
 				let faa = if let Some(x) = face_angle  { String::from(", $fa=") + &x.to_string() } else { String::from("") };
 				let fas = if let Some(x) = face_size   { String::from(", $fs=") + &x.to_string() } else { String::from("") };
 
-				tabs + "cylinder( h=" + &h.to_string() + ", r1=" + &r1.to_string() + ", r2=" + &r2.to_string() + &fan + &faa + &fas + ");"
+				String::from("cylinder( h=") + &h.to_string() + ", r1=" + &r1.to_string() + ", r2=" + &r2.to_string() + &fan + &faa + &fas + ")"
+			}
+			//}}}
+			//{{{
+			Shape3D::Polyhedron{points, faces, convexity} =>
+			{
+				let points : Vec<String> = points.iter().map(|point| format!("{:#}", point)).collect();
+				let faces  : Vec<String> = faces.iter().map(|face| format!("{:?}", face)).collect();
+				format!("polyhedron( points=[{0}], faces=[{1}], convexity={2})", points.join(", "), faces.join(", "), convexity)
 			}
 			//}}}
 			//{{{
 			Shape3D::Composite{op, children} =>
 			{
-				//retval = format!("{0}{1:?}()\n{0}{{\n{2:>indent$}\n{3:>indent$}\n{0} }};", tabs, &op, c1, c2, indent=indent);
-				let mut retval = format!("{0}{1:?}()\n{0}{{\n", tabs, &op);
-					for child in children
-					{
-						retval += &format!("{:>indent$}\n", child, indent=indent);
-					}
-				retval += &format!("{} }};", tabs);
-				retval
+				format!("{:?}()", &op)
 			}
 			//}}}
 		}
@@ -307,6 +368,38 @@ impl Shape3D
 
 //}}}
 
+//{{{ pub struct PartInfo
+
+// Marks an Object3D as a physical part of an assembly (e.g. a board), as opposed to cutouts, helpers or sub-assemblies
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct PartInfo
+{
+	pub number       : Option<String>,
+	pub description  : Option<String>,
+	pub purchased    : bool,           // Bought in (e.g. hardware) rather than made
+	pub material     : Option<String>, // Colours the part through a Palette
+	pub label_anchor : String,         // Face anchor used by engrave_part_labels()
+}
+
+//{{{
+impl Default for PartInfo
+{
+	fn default() -> Self
+	{
+		Self
+		{
+			number       : None,
+			description  : None,
+			purchased    : false,
+			material     : None,
+			label_anchor : String::from("label"),
+		}
+	}
+}
+//}}}
+//}}}
+
 //{{{pub struct Object3D
 
 #[derive(Debug)]
@@ -317,10 +410,13 @@ pub struct Object3D
 	pub shape       : Shape3D,
 	pub ref_sys     : crate::Matrix3D,
 	pub colour      : Colour,
+	pub resolution  : Resolution,             // $fn/$fa/$fs for the whole subtree, see set_resolution()
 	pub anchors     : HashMap<String, anchors::Anchor>,
-	scad_modifier   : crate::ScadModifier,
+	pub part        : Option<PartInfo>,
+	pub(crate) scad_modifier : crate::ScadModifier,
 	custom_modifier : crate::CustomModifier,
 	snap_parent     : bool,
+	handed          : bool,                   // A mirror image is a different thing, e.g. a thread, see set_handed()
 }
 
 //{{{
@@ -339,13 +435,16 @@ impl Object3D
 	{
 		Self{
 			name            : String::from(name),
-			shape           : shape,
+			shape,
 			ref_sys         : crate::Matrix3D::identity(),
 			colour          : Colour::Unset, 
+			resolution      : Resolution::default(),
 			anchors         : HashMap::new(),
+			part            : None,
 			scad_modifier   : crate::ScadModifier::Unset, 
 			custom_modifier : crate::CustomModifier::Unset, 
 			snap_parent     : false,
+			handed          : false,
 		}
 	}
 	//}}}
@@ -399,13 +498,14 @@ impl Object3D
 			Shape3D::Square{x,y}                                                                => {},
 			Shape3D::Circle{r,ref mut face_number,face_angle,face_size}                         => *face_number = Some(num),
 			Shape3D::Polygon{points, paths, convexity,ref mut face_number,face_angle,face_size} => *face_number = Some(num),
-			Shape3D::Text{ref text, ref font, size, spacing}                                    => {},
+			Shape3D::Text{ref mut face_number, ..}                                              => *face_number = Some(num),
 
 			Shape3D::Lextrude{ height, center, convexity, twist, slices, scale, face_number, face_angle, face_size, child } => child.set_fn(num),
 
 			Shape3D::Cube{x,y,z}                                                                => {},
 			Shape3D::Sphere{r,ref mut face_number,face_angle,face_size}                         => *face_number = Some(num),
 			Shape3D::Cylinder{h,r1,r2,ref mut face_number,face_angle,face_size}                 => *face_number = Some(num),
+			Shape3D::Polyhedron{points, faces, convexity}                                       => {},
 			Shape3D::Composite{ref op,ref mut children}                                         => { for child in children { child.set_fn(num) } },
 		}
 	}
@@ -418,13 +518,14 @@ impl Object3D
 			Shape3D::Square{x,y}                                                                => {},
 			Shape3D::Circle{r,face_number,ref mut face_angle,face_size}                         => *face_angle = Some(num),
 			Shape3D::Polygon{points, paths, convexity,face_number,ref mut face_angle,face_size} => *face_angle = Some(num),
-			Shape3D::Text{ref text, ref font, size, spacing}                                    => {},
+			Shape3D::Text{ref mut face_angle, ..}                                               => *face_angle = Some(num),
 
 			Shape3D::Lextrude{ height, center, convexity, twist, slices, scale, face_number, face_angle, face_size, child } => child.set_fa(num),
 
 			Shape3D::Cube{x,y,z}                                                                => {},
 			Shape3D::Sphere{r,face_number,ref mut face_angle,face_size}                         => *face_angle = Some(num),
 			Shape3D::Cylinder{h,r1,r2,face_number,ref mut face_angle,face_size}                 => *face_angle = Some(num),
+			Shape3D::Polyhedron{points, faces, convexity}                                       => {},
 			Shape3D::Composite{ref op,ref mut children}                                         => { for child in children { child.set_fa(num) } },
 		}
 	}
@@ -437,18 +538,59 @@ impl Object3D
 			Shape3D::Square{x,y}                                                                => {},
 			Shape3D::Circle{r,face_number,face_angle,ref mut face_size}                         => *face_size = Some(num),
 			Shape3D::Polygon{points, paths, convexity,face_number,face_angle,ref mut face_size} => *face_size = Some(num),
-			Shape3D::Text{ref text, ref font, size, spacing}									=> {},
+			Shape3D::Text{ref mut face_size, ..}                                        		=> *face_size = Some(num),
 
 			Shape3D::Lextrude{ height, center, convexity, twist, slices, scale, face_number, face_angle, face_size, child } => child.set_fs(num),
 
 			Shape3D::Cube{x,y,z}                                                        		=> {},
 			Shape3D::Sphere{r,face_number,face_angle,ref mut face_size}                 		=> *face_size = Some(num),
 			Shape3D::Cylinder{h,r1,r2,face_number,face_angle,ref mut face_size}         		=> *face_size = Some(num),
+			Shape3D::Polyhedron{points, faces, convexity}                               		=> {},
 			Shape3D::Composite{ref op,ref mut children}                                 		=> { for child in children { child.set_fs(num) } },
 		}
 	}
 	//}}}
 	//{{{
+	// $fn/$fa/$fs for the object and everything in it, written as a let() scope. Primitives keep their own set_fn(),
+	// set_fa() and set_fs().
+	pub fn set_resolution(&mut self, resolution: impl Into<Resolution>)
+	{
+		self.resolution = resolution.into();
+	}
+	//}}}
+
+	//{{{ Text options (ignored by other shapes)
+
+	//{{{
+	pub fn set_text_align(&mut self, horizontal: TextHAlign, vertical: TextVAlign)
+	{
+		if let Shape3D::Text{ref mut halign, ref mut valign, ..} = self.shape
+		{
+			*halign = horizontal;
+			*valign = vertical;
+		}
+	}
+	//}}}
+	//{{{
+	pub fn set_text_direction(&mut self, text_direction: TextDirection)
+	{
+		if let Shape3D::Text{ref mut direction, ..} = self.shape { *direction = text_direction; }
+	}
+	//}}}
+	//{{{
+	pub fn set_text_language(&mut self, text_language: &str)
+	{
+		if let Shape3D::Text{ref mut language, ..} = self.shape { *language = Some(String::from(text_language)); }
+	}
+	//}}}
+	//{{{
+	pub fn set_text_script(&mut self, text_script: &str)
+	{
+		if let Shape3D::Text{ref mut script, ..} = self.shape { *script = Some(String::from(text_script)); }
+	}
+	//}}}
+	//}}}
+	//{{{
 	pub fn set_colour(&mut self, colour : Colour)
 	{
 		self.colour = colour.clone();
@@ -459,17 +601,34 @@ impl Object3D
 	
 
 	//{{{
+	// Removes the colours of the object and everything in it
+	pub fn clear_colour(&mut self)
+	{
+		self.colour = Colour::Unset;
+		match &mut self.shape
+		{
+			Shape3D::Composite{children, ..} => for child in children { child.clear_colour(); },
+			Shape3D::Lextrude{child, ..}     => child.clear_colour(),
+			_                                => (),
+		}
+	}
+	//}}}
+	
+
+	//{{{
+	// The colours are cleared, so that OpenSCad's highlight shows
 	pub fn set_debug(&mut self)
 	{
 		self.scad_modifier = crate::ScadModifier::Debug;
-		// TODO: Iterate over compound object tree and delete colour
+		self.clear_colour();
 	}
 	//}}}
 	//{{{
+	// The colours are cleared, so that the object shows as transparent grey
 	pub fn set_background(&mut self)
 	{
 		self.scad_modifier = crate::ScadModifier::Background;
-		// TODO: Iterate over compound object tree and delete colour
+		self.clear_colour();
 	}
 	//}}}
 	//{{{
@@ -516,16 +675,198 @@ impl Object3D
 	}
 	//}}}
 
+	//{{{ Parts
+
+	//{{{
+	pub fn tag_as_part(&mut self)
+	{
+		if self.part.is_none() { self.part = Some(PartInfo::default()); }
+	}
+	//}}}
+	//{{{
+	pub fn set_part_number(&mut self, number: &str)
+	{
+		self.tag_as_part();
+		if let Some(part) = &mut self.part { part.number = Some(String::from(number)); }
+	}
+	//}}}
+	//{{{
+	pub fn set_part_description(&mut self, description: &str)
+	{
+		self.tag_as_part();
+		if let Some(part) = &mut self.part { part.description = Some(String::from(description)); }
+	}
+	//}}}
+	//{{{
+	pub fn set_material(&mut self, material: &str)
+	{
+		self.tag_as_part();
+		if let Some(part) = &mut self.part { part.material = Some(String::from(material)); }
+	}
+	//}}}
+	//{{{
+	pub fn set_label_anchor(&mut self, anchor_name: &str)
+	{
+		self.tag_as_part();
+		if let Some(part) = &mut self.part { part.label_anchor = String::from(anchor_name); }
+	}
+	//}}}
+	//{{{
+	pub fn is_part(&self) -> bool
+	{
+		self.part.is_some()
+	}
+	//}}}
+	//}}}
+
+	//{{{ Mirroring
+
+	//{{{
+	// Marks a feature whose mirror image is something else, like a right handed thread. Text counts as handed anyway.
+	pub fn set_handed(&mut self, handed: bool)
+	{
+		self.handed = handed;
+	}
+	//}}}
+	//{{{
+	// A mirror image across the global YZ-plane, e.g. the right side board from the left one. The anchors move along but
+	// stay right handed (their x-axis flips), so that parts snapped to them are not mirrored as well, and "_L" and "_R"
+	// swap in their names and in the part number. Text and handed features come out mirrored; the copy's
+	// mirrored_handed_features() lists them, so that the caller can warn about them or build those features anew.
+	pub fn mirrored_copy(&self, name: &str) -> Object3D
+	{
+		let mut copy = self.clone();
+		copy.name = String::from(name);
+		copy.mirror(crate::vector3D(1.0, 0.0, 0.0));
+		copy.mirror_anchors();
+		if let Some(part) = &mut copy.part { part.number = part.number.as_deref().map(swap_side); }
+		copy
+	}
+	//}}}
+	//{{{
+	// The text and handed objects in the tree that are mirrored, counting all mirrors on the way down (two mirrors cancel)
+	pub fn mirrored_handed_features(&self) -> Vec<String>
+	{
+		let mut features = vec![];
+		self.collect_mirrored_handed_features(false, &mut features);
+		features
+	}
+	//}}}
+	//{{{
+	fn collect_mirrored_handed_features(&self, mirrored: bool, features: &mut Vec<String>)
+	{
+		let mirrored = mirrored ^ self.ref_sys.is_mirrored();
+		match &self.shape
+		{
+			Shape3D::Text{text, ..} if mirrored          => features.push(format!("the text \"{}\" ({})", text, self.name)),
+			_ if mirrored && self.handed                 => features.push(self.name.clone()),
+			Shape3D::Composite{children, ..}             => for child in children { child.collect_mirrored_handed_features(mirrored, features); },
+			Shape3D::Lextrude{child, ..}                 => child.collect_mirrored_handed_features(mirrored, features),
+			_                                            => (),
+		}
+	}
+	//}}}
+	//{{{
+	fn mirror_anchors(&mut self)
+	{
+		let anchors = std::mem::take(&mut self.anchors);
+		for (name, mut anchor) in anchors
+		{
+			anchor.rel_scale_x(-1.0);
+			anchor.name = swap_side(&name);
+			self.anchors.insert(anchor.name.clone(), anchor);
+		}
+
+		match &mut self.shape
+		{
+			Shape3D::Composite{children, ..} => for child in children { child.mirror_anchors(); },
+			Shape3D::Lextrude{child, ..}     => child.mirror_anchors(),
+			_                                => (),
+		}
+	}
+	//}}}
+	//}}}
+
+	//{{{ Labels
+
+	//{{{
+	// Engraves 'text' centred on the face anchor, 'depth' deep, using the default label font and size.
+	// The anchor's xy-plane is the face, its z-axis points out of the material and the text reads along its x-axis.
+	pub fn engrave_label(&mut self, face_anchor: &str, text: &str, depth: f64) -> Result<(), crate::LabelError>
+	{
+		self.engrave_label_with(face_anchor, text, &crate::LabelOptions{ depth, ..Default::default() })
+	}
+	//}}}
+	//{{{
+	pub fn engrave_label_with(&mut self, face_anchor: &str, text: &str, options: &crate::LabelOptions) -> Result<(), crate::LabelError>
+	{
+		let anchor = match self.anchors.get(face_anchor)
+		{
+			Some(anchor) => anchor.clone(),
+			None         => return Err(crate::LabelError::MissingAnchor{ object: self.name.clone(), anchor: String::from(face_anchor) }),
+		};
+
+		// Overshoot the face a little, so that OpenSCad doesn't leave a skin on top of the engraving
+		let overshoot = 0.01;
+		let mut label = text_centered(&(String::from("Label for ")+&self.name), text, &options.font, options.size);
+		label.linear_extrude(options.depth + overshoot);
+		label.translate_z(-options.depth);
+		label.ref_sys = label.ref_sys * anchor.ref_sys;
+
+		// The engraved object keeps position, colour, anchors and part info, the original shape becomes its base
+		let mut base = self.clone();
+		base.name            = String::from("Base for ")+&self.name;
+		base.ref_sys         = crate::Matrix3D::identity();
+		base.colour          = Colour::Unset;
+		base.scad_modifier   = crate::ScadModifier::Unset;
+		base.custom_modifier = crate::CustomModifier::Unset;
+		base.anchors.clear();
+		base.part            = None;
+
+		self.shape = Shape3D::Composite{ op: BooleanOp::difference, children: vec![base, label] };
+		Ok(())
+	}
+	//}}}
+	//}}}
+
 	// TODO: Make sure this is only called on 2D objects
-	pub fn linear_extrude(&mut self, height: f64)
+	pub fn linear_extrude(&mut self, height: impl Into<Expr>)
 	{
 		let mut child = self.clone();
 		child.name = String::from("Base for ")+&self.name;
 
 		self.anchors.clear();
-		self.shape = Shape3D::Lextrude{height: height, center: false, convexity: 10, twist: 0.0, slices: 0, scale: [].to_vec(), face_number: None::<i32>, face_angle: None::<f64>, face_size: None::<f64>, child: Box::new(self.clone())};
+		self.shape = Shape3D::Lextrude{height: height.into(), center: false, convexity: 10, twist: 0.0, slices: 0, scale: [].to_vec(), face_number: None::<i32>, face_angle: None::<f64>, face_size: None::<f64>, child: Box::new(self.clone())};
 	}
 
+	//{{{ Serialisation (see ScadWriter)
+
+	//{{{
+	// The objects shown by the custom modifiers: anchor gizmos and the origin
+	pub(crate) fn gizmos(&self) -> Vec<Object3D>
+	{
+		let origin = || object_origin(&(String::from("Object3D Origin for ")+&self.name));
+		match self.custom_modifier
+		{
+			crate::CustomModifier::ShowAnchors =>
+			{
+				let mut gizmos : Vec<Object3D> = self.anchors.values().map(|anchor| anchor.get_object_anchor()).collect();
+				gizmos.push(origin());
+				gizmos
+			}
+			crate::CustomModifier::ShowOrigin  => vec![origin()],
+			crate::CustomModifier::Unset       => vec![],
+		}
+	}
+	//}}}
+	//{{{
+	pub(crate) fn has_gizmos(&self) -> bool
+	{
+		!matches!(self.custom_modifier, crate::CustomModifier::Unset)
+	}
+	//}}}
+	//}}}
+
 
 
 }
@@ -540,13 +881,16 @@ impl IsObject for Object3D
 	{
 		Self{
 			name            : String::from(name),
-			shape           : shape,
+			shape,
 			ref_sys         : Self::Matrix::identity(),
 			colour          : Colour::Unset, 
+			resolution      : Resolution::default(),
 			anchors         : HashMap::new(),
+			part            : None,
 			scad_modifier   : crate::ScadModifier::Unset, 
 			custom_modifier : crate::CustomModifier::Unset, 
 			snap_parent     : false,
+			handed          : false,
 		}
 	}
 	//}}}
@@ -572,43 +916,20 @@ impl HasRefSys3D for Object3D
 //}}}
 
 //{{{
+// OpenSCad code with the default ScadWriter settings, a width ({:>2}) is the indentation level to start at
 impl fmt::Display for Object3D
 {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
 	{
-		let indentation = if let Some(width) = f.width() { width } else { 0 as usize };
-
-		let scad_mod = self.scad_modifier.to_string();
-
-		//{{{ Custom Modifiers
-
-		let mut custom_mods = String::from("");
-
-		if let crate::CustomModifier::ShowAnchors = self.custom_modifier
-		{
-			for anchor in self.anchors.values()
-			{
-				custom_mods += &format!("{:>indent$}\n", anchor, indent=indentation+1);
-			}
-			custom_mods += &format!("{:>indent$}\n", object_origin(&(String::from("Object3D Origin for ")+&self.name)), indent=indentation+1);
-		}
-		if let crate::CustomModifier::ShowOrigin = self.custom_modifier
-		{
-			custom_mods += &format!("{:>indent$}\n", object_origin(&(String::from("Object3D Origin for ")+&self.name)), indent=indentation+1);
-		}
-		//}}}
-
-		write!(f, "\n{}//{}\n{}", "\t".repeat(indentation), &self.name,
-			&self.colour.serialise(indentation,
-				&self.ref_sys.serialise(indentation+1,
-					&(	scad_mod + &self.shape.serialise(indentation+2) + &custom_mods)
-				)
-			) 
-		)
+		let level      = f.width().unwrap_or(0);
+		let mut writer = ScadWriter::new(FormatterWriter(f));
+		writer.set_level(level);
+		writer.write_object(self).map_err(|_| fmt::Error)
 	}
 }
 //}}}
 
+
 //{{{
 impl Index<&str> for Object3D
 {
@@ -689,9 +1010,6 @@ impl Object3DIndexHelper<'_>
 		////}}}
 
 
-		let new_origin = !child_anchor.ref_sys * parent_anchor.ref_sys * parent_object.ref_sys;
-		eprintln!("new_origin = {}", new_origin);
-
 		child_object.ref_sys = !child_anchor.ref_sys * parent_anchor.ref_sys * parent_object.ref_sys;
 		//child_object.set_ref_sys(!child_anchor.ref_sys * parent_anchor.ref_sys * parent_object.ref_sys);
 	}
@@ -706,37 +1024,38 @@ impl Object3DIndexHelper<'_>
 
 //{{{ Create Object3Ds
 
-//// TODO: text
-//// TODO: Measure::Length
-//// TODO: Measure::Angle
-//// TODO: Measure::Triangle
+// TODO: text
+// TODO: Measure::Length
+// TODO: Measure::Angle
+// TODO: Measure::Triangle
 
 
 
 //{{{
-pub fn square(name: &str, x: f64, y: f64) -> Object3D
+pub fn square(name: &str, x: impl Into<Expr>, y: impl Into<Expr>) -> Object3D
 {
-	Object3D::new(name, Shape3D::Square{ x: x,y: y })
+	Object3D::new(name, Shape3D::Square{ x: x.into(),y: y.into() })
 }
 //}}}
 //{{{
-pub fn square_coords(name: &str, x1: f64, y1: f64, x2: f64, y2: f64) -> Object3D
+pub fn square_coords(name: &str, x1: impl Into<Length>, y1: impl Into<Length>, x2: impl Into<Length>, y2: impl Into<Length>) -> Object3D
 {
+	let (x1, y1, x2, y2) = (x1.into().to_mm(), y1.into().to_mm(), x2.into().to_mm(), y2.into().to_mm());
 	let x = (x1 - x2).abs();
 	let y = (y1 - y2).abs();
 
 	let x_shift = if x1<x2 { x1 } else { x2 };
 	let y_shift = if y1<y2 { y1 } else { y2 };
 
-	let mut cube = Object3D::new(name, Shape3D::Square{ x: x,y: y });
+	let mut cube = Object3D::new(name, Shape3D::Square{ x: x.into(),y: y.into() });
 	cube.translate(x/2.0+x_shift, y/2.0+y_shift, 0.0);
 	cube
 }
 //}}}
 //{{{
-pub fn circle(name: &str, r: f64) -> Object3D
+pub fn circle(name: &str, r: impl Into<Expr>) -> Object3D
 {
-	Object3D::new(name, Shape3D::Circle{ r: r, face_number: None::<i32>, face_angle: None::<f64>, face_size: None::<f64> })
+	Object3D::new(name, Shape3D::Circle{ r: r.into(), face_number: None::<i32>, face_angle: None::<f64>, face_size: None::<f64> })
 }
 //}}}
 //{{{
@@ -763,22 +1082,42 @@ pub fn polygon(name: &str, points_vec: Vec<Point2D>) -> Object3D
 }
 //}}}
 //{{{
-pub fn text(name: &str, text: &str, font: &str, size: i32, spacing: f64) -> Object3D
+// Like polygon(), but with explicit outlines. Each path lists the indices of its points, the first one is the outer outline, the others are holes.
+pub fn polygon_paths(name: &str, points_vec: Vec<Point2D>, paths_vec: Vec<Vec<i32>>) -> Object3D
+{
+	Object3D::new(name, Shape3D::Polygon{points: points_vec, paths: paths_vec, convexity: 10, face_number: None::<i32>, face_angle: None::<f64>, face_size: None::<f64> })
+}
+//}}}
+//{{{
+pub fn text(name: &str, text: &str, font: &str, size: impl Into<Length>, spacing: f64) -> Object3D
+{
+	Object3D::new(name, Shape3D::Text{ text: String::from(text), font: String::from(font), size: size.into().to_mm(), spacing,
+		halign: TextHAlign::left, valign: TextVAlign::baseline, direction: TextDirection::ltr, language: None, script: None,
+		face_number: None::<i32>, face_angle: None::<f64>, face_size: None::<f64> })
+}
+//}}}
+//{{{
+// Text centred on its origin, e.g. for labels on a face
+pub fn text_centered(name: &str, text: &str, font: &str, size: impl Into<Length>) -> Object3D
 {
-	Object3D::new(name, Shape3D::Text{ text: String::from(text), font: String::from(font), size: size, spacing: spacing })
+	let mut object = crate::object_3d::text(name, text, font, size, 1.0);
+	object.set_text_align(TextHAlign::center, TextVAlign::center);
+	object
 }
 //}}}
 
 
 //{{{
-pub fn cube(name: &str, x: f64, y: f64, z: f64) -> Object3D
+pub fn cube(name: &str, x: impl Into<Expr>, y: impl Into<Expr>, z: impl Into<Expr>) -> Object3D
 {
-	Object3D::new(name, Shape3D::Cube{ x: x,y: y,z: z })
+	Object3D::new(name, Shape3D::Cube{ x: x.into(),y: y.into(),z: z.into() })
 }
 //}}}
 //{{{
-pub fn cube_coords(name: &str, x1: f64, y1: f64, z1: f64, x2: f64, y2: f64, z2: f64) -> Object3D
+pub fn cube_coords(name: &str, x1: impl Into<Length>, y1: impl Into<Length>, z1: impl Into<Length>, x2: impl Into<Length>, y2: impl Into<Length>, z2: impl Into<Length>) -> Object3D
 {
+	let (x1, y1, z1) = (x1.into().to_mm(), y1.into().to_mm(), z1.into().to_mm());
+	let (x2, y2, z2) = (x2.into().to_mm(), y2.into().to_mm(), z2.into().to_mm());
 	let x = (x1 - x2).abs();
 	let y = (y1 - y2).abs();
 	let z = (z1 - z2).abs();
@@ -787,24 +1126,115 @@ pub fn cube_coords(name: &str, x1: f64, y1: f64, z1: f64, x2: f64, y2: f64, z2:
 	let y_shift = if y1<y2 { y1 } else { y2 };
 	let z_shift = if z1<z2 { z1 } else { z2 };
 
-	let mut cube = Object3D::new(name, Shape3D::Cube{ x: x,y: y,z: z });
+	let mut cube = Object3D::new(name, Shape3D::Cube{ x: x.into(),y: y.into(),z: z.into() });
 	cube.translate(x/2.0+x_shift, y/2.0+y_shift, z/2.0+z_shift);
 	cube
 }
 //}}}
 //{{{
-pub fn sphere(name: &str, r: f64) -> Object3D
+pub fn sphere(name: &str, r: impl Into<Expr>) -> Object3D
 {
-	Object3D::new(name, Shape3D::Sphere{ r: r, face_number: None::<i32>, face_angle: None::<f64>, face_size: None::<f64> })
+	Object3D::new(name, Shape3D::Sphere{ r: r.into(), face_number: None::<i32>, face_angle: None::<f64>, face_size: None::<f64> })
 }
 //}}}
 //{{{
-pub fn cylinder(name: &str, h: f64, r1: f64, r2: f64) -> Object3D // Stands along the z-axis, r1 is at the orgigin, r2 is h away from the origin
+pub fn cylinder(name: &str, h: impl Into<Expr>, r1: impl Into<Expr>, r2: impl Into<Expr>) -> Object3D // Stands along the z-axis, r1 is at the orgigin, r2 is h away from the origin
 {
-	Object3D::new(name, Shape3D::Cylinder{ h: h, r1: r1, r2: r2, face_number: None::<i32>, face_angle: None::<f64>, face_size: None::<f64> })
+	Object3D::new(name, Shape3D::Cylinder{ h: h.into(), r1: r1.into(), r2: r2.into(), face_number: None::<i32>, face_angle: None::<f64>, face_size: None::<f64> })
+}
+//}}}
+//{{{
+// Faces list point indices, ordered clockwise when looking at the face from outside
+pub fn polyhedron(name: &str, points: Vec<Point3D>, faces: Vec<Vec<i32>>) -> Object3D
+{
+	Object3D::new(name, Shape3D::Polyhedron{ points, faces, convexity: 10 })
+}
+//}}}
+//{{{
+// Closed outline made from consecutive curves, e.g. CubicBezier, UniformBSpline or QuadraticBezier pieces
+pub fn curve_polygon(name: &str, curves: &[&dyn IsCurve<Point2D>], tolerance: f64) -> Object3D
+{
+	let mut points : Vec<Point2D> = vec![];
+	for curve in curves
+	{
+		for point in curve.flatten(tolerance)
+		{
+			if let Some(last) = points.last()
+			{
+				if (last[0]-point[0]).abs() < 1e-12 && (last[1]-point[1]).abs() < 1e-12 { continue; }
+			}
+			points.push(point);
+		}
+	}
+
+	// The polygon closes itself
+	if points.len() > 1
+	{
+		let (first, last) = (points[0], points[points.len()-1]);
+		if (first[0]-last[0]).abs() < 1e-12 && (first[1]-last[1]).abs() < 1e-12 { points.pop(); }
+	}
+
+	polygon(name, points)
+}
+//}}}
+//{{{
+// Freeform panel: the patch surface, thickened by 'thickness' against the direction of its normal
+pub fn bezier_panel(name: &str, patch: &BezierPatch, thickness: f64, tolerance: f64) -> Object3D
+{
+	use crate::math::point3D;
+
+	let (nu, nv) = patch.resolution(tolerance);
+	let columns  = nv+1;
+	let layer    = (nu+1)*columns;
+
+	let mut points = vec![];
+	for i in 0..=nu
+	{
+		for j in 0..=nv
+		{
+			let (u, v) = ((i as f64)/(nu as f64), (j as f64)/(nv as f64));
+			points.push(patch.eval(u, v));
+		}
+	}
+	for index in 0..layer
+	{
+		let (i, j) = (index/columns, index%columns);
+		let (u, v) = ((i as f64)/(nu as f64), (j as f64)/(nv as f64));
+		let (p, n) = (points[index], patch.normal(u, v));
+		points.push(point3D(p[0]-thickness*n[0], p[1]-thickness*n[1], p[2]-thickness*n[2]));
+	}
+
+	let top    = |i: usize, j: usize| (i*columns+j) as i32;
+	let bottom = |i: usize, j: usize| (layer + i*columns+j) as i32;
+
+	let mut faces = vec![];
+	let mut quad  = |a: i32, b: i32, c: i32, d: i32|
+	{
+		faces.push(vec![a, b, c]);
+		faces.push(vec![a, c, d]);
+	};
+	for i in 0..nu
+	{
+		for j in 0..nv
+		{
+			quad(top(i, j),    top(i, j+1),    top(i+1, j+1),    top(i+1, j));
+			quad(bottom(i, j), bottom(i+1, j), bottom(i+1, j+1), bottom(i, j+1));
+		}
+	}
+	for i in 0..nu
+	{
+		quad(top(i, 0),    top(i+1, 0),     bottom(i+1, 0),  bottom(i, 0));
+		quad(top(i+1, nv), top(i, nv),      bottom(i, nv),   bottom(i+1, nv));
+	}
+	for j in 0..nv
+	{
+		quad(top(0, j+1),  top(0, j),       bottom(0, j),    bottom(0, j+1));
+		quad(top(nu, j),   top(nu, j+1),    bottom(nu, j+1), bottom(nu, j));
+	}
+
+	polyhedron(name, points, faces)
 }
 //}}}
-//// TODO: Polyhedron
 
 //{{{
 pub fn union<T: AsRef<[Object3D]>>(name: &str, children: T) -> Object3D
@@ -814,7 +1244,7 @@ pub fn union<T: AsRef<[Object3D]>>(name: &str, children: T) -> Object3D
 	{
 		for (anchor_name, anchor) in child.anchors.clone()
 		{
-			let aname = if child.name.len() == 0 { anchor_name } else { child.name.clone() + "::" + &anchor_name };
+			let aname = if child.name.is_empty() { anchor_name } else { child.name.clone() + "::" + &anchor_name };
 			anchors.insert(aname, anchor);
 		}
 		child.anchors.clear();
@@ -832,7 +1262,7 @@ pub fn difference<T: AsRef<[Object3D]>>(name: &str, children: T) -> Object3D
 	{
 		for (anchor_name, anchor) in child.anchors.clone()
 		{
-			let aname = if child.name.len() == 0 { anchor_name } else { child.name.clone() + "::" + &anchor_name };
+			let aname = if child.name.is_empty() { anchor_name } else { child.name.clone() + "::" + &anchor_name };
 			anchors.insert(aname, anchor);
 		}
 		child.anchors.clear();
@@ -850,7 +1280,7 @@ pub fn intersection<T: AsRef<[Object3D]>>(name: &str, children: T) -> Object3D
 	{
 		for (anchor_name, anchor) in child.anchors.clone()
 		{
-			let aname = if child.name.len() == 0 { anchor_name } else { child.name.clone() + "::" + &anchor_name };
+			let aname = if child.name.is_empty() { anchor_name } else { child.name.clone() + "::" + &anchor_name };
 			anchors.insert(aname, anchor);
 		}
 		child.anchors.clear();
@@ -868,7 +1298,7 @@ pub fn hull<T: AsRef<[Object3D]>>(name: &str, children: T) -> Object3D
 	{
 		for (anchor_name, anchor) in child.anchors.clone()
 		{
-			let aname = if child.name.len() == 0 { anchor_name } else { child.name.clone() + "::" + &anchor_name };
+			let aname = if child.name.is_empty() { anchor_name } else { child.name.clone() + "::" + &anchor_name };
 			anchors.insert(aname, anchor);
 		}
 		child.anchors.clear();
@@ -886,7 +1316,7 @@ pub fn minkowski<T: AsRef<[Object3D]>>(name: &str, children: T) -> Object3D
 	{
 		for (anchor_name, anchor) in child.anchors.clone()
 		{
-			let aname = if child.name.len() == 0 { anchor_name } else { child.name.clone() + "::" + &anchor_name };
+			let aname = if child.name.is_empty() { anchor_name } else { child.name.clone() + "::" + &anchor_name };
 			anchors.insert(aname, anchor);
 		}
 		child.anchors.clear();
@@ -898,8 +1328,9 @@ pub fn minkowski<T: AsRef<[Object3D]>>(name: &str, children: T) -> Object3D
 //}}}
 
 //{{{
-pub fn pipe(name: &str, l: f64, r_outer: f64, r_inner: f64) -> Object3D // Pipe of length l with inner radius r_inner and outer radius r_outer
+pub fn pipe(name: &str, l: impl Into<Length>, r_outer: impl Into<Length>, r_inner: impl Into<Length>) -> Object3D // Pipe of length l with inner radius r_inner and outer radius r_outer
 {
+	let (l, r_outer, r_inner) = (l.into().to_mm(), r_outer.into().to_mm(), r_inner.into().to_mm());
 	let     outer = cylinder(&(String::from("base cylinder for ")+name), l, r_outer, r_outer);
 	let mut inner = cylinder(&(String::from("base cylinder for ")+name), l+2.0, r_inner, r_inner);
 	inner.translate_z(-1.0);
@@ -907,8 +1338,9 @@ pub fn pipe(name: &str, l: f64, r_outer: f64, r_inner: f64) -> Object3D // Pipe
 }
 //}}}
 //{{{
-pub fn wedge(name: &str, x: f64, y: f64, angle: f64) -> Object3D
+pub fn wedge(name: &str, x: impl Into<Length>, y: impl Into<Length>, angle: impl Into<Angle>) -> Object3D
 {
+	let (x, y, angle) = (x.into(), y.into(), angle.into());
 	let     lower = cube_coords(&(String::from("base cylinder for ")+name), 0.0, 0.0, 0.0, x, y, 0.000000000000001);
 	let mut upper = cube_coords(&(String::from("base cylinder for ")+name), 0.0, 0.0, 0.0, x, y, 0.000000000000001);
 	upper.rotate_y(-angle);
@@ -916,8 +1348,9 @@ pub fn wedge(name: &str, x: f64, y: f64, angle: f64) -> Object3D
 }
 //}}}
 //{{{
-pub fn pipe_cut(name: &str, l: f64, r_outer: f64, r_inner: f64, angle: f64) -> Object3D
+pub fn pipe_cut(name: &str, l: impl Into<Length>, r_outer: impl Into<Length>, r_inner: impl Into<Length>, angle: impl Into<Angle>) -> Object3D
 {
+	let (l, r_outer, r_inner) = (l.into().to_mm(), r_outer.into().to_mm(), r_inner.into().to_mm());
 	let     pipe    = pipe(&(String::from("base pipe for ")+name), l, r_outer, r_inner);
 	let mut stencil = wedge(&(String::from("wedge for ")+name), 10.0*r_outer+2.0, -l, angle);
 	stencil.rotate_x(-90.0);
@@ -928,8 +1361,9 @@ pub fn pipe_cut(name: &str, l: f64, r_outer: f64, r_inner: f64, angle: f64) -> O
 
 
 //{{{
-pub fn arrow(name: &str, length: f64, width: f64) -> Object3D
+pub fn arrow(name: &str, length: impl Into<Length>, width: impl Into<Length>) -> Object3D
 {
+	let (length, width) = (length.into().to_mm(), width.into().to_mm());
 	let mut shaft = cylinder(&(String::from(name)+"::arrow::shaft"), 0.9*length,     width, width);
 	let mut tip   = cylinder(&(String::from(name)+"::arrow::tip"),   0.1*length, 2.0*width,   0.0);
 	tip.translate_z(0.9*length);
@@ -1035,3 +1469,116 @@ pub fn object_anchor(name: &str) -> Object3D
 
 //}}}
 
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	//{{{
+	#[test]
+	fn escaped_strings()
+	{
+		assert_eq!(escape_scad_string(r#"say "hi" \o/"#), r#"say \"hi\" \\o/"#);
+		assert_eq!(escape_scad_string("a\nb\tc\r\u{1}é"), r#"a\nb\tc\r\u0001é"#);
+
+		// Text, font, language and script all end up in string literals
+		let mut label = text("label", "5\" \\ bolt\nline 2", "Liberation Sans:style=\"Bold\"", 5.0, 1.0);
+		label.set_text_language("e\"n");
+		label.set_text_script("la\\tin");
+		let code = crate::scad_string(|writer| writer.write_objects(&[label]));
+		assert!(code.contains(r#"text("5\" \\ bolt\nline 2", size="#));
+		assert!(code.contains(r#"font="Liberation Sans:style=\"Bold\"""#));
+		assert!(code.contains(r#"language="e\"n", script="la\\tin""#));
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn sides()
+	{
+		assert_eq!(swap_side("Sideboard_L"), "Sideboard_R");
+		assert_eq!(swap_side("Sideboard_R"), "Sideboard_L");
+		assert_eq!(swap_side("Leg L"), "Leg R");
+		assert_eq!(swap_side("Leg R"), "Leg L");
+		assert_eq!(swap_side("Lid"), "Lid");
+		assert_eq!(swap_side("Leg_LR"), "Leg_LR");
+		assert_eq!(swap_side("L"), "L");
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn mirrored_copies()
+	{
+		let mut panel = cube("panel", 18.0, 200.0, 400.0);
+		panel.create_anchor("Hole L").translate_y(50.0);
+		let mut board = union("Sideboard_L", [panel]);
+		board.translate(100.0, 0.0, 0.0);
+		board.set_part_number("SB-01_L");
+		board.create_anchor("Hinge_L").translate(5.0, 20.0, 30.0);
+		board.create_anchor("Top").translate_z(200.0);
+
+		let copy = board.mirrored_copy("Sideboard_R");
+		assert_eq!(copy.name, "Sideboard_R");
+		assert!(copy.ref_sys.is_mirrored());
+		assert_eq!(copy.ref_sys.get_translate(), (-100.0, 0.0, 0.0));
+		assert_eq!(copy.part.as_ref().and_then(|part| part.number.as_deref()), Some("SB-01_R"));
+
+		// The anchors are renamed and moved along, but stay right handed
+		let mut names : Vec<&String> = copy.anchors.keys().collect();
+		names.sort();
+		assert_eq!(names, ["Hinge_R", "Top", "panel::Hole R"]);
+		let hinge = copy["Hinge_R"].ref_sys * copy.ref_sys;
+		assert_eq!(hinge.get_translate(), (-105.0, 20.0, 30.0));
+		assert!(!hinge.is_mirrored());
+		match &copy.shape
+		{
+			Shape3D::Composite{children, ..} => assert!(children[0].anchors.contains_key("Hole R")),
+			_                                => panic!("the copy is still a union"),
+		}
+
+		// The original is left alone
+		assert!(!board.ref_sys.is_mirrored() && board.anchors.contains_key("Hinge_L"));
+
+		// Text comes out mirrored, unless mirrored back
+		let mut labelled = union("labelled", [board, text("label", "SB-01", "Liberation Sans", 10.0, 1.0)]);
+		assert!(labelled.mirrored_handed_features().is_empty());
+		labelled = labelled.mirrored_copy("labelled R");
+		assert_eq!(labelled.mirrored_handed_features(), ["the text \"SB-01\" (label)"]);
+		assert!(labelled.mirrored_copy("labelled L").mirrored_handed_features().is_empty());
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn typed_arguments()
+	{
+		// Objects and anchors take Point3/Vector3 as well as point3D()/vector3D()
+		use crate::math::{Point3, Vector3, point3D, vector3D};
+
+		let mut old   = cube("old", 1.0, 2.0, 3.0);
+		let mut typed = cube("typed", 1.0, 2.0, 3.0);
+		old.rotate_about(vector3D(1.0, 1.0, 0.0), 30.0, point3D(5.0, 0.0, 0.0));
+		typed.rotate_about(Vector3::new(1.0, 1.0, 0.0), 30.0, Point3::new(5.0, 0.0, 0.0));
+		old.look_at(point3D(10.0, 20.0, 30.0), vector3D(0.0, 0.0, 1.0));
+		typed.look_at(Point3::new(10.0, 20.0, 30.0), Vector3::unit_z());
+		old.mirror(vector3D(0.0, 1.0, 0.0));
+		typed.mirror(Vector3::unit_y());
+		assert_eq!(format!("{:?}", typed.ref_sys), format!("{:?}", old.ref_sys));
+
+		typed.create_anchor("Axle").mirror(Vector3::unit_x());
+		assert!(typed["Axle"].ref_sys.is_mirrored());
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn identifiers()
+	{
+		assert_eq!(scad_identifier("Sideboard_L"), "Sideboard_L");
+		assert_eq!(scad_identifier("Leg L-1.5"), "Leg_L_1_5");
+		assert_eq!(scad_identifier("2nd shelf"), "part_2nd_shelf");
+		assert_eq!(scad_identifier("Bett ä"), "Bett__");
+		assert_eq!(scad_identifier(""), "part_");
+	}
+	//}}}
+}
+//}}}
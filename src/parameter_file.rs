@@ -0,0 +1,402 @@
+// Readers for parameter files. Both formats are read as a flat list of (name, value) pairs; tables (TOML) and nested
+// objects (JSON) only group the parameters and their names are dropped, like the Customizer groups.
+//
+// Only what parameter files need is supported: numbers, booleans and strings. Arrays, dates, inline tables and the like
+// are reported as errors.
+
+use crate::parameters::ParameterValue;
+
+// Parse error: line number (starting at 1) and message
+pub(crate) type ParseError = (usize, String);
+
+//{{{ TOML
+
+//{{{
+pub(crate) fn parse_toml(text: &str) -> Result<Vec<(String, ParameterValue)>, ParseError>
+{
+	let mut values = vec![];
+	for (index, line) in text.lines().enumerate()
+	{
+		let line_number = index+1;
+		let mut scanner = Scanner::new(line, line_number);
+		scanner.skip_whitespace();
+
+		match scanner.peek()
+		{
+			None | Some('#') => continue,
+			Some('[')        =>
+			{
+				// Table header, only used for grouping
+				if !scanner.rest().trim_end().ends_with(']') { return Err((line_number, String::from("unterminated table header"))); }
+				continue;
+			}
+			_ => (),
+		}
+
+		let key = match scanner.peek()
+		{
+			Some('"')  => scanner.basic_string()?,
+			Some('\'') => scanner.literal_string()?,
+			_          => scanner.take_while(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'),
+		};
+		if key.is_empty() { return Err((line_number, String::from("expected a parameter name"))); }
+
+		scanner.skip_whitespace();
+		if scanner.next() != Some('=') { return Err((line_number, format!("expected '=' after {}", key))); }
+		scanner.skip_whitespace();
+
+		let value = match scanner.peek()
+		{
+			Some('"')  => ParameterValue::Text(scanner.basic_string()?),
+			Some('\'') => ParameterValue::Text(scanner.literal_string()?),
+			_          =>
+			{
+				let word = scanner.take_while(|c| !c.is_whitespace() && c != '#');
+				match word.as_str()
+				{
+					"true"  => ParameterValue::Bool(true),
+					"false" => ParameterValue::Bool(false),
+					_       => parse_number(&word.replace('_', "")).ok_or((line_number, format!("unsupported value for {}: {}", key, word)))?,
+				}
+			}
+		};
+
+		scanner.skip_whitespace();
+		if !matches!(scanner.peek(), None | Some('#')) { return Err((line_number, format!("unexpected text after the value of {}", key))); }
+		values.push((key, value));
+	}
+	Ok(values)
+}
+//}}}
+//}}}
+
+//{{{ JSON
+
+//{{{
+pub(crate) fn parse_json(text: &str) -> Result<Vec<(String, ParameterValue)>, ParseError>
+{
+	let mut values  = vec![];
+	let mut scanner = Scanner::new(text, 1);
+	scanner.skip_json_whitespace();
+	json_object(&mut scanner, &mut values)?;
+	scanner.skip_json_whitespace();
+	if scanner.peek().is_some() { return Err(scanner.error("unexpected text after the top level object")); }
+	Ok(values)
+}
+//}}}
+//{{{
+fn json_object(scanner: &mut Scanner, values: &mut Vec<(String, ParameterValue)>) -> Result<(), ParseError>
+{
+	if scanner.next() != Some('{') { return Err(scanner.error("expected '{'")); }
+	scanner.skip_json_whitespace();
+	if scanner.peek() == Some('}') { scanner.next(); return Ok(()); }
+
+	loop
+	{
+		scanner.skip_json_whitespace();
+		if scanner.peek() != Some('"') { return Err(scanner.error("expected a parameter name")); }
+		let key = scanner.basic_string()?;
+
+		scanner.skip_json_whitespace();
+		if scanner.next() != Some(':') { return Err(scanner.error(&format!("expected ':' after {}", key))); }
+		scanner.skip_json_whitespace();
+
+		match scanner.peek()
+		{
+			Some('{') => json_object(scanner, values)?,
+			Some('"') => { let text = scanner.basic_string()?; values.push((key, ParameterValue::Text(text))); }
+			_         =>
+			{
+				let word  = scanner.take_while(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '+');
+				let value = match word.as_str()
+				{
+					"true"  => ParameterValue::Bool(true),
+					"false" => ParameterValue::Bool(false),
+					_       => parse_number(&word).ok_or_else(|| scanner.error(&format!("unsupported value for {}", key)))?,
+				};
+				values.push((key, value));
+			}
+		}
+
+		scanner.skip_json_whitespace();
+		match scanner.next()
+		{
+			Some(',') => continue,
+			Some('}') => return Ok(()),
+			_         => return Err(scanner.error("expected ',' or '}'")),
+		}
+	}
+}
+//}}}
+//}}}
+
+//{{{ Helpers
+
+//{{{
+// Integers stay integers, so that they can be checked against integer parameters
+fn parse_number(word: &str) -> Option<ParameterValue>
+{
+	if let Ok(value) = word.parse::<i64>() { return Some(ParameterValue::Integer(value)); }
+	match word.parse::<f64>()
+	{
+		Ok(value) if value.is_finite() => Some(ParameterValue::Number(value)),
+		_                              => None,
+	}
+}
+//}}}
+
+//{{{ struct Scanner
+
+struct Scanner<'a>
+{
+	chars : std::iter::Peekable<std::str::Chars<'a>>,
+	text  : &'a str,
+	used  : usize, // Bytes consumed
+	line  : usize,
+}
+
+//{{{
+impl<'a> Scanner<'a>
+{
+	//{{{
+	fn new(text: &'a str, line: usize) -> Self
+	{
+		Self { chars: text.chars().peekable(), text, used: 0, line }
+	}
+	//}}}
+	//{{{
+	fn error(&self, message: &str) -> ParseError
+	{
+		(self.line, String::from(message))
+	}
+	//}}}
+	//{{{
+	fn peek(&mut self) -> Option<char>
+	{
+		self.chars.peek().copied()
+	}
+	//}}}
+	//{{{
+	fn next(&mut self) -> Option<char>
+	{
+		let c = self.chars.next();
+		if let Some(c) = c
+		{
+			self.used += c.len_utf8();
+			if c == '\n' { self.line += 1; }
+		}
+		c
+	}
+	//}}}
+	//{{{
+	fn rest(&self) -> &'a str
+	{
+		&self.text[self.used..]
+	}
+	//}}}
+	//{{{
+	fn take_while(&mut self, accept: impl Fn(char) -> bool) -> String
+	{
+		let mut word = String::from("");
+		while let Some(c) = self.peek()
+		{
+			if !accept(c) { break; }
+			word.push(c);
+			self.next();
+		}
+		word
+	}
+	//}}}
+	//{{{
+	fn skip_whitespace(&mut self)
+	{
+		self.take_while(|c| c == ' ' || c == '\t');
+	}
+	//}}}
+	//{{{
+	fn skip_json_whitespace(&mut self)
+	{
+		self.take_while(char::is_whitespace);
+	}
+	//}}}
+	//{{{
+	// "..." with backslash escapes, as used by both TOML and JSON
+	fn basic_string(&mut self) -> Result<String, ParseError>
+	{
+		self.next();
+		let mut text = String::from("");
+		loop
+		{
+			match self.next()
+			{
+				None | Some('\n') => return Err(self.error("unterminated string")),
+				Some('"')         => return Ok(text),
+				Some('\\')        =>
+				{
+					let c = match self.next()
+					{
+						Some('n')  => '\n',
+						Some('t')  => '\t',
+						Some('r')  => '\r',
+						Some('"')  => '"',
+						Some('\\') => '\\',
+						Some('/')  => '/',
+						Some('u')  =>
+						{
+							let hex : String = (0..4).filter_map(|_| self.next()).collect();
+							u32::from_str_radix(&hex, 16).ok().and_then(std::char::from_u32).ok_or_else(|| self.error("invalid unicode escape"))?
+						}
+						_          => return Err(self.error("invalid escape sequence")),
+					};
+					text.push(c);
+				}
+				Some(c)           => text.push(c),
+			}
+		}
+	}
+	//}}}
+	//{{{
+	// TOML '...' without escapes
+	fn literal_string(&mut self) -> Result<String, ParseError>
+	{
+		self.next();
+		let text = self.take_while(|c| c != '\'' && c != '\n');
+		if self.next() != Some('\'') { return Err(self.error("unterminated string")); }
+		Ok(text)
+	}
+	//}}}
+}
+//}}}
+//}}}
+//}}}
+
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::parameters::{Parameter, ParameterError, Parameters};
+
+	//{{{
+	fn value(values: &[(String, ParameterValue)], name: &str) -> ParameterValue
+	{
+		values.iter().find(|(key, _)| key == name).map(|(_, value)| value.clone()).unwrap_or_else(|| panic!("no value for {}", name))
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn toml_values()
+	{
+		let values = parse_toml(r#"
+# Bed variant
+width      = 160          # cm
+length     = 2.05e2
+count      = 1_000
+negative   = -3
+headboard  = true
+footboard  = false
+"quoted key" = "say \"hi\"\t\u00e9\\"
+literal    = 'C:\no\escapes'
+
+[motor]
+motor-width = 50.5
+"#).expect("valid TOML");
+
+		assert_eq!(values.len(), 9);
+		assert_eq!(value(&values, "width"),       ParameterValue::Integer(160));
+		assert_eq!(value(&values, "length"),      ParameterValue::Number(205.0));
+		assert_eq!(value(&values, "count"),       ParameterValue::Integer(1000));
+		assert_eq!(value(&values, "negative"),    ParameterValue::Integer(-3));
+		assert_eq!(value(&values, "headboard"),   ParameterValue::Bool(true));
+		assert_eq!(value(&values, "footboard"),   ParameterValue::Bool(false));
+		assert_eq!(value(&values, "quoted key"),  ParameterValue::Text(String::from("say \"hi\"\té\\")));
+		assert_eq!(value(&values, "literal"),     ParameterValue::Text(String::from(r"C:\no\escapes")));
+		assert_eq!(value(&values, "motor-width"), ParameterValue::Number(50.5));  // Tables only group
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn json_values()
+	{
+		let values = parse_json(r#"
+{
+	"width"  : 160,
+	"length" : 2.05e2,
+	"name"   : "Bett \"L\"\n\u00e9\/",
+	"motor"  : { "MOTOR_WIDTH": -50.5, "strong": true, "inner": { "deep": false } },
+	"empty"  : {}
+}"#).expect("valid JSON");
+
+		assert_eq!(values.len(), 6);
+		assert_eq!(value(&values, "width"),       ParameterValue::Integer(160));
+		assert_eq!(value(&values, "length"),      ParameterValue::Number(205.0));
+		assert_eq!(value(&values, "name"),        ParameterValue::Text(String::from("Bett \"L\"\né/")));
+		assert_eq!(value(&values, "MOTOR_WIDTH"), ParameterValue::Number(-50.5));
+		assert_eq!(value(&values, "strong"),      ParameterValue::Bool(true));
+		assert_eq!(value(&values, "deep"),        ParameterValue::Bool(false));
+		assert_eq!(parse_json("{}"), Ok(vec![]));
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn malformed_input()
+	{
+		let line = |result: Result<Vec<(String, ParameterValue)>, ParseError>| result.expect_err("malformed input").0;
+
+		assert_eq!(line(parse_toml("a = 1\n[motor")),            2);
+		assert_eq!(line(parse_toml("width 160")),                1);
+		assert_eq!(line(parse_toml("= 160")),                    1);
+		assert_eq!(line(parse_toml("a = 1\nlist = [1, 2]")),     2);
+		assert_eq!(line(parse_toml("date = 2024-01-01")),        1);
+		assert_eq!(line(parse_toml("a = 1 2")),                  1);
+		assert_eq!(line(parse_toml("text = \"open")),            1);
+		assert_eq!(line(parse_toml("text = 'open")),             1);
+		assert_eq!(line(parse_toml(r#"text = "\q""#)),           1);
+		assert_eq!(line(parse_toml(r#"text = "\u12""#)),         1);
+		assert_eq!(line(parse_toml("nan = nan")),                1);
+
+		assert_eq!(line(parse_json("[1, 2]")),                   1);
+		assert_eq!(line(parse_json("{\n\"a\": 1\n\"b\": 2}")),   3);
+		assert_eq!(line(parse_json("{\"a\": [1]}")),             1);
+		assert_eq!(line(parse_json("{\"a\" 1}")),                1);
+		assert_eq!(line(parse_json("{a: 1}")),                   1);
+		assert_eq!(line(parse_json("{\"a\": 1}\n{}")),           2);
+		assert_eq!(line(parse_json("{\"a\": \"open}")),          1);
+		assert_eq!(line(parse_json("{\"a\": null}")),            1);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn unknown_and_mistyped_keys()
+	{
+		let mut parameters = Parameters::new();
+		parameters.add(Parameter::number("MATTRESS_WIDTH", 140.0).range(80.0, 10.0, 200.0)).unwrap();
+		parameters.add(Parameter::integer("SLATS", 28)).unwrap();
+		parameters.add(Parameter::boolean("MOTOR", true)).unwrap();
+		parameters.add(Parameter::text("NAME", "Bett")).unwrap();
+
+		parameters.load_toml("MATTRESS_WIDTH = 160\nSLATS = 30.0\nMOTOR = false\nNAME = 'Gast'").expect("valid values");
+		assert_eq!(parameters.value("MATTRESS_WIDTH"), 160.0);
+		assert_eq!(parameters.get("SLATS").map(|p| p.value.clone()), Some(ParameterValue::Integer(30)));
+		assert_eq!(parameters.get("MOTOR").map(|p| p.value.clone()), Some(ParameterValue::Bool(false)));
+		assert_eq!(parameters.get("NAME").map(|p| p.value.clone()), Some(ParameterValue::Text(String::from("Gast"))));
+
+		assert_eq!(parameters.load_toml("MATRESS_WIDTH = 160"), Err(ParameterError::Unknown(String::from("MATRESS_WIDTH"))));
+		assert!(matches!(parameters.load_json("{\"MATTRESS_WIDTH\": \"wide\"}"), Err(ParameterError::Type{expected: "number", ..})));
+		assert!(matches!(parameters.load_toml("SLATS = 30.5"), Err(ParameterError::Type{expected: "integer", ..})));
+		assert!(matches!(parameters.load_toml("MOTOR = 1"), Err(ParameterError::Type{expected: "boolean", ..})));
+		assert!(matches!(parameters.load_json("{\"MATTRESS_WIDTH\": 250}"), Err(ParameterError::Range{min, max, ..}) if min == 80.0 && max == 200.0));
+		assert!(matches!(parameters.load_json("{\"MATTRESS_WIDTH\": 1"), Err(ParameterError::Syntax{line: 1, ..})));
+		assert_eq!(parameters.value("MATTRESS_WIDTH"), 160.0);
+
+		let args = ["scad", "--set", "SLATS=20", "--set=NAME=\"Kind\"", "--part", "Sideboard_L"].iter().map(|&arg| String::from(arg));
+		assert_eq!(parameters.apply_args(args), Ok(vec![String::from("scad"), String::from("--part"), String::from("Sideboard_L")]));
+		assert_eq!(parameters.get("NAME").map(|p| p.value.clone()), Some(ParameterValue::Text(String::from("Kind"))));
+		assert!(matches!(parameters.apply_args([String::from("--set")]), Err(ParameterError::Usage(_))));
+		assert!(matches!(parameters.apply_args([String::from("--set"), String::from("SLATS")]), Err(ParameterError::Usage(_))));
+		assert!(matches!(parameters.apply_args([String::from("--parameters"), String::from("/nonexistent/bed.toml")]), Err(ParameterError::Io{..})));
+	}
+	//}}}
+}
+//}}}
@@ -0,0 +1,841 @@
+// Design parameters for the OpenSCad Customizer.
+//
+// Register parameters in a Parameters registry and use the returned Expr for dimensions:
+//     let mut parameters = Parameters::new();
+//     let width = parameters.add(Parameter::number("width", 20.0).range(10.0, 1.0, 50.0).unit("mm"))?;
+//     let panel = cube("panel", width.clone(), 2.0*width, 3.0);
+// The generated code then reads cube([width, (2 * width), 3]) instead of baked numbers.
+// Only the dimensions of the primitive shapes and linear_extrude() take expressions; transformations (and anchors) are
+// always evaluated to numbers with the values the parameters had when the design was built.
+// Expressions evaluate to the current values of their parameters, so meshes (STL, 3MF, nesting) follow values set after
+// the expressions were taken. Transformations don't, so set the values before building the design.
+//
+// Variants of a design can set the parameters from TOML or JSON files and from the command line, before building it:
+//     let args = parameters.apply_command_line(); // --parameters wide.toml --set MATTRESS_WIDTH=160
+
+use std::fmt;
+use std::ops::{Add, Sub, Mul, Div, Neg};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::parameter_file::{parse_json, parse_toml};
+
+//{{{ pub struct SharedValue
+
+// The numeric value of a parameter, shared between the parameter and the expressions referring to it
+#[derive(Debug, Clone)]
+pub struct SharedValue(Arc<AtomicU64>);
+
+//{{{
+impl SharedValue
+{
+	//{{{
+	fn new(value: f64) -> Self
+	{
+		Self(Arc::new(AtomicU64::new(value.to_bits())))
+	}
+	//}}}
+	//{{{
+	pub fn get(&self) -> f64
+	{
+		f64::from_bits(self.0.load(Ordering::Relaxed))
+	}
+	//}}}
+	//{{{
+	fn set(&self, value: f64)
+	{
+		self.0.store(value.to_bits(), Ordering::Relaxed);
+	}
+	//}}}
+}
+//}}}
+
+//{{{
+impl PartialEq for SharedValue
+{
+	fn eq(&self, other: &Self) -> bool
+	{
+		self.get() == other.get()
+	}
+}
+//}}}
+//}}}
+
+//{{{ pub enum Expr
+
+// A dimension that is either a plain number or an expression over design parameters.
+// Shapes keep the expression, so the generated OpenSCad code refers to the parameter variables instead of baked numbers.
+// value() evaluates it with the current values of the parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr
+{
+	Number(f64),
+	Parameter{ name: String, value: SharedValue },
+	Neg(Box<Expr>),
+	Binary{ op: char, left: Box<Expr>, right: Box<Expr> },
+}
+
+//{{{
+impl Expr
+{
+	//{{{
+	pub fn value(&self) -> f64
+	{
+		match self
+		{
+			Self::Number(value)              => *value,
+			Self::Parameter{value, ..}       => value.get(),
+			Self::Neg(expr)                  => -expr.value(),
+			Self::Binary{op, left, right}    =>
+			{
+				let (left, right) = (left.value(), right.value());
+				match op
+				{
+					'+' => left + right,
+					'-' => left - right,
+					'*' => left * right,
+					'/' => left / right,
+					_   => unreachable!("unknown operator {}", op),
+				}
+			}
+		}
+	}
+	//}}}
+	//{{{
+	// True if the expression refers to no parameter
+	pub fn is_constant(&self) -> bool
+	{
+		match self
+		{
+			Self::Number(_)               => true,
+			Self::Parameter{..}           => false,
+			Self::Neg(expr)               => expr.is_constant(),
+			Self::Binary{left, right, ..} => left.is_constant() && right.is_constant(),
+		}
+	}
+	//}}}
+	//{{{
+	fn binary(op: char, left: Expr, right: Expr) -> Expr
+	{
+		// Fold constants, so that plain numbers still come out as plain numbers
+		if left.is_constant() && right.is_constant()
+		{
+			return Self::Number(Self::Binary{op, left: Box::new(left), right: Box::new(right)}.value());
+		}
+		Self::Binary{op, left: Box::new(left), right: Box::new(right)}
+	}
+	//}}}
+}
+//}}}
+
+//{{{
+impl fmt::Display for Expr
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+	{
+		match self
+		{
+			Self::Number(value)           => write!(f, "{}", value),
+			Self::Parameter{name, ..}     => write!(f, "{}", name),
+			Self::Neg(expr)               => write!(f, "-({})", expr),
+			Self::Binary{op, left, right} => write!(f, "({} {} {})", left, op, right),
+		}
+	}
+}
+//}}}
+
+//{{{ Conversions and operators
+
+impl From<f64> for Expr { fn from(value: f64) -> Self { Self::Number(value) } }
+impl From<&Expr> for Expr { fn from(expr: &Expr) -> Self { expr.clone() } }
+
+impl<T: Into<Expr>> Add<T> for Expr { type Output = Expr; fn add(self, other: T) -> Expr { Expr::binary('+', self, other.into()) } }
+impl<T: Into<Expr>> Sub<T> for Expr { type Output = Expr; fn sub(self, other: T) -> Expr { Expr::binary('-', self, other.into()) } }
+impl<T: Into<Expr>> Mul<T> for Expr { type Output = Expr; fn mul(self, other: T) -> Expr { Expr::binary('*', self, other.into()) } }
+impl<T: Into<Expr>> Div<T> for Expr { type Output = Expr; fn div(self, other: T) -> Expr { Expr::binary('/', self, other.into()) } }
+
+impl Add<Expr> for f64 { type Output = Expr; fn add(self, other: Expr) -> Expr { Expr::binary('+', Expr::from(self), other) } }
+impl Sub<Expr> for f64 { type Output = Expr; fn sub(self, other: Expr) -> Expr { Expr::binary('-', Expr::from(self), other) } }
+impl Mul<Expr> for f64 { type Output = Expr; fn mul(self, other: Expr) -> Expr { Expr::binary('*', Expr::from(self), other) } }
+impl Div<Expr> for f64 { type Output = Expr; fn div(self, other: Expr) -> Expr { Expr::binary('/', Expr::from(self), other) } }
+
+impl Neg for Expr
+{
+	type Output = Expr;
+	fn neg(self) -> Expr
+	{
+		match self
+		{
+			Expr::Number(value) => Expr::Number(-value),
+			expr                => Expr::Neg(Box::new(expr)),
+		}
+	}
+}
+//}}}
+//}}}
+
+//{{{ pub enum ParameterValue
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParameterValue
+{
+	Number(f64),
+	Integer(i64),
+	Bool(bool),
+	Text(String),
+}
+
+//{{{
+impl ParameterValue
+{
+	//{{{
+	// Numeric value for expressions: booleans are 0 or 1, texts 0
+	pub fn as_f64(&self) -> f64
+	{
+		match self
+		{
+			Self::Number(value)  => *value,
+			Self::Integer(value) => *value as f64,
+			Self::Bool(value)    => if *value { 1.0 } else { 0.0 },
+			Self::Text(_)        => 0.0,
+		}
+	}
+	//}}}
+	//{{{
+	pub fn kind(&self) -> &'static str
+	{
+		match self
+		{
+			Self::Number(_)  => "number",
+			Self::Integer(_) => "integer",
+			Self::Bool(_)    => "boolean",
+			Self::Text(_)    => "text",
+		}
+	}
+	//}}}
+}
+//}}}
+
+//{{{
+impl fmt::Display for ParameterValue
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+	{
+		match self
+		{
+			Self::Number(value)  => write!(f, "{}", value),
+			Self::Integer(value) => write!(f, "{}", value),
+			Self::Bool(value)    => write!(f, "{}", value),
+			Self::Text(value)    => write!(f, "\"{}\"", crate::escape_scad_string(value)),
+		}
+	}
+}
+//}}}
+//}}}
+
+//{{{ pub enum ParameterError
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParameterError
+{
+	Io{ path: String, message: String },
+	Syntax{ path: String, line: usize, message: String },
+	Unknown(String),
+	Type{ name: String, expected: &'static str, value: String },
+	Range{ name: String, value: f64, min: f64, max: f64 },
+	Name(String), // Not usable as an OpenSCad variable
+	Usage(String),
+}
+
+//{{{
+impl fmt::Display for ParameterError
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+	{
+		match self
+		{
+			Self::Io{path, message}             => write!(f, "{}: {}", path, message),
+			Self::Syntax{path, line, message}   => write!(f, "{}:{}: {}", path, line, message),
+			Self::Unknown(name)                 => write!(f, "unknown parameter {}", name),
+			Self::Type{name, expected, value}   => write!(f, "parameter {} expects a {}, not {}", name, expected, value),
+			Self::Range{name, value, min, max}  => write!(f, "parameter {} = {} is outside of its range [{}, {}]", name, value, min, max),
+			Self::Name(name)                    => write!(f, "\"{}\" can not be used as an OpenSCad variable name", name),
+			Self::Usage(message)                => write!(f, "{}", message),
+		}
+	}
+}
+//}}}
+
+impl std::error::Error for ParameterError {}
+//}}}
+
+//{{{ pub struct Parameter
+
+#[derive(Debug)]
+pub struct Parameter
+{
+	pub name        : String,
+	pub default     : ParameterValue,
+	pub value       : ParameterValue,
+	pub range       : Option<(f64, f64, f64)>, // min, step, max
+	pub unit        : String,
+	pub description : String,
+	pub group       : String,                  // Customizer tab
+	shared          : SharedValue,             // The value for expressions
+}
+
+//{{{
+// Clones are independent of the original, expressions taken from one don't follow the other
+impl Clone for Parameter
+{
+	fn clone(&self) -> Self
+	{
+		Self
+		{
+			name        : self.name.clone(),
+			default     : self.default.clone(),
+			value       : self.value.clone(),
+			range       : self.range,
+			unit        : self.unit.clone(),
+			description : self.description.clone(),
+			group       : self.group.clone(),
+			shared      : SharedValue::new(self.shared.get()),
+		}
+	}
+}
+//}}}
+
+//{{{
+impl Parameter
+{
+	//{{{ Constructors
+
+	//{{{
+	fn new(name: &str, default: ParameterValue) -> Self
+	{
+		Self
+		{
+			name        : String::from(name),
+			value       : default.clone(),
+			shared      : SharedValue::new(default.as_f64()),
+			default,
+			range       : None,
+			unit        : String::from(""),
+			description : String::from(""),
+			group       : String::from("Parameters"),
+		}
+	}
+	//}}}
+	//{{{
+	pub fn number(name: &str, default: f64) -> Self
+	{
+		Self::new(name, ParameterValue::Number(default))
+	}
+	//}}}
+	//{{{
+	pub fn integer(name: &str, default: i64) -> Self
+	{
+		Self::new(name, ParameterValue::Integer(default))
+	}
+	//}}}
+	//{{{
+	pub fn boolean(name: &str, default: bool) -> Self
+	{
+		Self::new(name, ParameterValue::Bool(default))
+	}
+	//}}}
+	//{{{
+	pub fn text(name: &str, default: &str) -> Self
+	{
+		Self::new(name, ParameterValue::Text(String::from(default)))
+	}
+	//}}}
+	//}}}
+
+	//{{{ Settings
+
+	//{{{
+	pub fn range(mut self, min: f64, step: f64, max: f64) -> Self
+	{
+		self.range = Some((min, step, max));
+		self
+	}
+	//}}}
+	//{{{
+	pub fn unit(mut self, unit: &str) -> Self
+	{
+		self.unit = String::from(unit);
+		self
+	}
+	//}}}
+	//{{{
+	pub fn description(mut self, description: &str) -> Self
+	{
+		self.description = String::from(description);
+		self
+	}
+	//}}}
+	//{{{
+	pub fn group(mut self, group: &str) -> Self
+	{
+		self.group = String::from(group);
+		self
+	}
+	//}}}
+	//}}}
+
+	//{{{
+	// Expression referring to the parameter. It evaluates to the value the parameter has at the time, not when it was taken.
+	pub fn expr(&self) -> Expr
+	{
+		Expr::Parameter{ name: self.name.clone(), value: self.shared.clone() }
+	}
+	//}}}
+
+	//{{{ Setting values
+
+	//{{{
+	// Sets the value, converted to the type of the parameter and checked against its range
+	pub fn set(&mut self, value: ParameterValue) -> Result<(), ParameterError>
+	{
+		let value = match (&self.default, value)
+		{
+			(ParameterValue::Number(_),  ParameterValue::Integer(value))                        => ParameterValue::Number(value as f64),
+			(ParameterValue::Integer(_), ParameterValue::Number(value)) if value.fract() == 0.0 => ParameterValue::Integer(value as i64),
+			(default, value) if default.kind() == value.kind()                                  => value,
+			(default, value)                                                                    =>
+			{
+				return Err(ParameterError::Type{ name: self.name.clone(), expected: default.kind(), value: value.to_string() });
+			}
+		};
+		self.check(&value)?;
+		self.shared.set(value.as_f64());
+		self.value = value;
+		Ok(())
+	}
+	//}}}
+	//{{{
+	// Sets the value from its text, e.g. from the command line. Texts may be given with or without quotes.
+	pub fn set_str(&mut self, text: &str) -> Result<(), ParameterError>
+	{
+		let text  = text.trim();
+		let error = || ParameterError::Type{ name: self.name.clone(), expected: self.default.kind(), value: String::from(text) };
+		let value = match self.default
+		{
+			ParameterValue::Number(_)  => ParameterValue::Number(text.parse().map_err(|_| error())?),
+			ParameterValue::Integer(_) => ParameterValue::Integer(text.parse().map_err(|_| error())?),
+			ParameterValue::Bool(_)    => match text
+			{
+				"true"  | "1" => ParameterValue::Bool(true),
+				"false" | "0" => ParameterValue::Bool(false),
+				_             => return Err(error()),
+			},
+			ParameterValue::Text(_)    =>
+			{
+				let unquoted = text.strip_prefix('"').and_then(|t| t.strip_suffix('"')).unwrap_or(text);
+				ParameterValue::Text(String::from(unquoted))
+			}
+		};
+		self.set(value)
+	}
+	//}}}
+	//{{{
+	fn check(&self, value: &ParameterValue) -> Result<(), ParameterError>
+	{
+		if let (Some((min, _, max)), ParameterValue::Number(_) | ParameterValue::Integer(_)) = (self.range, value)
+		{
+			let v = value.as_f64();
+			if !v.is_finite() || v < min - 1e-9 || v > max + 1e-9
+			{
+				return Err(ParameterError::Range{ name: self.name.clone(), value: v, min, max });
+			}
+		}
+		Ok(())
+	}
+	//}}}
+	//}}}
+}
+//}}}
+//}}}
+
+//{{{
+fn is_identifier(name: &str) -> bool
+{
+	const KEYWORDS : [&str; 13] = ["module", "function", "if", "else", "let", "assert", "echo", "for", "each", "true", "false", "undef", "include"];
+
+	let mut chars = name.chars();
+	match chars.next()
+	{
+		Some(c) if c.is_ascii_alphabetic() || c == '_' => chars.all(|c| c.is_ascii_alphanumeric() || c == '_') && !KEYWORDS.contains(&name),
+		_                                              => false,
+	}
+}
+//}}}
+
+//{{{ pub struct Parameters
+
+// Registry of the design parameters. Its Display output declares them as OpenSCad variables, with comments for the
+// Customizer, and goes at the top of the file, before the objects using them.
+#[derive(Debug, Clone, Default)]
+pub struct Parameters
+{
+	parameters : Vec<Parameter>,
+}
+
+//{{{
+impl Parameters
+{
+	//{{{
+	pub fn new() -> Self
+	{
+		Self::default()
+	}
+	//}}}
+	//{{{
+	// Registers the parameter (replacing one with the same name) and returns an expression referring to it.
+	// The name has to be usable as an OpenSCad variable: ASCII letters, digits and underscores, not starting with a digit,
+	// and no keyword.
+	pub fn add(&mut self, parameter: Parameter) -> Result<Expr, ParameterError>
+	{
+		if !is_identifier(&parameter.name) { return Err(ParameterError::Name(parameter.name)); }
+
+		let expr = parameter.expr();
+		match self.parameters.iter_mut().find(|p| p.name == parameter.name)
+		{
+			Some(existing) => *existing = parameter,
+			None           => self.parameters.push(parameter),
+		}
+		Ok(expr)
+	}
+	//}}}
+	//{{{
+	pub fn get(&self, name: &str) -> Option<&Parameter>
+	{
+		self.parameters.iter().find(|p| p.name == name)
+	}
+	//}}}
+	//{{{
+	pub fn get_mut(&mut self, name: &str) -> Option<&mut Parameter>
+	{
+		self.parameters.iter_mut().find(|p| p.name == name)
+	}
+	//}}}
+	//{{{
+	pub fn is_empty(&self) -> bool
+	{
+		self.parameters.is_empty()
+	}
+	//}}}
+	//{{{
+	pub fn iter(&self) -> std::slice::Iter<'_, Parameter>
+	{
+		self.parameters.iter()
+	}
+	//}}}
+	//{{{
+	// Expression for a registered parameter. Panics for unknown names, like indexing an Object3D's anchors.
+	pub fn expr(&self, name: &str) -> Expr
+	{
+		match self.get(name)
+		{
+			Some(parameter) => parameter.expr(),
+			None            => panic!("There is no parameter {}", name),
+		}
+	}
+	//}}}
+	//{{{
+	pub fn value(&self, name: &str) -> f64
+	{
+		self.expr(name).value()
+	}
+	//}}}
+	//{{{ Loading and overriding values
+
+	//{{{
+	pub fn set(&mut self, name: &str, value: ParameterValue) -> Result<(), ParameterError>
+	{
+		self.get_mut(name).ok_or_else(|| ParameterError::Unknown(String::from(name)))?.set(value)
+	}
+	//}}}
+	//{{{
+	// NAME=VALUE, as given to --set
+	pub fn set_assignment(&mut self, assignment: &str) -> Result<(), ParameterError>
+	{
+		let (name, value) = assignment.split_once('=').ok_or_else(|| ParameterError::Usage(format!("expected NAME=VALUE instead of {}", assignment)))?;
+		let name          = name.trim();
+		self.get_mut(name).ok_or_else(|| ParameterError::Unknown(String::from(name)))?.set_str(value)
+	}
+	//}}}
+	//{{{
+	// Checks all values against their ranges, e.g. for defaults outside of the declared range
+	pub fn validate(&self) -> Result<(), ParameterError>
+	{
+		self.parameters.iter().try_for_each(|parameter| parameter.check(&parameter.value))
+	}
+	//}}}
+	//{{{
+	pub fn load_toml(&mut self, text: &str) -> Result<(), ParameterError>
+	{
+		let values = parse_toml(text).map_err(|(line, message)| ParameterError::Syntax{ path: String::from("<toml>"), line, message })?;
+		values.into_iter().try_for_each(|(name, value)| self.set(&name, value))
+	}
+	//}}}
+	//{{{
+	pub fn load_json(&mut self, text: &str) -> Result<(), ParameterError>
+	{
+		let values = parse_json(text).map_err(|(line, message)| ParameterError::Syntax{ path: String::from("<json>"), line, message })?;
+		values.into_iter().try_for_each(|(name, value)| self.set(&name, value))
+	}
+	//}}}
+	//{{{
+	// Sets the parameters from a file. Files ending in .json are read as JSON, everything else as TOML.
+	pub fn load(&mut self, path: impl AsRef<Path>) -> Result<(), ParameterError>
+	{
+		let path = path.as_ref();
+		let name = path.display().to_string();
+		let text = std::fs::read_to_string(path).map_err(|error| ParameterError::Io{ path: name.clone(), message: error.to_string() })?;
+
+		let result = match path.extension().and_then(|extension| extension.to_str())
+		{
+			Some(extension) if extension.eq_ignore_ascii_case("json") => self.load_json(&text),
+			_                                                         => self.load_toml(&text),
+		};
+		// Report the actual file name for syntax errors
+		result.map_err(|error| match error
+		{
+			ParameterError::Syntax{line, message, ..} => ParameterError::Syntax{ path: name, line, message },
+			error                                     => error,
+		})
+	}
+	//}}}
+	//{{{
+	// Applies "--parameters FILE" and "--set NAME=VALUE" options in the given order and returns the remaining arguments.
+	pub fn apply_args<I: IntoIterator<Item=String>>(&mut self, args: I) -> Result<Vec<String>, ParameterError>
+	{
+		let mut remaining = vec![];
+		let mut args      = args.into_iter();
+		while let Some(arg) = args.next()
+		{
+			if arg == "--set" || arg == "--parameters"
+			{
+				let value = args.next().ok_or_else(|| ParameterError::Usage(format!("{} needs a value", arg)))?;
+				if arg == "--set" { self.set_assignment(&value)?; } else { self.load(&value)?; }
+			}
+			else if let Some(assignment) = arg.strip_prefix("--set=")  { self.set_assignment(assignment)?; }
+			else if let Some(path) = arg.strip_prefix("--parameters=") { self.load(path)?; }
+			else                                                       { remaining.push(arg); }
+		}
+		self.validate()?;
+		Ok(remaining)
+	}
+	//}}}
+	//{{{
+	// apply_args() for the arguments of the program. On errors, it prints the message and exits, as binaries would anyway.
+	pub fn apply_command_line(&mut self) -> Vec<String>
+	{
+		match self.apply_args(std::env::args().skip(1))
+		{
+			Ok(remaining) => remaining,
+			Err(error)    =>
+			{
+				eprintln!("error: {}", error);
+				std::process::exit(2);
+			}
+		}
+	}
+	//}}}
+	//}}}
+
+	//{{{
+	// A complete OpenSCad file: the parameter declarations followed by the object
+	pub fn scad_file(&self, object: &crate::Object3D) -> String
+	{
+		format!("{}\n{}\n", self, object)
+	}
+	//}}}
+}
+//}}}
+
+//{{{
+impl fmt::Display for Parameters
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+	{
+		let mut groups : Vec<&str> = vec![];
+		for parameter in &self.parameters
+		{
+			if !groups.contains(&parameter.group.as_str()) { groups.push(&parameter.group); }
+		}
+
+		for group in groups
+		{
+			writeln!(f, "/* [{}] */", group)?;
+			for parameter in self.parameters.iter().filter(|p| p.group == group)
+			{
+				let unit = if parameter.unit.is_empty() { String::from("") } else { format!(" [{}]", parameter.unit) };
+				if !parameter.description.is_empty() || !unit.is_empty()
+				{
+					writeln!(f, "// {}", format!("{}{}", parameter.description, unit).trim_start())?;
+				}
+
+				let range = match parameter.range
+				{
+					Some((min, step, max)) => format!(" // [{}:{}:{}]", min, step, max),
+					None                   => String::from(""),
+				};
+				writeln!(f, "{} = {};{}", parameter.name, parameter.value, range)?;
+			}
+			writeln!(f)?;
+		}
+
+		// Everything after this is hidden from the Customizer
+		writeln!(f, "/* [Hidden] */")
+	}
+}
+//}}}
+//}}}
+
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::mesh::Mesh;
+	use crate::object_3d::cube;
+
+	//{{{
+	fn args(args: &[&str]) -> Vec<String>
+	{
+		args.iter().map(|&arg| String::from(arg)).collect()
+	}
+	//}}}
+
+	//{{{
+	#[test]
+	fn expressions()
+	{
+		let mut parameters = Parameters::new();
+		let width = parameters.add(Parameter::number("width", 20.0)).unwrap();
+
+		// Constants are folded, so they come out as plain numbers
+		assert_eq!(Expr::from(2.0)*3.0 + 1.0, Expr::Number(7.0));
+		assert_eq!(-Expr::from(2.0), Expr::Number(-2.0));
+		assert_eq!(10.0/Expr::from(4.0), Expr::Number(2.5));
+		assert!(!(width.clone() + 0.0).is_constant());
+
+		let expr = 2.0*width.clone() + (Expr::from(3.0) - 1.0);
+		assert_eq!(expr.to_string(), "((2 * width) + 2)");
+		assert_eq!(expr.value(), 42.0);
+		assert_eq!((-width.clone()).to_string(), "-(width)");
+		assert_eq!((width.clone()/(width.clone() - 10.0)).to_string(), "(width / (width - 10))");
+		assert_eq!((width.clone()/(width - 10.0)).value(), 2.0);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn late_values()
+	{
+		// Expressions taken before the values are set evaluate to the new values, in meshes too
+		let mut parameters = Parameters::new();
+		let width = parameters.add(Parameter::number("width", 20.0).range(10.0, 1.0, 50.0)).unwrap();
+		let depth = parameters.expr("width")*2.0;
+		let panel = cube("panel", width.clone(), depth.clone(), 3.0);
+
+		assert_eq!(parameters.apply_args(args(&["--set", "width=30"])), Ok(vec![]));
+		assert_eq!((width.value(), depth.value()), (30.0, 60.0));
+		let (low, high) = Mesh::from_object(&panel).expect("solid").bounding_box().expect("not empty");
+		assert_eq!((high[0] - low[0], high[1] - low[1]), (30.0, 60.0));
+
+		// A clone has values of its own
+		let mut copy = parameters.clone();
+		copy.set("width", ParameterValue::Number(40.0)).unwrap();
+		assert_eq!((width.value(), parameters.value("width"), copy.value("width")), (30.0, 30.0, 40.0));
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn names()
+	{
+		let mut parameters = Parameters::new();
+		for name in ["width", "_width", "MATTRESS_WIDTH", "panel2"]
+		{
+			assert!(parameters.add(Parameter::number(name, 1.0)).is_ok(), "{} is rejected", name);
+		}
+		for name in ["", "2nd_panel", "$fn", "panel width", "width-2", "höhe", "module", "true"]
+		{
+			assert_eq!(parameters.add(Parameter::number(name, 1.0)), Err(ParameterError::Name(String::from(name))));
+		}
+		assert_eq!(parameters.iter().count(), 4);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn declarations()
+	{
+		// Grouped in order of first appearance, with the description and unit above and the range after the value
+		let mut parameters = Parameters::new();
+		parameters.add(Parameter::number("width", 20.5).range(10.0, 0.5, 50.0).unit("mm").description("Panel width")).unwrap();
+		parameters.add(Parameter::boolean("motor", true).group("Motor")).unwrap();
+		parameters.add(Parameter::integer("slats", 28).description("Slats")).unwrap();
+		parameters.add(Parameter::text("name", "Bett \"L\"")).unwrap();
+		parameters.add(Parameter::integer("speed", 3).range(1.0, 1.0, 5.0).unit("rpm").group("Motor")).unwrap();
+
+		assert_eq!(parameters.to_string(), "\
+			/* [Parameters] */\n\
+			// Panel width [mm]\n\
+			width = 20.5; // [10:0.5:50]\n\
+			// Slats\n\
+			slats = 28;\n\
+			name = \"Bett \\\"L\\\"\";\n\
+			\n\
+			/* [Motor] */\n\
+			motor = true;\n\
+			// [rpm]\n\
+			speed = 3; // [1:1:5]\n\
+			\n\
+			/* [Hidden] */\n");
+
+		// Replacing a parameter keeps its place
+		parameters.add(Parameter::integer("slats", 30)).unwrap();
+		assert!(parameters.to_string().contains("width = 20.5; // [10:0.5:50]\nslats = 30;\nname"));
+		assert_eq!(Parameters::new().to_string(), "/* [Hidden] */\n");
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn checks()
+	{
+		let mut parameters = Parameters::new();
+		parameters.add(Parameter::number("width", 140.0).range(80.0, 10.0, 200.0)).unwrap();
+		parameters.add(Parameter::integer("slats", 28).range(10.0, 1.0, 40.0)).unwrap();
+		parameters.add(Parameter::boolean("motor", true)).unwrap();
+
+		// The ends of the ranges are included, integers take whole numbers only
+		assert_eq!(parameters.apply_args(args(&["--set", "width=200", "--set=slats=10", "--set", "motor=0"])), Ok(vec![]));
+		assert_eq!((parameters.value("width"), parameters.value("slats"), parameters.value("motor")), (200.0, 10.0, 0.0));
+		assert!(parameters.set("slats", ParameterValue::Number(12.0)).is_ok());
+		assert_eq!(parameters.get("slats").map(|p| p.value.clone()), Some(ParameterValue::Integer(12)));
+
+		let error = |set: &str| parameters.clone().apply_args(args(&["--set", set])).expect_err(set);
+		assert_eq!(error("width=200.5"),  ParameterError::Range{ name: String::from("width"), value: 200.5, min: 80.0, max: 200.0 });
+		assert!(matches!(error("width=nan"), ParameterError::Range{..}));
+		assert!(matches!(error("slats=9"),   ParameterError::Range{..}));
+		assert_eq!(error("slats=12.5"),   ParameterError::Type{ name: String::from("slats"), expected: "integer", value: String::from("12.5") });
+		assert!(matches!(error("width=wide"), ParameterError::Type{expected: "number", ..}));
+		assert!(matches!(error("motor=yes"),  ParameterError::Type{expected: "boolean", ..}));
+		assert_eq!(error("length=200"),   ParameterError::Unknown(String::from("length")));
+		assert!(matches!(parameters.set("motor", ParameterValue::Text(String::from("on"))), Err(ParameterError::Type{..})));
+
+		// Failed assignments keep the old value
+		assert!(parameters.apply_args(args(&["--set", "width=250"])).is_err());
+		assert_eq!(parameters.value("width"), 200.0);
+
+		// Defaults are checked too
+		parameters.add(Parameter::number("height", 50.0).range(0.0, 1.0, 40.0)).unwrap();
+		assert!(matches!(parameters.apply_args(vec![]), Err(ParameterError::Range{name, ..}) if name == "height"));
+	}
+	//}}}
+}
+//}}}
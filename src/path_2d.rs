@@ -0,0 +1,550 @@
+use std::f64::consts::PI;
+
+use crate::math::{Point2D, point2D, IsCurve, CubicBezier};
+use crate::object_3d::{Object3D, polygon_paths};
+
+//{{{ pub enum ArcDirection
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArcDirection
+{
+	Clockwise,
+	CounterClockwise,
+}
+//}}}
+
+//{{{ pub struct Path2D
+
+// Builds (possibly multiple) closed outlines from lines, arcs and bezier curves.
+// Curves are flattened as they are added, so that no segment deviates more than 'tolerance' from the true curve.
+// Every move_to() starts a new sub-path. All sub-paths end up in the same polygon, so inner ones become holes.
+#[derive(Debug, Clone)]
+pub struct Path2D
+{
+	paths     : Vec<Vec<Point2D>>,
+	closed    : bool,
+	tolerance : f64,
+}
+
+//{{{
+impl Default for Path2D
+{
+	fn default() -> Self
+	{
+		Self::new()
+	}
+}
+//}}}
+
+//{{{
+impl Path2D
+{
+	//{{{
+	pub fn new() -> Self
+	{
+		Self
+		{
+			paths     : vec![],
+			closed    : true,
+			tolerance : 0.01,
+		}
+	}
+	//}}}
+
+	//{{{ Segment tolerance
+
+	// The maximum distance between a curve and the line segments approximating it.
+	// Only affects segments that are added after the call.
+	pub fn tolerance(mut self, tolerance: f64) -> Self
+	{
+		self.tolerance = tolerance.abs().max(1e-9);
+		self
+	}
+
+	pub fn get_tolerance(&self) -> f64
+	{
+		self.tolerance
+	}
+	//}}}
+
+	//{{{ Helpers
+
+	//{{{
+	fn current(&self) -> Option<Point2D>
+	{
+		if self.closed { return None; }
+		self.paths.last().and_then(|path| path.last()).copied()
+	}
+	//}}}
+	//{{{
+	fn push(&mut self, point: Point2D)
+	{
+		if self.closed
+		{
+			self.paths.push(vec![point]);
+			self.closed = false;
+			return;
+		}
+
+		let path = self.paths.last_mut().expect("an open path always has a sub-path");
+		if let Some(last) = path.last()
+		{
+			if (last[0]-point[0]).abs() < 1e-12 && (last[1]-point[1]).abs() < 1e-12 { return; }
+		}
+		path.push(point);
+	}
+	//}}}
+	//{{{
+	// Number of segments needed so that the sagitta of each segment stays below the tolerance
+	fn arc_segments(&self, radius: f64, angle: f64) -> usize
+	{
+		let step = if self.tolerance >= radius { 0.5*PI } else { 2.0*(1.0-self.tolerance/radius).acos() };
+		((angle.abs()/step).ceil() as usize).max(1)
+	}
+	//}}}
+	//}}}
+
+	//{{{ Drawing
+
+	//{{{
+	pub fn move_to(mut self, point: Point2D) -> Self
+	{
+		self.close_current();
+		self.push(point);
+		self
+	}
+	//}}}
+	//{{{
+	pub fn line_to(mut self, point: Point2D) -> Self
+	{
+		self.push(point);
+		self
+	}
+	//}}}
+	//{{{
+	// Line relative to the pen. As in SVG, the pen is back at the start of an outline after close(), and at the origin on
+	// an empty path; a line from there starts a new sub-path.
+	pub fn line_by(self, dx: f64, dy: f64) -> Self
+	{
+		let pen = match self.current()
+		{
+			Some(point) => return self.line_to(point2D(point[0]+dx, point[1]+dy)),
+			None        => self.paths.last().and_then(|path| path.first()).copied().unwrap_or_else(|| point2D(0.0, 0.0)),
+		};
+		self.move_to(pen).line_to(point2D(pen[0]+dx, pen[1]+dy))
+	}
+	//}}}
+
+	//{{{
+	// Arc around 'centre' from the current point to 'end'.
+	// If the current point and 'end' have a different distance to 'centre', the radius changes linearly along the arc.
+	pub fn arc_to_centre(mut self, end: Point2D, centre: Point2D, direction: ArcDirection) -> Self
+	{
+		let start = match self.current()
+		{
+			Some(point) => point,
+			None        => return self.move_to(end),
+		};
+
+		let (sx, sy) = (start[0]-centre[0], start[1]-centre[1]);
+		let (ex, ey) = (  end[0]-centre[0],   end[1]-centre[1]);
+
+		let start_radius = sx.hypot(sy);
+		let   end_radius = ex.hypot(ey);
+		let start_angle  = sy.atan2(sx);
+		let   end_angle  = ey.atan2(ex);
+
+		let mut sweep = end_angle - start_angle;
+		match direction
+		{
+			ArcDirection::CounterClockwise => { while sweep <= 0.0 { sweep += 2.0*PI; } },
+			ArcDirection::Clockwise        => { while sweep >= 0.0 { sweep -= 2.0*PI; } },
+		}
+
+		let steps = self.arc_segments(start_radius.max(end_radius), sweep);
+		for i in 1..steps
+		{
+			let t   = (i as f64)/(steps as f64);
+			let phi = start_angle  + t*sweep;
+			let r   = start_radius + t*(end_radius-start_radius);
+			self.push(point2D(centre[0] + r*phi.cos(), centre[1] + r*phi.sin()));
+		}
+		self.push(end);
+		self
+	}
+	//}}}
+	//{{{
+	// Arc with the given radius from the current point to 'end'. Like in SVG, 'large_arc' selects which of the two possible arcs is drawn.
+	// If the radius is too small to reach 'end', it is enlarged to half the distance.
+	pub fn arc_to_radius(self, end: Point2D, radius: f64, direction: ArcDirection, large_arc: bool) -> Self
+	{
+		let start = match self.current()
+		{
+			Some(point) => point,
+			None        => return self.move_to(end),
+		};
+
+		let (dx, dy)    = (end[0]-start[0], end[1]-start[1]);
+		let chord       = dx.hypot(dy);
+		if chord < 1e-12 { return self; }
+
+		let half_chord  = 0.5*chord;
+		let radius      = radius.abs().max(half_chord);
+		let height      = (radius*radius - half_chord*half_chord).max(0.0).sqrt();
+
+		// The centre of a short counter-clockwise arc lies left of the chord
+		let left        = (direction == ArcDirection::CounterClockwise) != large_arc;
+		let sign        = if left { 1.0 } else { -1.0 };
+		let centre      = point2D(start[0] + 0.5*dx - sign*height*dy/chord,
+		                          start[1] + 0.5*dy + sign*height*dx/chord);
+
+		self.arc_to_centre(end, centre, direction)
+	}
+	//}}}
+	//{{{
+	// Arc from the current point through 'via' to 'end'. Degenerates to a line if the three points are collinear.
+	pub fn arc_through(self, via: Point2D, end: Point2D) -> Self
+	{
+		let start = match self.current()
+		{
+			Some(point) => point,
+			None        => return self.move_to(end),
+		};
+
+		let (ax, ay) = (start[0], start[1]);
+		let (bx, by) = (  via[0],   via[1]);
+		let (cx, cy) = (  end[0],   end[1]);
+
+		let d = 2.0*(ax*(by-cy) + bx*(cy-ay) + cx*(ay-by));
+		if d.abs() < 1e-12 { return self.line_to(end); }
+
+		let a2 = ax*ax + ay*ay;
+		let b2 = bx*bx + by*by;
+		let c2 = cx*cx + cy*cy;
+		let centre = point2D((a2*(by-cy) + b2*(cy-ay) + c2*(ay-by))/d,
+		                     (a2*(cx-bx) + b2*(ax-cx) + c2*(bx-ax))/d);
+
+		let direction = if d > 0.0 { ArcDirection::CounterClockwise } else { ArcDirection::Clockwise };
+		self.arc_to_centre(end, centre, direction)
+	}
+	//}}}
+
+	//{{{
+	// Line towards 'corner', rounded with 'radius' into the direction of 'next'. Ends where the rounding meets the line from
+	// 'corner' to 'next', so usually a line_to(next) or another fillet_to() follows. The radius shrinks if the lines are too
+	// short for it; collinear lines get a sharp corner.
+	pub fn fillet_to(self, corner: Point2D, next: Point2D, radius: f64) -> Self
+	{
+		let start = match self.current()
+		{
+			Some(point) => point,
+			None        => return self.move_to(corner),
+		};
+
+		let (ax, ay) = (start[0]-corner[0], start[1]-corner[1]);
+		let (bx, by) = ( next[0]-corner[0],  next[1]-corner[1]);
+		let (a, b)   = (ax.hypot(ay), bx.hypot(by));
+		if a < 1e-12 || b < 1e-12 { return self.line_to(corner); }
+
+		// Half the angle between the two lines at the corner
+		let (ux, uy) = (ax/a, ay/a);
+		let (vx, vy) = (bx/b, by/b);
+		let half     = 0.5*(ux*vx + uy*vy).clamp(-1.0, 1.0).acos();
+		if !(1e-9..=0.5*PI - 1e-9).contains(&half) { return self.line_to(corner); }
+
+		let distance = (radius.abs()/half.tan()).min(a).min(b);
+		let radius   = distance*half.tan();
+		let (wx, wy) = (ux+vx, uy+vy);
+		let w        = wx.hypot(wy);
+		let centre   = point2D(corner[0] + wx/w*radius/half.sin(), corner[1] + wy/w*radius/half.sin());
+
+		// Turning left from the incoming line means a counter-clockwise arc
+		let direction = if ux*vy - uy*vx < 0.0 { ArcDirection::CounterClockwise } else { ArcDirection::Clockwise };
+		self.line_to(point2D(corner[0] + ux*distance, corner[1] + uy*distance))
+			.arc_to_centre(point2D(corner[0] + vx*distance, corner[1] + vy*distance), centre, direction)
+	}
+	//}}}
+
+	//{{{
+	// Cubic bezier curve from the current point to 'end'
+	pub fn bezier_to(self, control1: Point2D, control2: Point2D, end: Point2D) -> Self
+	{
+		let start = match self.current()
+		{
+			Some(point) => point,
+			None        => return self.move_to(end),
+		};
+
+		self.curve_to(&CubicBezier::new(start, control1, control2, end))
+	}
+	//}}}
+	//{{{
+	// Appends any curve. It should start at the current point, otherwise a straight line leads to its start.
+	pub fn curve_to(mut self, curve: &dyn IsCurve<Point2D>) -> Self
+	{
+		for point in curve.flatten(self.tolerance)
+		{
+			self.push(point);
+		}
+		self
+	}
+	//}}}
+
+	//{{{
+	pub fn close(mut self) -> Self
+	{
+		self.close_current();
+		self
+	}
+	//}}}
+	//{{{
+	fn close_current(&mut self)
+	{
+		if self.closed { return; }
+		self.closed = true;
+
+		// The polygon is closed implicitly, so drop a closing point that duplicates the first one
+		if let Some(path) = self.paths.last_mut()
+		{
+			if path.len() > 1
+			{
+				let (first, last) = (path[0], path[path.len()-1]);
+				if (first[0]-last[0]).abs() < 1e-12 && (first[1]-last[1]).abs() < 1e-12 { path.pop(); }
+			}
+		}
+	}
+	//}}}
+	//}}}
+
+	//{{{ Output
+
+	//{{{
+	pub fn points(&self) -> Vec<Point2D>
+	{
+		let mut path = self.clone();
+		path.close_current();
+		path.paths.concat()
+	}
+	//}}}
+	//{{{
+	// Point indices for each sub-path, as used by the 'paths' argument of OpenSCad's polygon()
+	pub fn paths(&self) -> Vec<Vec<i32>>
+	{
+		let mut path = self.clone();
+		path.close_current();
+
+		let mut start = 0;
+		let mut paths = vec![];
+		for sub_path in &path.paths
+		{
+			paths.push((start..start+sub_path.len() as i32).collect());
+			start += sub_path.len() as i32;
+		}
+		paths
+	}
+	//}}}
+	//{{{
+	// The outlines moved outwards by 'distance' (inwards if negative), with sharp corners. As in OpenSCad's offset(delta),
+	// holes shrink when the outline grows.
+	pub fn offset(&self, distance: f64) -> Path2D
+	{
+		let mut path = self.clone();
+		path.close_current();
+
+		let outlines = path.paths.clone();
+		for (i, outline) in path.paths.iter_mut().enumerate()
+		{
+			let n = outline.len();
+			if n < 3 { continue; }
+
+			// Outwards is to the right of a counter-clockwise outline; holes are those inside an odd number of others
+			let holes = outlines.iter().enumerate().filter(|&(j, other)| j != i && contains(other, &outline[0])).count();
+			let sign  = if (signed_area(outline) > 0.0) == (holes % 2 == 0) { 1.0 } else { -1.0 };
+
+			let normals : Vec<(f64, f64)> = (0..n).map(|k|
+			{
+				let (a, b)   = (outline[k], outline[(k+1)%n]);
+				let (dx, dy) = (b[0]-a[0], b[1]-a[1]);
+				let length   = dx.hypot(dy).max(1e-12);
+				(sign*dy/length, -sign*dx/length)
+			}).collect();
+
+			let moved : Vec<Point2D> = (0..n).map(|k|
+			{
+				// The mitred corner lies on the bisector of the two edge normals
+				let (n0, n1) = (normals[(k+n-1)%n], normals[k]);
+				let scale    = distance/(1.0 + n0.0*n1.0 + n0.1*n1.1).max(1e-6);
+				point2D(outline[k][0] + scale*(n0.0+n1.0), outline[k][1] + scale*(n0.1+n1.1))
+			}).collect();
+			*outline = moved;
+		}
+		path
+	}
+	//}}}
+	//{{{
+	pub fn polygon(&self, name: &str) -> Object3D
+	{
+		if self.paths.len() > 1
+		{
+			polygon_paths(name, self.points(), self.paths())
+		} else {
+			polygon_paths(name, self.points(), vec![])
+		}
+	}
+	//}}}
+	//}}}
+}
+//}}}
+//}}}
+
+//{{{ Helpers
+
+//{{{
+fn signed_area(outline: &[Point2D]) -> f64
+{
+	let n = outline.len();
+	0.5*(0..n).map(|i| outline[i][0]*outline[(i+1)%n][1] - outline[(i+1)%n][0]*outline[i][1]).sum::<f64>()
+}
+//}}}
+//{{{
+// Even-odd point in polygon test
+fn contains(outline: &[Point2D], point: &Point2D) -> bool
+{
+	let n = outline.len();
+	let mut inside = false;
+	for i in 0..n
+	{
+		let (a, b) = (outline[i], outline[(i+1)%n]);
+		if (a[1] > point[1]) != (b[1] > point[1]) && point[0] < a[0] + (point[1]-a[1])*(b[0]-a[0])/(b[1]-a[1])
+		{
+			inside = !inside;
+		}
+	}
+	inside
+}
+//}}}
+//}}}
+
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	//{{{
+	fn distance(a: &Point2D, b: &Point2D) -> f64
+	{
+		(a[0]-b[0]).hypot(a[1]-b[1])
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn arcs()
+	{
+		// Quarter circle around the origin: all points on the radius, sagitta within the tolerance
+		let quarter = Path2D::new().tolerance(0.01).move_to(point2D(10.0, 0.0)).arc_to_centre(point2D(0.0, 10.0), point2D(0.0, 0.0), ArcDirection::CounterClockwise).points();
+		let origin  = point2D(0.0, 0.0);
+		assert!(quarter.iter().all(|p| (distance(p, &origin) - 10.0).abs() < 1e-9));
+		assert!(quarter.iter().all(|p| p[0] > -1e-9 && p[1] > -1e-9));
+		let step = 2.0*(1.0 - 0.01/10.0_f64).acos();
+		assert_eq!(quarter.len(), 1 + (0.5*PI/step).ceil() as usize);
+
+		// The same end points by radius: the short arc is the quarter circle, the large one goes three quarters around (10, 10)
+		let short = Path2D::new().move_to(point2D(10.0, 0.0)).arc_to_radius(point2D(0.0, 10.0), 10.0, ArcDirection::CounterClockwise, false).points();
+		let large = Path2D::new().move_to(point2D(10.0, 0.0)).arc_to_radius(point2D(0.0, 10.0), 10.0, ArcDirection::CounterClockwise, true).points();
+		assert!(short.iter().all(|p| (distance(p, &origin) - 10.0).abs() < 1e-9));
+		assert!(large.iter().all(|p| (distance(p, &point2D(10.0, 10.0)) - 10.0).abs() < 1e-9));
+		assert!(large.iter().any(|p| p[0] > 19.0) && large.iter().any(|p| p[1] > 19.0));
+
+		// Through three points on a circle around (5, 5); collinear points give a line
+		let through = Path2D::new().move_to(point2D(0.0, 5.0)).arc_through(point2D(5.0, 10.0), point2D(10.0, 5.0)).points();
+		assert!(through.iter().all(|p| (distance(p, &point2D(5.0, 5.0)) - 5.0).abs() < 1e-9));
+		let line = Path2D::new().move_to(point2D(0.0, 0.0)).arc_through(point2D(1.0, 1.0), point2D(2.0, 2.0)).points();
+		assert_eq!(line.len(), 2);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn fillets()
+	{
+		// Square with rounded corners: the tangent points lie on the edges, the arcs on the radius around the inset centres
+		let path = Path2D::new()
+			.move_to(point2D(5.0, 0.0))
+			.fillet_to(point2D(20.0, 0.0), point2D(20.0, 20.0), 5.0)
+			.fillet_to(point2D(20.0, 20.0), point2D(0.0, 20.0), 5.0)
+			.fillet_to(point2D(0.0, 20.0), point2D(0.0, 0.0), 5.0)
+			.fillet_to(point2D(0.0, 0.0), point2D(20.0, 0.0), 5.0)
+			.close();
+		let points = path.points();
+		assert!(points.iter().all(|p| p[0] > -1e-9 && p[0] < 20.0 + 1e-9 && p[1] > -1e-9 && p[1] < 20.0 + 1e-9));
+		assert!(points.iter().all(|p| distance(p, &point2D(20.0, 0.0)) > 5.0*(2.0_f64.sqrt() - 1.0) - 1e-9));
+		for corner in [point2D(15.0, 5.0), point2D(15.0, 15.0), point2D(5.0, 15.0), point2D(5.0, 5.0)]
+		{
+			let near : Vec<&Point2D> = points.iter().filter(|p| distance(p, &corner) < 5.0 + 1e-9).collect();
+			assert!(near.len() > 2);
+			assert!(near.iter().all(|p| (distance(p, &corner) - 5.0).abs() < 1e-9));
+		}
+		assert!(signed_area(&points) > 0.0);
+
+		// Too short for the radius: it shrinks; a straight line keeps its corner
+		let short = Path2D::new().move_to(point2D(0.0, 0.0)).fillet_to(point2D(2.0, 0.0), point2D(2.0, 2.0), 10.0).points();
+		assert!(distance(short.last().expect("not empty"), &point2D(2.0, 2.0)) < 1e-9);
+		let straight = Path2D::new().move_to(point2D(0.0, 0.0)).fillet_to(point2D(2.0, 0.0), point2D(4.0, 0.0), 1.0).points();
+		assert_eq!(straight.len(), 2);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn offsets()
+	{
+		// Clockwise square with a counter-clockwise hole: the outline grows and the hole shrinks, whatever the orientation
+		let path = Path2D::new()
+			.move_to(point2D(0.0, 0.0)).line_to(point2D(0.0, 10.0)).line_to(point2D(10.0, 10.0)).line_to(point2D(10.0, 0.0))
+			.move_to(point2D(3.0, 3.0)).line_to(point2D(7.0, 3.0)).line_to(point2D(7.0, 7.0)).line_to(point2D(3.0, 7.0))
+			.close();
+		let grown = path.offset(1.0);
+		assert_eq!(grown.paths(), path.paths());
+		let points = grown.points();
+		assert!(distance(&points[0], &point2D(-1.0, -1.0)) < 1e-9);
+		assert!(distance(&points[2], &point2D(11.0, 11.0)) < 1e-9);
+		assert!(distance(&points[4], &point2D( 4.0,  4.0)) < 1e-9);
+		assert!(distance(&points[6], &point2D( 6.0,  6.0)) < 1e-9);
+
+		let shrunk = path.offset(-1.0).points();
+		assert!(distance(&shrunk[0], &point2D(1.0, 1.0)) < 1e-9);
+		assert!(distance(&shrunk[4], &point2D(2.0, 2.0)) < 1e-9);
+
+		// Arcs keep their shape: a circle grows by the distance
+		let circle = Path2D::new().move_to(point2D(5.0, 0.0)).arc_to_centre(point2D(5.0, 0.0), point2D(0.0, 0.0), ArcDirection::CounterClockwise).close();
+		let area = signed_area(&circle.offset(1.0).points());
+		assert!((area - PI*36.0).abs() < 0.05*PI*36.0);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn sub_paths()
+	{
+		let path = Path2D::new()
+			.move_to(point2D(0.0, 0.0)).line_by(10.0, 0.0).line_by(0.0, 10.0).line_to(point2D(0.0, 0.0))
+			.move_to(point2D(2.0, 2.0)).line_by(1.0, 0.0).line_by(0.0, 1.0);
+		assert_eq!(path.points().len(), 6);
+		assert_eq!(path.paths(), vec![vec![0, 1, 2], vec![3, 4, 5]]);
+
+		// After close() relative lines start where the outline started, not at its last point or the origin
+		let points = Path2D::new()
+			.move_to(point2D(5.0, 5.0)).line_by(10.0, 0.0).line_by(0.0, 10.0).close()
+			.line_by(-1.0, 0.0).line_by(0.0, -1.0).points();
+		assert_eq!(points.len(), 6);
+		assert!(distance(&points[3], &point2D(5.0, 5.0)) < 1e-12);
+		assert!(distance(&points[4], &point2D(4.0, 5.0)) < 1e-12);
+		assert!(distance(&points[5], &point2D(4.0, 4.0)) < 1e-12);
+
+		// On an empty path they start at the origin
+		let points = Path2D::new().line_by(1.0, 2.0).points();
+		assert_eq!(points.len(), 2);
+		assert!(distance(&points[1], &point2D(1.0, 2.0)) < 1e-12);
+	}
+	//}}}
+}
+//}}}
@@ -0,0 +1,351 @@
+// A design as a set of named top level parts, written as one OpenSCad file per part plus an assembly:
+//
+//     out/Sideboard_L.scad    module Sideboard_L() { ... } and a call of it, so that the file renders the part alone
+//     out/assembly.scad       use <Sideboard_L.scad> ... and every part placed by its ref_sys
+//
+// The part modules are built in the part's own coordinate system; the assembly applies the ref_sys. With lay_flat, the
+// part files place their part for printing: turned onto its largest flat side and moved to the origin on the XY-plane.
+// The assembly is not affected by that.
+//
+// Every part file declares the design parameters, so that each part can be customised on its own; the assembly only
+// places the parts. Part names that end up as the same file or module name (e.g. "Leg L" and "Leg_L") are reported by
+// write() before anything is written.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::cli::{self, CliError};
+use crate::math::{HasRefSys3D, Matrix3D};
+use crate::mesh::{Mesh, TessellationError};
+use crate::object_3d::{Object3D, scad_identifier};
+use crate::parameters::Parameters;
+use crate::scad_writer::{ScadWriter, scad_string};
+use crate::units::LengthUnit;
+use crate::resolution::Resolution;
+
+//{{{ pub struct Project
+
+pub struct Project
+{
+	name       : String,
+	parts      : Vec<(String, Object3D)>,
+	lay_flat   : bool,
+	unit       : LengthUnit,
+	resolution : Resolution,
+	parameters : Parameters,
+}
+
+//{{{
+impl Project
+{
+	//{{{
+	pub fn new(name: &str) -> Self
+	{
+		Self { name: String::from(name), parts: vec![], lay_flat: false, unit: LengthUnit::Millimetre, resolution: Resolution::default(), parameters: Parameters::new() }
+	}
+	//}}}
+	//{{{
+	// Adds a top level object, named after the object
+	pub fn add(&mut self, object: Object3D) -> &mut Self
+	{
+		let name = object.name.clone();
+		self.add_named(&name, object)
+	}
+	//}}}
+	//{{{
+	pub fn add_named(&mut self, name: &str, object: Object3D) -> &mut Self
+	{
+		self.parts.push((String::from(name), object));
+		self
+	}
+	//}}}
+	//{{{
+	// Part files place their part flat on the XY-plane, ready for printing
+	pub fn set_lay_flat(&mut self, lay_flat: bool) -> &mut Self
+	{
+		self.lay_flat = lay_flat;
+		self
+	}
+	//}}}
+	//{{{
+	// The unit of the part files and the assembly (default: millimetres). The parts are scaled where they are placed,
+	// their modules stay in millimetres.
+	pub fn set_output_unit(&mut self, unit: LengthUnit) -> &mut Self
+	{
+		self.unit = unit;
+		self
+	}
+	//}}}
+	//{{{
+	// $fn/$fa/$fs at the top of every file, so that the parts come out the same alone and in the assembly. Also used for
+	// laying the parts flat.
+	pub fn set_resolution(&mut self, resolution: impl Into<Resolution>) -> &mut Self
+	{
+		self.resolution = resolution.into();
+		self
+	}
+	//}}}
+	//{{{
+	// The parameters the parts' expressions refer to, with their values already applied
+	pub fn set_parameters(&mut self, parameters: &Parameters) -> &mut Self
+	{
+		self.parameters = parameters.clone();
+		self
+	}
+	//}}}
+	//{{{
+	pub fn part_names(&self) -> Vec<&str>
+	{
+		self.parts.iter().map(|(name, _)| name.as_str()).collect()
+	}
+	//}}}
+
+	//{{{
+	// The file of one part: a module with the part in its own coordinate system and a call of it. Repeated objects of the
+	// part become modules local to the part's module (see ScadWriter::write_objects()).
+	pub fn part_scad(&self, name: &str, object: &Object3D) -> String
+	{
+		let module = scad_identifier(name);
+		let mut local = object.clone();
+		local.set_ref_sys(Matrix3D::identity());
+
+		scad_string(|writer|
+		{
+			writer.comment(&format!("{}: part {}", self.name, name))?;
+			writer.blank_line()?;
+			if !self.parameters.is_empty() { writer.parameters(&self.parameters)?; }
+			self.write_resolution(writer)?;
+			writer.open_block(&format!("module {}()", module))?;
+			writer.set_modules(true).write_objects(std::slice::from_ref(&local))?;
+			writer.close_block()?;
+			writer.blank_line()?;
+
+			let mut placement = Matrix3D::identity();
+			if self.lay_flat
+			{
+				match print_placement(&local, &self.resolution)
+				{
+					Ok(flat)    => { writer.comment("Laid flat for printing")?; placement = flat; }
+					Err(reason) => writer.comment(&format!("Not laid flat: {}", reason))?,
+				}
+			}
+			writer.transform(&self.unit.convert(&placement))?;
+			writer.statement(&format!("{}()", module))
+		})
+	}
+	//}}}
+	//{{{
+	// The assembly: uses the part files and places every part by its ref_sys
+	pub fn assembly_scad(&self) -> String
+	{
+		scad_string(|writer|
+		{
+			writer.comment(&format!("{}: assembly", self.name))?;
+			writer.blank_line()?;
+			self.write_resolution(writer)?;
+			for (name, _) in &self.parts
+			{
+				writer.line(&format!("use <{}.scad>", cli::file_name(name)))?;
+			}
+			for (name, object) in &self.parts
+			{
+				writer.blank_line()?;
+				writer.comment(name)?;
+				writer.transform(&self.unit.convert(&object.ref_sys))?;
+				writer.statement(&format!("{}()", scad_identifier(name)))?;
+			}
+			Ok(())
+		})
+	}
+	//}}}
+	//{{{
+	fn write_resolution(&self, writer: &mut ScadWriter<Vec<u8>>) -> io::Result<()>
+	{
+		if self.resolution.is_empty() { return Ok(()); }
+		writer.resolution(&self.resolution)?;
+		writer.blank_line()
+	}
+	//}}}
+	//{{{
+	// Writes <part>.scad for every part and assembly.scad into 'directory' (created if needed). Returns the files written.
+	pub fn write(&self, directory: &Path) -> Result<Vec<PathBuf>, CliError>
+	{
+		self.check_names()?;
+		let mut files = vec![];
+		for (name, object) in &self.parts
+		{
+			let path = directory.join(format!("{}.scad", cli::file_name(name)));
+			cli::write_output(Some(&path), self.part_scad(name, object).as_bytes())?;
+			files.push(path);
+		}
+		let path = directory.join("assembly.scad");
+		cli::write_output(Some(&path), self.assembly_scad().as_bytes())?;
+		files.push(path);
+		Ok(files)
+	}
+	//}}}
+	//{{{
+	// Every part needs a file and a module of its own. File names are compared ignoring case, as file systems may do.
+	fn check_names(&self) -> Result<(), CliError>
+	{
+		let file  = |name: &str| cli::file_name(name).to_lowercase();
+		let clash  = |first: &str, second: &str, name: String| CliError::NameClash{ first: String::from(first), second: String::from(second), name };
+		for (i, (first, _)) in self.parts.iter().enumerate()
+		{
+			if file(first) == "assembly" { return Err(clash(first, "assembly", String::from("assembly.scad"))); }
+			for (second, _) in &self.parts[..i]
+			{
+				if file(first) == file(second) { return Err(clash(second, first, format!("{}.scad", cli::file_name(first)))); }
+				if scad_identifier(first) == scad_identifier(second) { return Err(clash(second, first, format!("module {}()", scad_identifier(first)))); }
+			}
+		}
+		Ok(())
+	}
+	//}}}
+}
+//}}}
+//}}}
+
+//{{{ Helpers
+
+//{{{
+// Placement of a part (in its own coordinate system) for printing, see lay_flat(). 2D parts are already flat and stay as
+// they are.
+fn print_placement(object: &Object3D, resolution: &Resolution) -> Result<Matrix3D, String>
+{
+	match Mesh::from_object_with(object, resolution)
+	{
+		Ok(mesh)                        => Ok(lay_flat(&mesh)),
+		Err(TessellationError::Flat(_)) => Ok(Matrix3D::identity()),
+		Err(error)                      => Err(error.to_string()),
+	}
+}
+//}}}
+//{{{
+// Of the six sides of the mesh, the one with the largest flat area goes down, then the mesh is moved to the origin
+pub(crate) fn lay_flat(mesh: &Mesh) -> Matrix3D
+{
+	let turns : [fn(&mut Matrix3D); 6] =
+	[
+		|_| (),
+		|m| m.rotate_x(180.0),
+		|m| m.rotate_x(90.0),
+		|m| m.rotate_x(-90.0),
+		|m| m.rotate_y(90.0),
+		|m| m.rotate_y(-90.0),
+	];
+
+	let mut best : Option<(f64, Matrix3D, Mesh)> = None;
+	for turn in turns.iter()
+	{
+		let mut rotation = Matrix3D::identity();
+		turn(&mut rotation);
+		let mut turned = mesh.clone();
+		turned.transform(&rotation);
+		let area = turned.footprint_area();
+		// Only clearly larger areas win, so that parts already lying flat are not turned over
+		if best.as_ref().is_none_or(|(best_area, _, _)| area > best_area + 1e-6)
+		{
+			best = Some((area, rotation, turned));
+		}
+	}
+
+	let (_, mut placement, turned) = best.expect("there are six turns");
+	if let Some((min, _)) = turned.bounding_box()
+	{
+		placement.translate(-min[0], -min[1], -min[2]);
+	}
+	placement
+}
+//}}}
+//}}}
+
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::math::Is3DObject;
+	use crate::object_3d::cube;
+	use crate::parameters::Parameter;
+
+	//{{{
+	fn project() -> Project
+	{
+		let mut parameters = Parameters::new();
+		parameters.add(Parameter::number("height", 40.0).description("Leg height")).unwrap();
+		let height = parameters.expr("height");
+
+		let mut leg = cube("Leg L", 4.0, 4.0, height.clone());
+		leg.translate(10.0, 0.0, 20.0);
+		let mut top = cube("top", 30.0, 20.0, 2.0);
+		top.translate_z(40.0);
+
+		let mut project = Project::new("table");
+		project.add(leg).add_named("2nd top", top).set_parameters(&parameters);
+		project
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn part_and_assembly_files()
+	{
+		let project = project();
+		assert_eq!(project.part_names(), ["Leg L", "2nd top"]);
+
+		let (name, leg) = &project.parts[0];
+		let part = project.part_scad(name, leg);
+		assert!(part.starts_with("// table: part Leg L\n\n/* [Parameters] */\n// Leg height\nheight = 40;\n"));
+		assert!(part.contains("module Leg_L()"));
+		assert!(part.contains("height]"), "the part refers to the parameter: {}", part);
+		assert!(part.trim_end().ends_with("Leg_L();"));
+		assert!(!part.contains("[10, 0, 20"), "the part is in its own coordinate system: {}", part);
+
+		let assembly = project.assembly_scad();
+		assert!(assembly.contains("use <Leg_L.scad>\nuse <2nd_top.scad>\n"));
+		assert!(assembly.contains("part_2nd_top();"));
+		assert!(!assembly.contains("height = "));
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn written_files()
+	{
+		let directory = std::env::temp_dir().join(format!("rusty_scad-project-{}", std::process::id()));
+		let files     = project().write(&directory).expect("the files are written");
+		let names : Vec<String> = files.iter().map(|path| path.file_name().expect("a file").to_string_lossy().into_owned()).collect();
+		assert_eq!(names, ["Leg_L.scad", "2nd_top.scad", "assembly.scad"]);
+		assert!(std::fs::read_to_string(&files[1]).expect("written").contains("module part_2nd_top()"));
+		let _ = std::fs::remove_dir_all(&directory);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn name_clashes()
+	{
+		let directory = std::env::temp_dir().join(format!("rusty_scad-project-clash-{}", std::process::id()));
+		let clash     = |names: &[&str]|
+		{
+			let mut project = Project::new("clash");
+			for name in names { project.add(cube(name, 1.0, 1.0, 1.0)); }
+			match project.write(&directory)
+			{
+				Err(CliError::NameClash{first, second, name}) => Some((first, second, name)),
+				Err(error)                                    => panic!("{}", error),
+				Ok(_)                                         => None,
+			}
+		};
+
+		assert_eq!(clash(&["Leg L", "Leg_L"]), Some((String::from("Leg L"), String::from("Leg_L"), String::from("Leg_L.scad"))));
+		assert_eq!(clash(&["Leg_L", "leg_l"]), Some((String::from("Leg_L"), String::from("leg_l"), String::from("leg_l.scad"))));
+		assert_eq!(clash(&["Leg-L", "Leg_L"]), Some((String::from("Leg-L"), String::from("Leg_L"), String::from("module Leg_L()"))));
+		assert!(matches!(clash(&["Assembly"]), Some((_, _, name)) if name == "assembly.scad"));
+		assert!(!directory.exists(), "nothing is written");
+
+		assert_eq!(clash(&["Leg L", "Leg R"]), None);
+		let _ = std::fs::remove_dir_all(&directory);
+	}
+	//}}}
+}
+//}}}
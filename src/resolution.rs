@@ -0,0 +1,192 @@
+// How finely circles, spheres and cylinders are divided: OpenSCad's $fn, $fa and $fs. A resolution can be set for a whole
+// file (ScadWriter::set_resolution(), Project::set_resolution(), the --resolution option of the Cli) and for a subtree
+// (Object3D::set_resolution()); the innermost setting wins, and set_fn()/set_fa()/set_fs() on a primitive win over all
+// of them. The native tessellator follows the same rules, so STL and SVG output match the OpenSCad render.
+//
+//     let mut block = union("pulley", [housing, roll1, roll2]);
+//     block.set_resolution(Resolution::face_number(20));
+//
+//     cli.set_resolution(ResolutionProfile::Preview); // "--resolution draft" or "--resolution final" on the command line
+
+use std::fmt;
+use std::str::FromStr;
+
+//{{{ pub struct Resolution
+
+// The settings made, None where the surrounding one applies. As in OpenSCad, a face_number above 0 wins over the angle
+// and size, 0 switches back to them.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Resolution
+{
+	pub face_number : Option<i32>, // $fn
+	pub face_angle  : Option<f64>, // $fa
+	pub face_size   : Option<f64>, // $fs
+}
+
+//{{{
+impl Resolution
+{
+	//{{{
+	pub fn new(face_number: Option<i32>, face_angle: Option<f64>, face_size: Option<f64>) -> Self
+	{
+		Self { face_number, face_angle, face_size }
+	}
+	//}}}
+	//{{{
+	// A fixed number of fragments
+	pub fn face_number(face_number: i32) -> Self
+	{
+		Self::new(Some(face_number), None, None)
+	}
+	//}}}
+	//{{{
+	// Fragments of at most 'face_angle' degrees and 'face_size' length, whichever gives fewer. Overrides a surrounding
+	// face_number.
+	pub fn angle_and_size(face_angle: f64, face_size: f64) -> Self
+	{
+		Self::new(Some(0), Some(face_angle), Some(face_size))
+	}
+	//}}}
+
+	//{{{
+	pub fn is_empty(&self) -> bool
+	{
+		self.face_number.is_none() && self.face_angle.is_none() && self.face_size.is_none()
+	}
+	//}}}
+	//{{{
+	// These settings inside 'outer': the ones not made here come from there
+	pub fn within(&self, outer: &Resolution) -> Resolution
+	{
+		Self
+		{
+			face_number : self.face_number.or(outer.face_number),
+			face_angle  : self.face_angle.or(outer.face_angle),
+			face_size   : self.face_size.or(outer.face_size),
+		}
+	}
+	//}}}
+}
+//}}}
+//{{{
+impl From<ResolutionProfile> for Resolution
+{
+	fn from(profile: ResolutionProfile) -> Self { profile.resolution() }
+}
+//}}}
+//}}}
+
+//{{{ pub enum ResolutionProfile
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolutionProfile
+{
+	Draft,   // OpenSCad's defaults, fast to render
+	Preview,
+	Final,   // Smooth enough for printing
+}
+
+//{{{
+impl ResolutionProfile
+{
+	//{{{
+	pub fn resolution(&self) -> Resolution
+	{
+		match self
+		{
+			Self::Draft   => Resolution::angle_and_size(12.0, 2.0),
+			Self::Preview => Resolution::angle_and_size( 6.0, 1.0),
+			Self::Final   => Resolution::angle_and_size( 1.0, 0.4),
+		}
+	}
+	//}}}
+	//{{{
+	pub fn name(&self) -> &'static str
+	{
+		match self
+		{
+			Self::Draft   => "draft",
+			Self::Preview => "preview",
+			Self::Final   => "final",
+		}
+	}
+	//}}}
+}
+//}}}
+//{{{
+impl FromStr for ResolutionProfile
+{
+	type Err = String;
+
+	fn from_str(text: &str) -> Result<Self, String>
+	{
+		match text
+		{
+			"draft"   => Ok(Self::Draft),
+			"preview" => Ok(Self::Preview),
+			"final"   => Ok(Self::Final),
+			_         => Err(format!("unknown resolution {} (draft, preview or final)", text)),
+		}
+	}
+}
+//}}}
+//{{{
+impl fmt::Display for ResolutionProfile
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+	{
+		write!(f, "{}", self.name())
+	}
+}
+//}}}
+//}}}
+
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::mesh::Mesh;
+	use crate::object_3d::{cylinder, union};
+
+	//{{{
+	fn vertices(object: &crate::Object3D, resolution: impl Into<Resolution>) -> usize
+	{
+		Mesh::from_object_with(object, &resolution.into()).expect("a cylinder tessellates").vertices.len()
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn innermost_setting_wins()
+	{
+		let plain = cylinder("plain", 10.0, 10.0, 10.0);
+		assert_eq!(vertices(&plain, Resolution::default()), 2*30);            // 360°/12°
+		assert_eq!(vertices(&plain, ResolutionProfile::Final), 2*158);        // 2π·10/0.4
+		assert_eq!(vertices(&plain, Resolution::face_number(7)), 2*7);
+
+		let mut scoped = union("scoped", [plain.clone()]);
+		scoped.set_resolution(Resolution::face_number(12));
+		assert_eq!(vertices(&scoped, ResolutionProfile::Final), 2*12);
+
+		let mut own = plain.clone();
+		own.set_fn(5);
+		scoped = union("scoped", [own]);
+		scoped.set_resolution(ResolutionProfile::Draft);
+		assert_eq!(vertices(&scoped, Resolution::face_number(40)), 2*5);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn scad_scopes()
+	{
+		let mut block = union("block", [cylinder("roll", 1.0, 1.0, 1.0)]);
+		block.set_resolution(Resolution::face_number(20));
+		let code = crate::scad_string(|writer| writer.set_resolution(ResolutionProfile::Preview).write_objects(&[block]));
+		assert!(code.starts_with("$fn = 0;\n$fa = 6;\n$fs = 1;\n\n"));
+		assert!(code.contains("let($fn=20)\nunion()"));
+		assert_eq!("final".parse(), Ok(ResolutionProfile::Final));
+	}
+	//}}}
+}
+//}}}
@@ -0,0 +1,866 @@
+// Streaming OpenSCad serialiser. Objects are written statement by statement to any io::Write, so that large assemblies
+// don't have to be built up as strings first. Display of Object3D is a thin wrapper around it.
+//
+//     let mut writer = ScadWriter::new(io::BufWriter::new(file));
+//     writer.set_indentation(Indentation::Spaces(2)).set_comments(false);
+//     writer.write_objects(&[bed])?;
+//
+// Transformations and colours are chained in front of the statement they apply to, so that nesting only grows with the
+// CSG tree:
+//
+//     // Sideboard_L
+//     color("red")
+//     translate([...])
+//     rotate([...])
+//     difference()
+//     {
+//         ...
+//     }
+//
+// The compact mode writes each top level object on one line, without indentation, and comments (if enabled) as /* */.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use crate::math::{Matrix3D, Decomposition};
+use crate::object_3d::{Object3D, Shape3D};
+use crate::units::LengthUnit;
+use crate::resolution::Resolution;
+use crate::parameters::Parameters;
+
+//{{{ pub enum Indentation
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Indentation
+{
+	Tabs,
+	Spaces(usize),
+}
+//}}}
+
+//{{{ pub struct ScadWriter
+
+pub struct ScadWriter<W: Write>
+{
+	out          : W,
+	indentation  : Indentation,
+	comments     : bool,
+	compact      : bool,
+	modules      : bool,
+	level        : usize,
+	structures   : Rc<Structures>,          // During write_objects() with modules
+	module_names : HashMap<usize, String>,  // Structure id of repeated objects -> module name
+	output_unit  : LengthUnit,
+	resolution   : Resolution,
+	parameters   : Parameters,
+}
+
+//{{{
+impl<W: Write> ScadWriter<W>
+{
+	//{{{
+	pub fn new(out: W) -> Self
+	{
+		Self
+		{
+			out,
+			indentation  : Indentation::Tabs,
+			comments     : true,
+			compact      : false,
+			modules      : false,
+			level        : 0,
+			structures   : Rc::default(),
+			module_names : HashMap::new(),
+			output_unit  : LengthUnit::Millimetre,
+			resolution   : Resolution::default(),
+			parameters   : Parameters::new(),
+		}
+	}
+	//}}}
+	//{{{
+	pub fn into_inner(self) -> W
+	{
+		self.out
+	}
+	//}}}
+
+	//{{{ Options
+
+	//{{{
+	pub fn set_indentation(&mut self, indentation: Indentation) -> &mut Self
+	{
+		self.indentation = indentation;
+		self
+	}
+	//}}}
+	//{{{
+	// The object names as comments (default: on)
+	pub fn set_comments(&mut self, comments: bool) -> &mut Self
+	{
+		self.comments = comments;
+		self
+	}
+	//}}}
+	//{{{
+	pub fn set_compact(&mut self, compact: bool) -> &mut Self
+	{
+		self.compact = compact;
+		self
+	}
+	//}}}
+	//{{{
+	// Structurally repeated objects as modules, see write_objects() (default: off)
+	pub fn set_modules(&mut self, modules: bool) -> &mut Self
+	{
+		self.modules = modules;
+		self
+	}
+	//}}}
+	//{{{
+	// The unit of the written coordinates (default: millimetres). The top level objects of write_objects() are scaled
+	// into it; their insides and the modules stay in millimetres.
+	pub fn set_output_unit(&mut self, unit: LengthUnit) -> &mut Self
+	{
+		self.output_unit = unit;
+		self
+	}
+	//}}}
+	//{{{
+	// $fn/$fa/$fs for the whole file, written once in front of the objects by write_objects() (default: none, OpenSCad's
+	// defaults apply)
+	pub fn set_resolution(&mut self, resolution: impl Into<Resolution>) -> &mut Self
+	{
+		self.resolution = resolution.into();
+		self
+	}
+	//}}}
+	//{{{
+	// The design parameters the objects' expressions refer to, declared for the Customizer at the top of the file by
+	// write_objects() (default: none)
+	pub fn set_parameters(&mut self, parameters: &Parameters) -> &mut Self
+	{
+		self.parameters = parameters.clone();
+		self
+	}
+	//}}}
+	//{{{
+	// Indentation level to start at
+	pub fn set_level(&mut self, level: usize) -> &mut Self
+	{
+		self.level = level;
+		self
+	}
+	//}}}
+	//}}}
+
+	//{{{ Statements
+
+	//{{{
+	// A line as it is, e.g. include <...>
+	pub fn line(&mut self, text: &str) -> io::Result<()>
+	{
+		self.write_indentation()?;
+		self.out.write_all(text.as_bytes())?;
+		self.end_line()
+	}
+	//}}}
+	//{{{
+	pub fn blank_line(&mut self) -> io::Result<()>
+	{
+		if self.compact { return Ok(()); }
+		self.out.write_all(b"\n")
+	}
+	//}}}
+	//{{{
+	pub fn comment(&mut self, text: &str) -> io::Result<()>
+	{
+		if !self.comments { return Ok(()); }
+		let text = text.replace(['\n', '\r'], " ");
+		if self.compact { return write!(self.out, "/*{}*/", text.replace("*/", "* /")); }
+		self.write_indentation()?;
+		writeln!(self.out, "// {}", text)
+	}
+	//}}}
+	//{{{
+	// A call without children, e.g. cube(...): writes "code;"
+	pub fn statement(&mut self, code: &str) -> io::Result<()>
+	{
+		self.write_indentation()?;
+		write!(self.out, "{};", code)?;
+		self.end_line()
+	}
+	//}}}
+	//{{{
+	// An operator applied to the following statement or block, e.g. color(...)
+	pub fn header(&mut self, code: &str) -> io::Result<()>
+	{
+		self.write_indentation()?;
+		self.out.write_all(code.as_bytes())?;
+		if self.compact { Ok(()) } else { self.out.write_all(b"\n") }
+	}
+	//}}}
+	//{{{
+	// The transformation of the following statement or block: translate(), rotate(), mirror() and scale() where the matrix
+	// decomposes into them (identities are left out), multmatrix() for shears
+	pub fn transform(&mut self, matrix: &Matrix3D) -> io::Result<()>
+	{
+		match matrix.decompose()
+		{
+			Some(decomposition) if !decomposition.has_shear() => self.decomposed_transform(&decomposition),
+			_                                                  => self.multmatrix(matrix),
+		}
+	}
+	//}}}
+	//{{{
+	fn decomposed_transform(&mut self, decomposition: &Decomposition) -> io::Result<()>
+	{
+		let (x, y, z) = decomposition.translation;
+		self.vector_operator("translate", [x, y, z], "0")?;
+		let (x, y, z) = decomposition.euler_angles();
+		self.vector_operator("rotate", [x, y, z], "0")?;
+		if decomposition.mirror { self.vector_operator("mirror", [1.0, 0.0, 0.0], "")?; }
+		let (x, y, z) = decomposition.scale;
+		self.vector_operator("scale", [x, y, z], "1")
+	}
+	//}}}
+	//{{{
+	// "operator([x, y, z])", unless all components are 'identity'
+	fn vector_operator(&mut self, operator: &str, vector: [f64; 3], identity: &str) -> io::Result<()>
+	{
+		let components : Vec<String> = vector.iter().map(|&x| number(x)).collect();
+		if components.iter().all(|component| component == identity) { return Ok(()); }
+		let separator = if self.compact { "," } else { ", " };
+		self.header(&format!("{}([{}])", operator, components.join(separator)))
+	}
+	//}}}
+	//{{{
+	fn multmatrix(&mut self, matrix: &Matrix3D) -> io::Result<()>
+	{
+		if self.compact
+		{
+			let rows : Vec<String> = (0..4).map(|i| format!("[{},{},{},{}]", number(matrix[i][0]), number(matrix[i][1]), number(matrix[i][2]), number(matrix[i][3]))).collect();
+			return write!(self.out, "multmatrix(m=[{}])", rows.join(","));
+		}
+		self.write_indentation()?;
+		writeln!(self.out, "multmatrix(m = [{:16.10?},", matrix[0])?;
+		for i in 1..4
+		{
+			self.write_indentation()?;
+			write!(self.out, "                {:16.10?}", matrix[i])?;
+			self.out.write_all(if i < 3 { b",\n" } else { b"])\n" })?;
+		}
+		Ok(())
+	}
+	//}}}
+	//{{{
+	// The declarations of the design parameters for the Customizer, followed by a blank line. Always on separate lines,
+	// the Customizer reads the comments line by line.
+	pub fn parameters(&mut self, parameters: &Parameters) -> io::Result<()>
+	{
+		write!(self.out, "{}", parameters)?;
+		self.out.write_all(b"\n")
+	}
+	//}}}
+	//{{{
+	// The settings of the resolution as assignments of the special variables, e.g. "$fa = 1;"
+	pub fn resolution(&mut self, resolution: &Resolution) -> io::Result<()>
+	{
+		let separator = if self.compact { "=" } else { " = " };
+		for (variable, value) in special_variables(resolution)
+		{
+			self.statement(&format!("{}{}{}", variable, separator, value))?;
+		}
+		Ok(())
+	}
+	//}}}
+	//{{{
+	// "header {", the following statements are inside the block until close_block(). The header may be empty.
+	pub fn open_block(&mut self, header: &str) -> io::Result<()>
+	{
+		if !header.is_empty() { self.header(header)?; }
+		self.write_indentation()?;
+		self.out.write_all(if self.compact { b"{" } else { b"{\n" })?;
+		self.level += 1;
+		Ok(())
+	}
+	//}}}
+	//{{{
+	pub fn close_block(&mut self) -> io::Result<()>
+	{
+		self.level = self.level.saturating_sub(1);
+		self.write_indentation()?;
+		self.out.write_all(b"}")?;
+		self.end_line()
+	}
+	//}}}
+	//}}}
+
+	//{{{ Objects
+
+	//{{{
+	// The parameter declarations, the resolution and the objects, separated by blank lines. With modules enabled, the
+	// repeated objects (e.g. the anchor gizmos or identical slats) are written as modules first and called at every
+	// placement.
+	pub fn write_objects(&mut self, objects: &[Object3D]) -> io::Result<()>
+	{
+		if !self.parameters.is_empty()
+		{
+			let parameters = self.parameters.clone();
+			self.parameters(&parameters)?;
+		}
+		if !self.resolution.is_empty()
+		{
+			let resolution = self.resolution;
+			self.resolution(&resolution)?;
+			self.blank_line()?;
+		}
+
+		if self.modules
+		{
+			self.structures = Rc::new(Structures::new(objects));
+			let result = self.write_modules_and_objects(objects);
+			self.structures = Rc::default();
+			self.module_names.clear();
+			result?;
+		} else {
+			self.write_top_level(objects)?;
+		}
+		self.out.flush()
+	}
+	//}}}
+	//{{{
+	// The repeated objects as modules, then the objects using them
+	fn write_modules_and_objects(&mut self, objects: &[Object3D]) -> io::Result<()>
+	{
+		let structures = Rc::clone(&self.structures);
+		let repeated   = structures.repeated(objects);
+		for (i, (id, object, _)) in repeated.iter().enumerate()
+		{
+			self.module_names.insert(*id, format!("{}_{}", crate::object_3d::scad_identifier(&object.name), i+1));
+		}
+		for (id, object, count) in &repeated
+		{
+			let name = self.module_names[id].clone();
+			self.comment(&format!("{}, used {} times", object.name, count))?;
+			self.open_block(&format!("module {}()", name))?;
+			self.write_attributes(object)?;
+			self.write_body(object)?;
+			self.close_block()?;
+			self.blank_line()?;
+		}
+		self.write_top_level(objects)
+	}
+	//}}}
+	//{{{
+	fn write_top_level(&mut self, objects: &[Object3D]) -> io::Result<()>
+	{
+		for (i, object) in objects.iter().enumerate()
+		{
+			if i > 0 { self.blank_line()?; }
+			let placement = self.output_unit.convert(&object.ref_sys);
+			self.write_placed_object(object, &placement)?;
+		}
+		Ok(())
+	}
+	//}}}
+	//{{{
+	pub fn write_object(&mut self, object: &Object3D) -> io::Result<()>
+	{
+		self.write_placed_object(object, &object.ref_sys)
+	}
+	//}}}
+	//{{{
+	// The object with 'placement' instead of its ref_sys
+	fn write_placed_object(&mut self, object: &Object3D, placement: &Matrix3D) -> io::Result<()>
+	{
+		self.comment(&object.name)?;
+		if let Some(module) = self.module(object)
+		{
+			self.transform(placement)?;
+			return self.statement(&format!("{}()", module));
+		}
+
+		self.write_attributes(object)?;
+		self.transform(placement)?;
+		self.write_body(object)
+	}
+	//}}}
+	//{{{
+	// The colour and the resolution scope of the object
+	fn write_attributes(&mut self, object: &Object3D) -> io::Result<()>
+	{
+		if let Some(colour) = object.colour.scad_call() { self.header(&colour)?; }
+		if !object.resolution.is_empty()
+		{
+			let separator = if self.compact { "," } else { ", " };
+			let variables : Vec<String> = special_variables(&object.resolution).iter().map(|(variable, value)| format!("{}={}", variable, value)).collect();
+			self.header(&format!("let({})", variables.join(separator)))?;
+		}
+		Ok(())
+	}
+	//}}}
+	//{{{
+	// Everything but the name, colour and ref_sys: modifier, shape and the gizmos shown by the custom modifiers
+	fn write_body(&mut self, object: &Object3D) -> io::Result<()>
+	{
+		let modifier   = object.scad_modifier.to_string();
+		let structures = Rc::clone(&self.structures);
+		let built;
+		let gizmos     = match structures.gizmos(object)
+		{
+			Some(gizmos) => gizmos,
+			None         => { built = object.gizmos(); built.as_slice() }
+		};
+		if gizmos.is_empty()
+		{
+			return self.write_shape(&modifier, &object.shape);
+		}
+
+		self.open_block("")?;
+		self.write_shape(&modifier, &object.shape)?;
+		for gizmo in gizmos
+		{
+			self.write_object(gizmo)?;
+		}
+		self.close_block()
+	}
+	//}}}
+	//{{{
+	fn write_shape(&mut self, modifier: &str, shape: &Shape3D) -> io::Result<()>
+	{
+		match shape
+		{
+			Shape3D::Composite{children, ..} =>
+			{
+				self.open_block(&(String::from(modifier) + &shape.scad_call()))?;
+				for child in children
+				{
+					self.write_object(child)?;
+				}
+				self.close_block()
+			}
+			Shape3D::Lextrude{child, ..}     =>
+			{
+				self.header(&(String::from(modifier) + &shape.scad_call()))?;
+				self.write_object(child)
+			}
+			_                                => self.statement(&(String::from(modifier) + &shape.scad_call())),
+		}
+	}
+	//}}}
+	//}}}
+
+	//{{{ Helpers
+
+	//{{{
+	fn module(&self, object: &Object3D) -> Option<String>
+	{
+		if self.module_names.is_empty() || !is_module_candidate(object) { return None; }
+		self.structures.id(object).and_then(|id| self.module_names.get(&id)).cloned()
+	}
+	//}}}
+	//{{{
+	fn write_indentation(&mut self) -> io::Result<()>
+	{
+		if self.compact { return Ok(()); }
+		match self.indentation
+		{
+			Indentation::Tabs           => self.out.write_all("\t".repeat(self.level).as_bytes()),
+			Indentation::Spaces(spaces) => self.out.write_all(" ".repeat(self.level*spaces).as_bytes()),
+		}
+	}
+	//}}}
+	//{{{
+	// After a statement or block: a new line, in compact mode only after top level objects
+	fn end_line(&mut self) -> io::Result<()>
+	{
+		if self.compact && self.level > 0 { return Ok(()); }
+		self.out.write_all(b"\n")
+	}
+	//}}}
+	//}}}
+}
+//}}}
+//}}}
+
+//{{{ Helpers
+
+//{{{
+// Shortest form of a number, to 10 decimals like the pretty matrices
+pub(crate) fn number(x: f64) -> String
+{
+	let text = format!("{:.10}", x);
+	let text = text.trim_end_matches('0').trim_end_matches('.');
+	if text == "-0" { String::from("0") } else { String::from(text) }
+}
+//}}}
+//{{{
+// The special variables set by the resolution, with their values
+fn special_variables(resolution: &Resolution) -> Vec<(&'static str, String)>
+{
+	let mut variables = vec![];
+	if let Some(n)  = resolution.face_number { variables.push(("$fn", n.to_string())); }
+	if let Some(fa) = resolution.face_angle  { variables.push(("$fa", number(fa))); }
+	if let Some(fs) = resolution.face_size   { variables.push(("$fs", number(fs))); }
+	variables
+}
+//}}}
+//}}}
+
+//{{{ String output
+
+//{{{
+// Runs 'write' on a writer into memory and returns the code
+pub(crate) fn scad_string(write: impl FnOnce(&mut ScadWriter<Vec<u8>>) -> io::Result<()>) -> String
+{
+	let mut writer = ScadWriter::new(vec![]);
+	write(&mut writer).expect("writing to memory does not fail");
+	String::from_utf8(writer.into_inner()).expect("the OpenSCad code is UTF-8")
+}
+//}}}
+//{{{
+// OpenSCad code of the objects, with the repeated ones as modules
+pub fn scad_with_modules(objects: &[Object3D]) -> String
+{
+	scad_string(|writer| writer.set_modules(true).write_objects(objects))
+}
+//}}}
+
+//{{{ pub(crate) struct FormatterWriter
+
+// io::Write into a fmt::Formatter, for Display. ScadWriter always writes whole strings, so the bytes are valid UTF-8.
+pub(crate) struct FormatterWriter<'a, 'b>(pub(crate) &'a mut fmt::Formatter<'b>);
+
+//{{{
+impl io::Write for FormatterWriter<'_, '_>
+{
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize>
+	{
+		let text = std::str::from_utf8(buf).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+		self.0.write_str(text).map_err(|_| io::Error::other("formatter error"))?;
+		Ok(buf.len())
+	}
+	fn flush(&mut self) -> io::Result<()>
+	{
+		Ok(())
+	}
+}
+//}}}
+//}}}
+//}}}
+
+//{{{ Modules for repeated objects
+
+//{{{ struct Structures
+
+// Structural ids of the objects of an object tree, computed once bottom up: objects get the same id if they have the
+// same structure, i.e. are the same apart from their name and ref_sys (shape, modifiers, colour, and the children with
+// their ref_sys). The objects are looked up by address, so the ids are only valid while the tree is borrowed, i.e.
+// during write_objects(). The gizmos are built once and kept, so that they keep their addresses too.
+#[derive(Default)]
+struct Structures
+{
+	ids      : HashMap<*const Object3D, usize>,
+	gizmos   : HashMap<*const Object3D, Vec<Object3D>>,
+	interned : HashMap<Structure, usize>,
+}
+
+// The object itself (colour, resolution, modifier and the SCAD call without children), and its children and gizmos as
+// (id, bits of the ref_sys)
+#[derive(PartialEq, Eq, Hash)]
+struct Structure
+{
+	call     : String,
+	children : Vec<(usize, [u64; 16])>,
+}
+
+//{{{
+impl Structures
+{
+	//{{{
+	fn new(objects: &[Object3D]) -> Self
+	{
+		let mut structures = Self::default();
+		for object in objects { structures.add(object); }
+		structures
+	}
+	//}}}
+	//{{{
+	fn add(&mut self, object: &Object3D) -> usize
+	{
+		let mut children : Vec<(usize, [u64; 16])> = match &object.shape
+		{
+			Shape3D::Composite{children, ..} => children.iter().map(|child| (self.add(child), bits(&child.ref_sys))).collect(),
+			Shape3D::Lextrude{child, ..}     => vec![(self.add(child), bits(&child.ref_sys))],
+			_                                => vec![],
+		};
+
+		// The anchor gizmos come in the order of a hash map, which doesn't matter for the shape
+		let gizmos = object.gizmos();
+		if !gizmos.is_empty()
+		{
+			let mut ids : Vec<(usize, [u64; 16])> = gizmos.iter().map(|gizmo| (self.add(gizmo), bits(&gizmo.ref_sys))).collect();
+			ids.sort_unstable();
+			children.extend(ids);
+			self.gizmos.insert(object, gizmos);
+		}
+
+		let call = format!("{}|{:?}|{}{}", object.colour.scad_call().unwrap_or_default(), special_variables(&object.resolution), object.scad_modifier, object.shape.scad_call());
+		let next = self.interned.len();
+		let id   = *self.interned.entry(Structure{ call, children }).or_insert(next);
+		self.ids.insert(object, id);
+		id
+	}
+	//}}}
+	//{{{
+	fn id(&self, object: &Object3D) -> Option<usize>
+	{
+		self.ids.get(&(object as *const Object3D)).copied()
+	}
+	//}}}
+	//{{{
+	// The gizmos built by new(), or None for objects not seen by it
+	fn gizmos(&self, object: &Object3D) -> Option<&[Object3D]>
+	{
+		self.gizmos.get(&(object as *const Object3D)).map(Vec::as_slice)
+	}
+	//}}}
+	//{{{
+	// Structurally identical objects used more than once, in the order of first use: (id, first object, count)
+	fn repeated<'a>(&'a self, objects: &'a [Object3D]) -> Vec<(usize, &'a Object3D, usize)>
+	{
+		let mut counts = HashMap::new();
+		let mut order  = vec![];
+		for object in objects
+		{
+			self.count(object, &mut counts, &mut order);
+		}
+		order.into_iter().filter_map(|(id, object)| { let count = counts[&id]; if count > 1 { Some((id, object, count)) } else { None } }).collect()
+	}
+	//}}}
+	//{{{
+	// Counts the structures of the object tree. Repeated objects are only looked into once, so that the parts only used
+	// inside a module don't get modules of their own.
+	fn count<'a>(&'a self, object: &'a Object3D, counts: &mut HashMap<usize, usize>, order: &mut Vec<(usize, &'a Object3D)>)
+	{
+		if let Some(id) = self.id(object).filter(|_| is_module_candidate(object))
+		{
+			let count = counts.entry(id).or_insert(0);
+			*count += 1;
+			if *count > 1 { return; }
+			order.push((id, object));
+		}
+
+		match &object.shape
+		{
+			Shape3D::Composite{children, ..} => for child in children { self.count(child, counts, order); },
+			Shape3D::Lextrude{child, ..}     => self.count(child, counts, order),
+			_                                => (),
+		}
+		for gizmo in self.gizmos(object).unwrap_or_default()
+		{
+			self.count(gizmo, counts, order);
+		}
+	}
+	//}}}
+}
+//}}}
+//}}}
+//{{{
+// The ref_sys for comparing, without the sign of zeros
+fn bits(matrix: &Matrix3D) -> [u64; 16]
+{
+	let mut bits = [0; 16];
+	for (i, bit) in bits.iter_mut().enumerate() { *bit = (matrix[i/4][i%4] + 0.0).to_bits(); }
+	bits
+}
+//}}}
+//{{{
+// Single primitives are shorter than a module call
+fn is_module_candidate(object: &Object3D) -> bool
+{
+	match object.shape
+	{
+		Shape3D::Composite{..} | Shape3D::Lextrude{..} | Shape3D::Polygon{..} | Shape3D::Polyhedron{..} => true,
+		_                                                                                        => object.has_gizmos(),
+	}
+}
+//}}}
+//}}}
+
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::colour::colour_named;
+	use crate::math::Is3DObject;
+	use crate::object_3d::{cube, cylinder, difference, union};
+
+	//{{{
+	// A slat of two boards, the upper one moved by 'offset'
+	fn slat(offset: f64) -> Object3D
+	{
+		let mut upper = cube("upper", 10.0, 2.0, 1.0);
+		upper.translate_z(offset);
+		union("slat", [cube("lower", 10.0, 2.0, 1.0), upper])
+	}
+	//}}}
+	//{{{
+	// A coloured plate with a hole, moved
+	fn plate() -> Object3D
+	{
+		let mut hole = cylinder("hole", 4.0, 1.5, 1.5);
+		hole.translate(2.0, 0.0, -1.0);
+		let mut plate = difference("plate", [cube("board", 10.0, 6.0, 2.0), hole]);
+		plate.set_colour(colour_named("red"));
+		plate.translate_z(5.0);
+		plate
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn golden()
+	{
+		let write = |configure: &dyn Fn(&mut ScadWriter<Vec<u8>>)| scad_string(|writer| { configure(writer); writer.write_objects(&[plate(), cube("lid", 1.0, 1.0, 1.0)]) });
+
+		assert_eq!(write(&|_| ()), "\
+// plate
+color(\"red\")
+translate([0, 0, 5])
+difference()
+{
+	// board
+	color(\"red\")
+	cube([10, 6, 2], center=true);
+	// hole
+	color(\"red\")
+	translate([2, 0, -1])
+	cylinder( h=4, r1=1.5, r2=1.5);
+}
+
+// lid
+cube([1, 1, 1], center=true);
+");
+
+		assert_eq!(write(&|writer| { writer.set_compact(true); }), "\
+/*plate*/color(\"red\")translate([0,0,5])difference(){/*board*/color(\"red\")cube([10, 6, 2], center=true);/*hole*/color(\"red\")translate([2,0,-1])cylinder( h=4, r1=1.5, r2=1.5);}
+/*lid*/cube([1, 1, 1], center=true);
+");
+
+		assert_eq!(write(&|writer| { writer.set_comments(false).set_indentation(Indentation::Spaces(2)); }), "\
+color(\"red\")
+translate([0, 0, 5])
+difference()
+{
+  color(\"red\")
+  cube([10, 6, 2], center=true);
+  color(\"red\")
+  translate([2, 0, -1])
+  cylinder( h=4, r1=1.5, r2=1.5);
+}
+
+cube([1, 1, 1], center=true);
+");
+
+		assert_eq!(write(&|writer| { writer.set_compact(true).set_comments(false); }), "\
+color(\"red\")translate([0,0,5])difference(){color(\"red\")cube([10, 6, 2], center=true);color(\"red\")translate([2,0,-1])cylinder( h=4, r1=1.5, r2=1.5);}
+cube([1, 1, 1], center=true);
+");
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn golden_modules()
+	{
+		let mut second = plate();
+		second.translate_x(20.0);
+		let code = scad_string(|writer| writer.set_modules(true).set_comments(false).write_objects(&[plate(), second, cube("lid", 1.0, 1.0, 1.0), cube("lid", 1.0, 1.0, 1.0)]));
+		assert_eq!(code, "\
+module plate_1()
+{
+	color(\"red\")
+	difference()
+	{
+		color(\"red\")
+		cube([10, 6, 2], center=true);
+		color(\"red\")
+		translate([2, 0, -1])
+		cylinder( h=4, r1=1.5, r2=1.5);
+	}
+}
+
+translate([0, 0, 5])
+plate_1();
+
+translate([20, 0, 5])
+plate_1();
+
+cube([1, 1, 1], center=true);
+
+cube([1, 1, 1], center=true);
+");
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn repeated_gizmos()
+	{
+		// Built separately, so their anchors come out of their hash maps in different orders
+		let block = |x: f64|
+		{
+			let mut block = union("block", [cube("body", 2.0, 2.0, 2.0)]);
+			for (i, name) in ["a", "b", "c", "d", "e", "f"].iter().enumerate() { block.create_anchor(name).translate_x(i as f64); }
+			block.set_show_anchors();
+			block.translate_x(x);
+			block
+		};
+		let code = scad_string(|writer| writer.set_modules(true).write_objects(&[block(0.0), block(10.0)]));
+		assert!(code.starts_with("// block, used 2 times\nmodule block_1()\n"));
+		assert_eq!(code.matches("block_1();").count(), 2);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn repeated_objects_as_modules()
+	{
+		let mut slats = vec![];
+		for i in 0..3
+		{
+			let mut slat = slat(1.0);
+			slat.translate_y(5.0*(i as f64));
+			slats.push(slat);
+		}
+		let mut moved = slat(2.0);  // Only the inner transform differs
+		moved.translate_y(20.0);
+		let mut red = slat(1.0);    // Only the colour differs
+		red.set_colour(colour_named("red"));
+		red.translate_y(25.0);
+		slats.extend([moved.clone(), red]);
+
+		let code = scad_with_modules(&[union("frame", &slats)]);
+
+		// The three identical slats share one module, placed by their ref_sys
+		assert_eq!(code.matches("module ").count(), 1);
+		assert!(code.starts_with("// slat, used 3 times\nmodule slat_1()\n{\n"));
+		assert_eq!(code.matches("slat_1();").count(), 3);
+		assert!(code.contains("\t// slat\n\ttranslate([0, 5, 0])\n\tslat_1();\n"));
+
+		// The others are written out
+		assert_eq!(code.matches("union()").count(), 1 + 1 + 2);
+		assert!(code.contains("translate([0, 0, 2])"));
+		assert!(code.contains("color(\"red\")"));
+
+		// Two of the moved slat make a second module
+		slats.push(moved);
+		let code = scad_with_modules(&[union("frame", &slats)]);
+		assert_eq!(code.matches("module ").count(), 2);
+		assert!(code.contains("module slat_2()"));
+		assert_eq!(code.matches("slat_2();").count(), 2);
+	}
+	//}}}
+}
+//}}}
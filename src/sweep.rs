@@ -0,0 +1,434 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::math::{Matrix3D, Point2D, Point3, Point3D, Vector3, Vector3D, point2D, point3D};
+use crate::object_3d::{Object3D, Shape3D, polyhedron};
+use crate::path_2d::Path2D;
+
+//{{{ pub enum SweepFrame
+
+// How the profile is oriented along the path.
+// Frenet: The profile's x-axis points to the centre of curvature. Straight parts keep the orientation of the previous curved part.
+// FixedUp: The profile's y-axis points as close as possible towards the given up vector (e.g. vector3D(0.0, 0.0, 1.0)).
+#[derive(Debug, Clone, Copy)]
+pub enum SweepFrame
+{
+	Frenet,
+	FixedUp(Vector3D),
+}
+//}}}
+
+//{{{ pub enum SweepError
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SweepError
+{
+	UnsupportedProfile(String), // An object that is not a square, circle or polygon
+	ProfileWithHoles(String),   // A polygon with more than one path
+	InvalidPoint(String, i32),  // A polygon path with an index past its points
+	ShortProfile(usize),        // Fewer than 3 points
+	ShortPath(usize),           // Fewer than 2 points
+	TooFewProfiles(usize),      // loft() needs at least 2
+}
+
+//{{{
+impl fmt::Display for SweepError
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+	{
+		match self
+		{
+			Self::UnsupportedProfile(object) => write!(f, "{} can not be used as a profile, only squares, circles and polygons can", object),
+			Self::ProfileWithHoles(object)   => write!(f, "{} has holes, a profile can only have one outline", object),
+			Self::InvalidPoint(object, i)    => write!(f, "a path of {} refers to point {}, which it doesn't have", object, i),
+			Self::ShortProfile(points)       => write!(f, "a profile needs at least 3 points, not {}", points),
+			Self::ShortPath(points)          => write!(f, "a sweep path needs at least 2 points, not {}", points),
+			Self::TooFewProfiles(profiles)   => write!(f, "a loft needs at least 2 profiles, not {}", profiles),
+		}
+	}
+}
+//}}}
+
+impl std::error::Error for SweepError {}
+//}}}
+
+//{{{ pub trait IsProfile
+
+// Anything that can be used as a 2D cross section for sweep() and loft()
+pub trait IsProfile
+{
+	fn profile_points(&self) -> Result<Vec<Point2D>, SweepError>;
+}
+
+//{{{
+impl IsProfile for [Point2D]
+{
+	fn profile_points(&self) -> Result<Vec<Point2D>, SweepError>
+	{
+		Ok(self.to_vec())
+	}
+}
+//}}}
+//{{{
+impl IsProfile for Vec<Point2D>
+{
+	fn profile_points(&self) -> Result<Vec<Point2D>, SweepError>
+	{
+		Ok(self.clone())
+	}
+}
+//}}}
+//{{{
+impl IsProfile for Path2D
+{
+	fn profile_points(&self) -> Result<Vec<Point2D>, SweepError>
+	{
+		Ok(self.points())
+	}
+}
+//}}}
+//{{{
+// Only 2D objects with a single outline (square, circle, polygon without holes) can be used. Their ref_sys is applied in
+// the XY-plane.
+impl IsProfile for Object3D
+{
+	fn profile_points(&self) -> Result<Vec<Point2D>, SweepError>
+	{
+		use std::f64::consts::PI;
+
+		let points = match &self.shape
+		{
+			Shape3D::Square{x,y}                               =>
+			{
+				let (x, y) = (x.value(), y.value());
+				vec![point2D(-0.5*x, -0.5*y), point2D(0.5*x, -0.5*y), point2D(0.5*x, 0.5*y), point2D(-0.5*x, 0.5*y)]
+			}
+			Shape3D::Circle{r,face_number,face_angle,face_size} =>
+			{
+				let steps = face_number.unwrap_or(32).max(3);
+				let r     = r.value();
+				(0..steps).map(|i| { let phi = 2.0*PI*(i as f64)/(steps as f64); point2D(r*phi.cos(), r*phi.sin()) }).collect()
+			}
+			Shape3D::Polygon{points, paths, ..}               =>
+			{
+				if paths.len() > 1 { return Err(SweepError::ProfileWithHoles(self.name.clone())); }
+				match paths.first()
+				{
+					Some(path) => path.iter().map(|&index|
+					{
+						usize::try_from(index).ok().and_then(|i| points.get(i).copied()).ok_or_else(|| SweepError::InvalidPoint(self.name.clone(), index))
+					}).collect::<Result<Vec<Point2D>, SweepError>>()?,
+					None       => points.clone(),
+				}
+			}
+			_ => return Err(SweepError::UnsupportedProfile(self.name.clone())),
+		};
+
+		Ok(points.iter().map(|point|
+		{
+			let p = self.ref_sys * point3D(point[0], point[1], 0.0);
+			point2D(p[0], p[1])
+		}).collect())
+	}
+}
+//}}}
+//}}}
+
+//{{{ Helpers
+
+//{{{
+// Scaled to length 1. Zero vectors are returned unchanged.
+fn unit(a: Vector3) -> Vector3
+{
+	a.normalize().unwrap_or(a)
+}
+//}}}
+//{{{
+// Component of 'a' perpendicular to the unit vector 'n'
+fn reject(a: Vector3, n: Vector3) -> Vector3
+{
+	a - a.dot(&n)*n
+}
+//}}}
+//{{{
+// Any unit vector perpendicular to the unit vector 'n'
+fn perpendicular(n: Vector3) -> Vector3
+{
+	let helper = if n.x.abs() < 0.9 { Vector3::unit_x() } else { Vector3::unit_y() };
+	unit(reject(helper, n))
+}
+//}}}
+
+//{{{
+// Twice the signed area. Positive for counter-clockwise outlines.
+fn signed_area(points: &[Point2D]) -> f64
+{
+	let n = points.len();
+	(0..n).map(|i| { let (a, b) = (points[i], points[(i+1)%n]); a[0]*b[1] - b[0]*a[1] }).sum()
+}
+//}}}
+//{{{
+// The profile's points, at least 3 and counter-clockwise
+fn outline<P: IsProfile + ?Sized>(profile: &P) -> Result<Vec<Point2D>, SweepError>
+{
+	let mut points = profile.profile_points()?;
+	if points.len() < 3 { return Err(SweepError::ShortProfile(points.len())); }
+	if signed_area(&points) < 0.0 { points.reverse(); }
+	Ok(points)
+}
+//}}}
+//{{{
+// Resample a closed outline to 'count' points, evenly spaced along its circumference
+fn resample(points: &[Point2D], count: usize) -> Vec<Point2D>
+{
+	let n = points.len();
+	if n == count { return points.to_vec(); }
+
+	let segment = |i: usize| { let (a, b) = (points[i], points[(i+1)%n]); (b[0]-a[0]).hypot(b[1]-a[1]) };
+	let total : f64 = (0..n).map(segment).sum();
+
+	let mut result  = vec![];
+	let mut i       = 0;
+	let mut start   = 0.0;
+	for k in 0..count
+	{
+		let target = total*(k as f64)/(count as f64);
+		while i < n-1 && start + segment(i) < target
+		{
+			start += segment(i);
+			i     += 1;
+		}
+		let (a, b) = (points[i], points[(i+1)%n]);
+		let t = if segment(i) > 0.0 { ((target-start)/segment(i)).clamp(0.0, 1.0) } else { 0.0 };
+		result.push(point2D(a[0] + t*(b[0]-a[0]), a[1] + t*(b[1]-a[1])));
+	}
+	result
+}
+//}}}
+
+//{{{
+// Connects consecutive rings of equal size. Without 'closed', the first and last ring are capped.
+// The faces are flipped if necessary, so that they are ordered clockwise when seen from outside, as OpenSCad expects.
+// Without rings, or with empty ones, the polyhedron is empty.
+pub(crate) fn rings_to_polyhedron(name: &str, rings: Vec<Vec<Point3D>>, closed: bool) -> Object3D
+{
+	let ring_count = rings.len();
+	let m          = rings.first().map_or(0, |ring| ring.len());
+	if m == 0 { return polyhedron(name, vec![], vec![]); }
+	let index      = |ring: usize, k: usize| ((ring % ring_count)*m + k % m) as i32;
+
+	let mut faces = vec![];
+	let segments  = if closed { ring_count } else { ring_count-1 };
+	for ring in 0..segments
+	{
+		for k in 0..m
+		{
+			faces.push(vec![index(ring, k), index(ring+1, k),   index(ring+1, k+1)]);
+			faces.push(vec![index(ring, k), index(ring+1, k+1), index(ring,   k+1)]);
+		}
+	}
+	if !closed
+	{
+		faces.push((0..m).map(|k| index(0, k)).collect());
+		faces.push((0..m).rev().map(|k| index(ring_count-1, k)).collect());
+	}
+
+	let points : Vec<Point3D> = rings.concat();
+
+	// Signed volume (divergence theorem over a triangle fan of every face)
+	let mut volume = 0.0;
+	for face in &faces
+	{
+		let a = points[face[0] as usize];
+		for i in 1..face.len()-1
+		{
+			let (b, c) = (points[face[i] as usize], points[face[i+1] as usize]);
+			volume += a[0]*(b[1]*c[2] - b[2]*c[1]) - a[1]*(b[0]*c[2] - b[2]*c[0]) + a[2]*(b[0]*c[1] - b[1]*c[0]);
+		}
+	}
+	if volume > 0.0
+	{
+		for face in &mut faces { face.reverse(); }
+	}
+
+	polyhedron(name, points, faces)
+}
+//}}}
+//}}}
+
+//{{{
+// Sweep a 2D profile along a path of Point3 or Point3D. If the path ends where it starts, the result is a closed ring without caps.
+pub fn sweep<P: IsProfile + ?Sized, Q: Copy + Into<Point3>>(name: &str, profile: &P, path: &[Q], frame: SweepFrame) -> Result<Object3D, SweepError>
+{
+	let profile = outline(profile)?;
+	if path.len() < 2 { return Err(SweepError::ShortPath(path.len())); }
+
+	let mut path : Vec<Point3> = path.iter().map(|&point| point.into()).collect();
+	let closed   = path.len() > 2 && path[0].distance(&path[path.len()-1]) < 1e-9;
+	if closed { path.pop(); }
+	let n = path.len();
+
+	//{{{ Tangents
+
+	let tangents : Vec<Vector3> = (0..n).map(|i|
+	{
+		let (previous, next) = if closed { ((i+n-1)%n, (i+1)%n) } else { (i.saturating_sub(1), (i+1).min(n-1)) };
+		unit(path[next] - path[previous])
+	}).collect();
+	//}}}
+
+	//{{{ Frames: profile x- and y-axis at each path point
+
+	let mut frames : Vec<(Vector3, Vector3)> = vec![];
+	for i in 0..n
+	{
+		let tangent = tangents[i];
+		let y = match frame
+		{
+			SweepFrame::FixedUp(up) =>
+			{
+				let y = reject(Vector3::from(up), tangent);
+				if y.length() > 1e-9 { unit(y) } else { frames.last().map(|f: &(Vector3, Vector3)| unit(reject(f.1, tangent))).unwrap_or_else(|| perpendicular(tangent)) }
+			}
+			SweepFrame::Frenet =>
+			{
+				let (previous, next) = if closed { ((i+n-1)%n, (i+1)%n) } else { (i.saturating_sub(1), (i+1).min(n-1)) };
+				let curvature = reject(tangents[next] - tangents[previous], tangent);
+				let normal = if curvature.length() > 1e-9
+				{
+					unit(curvature)
+				} else {
+					frames.last().map(|f: &(Vector3, Vector3)| unit(reject(f.0, tangent))).unwrap_or_else(|| perpendicular(tangent))
+				};
+				// The binormal
+				unit(tangent.cross(&normal))
+			}
+		};
+		let x = unit(y.cross(&tangent));
+		frames.push((x, y));
+	}
+	//}}}
+
+	let rings = (0..n).map(|i|
+	{
+		let (x, y) = frames[i];
+		profile.iter().map(|p| Point3D::from(path[i] + p[0]*x + p[1]*y)).collect()
+	}).collect();
+
+	Ok(rings_to_polyhedron(name, rings, closed))
+}
+//}}}
+
+//{{{
+// Skin a solid over a sequence of 2D profiles, each placed in 3D by its matrix (the profile lies in the matrix' XY-plane).
+// Profiles with a different number of points are resampled evenly along their outline, starting at their first point.
+pub fn loft<P: IsProfile>(name: &str, profiles: &[(P, Matrix3D)]) -> Result<Object3D, SweepError>
+{
+	if profiles.len() < 2 { return Err(SweepError::TooFewProfiles(profiles.len())); }
+	let outlines = profiles.iter().map(|(profile, _)| outline(profile)).collect::<Result<Vec<Vec<Point2D>>, SweepError>>()?;
+	let count    = outlines.iter().map(|outline| outline.len()).max().unwrap_or(0);
+
+	let rings = outlines.iter().zip(profiles).map(|(outline, (_, matrix))|
+	{
+		resample(outline, count).iter().map(|p| *matrix * point3D(p[0], p[1], 0.0)).collect()
+	}).collect();
+
+	Ok(rings_to_polyhedron(name, rings, false))
+}
+//}}}
+
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::mesh::Mesh;
+	use crate::object_3d::{circle, cube, polygon_paths, square};
+	use crate::math::vector3D;
+
+	//{{{
+	fn counts(object: &Object3D) -> (usize, usize)
+	{
+		match &object.shape
+		{
+			Shape3D::Polyhedron{points, faces, ..} => (points.len(), faces.len()),
+			_                                      => panic!("{} is not a polyhedron", object.name),
+		}
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn sweep_rings()
+	{
+		// A 2x2 square along a straight 10 long path: one ring per path point, two triangles per side and ring gap, two caps
+		let path = [point3D(0.0, 0.0, 0.0), point3D(0.0, 0.0, 5.0), point3D(0.0, 0.0, 10.0)];
+		let bar  = sweep("bar", &square("profile", 2.0, 2.0), &path, SweepFrame::FixedUp(vector3D(0.0, 1.0, 0.0))).expect("a valid sweep");
+		assert_eq!(counts(&bar), (3*4, 2*4*2 + 2));
+		let volume = Mesh::from_object(&bar).expect("a polyhedron tessellates").volume();
+		assert!((volume - 40.0).abs() < 1e-6);
+
+		// The same path as Point3 gives the same bar
+		let path = [Point3::new(0.0, 0.0, 0.0), Point3::new(0.0, 0.0, 5.0), Point3::new(0.0, 0.0, 10.0)];
+		let typed = sweep("bar", &square("profile", 2.0, 2.0), &path, SweepFrame::FixedUp(vector3D(0.0, 1.0, 0.0))).expect("a valid sweep");
+		assert_eq!(format!("{:?}", typed.shape), format!("{:?}", bar.shape));
+
+		// A closed square path: no caps, one ring gap more, and still outwards facing
+		let ring = [point3D(0.0, 0.0, 0.0), point3D(20.0, 0.0, 0.0), point3D(20.0, 20.0, 0.0), point3D(0.0, 20.0, 0.0), point3D(0.0, 0.0, 0.0)];
+		let profile = vec![point2D(0.0, 0.0), point2D(0.0, 1.0), point2D(1.0, 1.0), point2D(1.0, 0.0)];
+		let frame = sweep("frame", &profile, &ring, SweepFrame::Frenet).expect("a valid sweep");
+		assert_eq!(counts(&frame), (4*4, 2*4*4));
+		assert!(Mesh::from_object(&frame).expect("a polyhedron tessellates").volume() > 0.0);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn loft_rings()
+	{
+		// Square to 8 sided circle: the square is resampled to 8 points
+		let mut top = Matrix3D::identity();
+		top.translate(0.0, 0.0, 10.0);
+		let mut octagon = circle("octagon", 5.0);
+		octagon.set_fn(8);
+		let profiles = [(square("bottom", 10.0, 10.0), Matrix3D::identity()), (octagon, top)];
+		let lofted = loft("loft", &profiles).expect("a valid loft");
+		assert_eq!(counts(&lofted), (2*8, 2*8 + 2));
+		assert!(Mesh::from_object(&lofted).expect("a polyhedron tessellates").volume() > 0.0);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn degenerate_input()
+	{
+		let profile = square("profile", 1.0, 1.0);
+		let up      = SweepFrame::FixedUp(vector3D(0.0, 0.0, 1.0));
+		let empty : [Point3D; 0] = [];
+		assert_eq!(sweep("none", &profile, &empty, up).err(), Some(SweepError::ShortPath(0)));
+		assert_eq!(sweep("one", &profile, &[point3D(1.0, 2.0, 3.0)], up).err(), Some(SweepError::ShortPath(1)));
+
+		let path = [point3D(0.0, 0.0, 0.0), point3D(1.0, 0.0, 0.0)];
+		let line = vec![point2D(0.0, 0.0), point2D(1.0, 0.0)];
+		assert_eq!(sweep("line", &line, &path, up).err(), Some(SweepError::ShortProfile(2)));
+		assert_eq!(sweep("cube", &cube("box", 1.0, 1.0, 1.0), &path, up).err(), Some(SweepError::UnsupportedProfile(String::from("box"))));
+
+		// A square with a square hole, and a path past the points
+		let corners = vec![point2D(0.0, 0.0), point2D(4.0, 0.0), point2D(4.0, 4.0), point2D(0.0, 4.0),
+		                   point2D(1.0, 1.0), point2D(3.0, 1.0), point2D(3.0, 3.0), point2D(1.0, 3.0)];
+		let frame = polygon_paths("frame", corners.clone(), vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7]]);
+		assert_eq!(sweep("tube", &frame, &path, up).err(), Some(SweepError::ProfileWithHoles(String::from("frame"))));
+		assert_eq!(loft("tube", &[(frame, Matrix3D::identity()), (square("end", 4.0, 4.0), Matrix3D::identity())]).err(), Some(SweepError::ProfileWithHoles(String::from("frame"))));
+		let broken = polygon_paths("broken", corners.clone(), vec![vec![0, 1, 8]]);
+		assert_eq!(sweep("broken", &broken, &path, up).err(), Some(SweepError::InvalidPoint(String::from("broken"), 8)));
+		let negative = polygon_paths("negative", corners, vec![vec![0, -1, 2]]);
+		assert_eq!(sweep("negative", &negative, &path, up).err(), Some(SweepError::InvalidPoint(String::from("negative"), -1)));
+
+		let none : [(Vec<Point2D>, Matrix3D); 0] = [];
+		assert_eq!(loft("none", &none).err(), Some(SweepError::TooFewProfiles(0)));
+		assert_eq!(loft("one", &[(profile, Matrix3D::identity())]).err(), Some(SweepError::TooFewProfiles(1)));
+
+		assert_eq!(counts(&rings_to_polyhedron("empty", vec![], false)), (0, 0));
+		assert_eq!(counts(&rings_to_polyhedron("empty", vec![vec![], vec![]], true)), (0, 0));
+	}
+	//}}}
+}
+//}}}
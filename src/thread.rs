@@ -0,0 +1,406 @@
+// Screw threads as polyhedra, for 3D printed parts. All dimensions are in mm.
+//
+// The thread stands on the z-axis from z = 0 to its length and is right handed. external() is the threaded rod,
+// internal() the body to subtract from a part to get the matching threaded hole (it reaches slightly past both ends).
+//
+// The surface is a height field over (angle, z): every angle gets the same profile, shifted up by the helix. Its rows are
+// P/segments apart, so that every angular step shifts the profile by exactly one row and the profile vertices end up on
+// the mesh whenever they sit on a multiple of P/segments (always true for metric threads with 16 or 32 segments).
+
+use std::f64::consts::PI;
+
+use crate::math::{Point2D, Point3D, point2D, point3D};
+use crate::object_3d::Object3D;
+use crate::sweep::rings_to_polyhedron;
+
+// The internal thread reaches this far past both ends, so OpenSCad doesn't leave zero thickness skins
+const OVERSHOOT : f64 = 0.01;
+
+//{{{ pub enum ThreadForm
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThreadForm
+{
+	Metric,      // ISO 68-1, 60° flanks
+	Trapezoidal, // ISO 2904 / DIN 103, 30° flanks
+}
+//}}}
+
+//{{{ pub enum ToleranceClass
+
+// Radial play between external and internal thread. The ISO classes are far too tight for printed parts,
+// so these are typical clearances for FDM prints. Each side (external and internal) gets half of it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToleranceClass
+{
+	Exact, // The basic profile, e.g. for resin prints or to compare against the standard
+	Fine,
+	Medium,
+	Coarse,
+	Custom(f64),
+}
+
+//{{{
+impl ToleranceClass
+{
+	pub fn clearance(&self) -> f64
+	{
+		match self
+		{
+			Self::Exact             => 0.0,
+			Self::Fine              => 0.1,
+			Self::Medium            => 0.2,
+			Self::Coarse            => 0.4,
+			Self::Custom(clearance) => *clearance,
+		}
+	}
+}
+//}}}
+//}}}
+
+//{{{ pub struct Thread
+
+#[derive(Debug, Clone)]
+pub struct Thread
+{
+	form      : ThreadForm,
+	d         : f64,
+	pitch     : f64,
+	length    : f64,
+	tolerance : ToleranceClass,
+	chamfer   : f64,
+	segments  : usize,
+}
+
+//{{{
+impl Thread
+{
+	//{{{ Constructors
+
+	//{{{
+	// Metric ISO thread with the coarse pitch of the standard size, e.g. Thread::metric(8.0, 20.0) for M8. None for sizes
+	// without a standard pitch; Thread::new(ThreadForm::Metric, d, pitch, length) makes those.
+	pub fn metric(d: f64, length: f64) -> Option<Self>
+	{
+		crate::hardware::metric_size(d).map(|size| Self::new(ThreadForm::Metric, d, size.pitch, length))
+	}
+	//}}}
+	//{{{
+	// Trapezoidal thread, e.g. Thread::trapezoidal(8.0, 2.0, 100.0) for a Tr8x2 lead screw
+	pub fn trapezoidal(d: f64, pitch: f64, length: f64) -> Self
+	{
+		Self::new(ThreadForm::Trapezoidal, d, pitch, length)
+	}
+	//}}}
+	//{{{
+	pub fn new(form: ThreadForm, d: f64, pitch: f64, length: f64) -> Self
+	{
+		Self
+		{
+			form,
+			d,
+			pitch,
+			length,
+			tolerance : ToleranceClass::Medium,
+			chamfer   : 0.0,
+			segments  : 32,
+		}
+	}
+	//}}}
+	//}}}
+
+	//{{{ Settings
+
+	//{{{
+	// Fine pitch metric threads, e.g. Thread::metric(8.0, 20.0)?.pitch(1.0) for M8x1
+	pub fn pitch(mut self, pitch: f64) -> Self
+	{
+		self.pitch = pitch;
+		self
+	}
+	//}}}
+	//{{{
+	pub fn tolerance(mut self, tolerance: ToleranceClass) -> Self
+	{
+		self.tolerance = tolerance;
+		self
+	}
+	//}}}
+	//{{{
+	// Axial length of a 45° lead-in chamfer at both ends
+	pub fn chamfer(mut self, chamfer: f64) -> Self
+	{
+		self.chamfer = chamfer.max(0.0);
+		self
+	}
+	//}}}
+	//{{{
+	// Angular steps per turn
+	pub fn segments(mut self, segments: usize) -> Self
+	{
+		self.segments = segments.max(3);
+		self
+	}
+	//}}}
+	//}}}
+
+	//{{{ Profile
+
+	//{{{
+	// Trapezoidal threads: clearance at crest and root (ac in DIN 103)
+	fn crest_clearance(&self) -> f64
+	{
+		match self.pitch
+		{
+			p if p < 1.75 => 0.15,
+			p if p < 5.5  => 0.25,
+			p if p < 13.0 => 0.5,
+			_             => 1.0,
+		}
+	}
+	//}}}
+	//{{{
+	// Outer radius, thread depth, crest width and tangent of the flank angle (to the radial direction) of the basic profile
+	fn basic_profile(&self, internal: bool) -> (f64, f64, f64, f64)
+	{
+		let p = self.pitch;
+		match self.form
+		{
+			ThreadForm::Metric =>
+			{
+				// External and internal thread share the basic profile: the fundamental triangle of height H,
+				// with a crest flat of P/8 at the major diameter and a root flat of P/4 at the minor one.
+				let h = 3.0_f64.sqrt()/2.0*p;
+				(self.d/2.0, 5.0/8.0*h, p/8.0, 1.0/3.0_f64.sqrt())
+			}
+			ThreadForm::Trapezoidal =>
+			{
+				// Depth P/2 plus the crest clearance, crest width where the 15° flanks cross the major diameter:
+				// 0.366 P at d for the screw. The nut's groove reaches the crest clearance further out, to D4, where
+				// the flanks are closer together.
+				let tan   = (15.0_f64).to_radians().tan();
+				let ac    = self.crest_clearance();
+				let crest = p/2.0*(1.0-tan);
+				if internal
+				{
+					(self.d/2.0 + ac, p/2.0 + ac, crest - 2.0*ac*tan, tan)
+				} else {
+					(self.d/2.0, p/2.0 + ac, crest, tan)
+				}
+			}
+		}
+	}
+	//}}}
+	//{{{
+	// One pitch of the profile as (radius, z) points, starting at the crest, including the tolerance
+	pub fn profile(&self, internal: bool) -> Vec<Point2D>
+	{
+		let (r, depth, crest, tan) = self.basic_profile(internal);
+		let play  = if internal { self.tolerance.clearance()/2.0 } else { -self.tolerance.clearance()/2.0 };
+		let flank = depth*tan;
+
+		vec![
+			point2D(r + play,         0.0),
+			point2D(r + play,         crest),
+			point2D(r + play - depth, crest + flank),
+			point2D(r + play - depth, self.pitch - flank),
+		]
+	}
+	//}}}
+	//{{{
+	// Radius of the profile at height z, interpolated linearly between the profile points
+	fn radius(profile: &[Point2D], pitch: f64, z: f64) -> f64
+	{
+		let z = z.rem_euclid(pitch);
+		let n = profile.len();
+		for i in 0..n
+		{
+			let a = profile[i];
+			let b = if i+1 < n { profile[i+1] } else { point2D(profile[0][0], profile[0][1]+pitch) };
+			if z <= b[1]
+			{
+				if b[1]-a[1] < 1e-12 { return b[0]; }
+				return a[0] + (b[0]-a[0])*(z-a[1])/(b[1]-a[1]);
+			}
+		}
+		profile[0][0]
+	}
+	//}}}
+	//}}}
+
+	//{{{ Geometry
+
+	//{{{
+	fn solid(&self, name: &str, internal: bool) -> Object3D
+	{
+		let profile   = self.profile(internal);
+		let r_major   = profile[0][0];
+		let r_minor   = profile[2][0];
+		let n         = self.segments;
+		let row       = self.pitch/(n as f64);
+
+		let (z0, z1)  = if internal { (-OVERSHOOT, self.length+OVERSHOOT) } else { (0.0, self.length) };
+		let rows      = ((z1-z0)/row - 1e-9).ceil().max(1.0) as usize;
+
+		let rings : Vec<Vec<Point3D>> = (0..=rows).map(|i|
+		{
+			let z = (z0 + (i as f64)*row).min(z1);
+
+			// Distance to the nearer end, for the lead-in chamfers
+			let end = z.min(self.length-z);
+
+			(0..n).map(|k|
+			{
+				let phi = 2.0*PI*(k as f64)/(n as f64);
+				let mut r = Self::radius(&profile, self.pitch, z - self.pitch*(k as f64)/(n as f64));
+				if self.chamfer > 0.0
+				{
+					r = if internal { r.max(r_minor + self.chamfer - end) } else { r.min(r_major - self.chamfer + end) };
+				}
+				let r = r.clamp(0.01*r_minor, r_major);
+				point3D(r*phi.cos(), r*phi.sin(), z)
+			}).collect()
+		}).collect();
+
+		// Right handed: a mirrored copy would be a left handed thread
+		let mut solid = rings_to_polyhedron(name, rings, false);
+		solid.set_handed(true);
+		solid
+	}
+	//}}}
+	//{{{
+	// Threaded rod
+	pub fn external(&self, name: &str) -> Object3D
+	{
+		self.solid(name, false)
+	}
+	//}}}
+	//{{{
+	// Body to subtract from a part for the matching threaded hole
+	pub fn internal(&self, name: &str) -> Object3D
+	{
+		self.solid(name, true)
+	}
+	//}}}
+	//}}}
+}
+//}}}
+//}}}
+
+//{{{
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	//{{{
+	fn coordinates(profile: &[Point2D]) -> Vec<(f64, f64)>
+	{
+		profile.iter().map(|p| (p[0], p[1])).collect()
+	}
+	//}}}
+
+	//{{{
+	#[test]
+	fn metric_profile()
+	{
+		// M8x1.25, ISO 68-1: H = sqrt(3)/2 P, thread depth 5/8 H, crest flat P/8, root flat P/4
+		let thread = Thread::metric(8.0, 10.0).expect("M8 is a standard size").tolerance(ToleranceClass::Exact);
+		let h      = 3.0_f64.sqrt()/2.0*1.25;
+		let depth  = 5.0/8.0*h;
+		let flank  = depth*(1.0/3.0_f64.sqrt());
+
+		assert_eq!(coordinates(&thread.profile(false)), vec![
+			(4.0,         0.0),
+			(4.0,         0.15625),
+			(4.0 - depth, 0.15625 + flank),
+			(4.0 - depth, 1.25 - flank),
+		]);
+		assert_eq!(coordinates(&thread.profile(true)), coordinates(&thread.profile(false)));
+
+		// The flanks leave the root flat of P/4
+		assert!(((1.25 - flank) - (0.15625 + flank) - 0.3125).abs() < 1e-12);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn metric_profile_with_tolerance()
+	{
+		let thread   = Thread::metric(8.0, 10.0).expect("M8 is a standard size").tolerance(ToleranceClass::Custom(0.3));
+		let external = coordinates(&thread.profile(false));
+		let internal = coordinates(&thread.profile(true));
+
+		assert_eq!(external[0], (3.85, 0.0));
+		assert_eq!(internal[0], (4.15, 0.0));
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn trapezoidal_profile()
+	{
+		// Tr8x2, DIN 103: ac = 0.25, depth P/2 + ac, 15° flanks
+		let thread = Thread::trapezoidal(8.0, 2.0, 100.0).tolerance(ToleranceClass::Exact);
+		let tan    = (15.0_f64).to_radians().tan();
+		let crest  = 1.0*(1.0-tan);              // 0.366 P at d
+		let groove = 1.0*(1.0-tan) - 0.5*tan;    // Narrower at D4 = d + 2 ac
+		let flank  = 1.25*tan;
+
+		assert_eq!(coordinates(&thread.profile(false)), vec![
+			(4.0,  0.0),
+			(4.0,  crest),
+			(2.75, crest + flank),
+			(2.75, 2.0 - flank),
+		]);
+		assert_eq!(coordinates(&thread.profile(true)), vec![
+			(4.25, 0.0),
+			(4.25, groove),
+			(3.0,  groove + flank),
+			(3.0,  2.0 - flank),
+		]);
+		assert!((crest - 0.732).abs() < 1e-3);
+
+		// The screw's root flat at d3 is as wide as the nut's groove at D4, the nut's crest at D1 as the screw's crest
+		let external = thread.profile(false);
+		let internal = thread.profile(true);
+		assert!(((external[3][1] - external[2][1]) - internal[1][1]).abs() < 1e-9);
+		assert!(((internal[3][1] - internal[2][1]) - external[1][1]).abs() < 1e-9);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn profile_vertices_on_mesh()
+	{
+		// With 32 segments, every metric profile vertex is hit by the first column of the mesh
+		let thread = Thread::metric(8.0, 2.5).expect("M8 is a standard size").tolerance(ToleranceClass::Exact);
+		let rod    = thread.external("rod");
+		let points = match rod.shape
+		{
+			crate::Shape3D::Polyhedron{points, ..} => points,
+			_                                      => panic!("threads are polyhedra"),
+		};
+
+		for vertex in thread.profile(false)
+		{
+			let hit = points.iter().any(|p| (p[0]-vertex[0]).abs() < 1e-9 && p[1].abs() < 1e-9 && (p[2]-vertex[1]).abs() < 1e-9);
+			assert!(hit, "profile vertex ({}, {}) is not on the mesh", vertex[0], vertex[1]);
+		}
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn handed()
+	{
+		let thread = Thread::metric(8.0, 4.0).expect("M8 is a standard size").segments(8);
+		let rod    = thread.external("rod");
+		assert!(rod.mirrored_handed_features().is_empty());
+		assert_eq!(rod.mirrored_copy("rod L").mirrored_handed_features(), ["rod L"]);
+
+		// Found inside an assembly, and two mirrors cancel
+		let nut  = crate::object_3d::difference("nut", [crate::object_3d::cylinder("body", 4.0, 7.0, 7.0), thread.internal("nut thread")]);
+		let left = nut.mirrored_copy("nut L");
+		assert_eq!(left.mirrored_handed_features(), ["nut thread"]);
+		assert!(left.mirrored_copy("nut R").mirrored_handed_features().is_empty());
+	}
+	//}}}
+}
+//}}}
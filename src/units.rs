@@ -0,0 +1,446 @@
+// Lengths and angles with their units. Models are built in millimetres and degrees: bare numbers passed where a Length
+// or Angle is expected are taken as those, so
+//
+//     const ROLL_DIAMETER : Length = Length::cm(2.5);
+//     let mut roll = cylinder("roll", Length::mm(8.0), ROLL_DIAMETER/2.0, ROLL_DIAMETER/2.0);
+//     roll.rotate_z(Angle::rad(y.atan2(x)));
+//
+// mix units safely. Output files are in millimetres unless a different output unit is chosen (see
+// ScadWriter::set_output_unit() and Project::set_output_unit()).
+
+use std::fmt;
+use std::ops::{Add, Sub, Neg, Mul, Div, AddAssign, SubAssign};
+use std::str::FromStr;
+
+use crate::math::Matrix3D;
+use crate::parameters::Expr;
+
+//{{{ pub enum LengthUnit
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LengthUnit
+{
+	#[default]
+	Millimetre,
+	Centimetre,
+	Metre,
+	Inch,
+}
+
+//{{{
+impl LengthUnit
+{
+	//{{{
+	// The length of one unit in millimetres
+	pub fn millimetres(&self) -> f64
+	{
+		match self
+		{
+			Self::Millimetre => 1.0,
+			Self::Centimetre => 10.0,
+			Self::Metre      => 1000.0,
+			Self::Inch       => 25.4,
+		}
+	}
+	//}}}
+	//{{{
+	// 'placement' (in millimetres) followed by the conversion into this unit, for the output files. Millimetres leave it
+	// as it is.
+	pub fn convert(&self, placement: &Matrix3D) -> Matrix3D
+	{
+		let mut converted = *placement;
+		if *self != Self::Millimetre
+		{
+			let factor = 1.0/self.millimetres();
+			converted.scale(factor, factor, factor);
+		}
+		converted
+	}
+	//}}}
+	//{{{
+	pub fn symbol(&self) -> &'static str
+	{
+		match self
+		{
+			Self::Millimetre => "mm",
+			Self::Centimetre => "cm",
+			Self::Metre      => "m",
+			Self::Inch       => "in",
+		}
+	}
+	//}}}
+}
+//}}}
+//{{{
+impl FromStr for LengthUnit
+{
+	type Err = String;
+
+	fn from_str(text: &str) -> Result<Self, String>
+	{
+		match text
+		{
+			"mm"          => Ok(Self::Millimetre),
+			"cm"          => Ok(Self::Centimetre),
+			"m"           => Ok(Self::Metre),
+			"in" | "inch" => Ok(Self::Inch),
+			_             => Err(format!("unknown unit {} (mm, cm, m or in)", text)),
+		}
+	}
+}
+//}}}
+//{{{
+impl fmt::Display for LengthUnit
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+	{
+		write!(f, "{}", self.symbol())
+	}
+}
+//}}}
+//}}}
+
+//{{{ pub struct Length
+
+// Stored in millimetres
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Length(f64);
+
+//{{{
+impl Length
+{
+	//{{{
+	pub const fn mm(mm: f64) -> Self
+	{
+		Self(mm)
+	}
+	//}}}
+	//{{{
+	pub const fn cm(cm: f64) -> Self
+	{
+		Self(cm*10.0)
+	}
+	//}}}
+	//{{{
+	pub const fn m(m: f64) -> Self
+	{
+		Self(m*1000.0)
+	}
+	//}}}
+	//{{{
+	pub const fn inch(inch: f64) -> Self
+	{
+		Self(inch*25.4)
+	}
+	//}}}
+	//{{{
+	pub fn new(value: f64, unit: LengthUnit) -> Self
+	{
+		Self(value*unit.millimetres())
+	}
+	//}}}
+
+	//{{{
+	pub const fn to_mm(&self) -> f64
+	{
+		self.0
+	}
+	//}}}
+	//{{{
+	pub fn to_cm(&self) -> f64
+	{
+		self.to(LengthUnit::Centimetre)
+	}
+	//}}}
+	//{{{
+	pub fn to_m(&self) -> f64
+	{
+		self.to(LengthUnit::Metre)
+	}
+	//}}}
+	//{{{
+	pub fn to_inches(&self) -> f64
+	{
+		self.to(LengthUnit::Inch)
+	}
+	//}}}
+	//{{{
+	pub fn to(&self, unit: LengthUnit) -> f64
+	{
+		self.0/unit.millimetres()
+	}
+	//}}}
+	//{{{
+	pub fn abs(&self) -> Self
+	{
+		Self(self.0.abs())
+	}
+	//}}}
+}
+//}}}
+//{{{
+// Bare numbers are millimetres
+impl From<f64> for Length
+{
+	fn from(mm: f64) -> Self { Self(mm) }
+}
+//}}}
+//{{{
+impl From<Length> for Expr
+{
+	fn from(length: Length) -> Self { Expr::Number(length.to_mm()) }
+}
+//}}}
+//{{{
+impl fmt::Display for Length
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+	{
+		write!(f, "{} mm", self.0)
+	}
+}
+//}}}
+//{{{
+impl Add for Length
+{
+	type Output = Self;
+
+	fn add(self, other: Self) -> Self { Self(self.0 + other.0) }
+}
+//}}}
+//{{{
+impl Sub for Length
+{
+	type Output = Self;
+
+	fn sub(self, other: Self) -> Self { Self(self.0 - other.0) }
+}
+//}}}
+//{{{
+impl Neg for Length
+{
+	type Output = Self;
+
+	fn neg(self) -> Self { Self(-self.0) }
+}
+//}}}
+//{{{
+impl Mul<f64> for Length
+{
+	type Output = Self;
+
+	fn mul(self, a: f64) -> Self { Self(self.0*a) }
+}
+//}}}
+//{{{
+impl Mul<Length> for f64
+{
+	type Output = Length;
+
+	fn mul(self, length: Length) -> Length { Length(self*length.0) }
+}
+//}}}
+//{{{
+impl Div<f64> for Length
+{
+	type Output = Self;
+
+	fn div(self, a: f64) -> Self { Self(self.0/a) }
+}
+//}}}
+//{{{
+// The ratio of two lengths
+impl Div for Length
+{
+	type Output = f64;
+
+	fn div(self, other: Self) -> f64 { self.0/other.0 }
+}
+//}}}
+//{{{
+impl AddAssign for Length
+{
+	fn add_assign(&mut self, other: Self) { self.0 += other.0; }
+}
+//}}}
+//{{{
+impl SubAssign for Length
+{
+	fn sub_assign(&mut self, other: Self) { self.0 -= other.0; }
+}
+//}}}
+//}}}
+
+//{{{ pub struct Angle
+
+// Stored in degrees, like OpenSCad's angles
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default)]
+pub struct Angle(f64);
+
+//{{{
+impl Angle
+{
+	//{{{
+	pub const fn deg(deg: f64) -> Self
+	{
+		Self(deg)
+	}
+	//}}}
+	//{{{
+	pub fn rad(rad: f64) -> Self
+	{
+		Self(rad.to_degrees())
+	}
+	//}}}
+	//{{{
+	// The angle of the point (x, y) from the x-axis, -180° to 180°
+	pub fn atan2(y: f64, x: f64) -> Self
+	{
+		Self::rad(y.atan2(x))
+	}
+	//}}}
+
+	//{{{
+	pub const fn to_deg(&self) -> f64
+	{
+		self.0
+	}
+	//}}}
+	//{{{
+	pub fn to_rad(&self) -> f64
+	{
+		self.0.to_radians()
+	}
+	//}}}
+	//{{{
+	pub fn sin(&self) -> f64
+	{
+		self.to_rad().sin()
+	}
+	//}}}
+	//{{{
+	pub fn cos(&self) -> f64
+	{
+		self.to_rad().cos()
+	}
+	//}}}
+	//{{{
+	pub fn tan(&self) -> f64
+	{
+		self.to_rad().tan()
+	}
+	//}}}
+}
+//}}}
+//{{{
+// Bare numbers are degrees
+impl From<f64> for Angle
+{
+	fn from(deg: f64) -> Self { Self(deg) }
+}
+//}}}
+//{{{
+impl fmt::Display for Angle
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+	{
+		write!(f, "{}°", self.0)
+	}
+}
+//}}}
+//{{{
+impl Add for Angle
+{
+	type Output = Self;
+
+	fn add(self, other: Self) -> Self { Self(self.0 + other.0) }
+}
+//}}}
+//{{{
+impl Sub for Angle
+{
+	type Output = Self;
+
+	fn sub(self, other: Self) -> Self { Self(self.0 - other.0) }
+}
+//}}}
+//{{{
+impl Neg for Angle
+{
+	type Output = Self;
+
+	fn neg(self) -> Self { Self(-self.0) }
+}
+//}}}
+//{{{
+impl Mul<f64> for Angle
+{
+	type Output = Self;
+
+	fn mul(self, a: f64) -> Self { Self(self.0*a) }
+}
+//}}}
+//{{{
+impl Div<f64> for Angle
+{
+	type Output = Self;
+
+	fn div(self, a: f64) -> Self { Self(self.0/a) }
+}
+//}}}
+//}}}
+
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+	use crate::math::{Is3DObject, HasRefSys3D};
+	use crate::object_3d::cube;
+
+	//{{{
+	#[test]
+	fn conversions()
+	{
+		assert_eq!(Length::cm(2.5).to_mm(), 25.0);
+		assert_eq!(Length::inch(1.0), Length::new(25.4, LengthUnit::Millimetre));
+		assert!((Length::mm(2540.0).to_inches() - 100.0).abs() < 1e-12);
+		assert_eq!(Length::m(1.2) - Length::cm(20.0), Length::m(1.0));
+		assert_eq!(Length::cm(3.0)/Length::mm(10.0), 3.0);
+		assert!((Angle::rad(std::f64::consts::PI).to_deg() - 180.0).abs() < 1e-12);
+		assert_eq!("inch".parse::<LengthUnit>(), Ok(LengthUnit::Inch));
+		assert!("ft".parse::<LengthUnit>().is_err());
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn units_and_bare_numbers_agree()
+	{
+		let mut a = cube("a", 1.0, 1.0, 1.0);
+		let mut b = cube("b", Length::cm(0.1), 1.0, 1.0);
+		a.translate(10.0, 0.0, 25.4);
+		a.rotate_z(90.0);
+		b.translate(Length::cm(1.0), 0.0, Length::inch(1.0));
+		b.rotate_z(Angle::rad(std::f64::consts::FRAC_PI_2));
+		for (row_a, row_b) in a.ref_sys().iter().zip(b.ref_sys().iter())
+		{
+			for (x, y) in row_a.iter().zip(row_b.iter()) { assert!((x - y).abs() < 1e-12); }
+		}
+		assert_eq!(a.to_string().replace("// a", ""), b.to_string().replace("// b", ""));
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn output_unit()
+	{
+		let mut placement = Matrix3D::identity();
+		placement.translate(10.0, 20.0, 254.0);
+		let converted = LengthUnit::Inch.convert(&placement);
+		assert!((converted[2][3] - 10.0).abs() < 1e-12);
+		assert!((converted[0][0] - 1.0/25.4).abs() < 1e-12);
+		assert_eq!(LengthUnit::Millimetre.convert(&placement)[0][3], 10.0);
+	}
+	//}}}
+}
+//}}}
@@ -0,0 +1,154 @@
+// Minimal zip archive writer for container formats like 3MF. The entries are stored uncompressed, which every zip reader
+// (and the 3MF/OPC specification) accepts, so no deflate implementation is needed. There is no zip64 either: archives
+// beyond 4 GiB, 65535 entries or names longer than 65535 bytes are an error instead of a corrupt file.
+
+use std::convert::TryFrom;
+use std::io::{self, Write};
+
+//{{{ pub(crate) struct ZipArchive
+
+#[derive(Debug, Default)]
+pub(crate) struct ZipArchive
+{
+	data      : Vec<u8>,  // Local headers and file data
+	directory : Vec<u8>,  // Central directory
+	entries   : u16,
+}
+
+//{{{
+impl ZipArchive
+{
+	//{{{
+	pub(crate) fn new() -> Self
+	{
+		Self::default()
+	}
+	//}}}
+	//{{{
+	pub(crate) fn add(&mut self, name: &str, contents: &[u8]) -> io::Result<()>
+	{
+		let offset      = fits_u32(self.data.len(), "archive")?;
+		let size        = fits_u32(contents.len(), name)?;
+		let name_length = u16::try_from(name.len()).map_err(|_| too_large("the name of an entry"))?;
+		let entries     = self.entries.checked_add(1).ok_or_else(|| too_large("the number of entries"))?;
+		let crc         = crc32(contents);
+
+		// Local file header: version 2.0, no flags, stored, 1980-01-01 00:00
+		put_u32(&mut self.data, 0x04034b50);
+		for value in [20u16, 0, 0, 0, 0x21] { put_u16(&mut self.data, value); }
+		for value in [crc, size, size] { put_u32(&mut self.data, value); }
+		put_u16(&mut self.data, name_length);
+		put_u16(&mut self.data, 0);
+		self.data.extend_from_slice(name.as_bytes());
+		self.data.extend_from_slice(contents);
+
+		// Central directory entry
+		put_u32(&mut self.directory, 0x02014b50);
+		for value in [20u16, 20, 0, 0, 0, 0x21] { put_u16(&mut self.directory, value); }
+		for value in [crc, size, size] { put_u32(&mut self.directory, value); }
+		for value in [name_length, 0, 0, 0, 0] { put_u16(&mut self.directory, value); }
+		put_u32(&mut self.directory, 0);
+		put_u32(&mut self.directory, offset);
+		self.directory.extend_from_slice(name.as_bytes());
+
+		self.entries = entries;
+		Ok(())
+	}
+	//}}}
+	//{{{
+	pub(crate) fn write(&self, out: &mut dyn Write) -> io::Result<()>
+	{
+		let mut end = vec![];
+		put_u32(&mut end, 0x06054b50);
+		for value in [0, 0, self.entries, self.entries] { put_u16(&mut end, value); }
+		put_u32(&mut end, fits_u32(self.directory.len(), "central directory")?);
+		put_u32(&mut end, fits_u32(self.data.len(), "archive")?);
+		put_u16(&mut end, 0);
+
+		out.write_all(&self.data)?;
+		out.write_all(&self.directory)?;
+		out.write_all(&end)
+	}
+	//}}}
+}
+//}}}
+//}}}
+
+//{{{ Helpers
+
+//{{{
+fn too_large(what: &str) -> io::Error
+{
+	io::Error::new(io::ErrorKind::InvalidInput, format!("{} is too large for a zip archive", what))
+}
+//}}}
+//{{{
+fn fits_u32(size: usize, what: &str) -> io::Result<u32>
+{
+	u32::try_from(size).map_err(|_| too_large(what))
+}
+//}}}
+//{{{
+fn put_u16(bytes: &mut Vec<u8>, value: u16)
+{
+	bytes.extend_from_slice(&value.to_le_bytes());
+}
+//}}}
+//{{{
+fn put_u32(bytes: &mut Vec<u8>, value: u32)
+{
+	bytes.extend_from_slice(&value.to_le_bytes());
+}
+//}}}
+//{{{
+// CRC-32 as zip uses it (reflected, polynomial 0xedb88320)
+pub(crate) fn crc32(bytes: &[u8]) -> u32
+{
+	let mut crc = !0u32;
+	for &byte in bytes
+	{
+		crc ^= byte as u32;
+		for _ in 0..8
+		{
+			crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb88320 } else { crc >> 1 };
+		}
+	}
+	!crc
+}
+//}}}
+//}}}
+
+//{{{ Tests
+
+#[cfg(test)]
+mod tests
+{
+	use super::*;
+
+	//{{{
+	#[test]
+	fn crc()
+	{
+		assert_eq!(crc32(b""), 0);
+		assert_eq!(crc32(b"123456789"), 0xcbf43926);
+	}
+	//}}}
+	//{{{
+	#[test]
+	fn limits()
+	{
+		let mut archive = ZipArchive::new();
+		let error = archive.add(&"x".repeat(65536), b"").expect_err("the name is too long");
+		assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+		assert_eq!(archive.entries, 0);
+
+		for i in 0..65535 { archive.add(&i.to_string(), b"").expect("room for more"); }
+		assert_eq!(archive.add("one more", b"").expect_err("too many entries").to_string(), "the number of entries is too large for a zip archive");
+		assert_eq!(archive.entries, 65535);
+		let mut bytes = vec![];
+		archive.write(&mut bytes).expect("writing to memory");
+		assert_eq!(&bytes[bytes.len()-12..bytes.len()-10], [0xff, 0xff]);
+	}
+	//}}}
+}
+//}}}